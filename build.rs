@@ -0,0 +1,5 @@
+fn main() {
+    // Node resolves the N-API symbols when it loads the addon, macOS needs linker flags for that
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
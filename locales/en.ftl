@@ -0,0 +1,83 @@
+# Interactive prompts
+prompt-kind = What to encode
+kind-text = Text or URL
+kind-sms = SMS
+prompt-phone = Phone number
+prompt-message = Message
+prompt-text = Enter text or URL
+prompt-fg-color = Foreground color (hex)
+prompt-bg-color = Background color (hex or 'transparent')
+prompt-gradient = Gradient colors (format: #ff0000,#0000ff, empty for none)
+prompt-dot-style = Dot style
+prompt-eye-style = Eye style
+prompt-logo-path = Logo path (empty for none)
+prompt-logo-size = Logo size ratio (0.1 to 0.3)
+prompt-error-level = Error correction level
+prompt-size = Image size (pixels)
+prompt-format = Output format
+prompt-output = Output file path
+prompt-eye-gradient = Eye gradient colors (format: #aa0000,#ff8800, empty for none)
+prompt-version = QR version (1-40 or auto)
+prompt-border = Border (quiet zone)
+version-range = Enter a version from 1 to 40, or auto
+
+# Interactive menu
+menu-prompt = Pick a setting to change, then Generate
+menu-generate = Generate
+menu-cancel = Cancel
+menu-needs-data = Enter the data to encode first
+setting-data = Data
+setting-fg-color = Foreground
+setting-bg-color = Background
+setting-gradient = Gradient
+setting-eye-gradient = Eye colors
+setting-dot-style = Dot style
+setting-eye-style = Eye style
+setting-logo = Logo
+setting-error = Error correction
+setting-version = Version
+setting-border = Border
+setting-size = Size
+setting-encode = Base64 encode
+setting-format = Format
+setting-output = Output
+setting-copy = Copy path
+setting-show = Show in terminal
+value-none = none
+value-auto = auto
+value-yes = yes
+value-no = no
+review-prompt = Save this code?
+review-save = Save
+review-adjust = Adjust settings
+review-cancel = Cancel
+preset-offer = Save these settings as a preset?
+preset-name = Preset name
+preset-name-invalid = Use letters, digits, '-' and '_' only
+
+# Status messages
+saved = ✓ { $kind ->
+    [datamatrix] Data Matrix code
+    [aztec] Aztec code
+    [barcode] Barcode
+   *[qr] QR code
+} saved to: { $path }
+up-to-date = ✓ Up to date: { $path }
+discarded = ✗ Discarded, nothing was saved
+cancelled = ✗ Cancelled, nothing was generated
+sent-to-printer = ✓ Sent to printer: { $printer }
+path-copied = ✓ Path copied to clipboard
+batch-row-failed = ✗ Batch row { $row } failed: { $error }
+batch-checked = ✓ Checked { $count } QR codes, nothing was written
+batch-archived = ✓ Generated { $count } QR codes into { $path }
+batch-up-to-date = ✓ Generated { $count } QR codes, { $current } already up to date
+batch-generated = ✓ Generated { $count } QR codes
+manifest-saved = ✓ Manifest saved to: { $path }
+preset-saved = ✓ Preset { $name } saved to: { $path } (use it with --preset { $name })
+ids-saved = ✓ Identifiers listed in: { $path }
+sheet-saved = ✓ Sheet with { $count } codes saved to: { $path }
+card-saved = ✓ Business card saved to: { $path }
+page-saved = ✓ Page saved to: { $path }
+payload-saved = ✓ Payload saved to: { $path }
+logo-plate = ✓ Added a plate behind the logo, it hides about { $modules } modules
+validate-ok = ✓ No problems found, the code can be generated
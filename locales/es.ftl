@@ -0,0 +1,95 @@
+# Preguntas del modo interactivo
+prompt-kind = Qué codificar
+kind-text = Texto o URL
+kind-sms = SMS
+prompt-phone = Número de teléfono
+prompt-message = Mensaje
+prompt-text = Introduce el texto o la URL
+prompt-fg-color = Color de primer plano (hex)
+prompt-bg-color = Color de fondo (hex o 'transparent')
+prompt-gradient = Colores del degradado (formato: #ff0000,#0000ff, vacío para ninguno)
+prompt-dot-style = Estilo de los puntos
+prompt-eye-style = Estilo de los ojos
+prompt-logo-path = Ruta del logotipo (vacío para ninguno)
+prompt-logo-size = Proporción del logotipo (0.1 a 0.3)
+prompt-error-level = Nivel de corrección de errores
+prompt-size = Tamaño de la imagen (píxeles)
+prompt-format = Formato de salida
+prompt-output = Ruta del archivo de salida
+prompt-eye-gradient = Colores del degradado de los ojos (formato: #aa0000,#ff8800, vacío para ninguno)
+prompt-version = Versión QR (1-40 o auto)
+prompt-border = Borde (zona de silencio)
+version-range = Introduce una versión de 1 a 40, o auto
+
+# Menú interactivo
+menu-prompt = Elige un ajuste para cambiarlo y luego Generar
+menu-generate = Generar
+menu-cancel = Cancelar
+menu-needs-data = Primero introduce los datos a codificar
+setting-data = Datos
+setting-fg-color = Primer plano
+setting-bg-color = Fondo
+setting-gradient = Degradado
+setting-eye-gradient = Colores de los ojos
+setting-dot-style = Estilo de puntos
+setting-eye-style = Estilo de ojos
+setting-logo = Logotipo
+setting-error = Corrección de errores
+setting-version = Versión
+setting-border = Borde
+setting-size = Tamaño
+setting-encode = Codificar en Base64
+setting-format = Formato
+setting-output = Salida
+setting-copy = Copiar la ruta
+setting-show = Mostrar en la terminal
+value-none = ninguno
+value-auto = automático
+value-yes = sí
+value-no = no
+review-prompt = ¿Guardar este código?
+review-save = Guardar
+review-adjust = Cambiar los ajustes
+review-cancel = Cancelar
+preset-offer = ¿Guardar estos ajustes como preajuste?
+preset-name = Nombre del preajuste
+preset-name-invalid = Usa solo letras, dígitos, '-' y '_'
+
+# Mensajes de estado
+saved = ✓ { $kind ->
+    [datamatrix] Código Data Matrix
+    [aztec] Código Aztec
+    [barcode] Código de barras
+   *[qr] Código QR
+} guardado en: { $path }
+up-to-date = ✓ Sin cambios: { $path }
+discarded = ✗ Descartado, no se guardó nada
+cancelled = ✗ Cancelado, no se generó nada
+sent-to-printer = ✓ Enviado a la impresora: { $printer }
+path-copied = ✓ Ruta copiada al portapapeles
+batch-row-failed = ✗ Falló la fila { $row } del lote: { $error }
+batch-checked = ✓ { $count ->
+    [one] Comprobado 1 código QR
+   *[other] Comprobados { $count } códigos QR
+}, no se escribió nada
+batch-archived = ✓ { $count ->
+    [one] Generado 1 código QR
+   *[other] Generados { $count } códigos QR
+} en { $path }
+batch-up-to-date = ✓ { $count ->
+    [one] Generado 1 código QR
+   *[other] Generados { $count } códigos QR
+}, { $current } ya al día
+batch-generated = ✓ { $count ->
+    [one] Generado 1 código QR
+   *[other] Generados { $count } códigos QR
+}
+manifest-saved = ✓ Manifiesto guardado en: { $path }
+preset-saved = ✓ Preajuste { $name } guardado en: { $path } (úsalo con --preset { $name })
+ids-saved = ✓ Identificadores listados en: { $path }
+sheet-saved = ✓ Hoja con { $count } códigos guardada en: { $path }
+card-saved = ✓ Tarjeta de visita guardada en: { $path }
+page-saved = ✓ Página guardada en: { $path }
+payload-saved = ✓ Contenido guardado en: { $path }
+logo-plate = ✓ Se añadió una placa detrás del logo, oculta unos { $modules } módulos
+validate-ok = ✓ No se encontraron problemas, el código se puede generar
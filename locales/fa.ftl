@@ -0,0 +1,83 @@
+# پرسش‌های حالت تعاملی
+prompt-kind = چه چیزی رمزگذاری شود
+kind-text = متن یا نشانی اینترنتی
+kind-sms = پیامک
+prompt-phone = شماره تلفن
+prompt-message = پیام
+prompt-text = متن یا نشانی اینترنتی را وارد کنید
+prompt-fg-color = رنگ پیش‌زمینه (hex)
+prompt-bg-color = رنگ پس‌زمینه (hex یا 'transparent')
+prompt-gradient = رنگ‌های گرادیان (قالب: #ff0000,#0000ff، خالی برای هیچ)
+prompt-dot-style = سبک نقطه‌ها
+prompt-eye-style = سبک چشم‌ها
+prompt-logo-path = مسیر لوگو (خالی برای هیچ)
+prompt-logo-size = نسبت اندازه لوگو (0.1 تا 0.3)
+prompt-error-level = سطح تصحیح خطا
+prompt-size = اندازه تصویر (پیکسل)
+prompt-format = قالب خروجی
+prompt-output = مسیر فایل خروجی
+prompt-eye-gradient = رنگ‌های گرادیان چشم‌ها (قالب: #aa0000,#ff8800، خالی برای هیچ)
+prompt-version = نسخه QR (1 تا 40 یا auto)
+prompt-border = حاشیه (ناحیه خاموش)
+version-range = نسخه‌ای از 1 تا 40 یا auto وارد کنید
+
+# منوی تعاملی
+menu-prompt = تنظیمی را برای تغییر برگزینید، سپس ساختن
+menu-generate = ساختن
+menu-cancel = لغو
+menu-needs-data = نخست داده‌ای برای رمزگذاری وارد کنید
+setting-data = داده
+setting-fg-color = پیش‌زمینه
+setting-bg-color = پس‌زمینه
+setting-gradient = گرادیان
+setting-eye-gradient = رنگ چشم‌ها
+setting-dot-style = سبک نقطه‌ها
+setting-eye-style = سبک چشم‌ها
+setting-logo = لوگو
+setting-error = تصحیح خطا
+setting-version = نسخه
+setting-border = حاشیه
+setting-size = اندازه
+setting-encode = رمزگذاری Base64
+setting-format = قالب
+setting-output = خروجی
+setting-copy = کپی مسیر
+setting-show = نمایش در ترمینال
+value-none = هیچ
+value-auto = خودکار
+value-yes = بله
+value-no = خیر
+review-prompt = این کد ذخیره شود؟
+review-save = ذخیره
+review-adjust = تغییر تنظیمات
+review-cancel = لغو
+preset-offer = این تنظیمات به‌عنوان پیش‌تنظیم ذخیره شود؟
+preset-name = نام پیش‌تنظیم
+preset-name-invalid = فقط حروف، ارقام، '-' و '_' به کار ببرید
+
+# پیام‌های وضعیت
+saved = ✓ { $kind ->
+    [datamatrix] کد Data Matrix
+    [aztec] کد Aztec
+    [barcode] بارکد
+   *[qr] کد QR
+} ذخیره شد در: { $path }
+up-to-date = ✓ به‌روز است: { $path }
+discarded = ✗ کنار گذاشته شد، چیزی ذخیره نشد
+cancelled = ✗ لغو شد، چیزی ساخته نشد
+sent-to-printer = ✓ به چاپگر فرستاده شد: { $printer }
+path-copied = ✓ مسیر در کلیپ‌بورد کپی شد
+batch-row-failed = ✗ ردیف { $row } دسته ناموفق بود: { $error }
+batch-checked = ✓ { $count } کد QR بررسی شد، چیزی نوشته نشد
+batch-archived = ✓ { $count } کد QR در { $path } ساخته شد
+batch-up-to-date = ✓ { $count } کد QR ساخته شد، { $current } کد از پیش به‌روز بود
+batch-generated = ✓ { $count } کد QR ساخته شد
+manifest-saved = ✓ گزارش دسته ذخیره شد در: { $path }
+preset-saved = ✓ پیش‌تنظیم { $name } ذخیره شد در: { $path } (با --preset { $name } به کار ببرید)
+ids-saved = ✓ شناسه‌ها فهرست شدند در: { $path }
+sheet-saved = ✓ برگه با { $count } کد ذخیره شد در: { $path }
+card-saved = ✓ کارت ویزیت ذخیره شد در: { $path }
+page-saved = ✓ صفحه ذخیره شد در: { $path }
+payload-saved = ✓ محتوا ذخیره شد در: { $path }
+logo-plate = ✓ یک صفحه پشت لوگو اضافه شد، حدود { $modules } ماژول را می‌پوشاند
+validate-ok = ✓ مشکلی پیدا نشد، کد را می‌توان ساخت
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+/// Checks a Bitcoin address: Base58Check (P2PKH/P2SH) or Bech32/Bech32m (SegWit)
+pub fn validate_bitcoin(address: &str) -> Result<()> {
+    let lower = address.to_lowercase();
+    if lower.starts_with("bc1") || lower.starts_with("tb1") {
+        validate_segwit(address)
+    } else {
+        validate_base58check(address)
+    }
+}
+
+fn validate_base58check(address: &str) -> Result<()> {
+    let bytes = decode_base58(address)
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not a valid Bitcoin address", address))?;
+    if bytes.len() != 25 || !matches!(bytes[0], 0x00 | 0x05 | 0x6f | 0xc4) {
+        anyhow::bail!("'{}' is not a valid Bitcoin address", address);
+    }
+
+    let hash = Sha256::digest(Sha256::digest(&bytes[..21]));
+    if hash[..4] != bytes[21..] {
+        anyhow::bail!("Bitcoin address '{}' has an invalid checksum", address);
+    }
+    Ok(())
+}
+
+fn decode_base58(input: &str) -> Option<Vec<u8>> {
+    // Big-endian base-256 digits, grown as the number does
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in input.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    // Each leading '1' stands for a leading zero byte
+    let zeros = input.bytes().take_while(|&c| c == b'1').count();
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes);
+    Some(out)
+}
+
+fn validate_segwit(address: &str) -> Result<()> {
+    let invalid = || anyhow::anyhow!("'{}' is not a valid Bitcoin address", address);
+
+    if address.to_lowercase() != address && address.to_uppercase() != address {
+        return Err(invalid());
+    }
+    let address_lower = address.to_lowercase();
+    let (hrp, data) = address_lower.rsplit_once('1').ok_or_else(invalid)?;
+    let values: Vec<u8> = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|&a| a == c).map(|v| v as u8))
+        .collect::<Option<_>>()
+        .ok_or_else(invalid)?;
+    if values.len() < 7 {
+        return Err(invalid());
+    }
+
+    // Witness v0 uses Bech32, v1 and later use Bech32m
+    let version = values[0];
+    let expected = if version == 0 { BECH32_CONST } else { BECH32M_CONST };
+    if bech32_polymod(hrp, &values) != expected {
+        anyhow::bail!("Bitcoin address '{}' has an invalid checksum", address);
+    }
+
+    let program = convert_bits(&values[1..values.len() - 6]).ok_or_else(invalid)?;
+    let length_ok = match version {
+        0 => program.len() == 20 || program.len() == 32,
+        1..=16 => (2..=40).contains(&program.len()),
+        _ => false,
+    };
+    if !length_ok {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+fn bech32_polymod(hrp: &str, values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+
+    let expanded = hrp
+        .bytes()
+        .map(|c| c >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|c| c & 31))
+        .chain(values.iter().copied());
+
+    let mut checksum = 1u32;
+    for value in expanded {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x01ff_ffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+/// Regroups 5-bit values into bytes, rejecting non-zero padding
+fn convert_bits(values: &[u8]) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut out = Vec::new();
+    for &value in values {
+        acc = (acc << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || (acc & ((1 << bits) - 1)) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+/// Returns the EIP-55 checksummed form of an Ethereum address.
+/// Mixed-case input must already carry a correct checksum.
+pub fn checksum_ethereum(address: &str) -> Result<String> {
+    let hex = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("'{}' is not a valid Ethereum address", address);
+    }
+
+    let lower = hex.to_lowercase();
+    let hash = Keccak256::digest(lower.as_bytes());
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let mixed_case = hex != lower && hex != hex.to_uppercase();
+    if mixed_case && hex != checksummed {
+        anyhow::bail!("Ethereum address '{}' has an invalid checksum", address);
+    }
+    Ok(format!("0x{}", checksummed))
+}
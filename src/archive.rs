@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use flate2::Crc;
+use flate2::Compression;
+use flate2::write::DeflateEncoder;
+use std::io::Write;
+
+/// 1980-01-01 00:00 in MS-DOS date/time format; a fixed timestamp keeps archives reproducible
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+
+/// A minimal ZIP writer: deflated entries written one after another, no ZIP64
+pub struct ZipWriter<W: Write> {
+    out: W,
+    offset: u64,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(out: W) -> Self {
+        ZipWriter { out, offset: 0, central: Vec::new(), entries: 0 }
+    }
+
+    /// Compresses and writes one file; `name` uses forward slashes for folders
+    pub fn add(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        if self.entries == u16::MAX {
+            anyhow::bail!("ZIP archives hold at most {} files", u16::MAX);
+        }
+        let mut crc = Crc::new();
+        crc.update(data);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        if self.offset + compressed.len() as u64 > u32::MAX as u64 || data.len() as u64 > u32::MAX as u64 {
+            anyhow::bail!("ZIP archive would exceed 4 GB");
+        }
+
+        // Fields shared by the local header and the central directory record
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&20u16.to_le_bytes()); // version needed: 2.0 for deflate
+        common.extend_from_slice(&(1u16 << 11).to_le_bytes()); // flags: UTF-8 names
+        common.extend_from_slice(&8u16.to_le_bytes()); // method: deflate
+        common.extend_from_slice(&DOS_TIME.to_le_bytes());
+        common.extend_from_slice(&DOS_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.sum().to_le_bytes());
+        common.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+
+        let mut local = 0x0403_4b50u32.to_le_bytes().to_vec();
+        local.extend_from_slice(&common);
+        local.extend_from_slice(name.as_bytes());
+        self.out.write_all(&local).context("Failed to write archive")?;
+        self.out.write_all(&compressed).context("Failed to write archive")?;
+
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        self.central.extend_from_slice(&common);
+        self.central.extend_from_slice(&[0; 6]); // comment length, disk number, internal attributes
+        self.central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        self.central.extend_from_slice(&(self.offset as u32).to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+
+        self.offset += (local.len() + compressed.len()) as u64;
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Writes the central directory and returns the underlying writer
+    pub fn finish(mut self) -> Result<W> {
+        if self.offset + self.central.len() as u64 > u32::MAX as u64 {
+            anyhow::bail!("ZIP archive would exceed 4 GB");
+        }
+        let mut end = 0x0605_4b50u32.to_le_bytes().to_vec();
+        end.extend_from_slice(&[0; 4]); // disk numbers
+        end.extend_from_slice(&self.entries.to_le_bytes());
+        end.extend_from_slice(&self.entries.to_le_bytes());
+        end.extend_from_slice(&(self.central.len() as u32).to_le_bytes());
+        end.extend_from_slice(&(self.offset as u32).to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+        self.out.write_all(&self.central).context("Failed to write archive")?;
+        self.out.write_all(&end).context("Failed to write archive")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
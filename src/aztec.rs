@@ -0,0 +1,383 @@
+use crate::symbol::{Symbol, Symbology};
+use anyhow::Result;
+
+/// Error correction as a share of the data bits, plus a fixed margin
+const EC_PERCENT: usize = 33;
+const EC_EXTRA_BITS: usize = 11;
+
+/// Codeword size in bits by layer count (index 0 is the mode message)
+const WORD_SIZE: [usize; 33] = [
+    4, 6, 6, 8, 8, 8, 8, 8, 8, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 12, 12, 12, 12, 12, 12, 12, 12,
+    12, 12,
+];
+
+/// Text modes the encoder switches between; anything else goes out through Binary Shift
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Upper,
+    Lower,
+    Digit,
+}
+
+impl Mode {
+    /// Code of a character in this mode's table
+    fn code(self, c: u8) -> Option<u32> {
+        match (self, c) {
+            (_, b' ') => Some(1),
+            (Mode::Upper, b'A'..=b'Z') => Some((c - b'A') as u32 + 2),
+            (Mode::Lower, b'a'..=b'z') => Some((c - b'a') as u32 + 2),
+            (Mode::Digit, b'0'..=b'9') => Some((c - b'0') as u32 + 2),
+            (Mode::Digit, b',') => Some(12),
+            (Mode::Digit, b'.') => Some(13),
+            _ => None,
+        }
+    }
+
+    fn bits(self) -> usize {
+        if self == Mode::Digit { 4 } else { 5 }
+    }
+}
+
+/// Encodes bytes as the smallest Aztec code (compact first) that holds them
+pub fn encode(data: &[u8]) -> Result<Symbol> {
+    if data.is_empty() {
+        anyhow::bail!("Aztec codes need at least one byte of data");
+    }
+    let bits = high_level_encode(data);
+    let ecc_bits = bits.len() * EC_PERCENT / 100 + EC_EXTRA_BITS;
+
+    // Compact symbols with 1-4 layers, then full-range ones with 4-32
+    let candidates = (1..=4).map(|layers| (true, layers)).chain((4..=32).map(|layers| (false, layers)));
+    for (compact, layers) in candidates {
+        let total_bits = layer_bits(layers, compact);
+        let word_size = WORD_SIZE[layers];
+        let usable_bits = total_bits - total_bits % word_size;
+        if bits.len() + ecc_bits > usable_bits {
+            continue;
+        }
+        let stuffed = stuff_bits(&bits, word_size);
+        let data_words = stuffed.len() / word_size;
+        // The mode message has 6 bits (compact) or 11 bits (full) for the data word count
+        if stuffed.len() + ecc_bits > usable_bits || (compact && data_words > 64) || data_words > 2048 {
+            continue;
+        }
+        return Ok(build(&stuffed, compact, layers, data_words, total_bits, word_size));
+    }
+    anyhow::bail!(
+        "Data too long for Aztec: {} bytes need more than the 32 layers of the largest symbol",
+        data.len()
+    )
+}
+
+/// Bits in a ring of layers around the core
+fn layer_bits(layers: usize, compact: bool) -> usize {
+    ((if compact { 88 } else { 112 }) + 16 * layers) * layers
+}
+
+/// Upper, Lower and Digit modes with latches between them, and Binary Shift for everything else.
+/// Not the densest possible encoding, but every reader handles it.
+fn high_level_encode(data: &[u8]) -> Vec<bool> {
+    let mut bits = Vec::new();
+    let mut mode = Mode::Upper;
+    let mut i = 0;
+    while i < data.len() {
+        let c = data[i];
+        if let Some(code) = mode.code(c) {
+            push_bits(&mut bits, code, mode.bits());
+            i += 1;
+            continue;
+        }
+
+        let target = [Mode::Upper, Mode::Lower, Mode::Digit].into_iter().find(|m| m.code(c).is_some());
+        match target {
+            Some(target) => {
+                latch(&mut bits, mode, target);
+                mode = target;
+            }
+            None => {
+                // A run of bytes no text mode can take, shifted from Upper or Lower
+                let run = data[i..].iter().take_while(|&&b| [Mode::Upper, Mode::Lower, Mode::Digit].iter().all(|m| m.code(b).is_none())).count();
+                if mode == Mode::Digit {
+                    latch(&mut bits, mode, Mode::Upper);
+                    mode = Mode::Upper;
+                }
+                for chunk in data[i..i + run].chunks(31 + 2047) {
+                    push_bits(&mut bits, 31, 5);
+                    if chunk.len() <= 31 {
+                        push_bits(&mut bits, chunk.len() as u32, 5);
+                    } else {
+                        push_bits(&mut bits, 0, 5);
+                        push_bits(&mut bits, (chunk.len() - 31) as u32, 11);
+                    }
+                    for &byte in chunk {
+                        push_bits(&mut bits, byte as u32, 8);
+                    }
+                }
+                i += run;
+            }
+        }
+    }
+    bits
+}
+
+fn latch(bits: &mut Vec<bool>, from: Mode, to: Mode) {
+    match (from, to) {
+        (Mode::Upper, Mode::Lower) => push_bits(bits, 28, 5),
+        (Mode::Upper | Mode::Lower, Mode::Digit) => push_bits(bits, 30, 5),
+        (Mode::Digit, Mode::Upper) => push_bits(bits, 14, 4),
+        // Lower has no latch back to Upper, Digit has one
+        (Mode::Lower, Mode::Upper) => {
+            push_bits(bits, 30, 5);
+            push_bits(bits, 14, 4);
+        }
+        (Mode::Digit, Mode::Lower) => {
+            push_bits(bits, 14, 4);
+            push_bits(bits, 28, 5);
+        }
+        _ => {}
+    }
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
+    bits.extend((0..count).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Splits into codewords, never all zeros or all ones: when the first bits of a word are all
+/// equal, the last bit is forced to the opposite value and the real bit moves to the next word
+fn stuff_bits(bits: &[bool], word_size: usize) -> Vec<bool> {
+    let mut out = Vec::with_capacity(bits.len() + bits.len() / word_size + word_size);
+    let mask = (1u32 << word_size) - 2;
+    let mut i = 0;
+    while i < bits.len() {
+        let mut word = 0u32;
+        for j in 0..word_size {
+            // The final word is padded with ones
+            if bits.get(i + j).copied().unwrap_or(true) {
+                word |= 1 << (word_size - 1 - j);
+            }
+        }
+        if word & mask == mask {
+            push_bits(&mut out, word & mask, word_size);
+            i += word_size - 1;
+        } else if word & mask == 0 {
+            push_bits(&mut out, word | 1, word_size);
+            i += word_size - 1;
+        } else {
+            push_bits(&mut out, word, word_size);
+            i += word_size;
+        }
+    }
+    out
+}
+
+/// Pads the data words to `total_bits` with Reed-Solomon check words in front of any leftover bits
+fn with_check_words(bits: &[bool], total_bits: usize, word_size: usize) -> Vec<bool> {
+    let words: Vec<u32> = bits
+        .chunks(word_size)
+        .map(|chunk| chunk.iter().fold(0, |word, &bit| word << 1 | bit as u32))
+        .collect();
+    let total_words = total_bits / word_size;
+    let field = Field::new(word_size);
+    let check = field.reed_solomon(&words, total_words - words.len());
+
+    let mut out = vec![false; total_bits % word_size];
+    for word in words.into_iter().chain(check) {
+        push_bits(&mut out, word, word_size);
+    }
+    out
+}
+
+fn build(data: &[bool], compact: bool, layers: usize, data_words: usize, total_bits: usize, word_size: usize) -> Symbol {
+    let message = with_check_words(data, total_bits, word_size);
+
+    let mut mode = Vec::new();
+    if compact {
+        push_bits(&mut mode, layers as u32 - 1, 2);
+        push_bits(&mut mode, data_words as u32 - 1, 6);
+    } else {
+        push_bits(&mut mode, layers as u32 - 1, 5);
+        push_bits(&mut mode, data_words as u32 - 1, 11);
+    }
+    let mode = with_check_words(&mode, if compact { 28 } else { 40 }, 4);
+
+    // Full-range symbols get a reference grid line every 16 modules from the center, which the
+    // data skips over
+    let base_size = if compact { 11 } else { 14 } + layers * 4;
+    let size = if compact { base_size } else { base_size + 1 + 2 * ((base_size / 2 - 1) / 15) };
+    let center = size / 2;
+    let mut position = vec![0usize; base_size];
+    if compact {
+        position.iter_mut().enumerate().for_each(|(i, p)| *p = i);
+    } else {
+        let base_center = base_size / 2;
+        for i in 0..base_center {
+            let offset = i + i / 15;
+            position[base_center - i - 1] = center - offset - 1;
+            position[base_center + i] = center + offset + 1;
+        }
+    }
+
+    let mut dark = vec![false; size * size];
+    let mut set = |x: usize, y: usize| dark[y * size + x] = true;
+
+    // Layers spiral inward from the outside, two modules thick, one side after the other
+    let mut offset = 0;
+    for i in 0..layers {
+        let row_size = (layers - i) * 4 + if compact { 9 } else { 12 };
+        for j in 0..row_size {
+            for k in 0..2 {
+                let bit = |n: usize| message.get(offset + n + j * 2 + k).copied().unwrap_or(false);
+                let far = base_size - 1 - i * 2;
+                if bit(0) {
+                    set(position[i * 2 + k], position[i * 2 + j]);
+                }
+                if bit(row_size * 2) {
+                    set(position[i * 2 + j], position[far - k]);
+                }
+                if bit(row_size * 4) {
+                    set(position[far - k], position[far - j]);
+                }
+                if bit(row_size * 6) {
+                    set(position[far - j], position[i * 2 + k]);
+                }
+            }
+        }
+        offset += row_size * 8;
+    }
+
+    // Mode message around the bullseye, clockwise from the top left
+    if compact {
+        for i in 0..7 {
+            let o = center - 3 + i;
+            if mode[i] {
+                set(o, center - 5);
+            }
+            if mode[i + 7] {
+                set(center + 5, o);
+            }
+            if mode[20 - i] {
+                set(o, center + 5);
+            }
+            if mode[27 - i] {
+                set(center - 5, o);
+            }
+        }
+    } else {
+        for i in 0..10 {
+            let o = center - 5 + i + i / 5;
+            if mode[i] {
+                set(o, center - 7);
+            }
+            if mode[i + 10] {
+                set(center + 7, o);
+            }
+            if mode[29 - i] {
+                set(o, center + 7);
+            }
+            if mode[39 - i] {
+                set(center - 7, o);
+            }
+        }
+    }
+
+    // Bullseye rings and the orientation marks at its corners
+    let rings = if compact { 5 } else { 7 };
+    for i in (0..rings).step_by(2) {
+        for j in center - i..=center + i {
+            set(j, center - i);
+            set(j, center + i);
+            set(center - i, j);
+            set(center + i, j);
+        }
+    }
+    set(center - rings, center - rings);
+    set(center - rings + 1, center - rings);
+    set(center - rings, center - rings + 1);
+    set(center + rings, center - rings);
+    set(center + rings, center - rings + 1);
+    set(center + rings, center + rings - 1);
+
+    if !compact {
+        for j in (0..base_size / 2 - 1).step_by(15).map(|i| i / 15 * 16) {
+            for k in (center & 1..size).step_by(2) {
+                set(center - j, k);
+                set(center + j, k);
+                set(k, center - j);
+                set(k, center + j);
+            }
+        }
+    }
+
+    // The rings without the orientation marks and mode message around them
+    let eyes = vec![(center - rings + 1, center - rings + 1, 2 * rings - 1)];
+    Symbol::from_modules(size, dark, eyes, Symbology::Aztec)
+}
+
+/// GF(2^m) arithmetic for the codeword sizes Aztec uses
+struct Field {
+    exp: Vec<u32>,
+    log: Vec<u32>,
+    size: u32,
+}
+
+impl Field {
+    fn new(word_size: usize) -> Self {
+        let polynomial = match word_size {
+            4 => 0x13,
+            6 => 0x43,
+            8 => 0x12d,
+            10 => 0x409,
+            _ => 0x1069,
+        };
+        let size = 1u32 << word_size;
+        let mut exp = vec![0; size as usize];
+        let mut log = vec![0; size as usize];
+        let mut x = 1u32;
+        for slot in exp.iter_mut() {
+            *slot = x;
+            x <<= 1;
+            if x >= size {
+                x ^= polynomial;
+            }
+        }
+        for i in 0..size - 1 {
+            log[exp[i as usize] as usize] = i;
+        }
+        Field { exp, log, size }
+    }
+
+    fn mul(&self, a: u32, b: u32) -> u32 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[((self.log[a as usize] + self.log[b as usize]) % (self.size - 1)) as usize]
+    }
+
+    /// Check words for `data`, from the generator with roots a^1..a^n
+    fn reed_solomon(&self, data: &[u32], count: usize) -> Vec<u32> {
+        let mut generator = vec![1u32];
+        for i in 1..=count {
+            let root = self.exp[i % (self.size as usize - 1)];
+            let mut next = vec![0u32; generator.len() + 1];
+            for (j, &c) in generator.iter().enumerate() {
+                next[j] ^= c;
+                next[j + 1] ^= self.mul(c, root);
+            }
+            generator = next;
+        }
+
+        let mut remainder = vec![0u32; count];
+        if count == 0 {
+            return remainder;
+        }
+        for &value in data {
+            let factor = value ^ remainder[0];
+            remainder.rotate_left(1);
+            remainder[count - 1] = 0;
+            for i in 0..count {
+                remainder[i] ^= self.mul(generator[i + 1], factor);
+            }
+        }
+        remainder
+    }
+}
@@ -0,0 +1,228 @@
+use crate::gs1::GS;
+use crate::symbol::{Symbol, Symbology};
+use anyhow::Result;
+
+pub struct BarcodeOptions {
+    /// Bar height in modules, None for the symbology's usual proportions
+    pub height: Option<usize>,
+    /// Light modules left and right of the bars, None for the symbology's minimum
+    pub quiet_zone: Option<usize>,
+}
+
+/// Bar and space widths of the 107 Code 128 symbols, starting with a bar; 106 is the stop
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213", "221312",
+    "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132", "221231", "213212",
+    "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211", "212123", "212321", "232121",
+    "111323", "131123", "131321", "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331",
+    "132131", "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131", "311123",
+    "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111", "111224", "111422", "121124",
+    "121421", "141122", "141221", "112214", "112412", "122114", "122411", "142112", "142211", "241211", "221114",
+    "413111", "241112", "134111", "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112",
+    "421211", "212141", "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const START_A: usize = 103;
+const START_B: usize = 104;
+const START_C: usize = 105;
+const STOP: usize = 106;
+
+/// Code set switches, the same values in every set that has them
+const CODE_A: usize = 101;
+const CODE_B: usize = 100;
+const CODE_C: usize = 99;
+const FNC1: usize = 102;
+
+/// EAN-13 left-hand digits with odd parity (set A); set C inverts them and set B mirrors set C
+const EAN_SET_A: [&str; 10] = [
+    "0001101", "0011001", "0010011", "0111101", "0100011", "0110001", "0101111", "0111011", "0110111", "0001011",
+];
+
+/// Which of the six left-hand digits use set B, keyed by the leading digit
+const EAN_PARITY: [&str; 10] = [
+    "AAAAAA", "AABABB", "AABBAB", "AABBBA", "ABAABB", "ABBAAB", "ABBBAA", "ABABAB", "ABABBA", "ABBABA",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum CodeSet {
+    A,
+    B,
+    C,
+}
+
+/// Encodes ASCII as Code 128, switching to set C for runs of digits and set A for control
+/// characters, with a quiet zone of 10 modules by default
+pub fn encode_code128(data: &[u8], options: &BarcodeOptions) -> Result<Symbol> {
+    code128(data, false, options)
+}
+
+/// GS1-128: Code 128 starting with FNC1, where every GS separator is written as FNC1 too
+pub fn encode_gs1_128(data: &[u8], options: &BarcodeOptions) -> Result<Symbol> {
+    code128(data, true, options)
+}
+
+fn code128(data: &[u8], gs1: bool, options: &BarcodeOptions) -> Result<Symbol> {
+    if data.is_empty() {
+        anyhow::bail!("Code 128 needs at least one character");
+    }
+    if let Some(&byte) = data.iter().find(|b| !b.is_ascii()) {
+        anyhow::bail!("Code 128 only holds ASCII, found byte 0x{:02x}", byte);
+    }
+
+    let digits_at = |i: usize| data[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let text_set = |byte: u8| if byte < 32 { CodeSet::A } else { CodeSet::B };
+
+    let mut set = if digits_at(0) >= 4 || (digits_at(0) == data.len() && data.len().is_multiple_of(2)) {
+        CodeSet::C
+    } else {
+        text_set(data[0])
+    };
+    let mut values = vec![match set {
+        CodeSet::A => START_A,
+        CodeSet::B => START_B,
+        CodeSet::C => START_C,
+    }];
+    if gs1 {
+        values.push(FNC1);
+    }
+
+    let mut i = 0;
+    while i < data.len() {
+        // FNC1 exists in every code set
+        if gs1 && data[i] == GS {
+            values.push(FNC1);
+            i += 1;
+            continue;
+        }
+        let digits = digits_at(i);
+        if set == CodeSet::C {
+            if digits >= 2 {
+                values.push(((data[i] - b'0') * 10 + data[i + 1] - b'0') as usize);
+                i += 2;
+                continue;
+            }
+            set = text_set(data[i]);
+            values.push(if set == CodeSet::A { CODE_A } else { CODE_B });
+        }
+
+        // Four or more digits are worth a switch; an odd run keeps its first digit in this set
+        if digits >= 4 && digits.is_multiple_of(2) {
+            set = CodeSet::C;
+            values.push(CODE_C);
+            continue;
+        }
+
+        let byte = data[i];
+        let needed = match byte {
+            0..=31 => Some(CodeSet::A),
+            96..=127 => Some(CodeSet::B),
+            _ => None,
+        };
+        if let Some(needed) = needed.filter(|&needed| needed != set) {
+            set = needed;
+            values.push(if set == CodeSet::A { CODE_A } else { CODE_B });
+        }
+        values.push(match (set, byte) {
+            (CodeSet::A, 0..=31) => byte as usize + 64,
+            _ => byte as usize - 32,
+        });
+        i += 1;
+    }
+
+    let checksum = values.iter().enumerate().map(|(i, &v)| v * i.max(1)).sum::<usize>() % 103;
+    values.push(checksum);
+    values.push(STOP);
+
+    let mut bars = Vec::new();
+    for value in values {
+        for (i, width) in CODE128_PATTERNS[value].bytes().enumerate() {
+            bars.extend(std::iter::repeat_n(i % 2 == 0, (width - b'0') as usize));
+        }
+    }
+
+    let quiet = options.quiet_zone.unwrap_or(10);
+    let height = options.height.unwrap_or((bars.len() + 2 * quiet) / 4);
+    Ok(Symbol::from_bars(&with_quiet_zone(&bars, quiet, quiet), height, Symbology::Code128))
+}
+
+/// Encodes 12 digits, or 13 with a valid check digit, as EAN-13
+pub fn encode_ean13(data: &str, options: &BarcodeOptions) -> Result<Symbol> {
+    let digits: Vec<usize> = data
+        .trim()
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as usize))
+        .collect::<Option<_>>()
+        .ok_or_else(|| anyhow::anyhow!("EAN-13 holds digits only, got '{}'", data))?;
+    let check = match digits.len() {
+        12 | 13 => ean_check_digit(&digits[..12]),
+        n => anyhow::bail!("EAN-13 needs 12 digits (or 13 with the check digit), got {}", n),
+    };
+    if digits.len() == 13 && digits[12] != check {
+        anyhow::bail!("EAN-13 check digit should be {}, not {}", check, digits[12]);
+    }
+
+    // The leading digit is not drawn, it picks the parity of the next six
+    let mut bars = bits("101");
+    for (i, parity) in EAN_PARITY[digits[0]].chars().enumerate() {
+        let pattern = bits(EAN_SET_A[digits[i + 1]]);
+        bars.extend(match parity {
+            'A' => pattern,
+            _ => pattern.iter().rev().map(|&bit| !bit).collect(),
+        });
+    }
+    bars.extend(bits("01010"));
+    for &digit in digits[7..12].iter().chain([&check]) {
+        bars.extend(bits(EAN_SET_A[digit]).iter().map(|&bit| !bit));
+    }
+    bars.extend(bits("101"));
+
+    // 69 modules is the nominal height at 100% magnification
+    let (left, right) = options.quiet_zone.map_or((11, 7), |quiet| (quiet, quiet));
+    Ok(Symbol::from_bars(&with_quiet_zone(&bars, left, right), options.height.unwrap_or(69), Symbology::Ean13))
+}
+
+/// Weights 1 and 3 alternating from the left, the check digit rounds the sum up to a multiple of 10
+fn ean_check_digit(digits: &[usize]) -> usize {
+    let sum: usize = digits.iter().enumerate().map(|(i, &d)| if i % 2 == 0 { d } else { d * 3 }).sum();
+    (10 - sum % 10) % 10
+}
+
+fn bits(pattern: &str) -> Vec<bool> {
+    pattern.bytes().map(|b| b == b'1').collect()
+}
+
+fn with_quiet_zone(bars: &[bool], left: usize, right: usize) -> Vec<bool> {
+    let mut row = vec![false; left];
+    row.extend_from_slice(bars);
+    row.resize(row.len() + right, false);
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: BarcodeOptions = BarcodeOptions { height: None, quiet_zone: None };
+
+    #[test]
+    fn ean_check_digits() {
+        let digits = |text: &str| text.bytes().map(|b| (b - b'0') as usize).collect::<Vec<_>>();
+        assert_eq!(ean_check_digit(&digits("400638133393")), 1);
+        assert_eq!(ean_check_digit(&digits("590123412345")), 7);
+        assert_eq!(ean_check_digit(&digits("000000000000")), 0);
+    }
+
+    #[test]
+    fn ean13_appends_or_checks_the_check_digit() {
+        let appended = encode_ean13("400638133393", &OPTIONS).unwrap();
+        let given = encode_ean13("4006381333931", &OPTIONS).unwrap();
+        // 95 modules between quiet zones of 11 and 7
+        assert_eq!((appended.width(), given.width()), (113, 113));
+        assert!((0..113).all(|x| appended.is_dark(x, 0) == given.is_dark(x, 0)));
+
+        assert!(encode_ean13("4006381333932", &OPTIONS).is_err());
+        assert!(encode_ean13("40063813339", &OPTIONS).is_err());
+        assert!(encode_ean13("40063813339x", &OPTIONS).is_err());
+    }
+}
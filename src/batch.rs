@@ -0,0 +1,284 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::template;
+
+pub struct BatchRow {
+    /// 1-based row number, or the number of a `--sequence` row, used for default file names and error messages
+    pub index: usize,
+    pub data: String,
+    pub output: Option<PathBuf>,
+    pub caption: Option<String>,
+    /// Overrides `--symbology` for this row, so one sheet can mix QR codes and barcodes
+    pub symbology: Option<String>,
+}
+
+/// Where `read_batch` finds the values of each row
+pub struct BatchOptions<'a> {
+    /// `--data` with `{col:NAME}` placeholders, composing the payload from the row
+    pub data_template: Option<&'a str>,
+    /// Worksheet of a spreadsheet, the first one when None
+    pub worksheet: Option<&'a str>,
+    pub data_column: &'a str,
+    pub output_column: &'a str,
+    pub caption_column: &'a str,
+}
+
+/// Reads a CSV file or a spreadsheet (xlsx, xlsm, xls, ods) with a header row.
+/// The data column holds the payload, an optional output column names the file
+/// for that row, an optional caption column labels it on sheets and an optional
+/// `symbology` column picks the code type per row. With a `data_template` the
+/// payload is composed from the row instead.
+pub fn read_batch(path: &Path, options: &BatchOptions) -> Result<Vec<BatchRow>> {
+    let (headers, records) = if is_spreadsheet(path) {
+        read_spreadsheet(path, options.worksheet)?
+    } else if options.worksheet.is_some() {
+        anyhow::bail!("--worksheet picks a sheet of a spreadsheet, {} is read as CSV", path.display());
+    } else {
+        read_csv(path)?
+    };
+
+    batch_rows(&headers, &records, options, &format!("Batch file {}", path.display()))
+}
+
+/// Runs `query` against a database, one code per result row; columns are picked by name like
+/// the columns of a batch file. Only SQLite databases are supported, given as sqlite://PATH.
+#[cfg(feature = "sql")]
+pub fn read_sql(url: &str, query: &str, options: &BatchOptions) -> Result<Vec<BatchRow>> {
+    use rusqlite::types::ValueRef;
+    use rusqlite::{Connection, OpenFlags};
+
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+        .with_context(|| format!("--batch-sql needs a sqlite://PATH database, got '{}'", url))?;
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+        .with_context(|| format!("Failed to open database {}", path))?;
+    let mut statement = conn.prepare(query).with_context(|| format!("Failed to prepare --batch-query on {}", path))?;
+    let headers: Vec<String> = statement.column_names().into_iter().map(str::to_string).collect();
+
+    let mut records = Vec::new();
+    let mut result = statement.query([]).context("Failed to run --batch-query")?;
+    while let Some(row) = result.next().context("Failed to read a --batch-query row")? {
+        let record = (0..headers.len())
+            .map(|i| {
+                Ok(match row.get_ref(i)? {
+                    ValueRef::Null => String::new(),
+                    ValueRef::Integer(value) => value.to_string(),
+                    ValueRef::Real(value) => value.to_string(),
+                    ValueRef::Text(text) | ValueRef::Blob(text) => String::from_utf8(text.to_vec())
+                        .with_context(|| format!("Column '{}' of row {} is not UTF-8 text", headers[i], records.len() + 1))?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        records.push(record);
+    }
+    batch_rows(&headers, &records, options, "The query")
+}
+
+#[cfg(not(feature = "sql"))]
+pub fn read_sql(_url: &str, _query: &str, _options: &BatchOptions) -> Result<Vec<BatchRow>> {
+    anyhow::bail!("This build reads no databases, --batch-sql needs the sql feature")
+}
+
+/// Turns a header and its records into rows, reading the columns `options` names
+fn batch_rows(headers: &[String], records: &[Vec<String>], options: &BatchOptions, source: &str) -> Result<Vec<BatchRow>> {
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let data_col = column(options.data_column);
+    if data_col.is_none() && options.data_template.is_none() {
+        anyhow::bail!(
+            "{} needs a '{}' column, or --data with {{col:NAME}} placeholders (columns: {})",
+            source,
+            options.data_column,
+            headers.join(", ")
+        );
+    }
+    let output_col = column(options.output_column);
+    let caption_col = column(options.caption_column);
+    let symbology_col = column("symbology");
+
+    let mut rows = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        let field = |col: Option<usize>| col.and_then(|c| record.get(c)).map(String::as_str).filter(|value| !value.is_empty());
+        let data = match options.data_template {
+            Some(template) => {
+                let fields: Vec<(&str, &str)> = headers.iter().map(String::as_str).zip(record.iter().map(String::as_str)).collect();
+                template::expand_data(template, Some(&fields)).with_context(|| format!("Batch row {} failed", i + 1))?
+            }
+            None => field(data_col).with_context(|| format!("Batch row {} has no data", i + 1))?.to_string(),
+        };
+
+        rows.push(BatchRow {
+            index: i + 1,
+            data,
+            output: field(output_col).map(PathBuf::from),
+            caption: field(caption_col).map(str::to_string),
+            symbology: field(symbology_col).map(str::to_string),
+        });
+    }
+
+    if rows.is_empty() {
+        anyhow::bail!("{} has no rows", source);
+    }
+    Ok(rows)
+}
+
+fn is_spreadsheet(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ["xlsx", "xlsm", "xlsb", "xls", "ods"].iter().any(|known| ext.eq_ignore_ascii_case(known)))
+}
+
+/// The header and the records of a CSV file, trimmed
+fn read_csv(path: &Path) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .with_context(|| format!("Failed to open batch file {}", path.display()))?;
+
+    let headers = reader.headers().context("Failed to read batch header")?.iter().map(str::to_string).collect();
+    let records = reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record.with_context(|| format!("Failed to read batch row {}", i + 1))?;
+            Ok(record.iter().map(str::to_string).collect())
+        })
+        .collect::<Result<_>>()?;
+    Ok((headers, records))
+}
+
+/// The header and the records of one worksheet, with blank lines left out. Whole numbers read
+/// as integers and dates as YYYY-MM-DD, the way they show in the spreadsheet.
+#[cfg(feature = "xlsx")]
+fn read_spreadsheet(path: &Path, worksheet: Option<&str>) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    use calamine::{Data, Reader};
+
+    let mut workbook = calamine::open_workbook_auto(path).with_context(|| format!("Failed to open batch file {}", path.display()))?;
+    let names = workbook.sheet_names();
+    let name = match worksheet {
+        Some(name) => names
+            .iter()
+            .find(|sheet| sheet.eq_ignore_ascii_case(name))
+            .with_context(|| format!("{} has no worksheet '{}', it has {}", path.display(), name, names.join(", ")))?,
+        None => names.first().with_context(|| format!("{} has no worksheets", path.display()))?,
+    };
+    let range = workbook
+        .worksheet_range(name)
+        .with_context(|| format!("Failed to read worksheet '{}' of {}", name, path.display()))?;
+
+    let cell = |data: &Data| match data {
+        Data::Float(value) if value.fract() == 0.0 && value.abs() < 1e15 => format!("{}", *value as i64),
+        Data::DateTime(value) => match value.as_datetime() {
+            Some(time) if time.time() == chrono::NaiveTime::MIN => time.format("%Y-%m-%d").to_string(),
+            Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => value.to_string(),
+        },
+        other => other.to_string().trim().to_string(),
+    };
+    let mut lines = range.rows().map(|row| row.iter().map(cell).collect::<Vec<_>>()).filter(|row| row.iter().any(|value| !value.is_empty()));
+    let headers = lines.next().with_context(|| format!("Worksheet '{}' of {} is empty", name, path.display()))?;
+    Ok((headers, lines.collect()))
+}
+
+#[cfg(not(feature = "xlsx"))]
+fn read_spreadsheet(path: &Path, _worksheet: Option<&str>) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    anyhow::bail!("This build reads no spreadsheets, {} needs the xlsx feature (or export it as CSV)", path.display())
+}
+
+/// Rows for `--sequence START..END`, counting up by `step` with both ends included. Every `{n}` in
+/// the template becomes the number, `{n:06}` zero-pads it to six digits. The number also names
+/// each file, as qrcode-42.png, or ticket-42.png when `output` is ticket.png.
+pub fn sequence_rows(range: &str, template: &str, step: u64, output: Option<&Path>, format: &str) -> Result<Vec<BatchRow>> {
+    let (start, end) = range
+        .split_once("..=")
+        .or_else(|| range.split_once(".."))
+        .with_context(|| format!("--sequence must be START..END, e.g. 1..5000, got '{}'", range))?;
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<u64>()
+            .with_context(|| format!("--sequence bounds must be whole numbers, got '{}'", value))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start > end {
+        anyhow::bail!("--sequence starts at {} but ends before it at {}", start, end);
+    }
+    if step == 0 {
+        anyhow::bail!("--step must be at least 1");
+    }
+    if output == Some(Path::new("-")) {
+        anyhow::bail!("--sequence makes one file per number and cannot write them to stdout, give --output a file name such as ticket.png");
+    }
+    if !template.contains("{n") {
+        anyhow::bail!("--template needs an {{n}} placeholder for the number, e.g. \"https://example.com/ticket/{{n:06}}\"");
+    }
+
+    (start..=end)
+        .step_by(usize::try_from(step).unwrap_or(usize::MAX))
+        .map(|n| {
+            let data = template::expand(template, |name, spec| match name {
+                "n" => template::format_number(n, spec).map(Some),
+                other => anyhow::bail!("Unknown placeholder {{{}}} in --template, sequences only have {{n}}", other),
+            })?;
+            Ok(BatchRow {
+                index: usize::try_from(n).unwrap_or(usize::MAX),
+                data,
+                output: output.map(|path| numbered_path(path, n, format)),
+                caption: None,
+                symbology: None,
+            })
+        })
+        .collect()
+}
+
+/// `path` with the number added to its stem, as out/ticket-42.png; the format names a missing extension
+fn numbered_path(path: &Path, n: u64, format: &str) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let extension = path.extension().map_or_else(|| format.into(), |extension| extension.to_string_lossy());
+    path.with_file_name(format!("{}-{}.{}", stem, n, extension))
+}
+
+/// Rows for `--generate`: `count` fresh identifiers (uuid v4 or ulid), each encoded on its own and
+/// saved as `<id>.<format>`, or to `output` when there is only one. The identifier is also the
+/// row's caption, so sheets print it under each code.
+pub fn generated_rows(kind: &str, count: usize, format: &str, output: Option<&Path>) -> Result<Vec<BatchRow>> {
+    if count == 0 {
+        anyhow::bail!("--count must be at least 1");
+    }
+    (1..=count)
+        .map(|index| {
+            let id = new_id(kind)?;
+            let output = match output {
+                Some(path) if count == 1 => path.to_path_buf(),
+                _ => PathBuf::from(format!("{}.{}", id, format)),
+            };
+            Ok(BatchRow {
+                index,
+                data: id.clone(),
+                output: Some(output),
+                caption: Some(id),
+                symbology: None,
+            })
+        })
+        .collect()
+}
+
+/// A fresh uuid v4 or ulid
+#[cfg(feature = "native")]
+fn new_id(kind: &str) -> Result<String> {
+    match kind {
+        "uuid" => Ok(uuid::Uuid::new_v4().to_string()),
+        // The 80 random bits come from the same source as UUIDs
+        "ulid" => {
+            let millis = u64::try_from(chrono::Utc::now().timestamp_millis()).unwrap_or_default();
+            Ok(ulid::Ulid::from_parts(millis, uuid::Uuid::new_v4().as_u128()).to_string())
+        }
+        other => anyhow::bail!("Unknown identifier '{}'. Use uuid or ulid", other),
+    }
+}
+
+#[cfg(not(feature = "native"))]
+fn new_id(_kind: &str) -> Result<String> {
+    anyhow::bail!("This build has no identifier generator, --generate needs the native feature")
+}
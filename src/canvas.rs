@@ -0,0 +1,185 @@
+use crate::{Args, parse_color};
+use anyhow::Result;
+use image::{Rgba, RgbaImage, imageops};
+
+/// The card the finished output sits on: padding around it and rounded corners
+pub struct Canvas {
+    pub padding: u32,
+    pub radius: u32,
+    pub color: Rgba<u8>,
+}
+
+impl Canvas {
+    /// The card from --canvas-radius, --canvas-padding and --canvas-color, when either size is set
+    pub fn from_args(args: &Args) -> Result<Option<Self>> {
+        if args.canvas_radius == 0 && args.canvas_padding == 0 {
+            return Ok(None);
+        }
+        let color = parse_color(args.canvas_color.as_deref().unwrap_or(&args.bg_color))?;
+        Ok(Some(Canvas { padding: args.canvas_padding, radius: args.canvas_radius, color }))
+    }
+
+    /// Pads the image with the canvas color, then clears everything outside the rounded corners
+    pub fn apply(&self, img: &RgbaImage) -> RgbaImage {
+        let (width, height) = (img.width() + 2 * self.padding, img.height() + 2 * self.padding);
+        let mut card = RgbaImage::from_pixel(width, height, self.color);
+        imageops::overlay(&mut card, img, self.padding as i64, self.padding as i64);
+
+        let radius = self.radius.min(width / 2).min(height / 2);
+        if radius == 0 {
+            return card;
+        }
+        let r = radius as f32;
+        for y in 0..height {
+            for x in 0..width {
+                // Distance into the corner square, measured from the arc's centre
+                let cx = if x < radius { r - x as f32 } else if x >= width - radius { (x - (width - radius)) as f32 + 1.0 } else { continue };
+                let cy = if y < radius { r - y as f32 } else if y >= height - radius { (y - (height - radius)) as f32 + 1.0 } else { continue };
+                let coverage = corner_coverage(cx, cy, r);
+                let pixel = card.get_pixel_mut(x, y);
+                pixel[3] = (pixel[3] as f32 * coverage).round() as u8;
+            }
+        }
+        card
+    }
+
+    /// How far the corner arc cuts in along the diagonal, in pixels
+    pub fn corner_inset(&self) -> f32 {
+        self.radius as f32 * (1.0 - std::f32::consts::FRAC_1_SQRT_2)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BorderStyle {
+    Solid,
+    /// Dashes and gaps twice as long as the line is wide
+    Dashed,
+    /// Two lines a third of the width each, with a gap of the same size
+    Double,
+}
+
+/// `--outer-border`: a decorative line around the finished output, outside the quiet zone
+pub struct OuterBorder {
+    pub style: BorderStyle,
+    pub width: u32,
+    pub color: Rgba<u8>,
+    /// Shows through the gaps of dashed and double lines
+    pub background: Rgba<u8>,
+}
+
+impl OuterBorder {
+    pub fn from_args(args: &Args) -> Result<Option<Self>> {
+        let Some(ref style) = args.outer_border else {
+            return Ok(None);
+        };
+        let style = match style.to_lowercase().as_str() {
+            "solid" => BorderStyle::Solid,
+            "dashed" => BorderStyle::Dashed,
+            "double" => BorderStyle::Double,
+            other => anyhow::bail!("Unknown outer border '{}'. Use solid, dashed or double", other),
+        };
+        if args.outer_border_width == 0 || (style == BorderStyle::Double && args.outer_border_width < 3) {
+            anyhow::bail!("--outer-border-width must be at least 1 pixel, or 3 for double borders");
+        }
+        Ok(Some(OuterBorder {
+            style,
+            width: args.outer_border_width,
+            color: parse_color(args.outer_border_color.as_deref().unwrap_or(&args.fg_color))?,
+            background: parse_color(&args.bg_color)?,
+        }))
+    }
+
+    /// Whether the pixel `depth` pixels in from the outer edge and `along` pixels around the border
+    /// is inked
+    fn inked(&self, depth: u32, along: f32) -> bool {
+        match self.style {
+            BorderStyle::Solid => true,
+            BorderStyle::Dashed => ((along / (2 * self.width) as f32) as u32).is_multiple_of(2),
+            BorderStyle::Double => {
+                let line = self.width / 3;
+                depth < line || depth >= self.width - line
+            }
+        }
+    }
+
+    /// Surrounds the image with the border
+    pub fn apply(&self, img: &RgbaImage) -> RgbaImage {
+        let w = self.width;
+        let (width, height) = (img.width() + 2 * w, img.height() + 2 * w);
+        // The line's centre runs half a width in; dashes are laid out along it, clockwise from the top left
+        let (run_x, run_y) = ((width - w) as f32, (height - w) as f32);
+        let half = w as f32 / 2.0;
+        let mut out = RgbaImage::from_fn(width, height, |x, y| {
+            let (fx, fy) = (x as f32 + 0.5 - half, y as f32 + 0.5 - half);
+            let (depth, along) = if y < w && y <= x.min(width - 1 - x) {
+                (y, fx)
+            } else if height - 1 - y < w && height - 1 - y <= x.min(width - 1 - x) {
+                (height - 1 - y, 2.0 * run_x + run_y - fx)
+            } else if x < w {
+                (x, 2.0 * (run_x + run_y) - fy)
+            } else if width - 1 - x < w {
+                (width - 1 - x, run_x + fy)
+            } else {
+                return self.background;
+            };
+            if self.inked(depth, along.max(0.0)) { self.color } else { self.background }
+        });
+        imageops::overlay(&mut out, img, w as i64, w as i64);
+        out
+    }
+
+    /// SVG shapes drawing the border around a `width` x `height` area that starts at the origin,
+    /// `w` units wide
+    pub fn svg(&self, width: f32, height: f32, w: f32) -> String {
+        let rect = |inset: f32, stroke: f32, extra: &str| {
+            format!(
+                "  <rect x=\"{inset}\" y=\"{inset}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{stroke}\"{extra}/>\n",
+                width - 2.0 * inset,
+                height - 2.0 * inset,
+                crate::fill::svg_color(self.color),
+            )
+        };
+        match self.style {
+            BorderStyle::Solid => rect(w / 2.0, w, ""),
+            BorderStyle::Dashed => rect(w / 2.0, w, &format!(" stroke-dasharray=\"{} {}\"", 2.0 * w, 2.0 * w)),
+            BorderStyle::Double => {
+                let line = w / 3.0;
+                format!("{}{}", rect(line / 2.0, line, ""), rect(w - line / 2.0, line, ""))
+            }
+        }
+    }
+}
+
+/// Clears everything outside the circle inscribed in the top-left `side` x `side` square, for
+/// `--shape circle`
+pub fn clip_to_circle(img: &mut RgbaImage, side: u32) {
+    let r = side as f32 / 2.0;
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let distance = (x as f32 + 0.5 - r).hypot(y as f32 + 0.5 - r);
+        let coverage = if distance <= r - 1.0 {
+            continue;
+        } else if distance >= r + 1.0 {
+            0.0
+        } else {
+            // The edge pixels, sampled 4x4 like the card corners
+            let (cx, cy) = ((x as f32 - r).abs().max((x as f32 + 1.0 - r).abs()), (y as f32 - r).abs().max((y as f32 + 1.0 - r).abs()));
+            corner_coverage(cx, cy, r)
+        };
+        pixel[3] = (pixel[3] as f32 * coverage).round() as u8;
+    }
+}
+
+/// Share of a corner pixel inside the arc, sampled 4x4; (cx, cy) is the pixel's outer edge, the
+/// inner edge lies one pixel closer to the arc's centre
+fn corner_coverage(cx: f32, cy: f32, r: f32) -> f32 {
+    let mut inside = 0;
+    for sy in 0..4 {
+        for sx in 0..4 {
+            let (px, py) = (cx - (sx as f32 + 0.5) / 4.0, cy - (sy as f32 + 0.5) / 4.0);
+            if px.hypot(py) <= r {
+                inside += 1;
+            }
+        }
+    }
+    inside as f32 / 16.0
+}
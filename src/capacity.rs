@@ -0,0 +1,118 @@
+use qrcode::bits::Bits;
+use qrcode::{EcLevel, Version};
+use std::ops::RangeInclusive;
+
+/// Encoding mode of a single-segment payload
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Numeric,
+    Alphanumeric,
+    Byte,
+}
+
+impl Mode {
+    /// The densest mode that can hold every character of the data
+    pub fn detect(data: &[u8]) -> Self {
+        if data.iter().all(u8::is_ascii_digit) {
+            Mode::Numeric
+        } else if data.iter().all(|b| b.is_ascii_digit() || b.is_ascii_uppercase() || b" $%*+-./:".contains(b)) {
+            Mode::Alphanumeric
+        } else {
+            Mode::Byte
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Mode::Numeric => "digits",
+            Mode::Alphanumeric => "characters",
+            Mode::Byte => "bytes",
+        }
+    }
+
+    fn count_bits(&self, version: i16) -> usize {
+        let tier = match version {
+            1..=9 => 0,
+            10..=26 => 1,
+            _ => 2,
+        };
+        match self {
+            Mode::Numeric => [10, 12, 14][tier],
+            Mode::Alphanumeric => [9, 11, 13][tier],
+            Mode::Byte => [8, 16, 16][tier],
+        }
+    }
+}
+
+/// How many characters of the given mode fit in one segment at this version and level
+pub fn max_chars(version: i16, ec_level: EcLevel, mode: Mode) -> usize {
+    let data_bits = Bits::new(Version::Normal(version)).max_len(ec_level).unwrap_or(0);
+    let bits = data_bits.saturating_sub(4 + mode.count_bits(version));
+    match mode {
+        Mode::Numeric => bits / 10 * 3 + [0, 0, 0, 0, 1, 1, 1, 2, 2, 2][bits % 10],
+        Mode::Alphanumeric => bits / 11 * 2 + usize::from(bits % 11 >= 6),
+        Mode::Byte => bits / 8,
+    }
+}
+
+/// Explains a payload that does not fit in `version`, naming the limit at the chosen level
+pub fn too_long_error(data: &[u8], mode: Mode, ec_level: EcLevel, version: i16) -> anyhow::Error {
+    let mut message = format!(
+        "Payload is {} {}, but version {} holds at most {} {} at error correction level {:?}",
+        data.len(),
+        mode.unit(),
+        version,
+        max_chars(version, ec_level, mode),
+        mode.unit(),
+        ec_level
+    );
+
+    if ec_level != EcLevel::L {
+        message.push_str(&format!(" ({} at level L)", max_chars(version, EcLevel::L, mode)));
+    }
+    if let Some(needed) = (version + 1..=40).find(|&v| data.len() <= max_chars(v, ec_level, mode)) {
+        message.push_str(&format!("; it needs version {}", needed));
+    }
+    anyhow::anyhow!(message)
+}
+
+/// How a payload sits in one version at one level
+pub struct Fit {
+    pub version: i16,
+    pub used_bits: usize,
+    pub capacity_bits: usize,
+}
+
+impl Fit {
+    pub fn modules(&self) -> i16 {
+        self.version * 4 + 17
+    }
+
+    pub fn headroom_bits(&self) -> usize {
+        self.capacity_bits - self.used_bits
+    }
+}
+
+/// Encodes the payload the way the generator does (optimal segments, or one byte segment
+/// for raw binary data) and returns `None` when it does not fit
+fn fit(data: &[u8], binary: bool, version: i16, ec_level: EcLevel) -> Option<Fit> {
+    let mut bits = Bits::new(Version::Normal(version));
+    let pushed = if binary {
+        bits.push_byte_data(data)
+    } else {
+        bits.push_optimal_data(data)
+    };
+    let capacity_bits = bits.max_len(ec_level).ok()?;
+    if pushed.is_err() || bits.len() > capacity_bits {
+        return None;
+    }
+    Some(Fit {
+        version,
+        used_bits: bits.len(),
+        capacity_bits,
+    })
+}
+
+pub fn smallest_fit(data: &[u8], binary: bool, ec_level: EcLevel, versions: RangeInclusive<i16>) -> Option<Fit> {
+    versions.into_iter().find_map(|version| fit(data, binary, version, ec_level))
+}
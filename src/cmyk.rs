@@ -0,0 +1,47 @@
+use anyhow::Result;
+use image::RgbImage;
+
+/// Rich black used when `--rich-black` is given without a value (C60 M40 Y40 K100)
+pub const DEFAULT_RICH_BLACK: &str = "60,40,40,100";
+
+/// Parses a `C,M,Y,K` mix in percent into 8-bit ink values
+pub fn parse_rich_black(spec: &str) -> Result<[u8; 4]> {
+    let inks: Vec<f32> = spec
+        .split(',')
+        .map(|part| part.trim().trim_end_matches('%').parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| anyhow::anyhow!("Rich black must be four percentages like 60,40,40,100, got '{}'", spec))?;
+    if inks.len() != 4 || inks.iter().any(|ink| !(0.0..=100.0).contains(ink)) {
+        anyhow::bail!("Rich black must be four percentages like 60,40,40,100, got '{}'", spec);
+    }
+    let total: f32 = inks.iter().sum();
+    if total > 320.0 {
+        log::warn!("⚠ Rich black {} has {:.0}% total ink, most presses want at most 300%", spec, total);
+    }
+    Ok([0, 1, 2, 3].map(|i| (inks[i] * 2.55).round() as u8))
+}
+
+/// Converts to interleaved 8-bit CMYK with the naive device formula (no color management).
+/// With a rich black, neutral pixels get that mix scaled by their darkness instead of plain K,
+/// so anti-aliased module edges blend into the rich black too.
+pub fn to_cmyk(img: &RgbImage, rich_black: Option<[u8; 4]>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(img.as_raw().len() / 3 * 4);
+    for pixel in img.pixels() {
+        let [r, g, b] = pixel.0;
+        let max = r.max(g).max(b);
+
+        if let Some(rich) = rich_black.filter(|_| r == g && g == b) {
+            let darkness = (255 - max) as u32;
+            out.extend(rich.map(|ink| (ink as u32 * darkness / 255) as u8));
+            continue;
+        }
+
+        if max == 0 {
+            out.extend_from_slice(&[0, 0, 0, 255]);
+            continue;
+        }
+        let ink = |c: u8| ((max - c) as u32 * 255 / max as u32) as u8;
+        out.extend_from_slice(&[ink(r), ink(g), ink(b), 255 - max]);
+    }
+    out
+}
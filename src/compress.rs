@@ -0,0 +1,94 @@
+use anyhow::Result;
+use base64::{Engine as _, engine::general_purpose};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::io::{Read, Write};
+
+/// Marks deflated payloads (zlib stream, as in EU DCC)
+const DEFLATE_PREFIX: &str = "RQZ1:";
+/// Marks brotli payloads
+const BROTLI_PREFIX: &str = "RQB1:";
+
+#[derive(Debug, Clone, Copy)]
+pub enum Compressor {
+    Deflate,
+    Brotli,
+}
+
+impl Compressor {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "deflate" | "zlib" => Some(Compressor::Deflate),
+            "brotli" => Some(Compressor::Brotli),
+            _ => None,
+        }
+    }
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            Compressor::Deflate => DEFLATE_PREFIX,
+            Compressor::Brotli => BROTLI_PREFIX,
+        }
+    }
+}
+
+/// Compresses the data and returns `<prefix><base45>`, or base64 after the prefix when `base64` is set.
+/// Base45 keeps the QR code in alphanumeric mode, which usually beats byte mode with base64.
+pub fn compress(data: &str, compressor: Compressor, base64: bool) -> Result<String> {
+    let bytes = match compressor {
+        Compressor::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(data.as_bytes())?;
+            encoder.finish()?
+        }
+        Compressor::Brotli => {
+            let mut output = Vec::new();
+            let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, 11, 22);
+            encoder.write_all(data.as_bytes())?;
+            drop(encoder);
+            output
+        }
+    };
+
+    let body = if base64 {
+        general_purpose::STANDARD.encode(&bytes)
+    } else {
+        base45::encode(&bytes)
+    };
+    Ok(format!("{}{}", compressor.prefix(), body))
+}
+
+/// Reverses `compress`, accepting either text encoding
+pub fn decompress(payload: &str) -> Result<String> {
+    let payload = payload.trim();
+    let (compressor, body) = [Compressor::Deflate, Compressor::Brotli]
+        .into_iter()
+        .find_map(|c| payload.strip_prefix(c.prefix()).map(|body| (c, body)))
+        .ok_or_else(|| anyhow::anyhow!("The code does not hold a compressed payload"))?;
+
+    // A body can be valid in both alphabets, so take whichever one inflates
+    let candidates = [
+        base45::decode(body).ok(),
+        general_purpose::STANDARD.decode(body).ok(),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .find_map(|bytes| inflate(&bytes, compressor).ok())
+        .ok_or_else(|| anyhow::anyhow!("Compressed payload is corrupt"))
+}
+
+pub fn is_compressed(payload: &str) -> bool {
+    let payload = payload.trim();
+    payload.starts_with(DEFLATE_PREFIX) || payload.starts_with(BROTLI_PREFIX)
+}
+
+fn inflate(bytes: &[u8], compressor: Compressor) -> Result<String> {
+    let mut output = String::new();
+    match compressor {
+        Compressor::Deflate => ZlibDecoder::new(bytes).read_to_string(&mut output)?,
+        Compressor::Brotli => brotli::Decompressor::new(bytes, 4096).read_to_string(&mut output)?,
+    };
+    Ok(output)
+}
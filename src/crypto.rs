@@ -0,0 +1,122 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Result;
+use argon2::Argon2;
+#[cfg(feature = "cli")]
+use dialoguer::Password;
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, IsTerminal};
+
+/// Marks passphrase-encrypted payloads; everything after it is base45
+const ENCRYPTED_PREFIX: &str = "RQE1:";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Encrypts with AES-256-GCM under an Argon2id key and returns `RQE1:<base45>`.
+/// Base45 keeps the payload in the QR alphanumeric mode, which is denser than base64.
+///
+/// With a seed the salt comes from the seed alone and the nonce from the derived key and the
+/// data, so identical input gives identical output. The salt is public and anyone who knows the
+/// seed can compute it, so codes sharing a seed share it too and one guessing run covers them all;
+/// the nonce needs the key, and only repeats together with the same key and plaintext.
+pub fn encrypt(data: &str, passphrase: &str, seed: Option<u64>) -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    let key = match seed {
+        Some(seed) => {
+            let digest = Sha256::new().chain_update(b"RQE1 salt").chain_update(seed.to_be_bytes()).finalize();
+            salt.copy_from_slice(&digest[..SALT_LEN]);
+            let key = derive_key(passphrase, &salt)?;
+            let digest = Sha256::new().chain_update(b"RQE1 nonce").chain_update(key).chain_update(data).finalize();
+            nonce.copy_from_slice(&digest[..NONCE_LEN]);
+            key
+        }
+        None => {
+            OsRng.fill_bytes(&mut salt);
+            OsRng.fill_bytes(&mut nonce);
+            derive_key(passphrase, &salt)?
+        }
+    };
+
+    let cipher = Aes256Gcm::new(&key.into());
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), data.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    let mut blob = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    blob.extend_from_slice(&salt);
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, base45::encode(blob)))
+}
+
+/// Reverses `encrypt`
+pub fn decrypt(payload: &str, passphrase: &str) -> Result<String> {
+    let encoded = payload
+        .trim()
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .ok_or_else(|| anyhow::anyhow!("The code does not hold an encrypted payload"))?;
+    let blob = base45::decode(encoded).map_err(|_| anyhow::anyhow!("Encrypted payload is not valid base45"))?;
+    if blob.len() < SALT_LEN + NONCE_LEN + 16 {
+        anyhow::bail!("Encrypted payload is truncated");
+    }
+
+    let (salt, rest) = blob.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt)?.into());
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Wrong passphrase or tampered payload"))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+pub fn is_encrypted(payload: &str) -> bool {
+    payload.trim().starts_with(ENCRYPTED_PREFIX)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Takes the passphrase from RUSTQR_PASSPHRASE, a terminal prompt, or the first line of stdin
+pub fn read_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("RUSTQR_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+
+    let passphrase = if std::io::stdin().is_terminal() {
+        prompt_passphrase(confirm)?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    };
+
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+    Ok(passphrase)
+}
+
+/// Hidden terminal prompt, asked twice when `confirm` is set
+#[cfg(feature = "cli")]
+fn prompt_passphrase(confirm: bool) -> Result<String> {
+    let prompt = Password::new().with_prompt("Passphrase");
+    if confirm {
+        Ok(prompt
+            .with_confirmation("Repeat passphrase", "Passphrases do not match")
+            .interact()?)
+    } else {
+        Ok(prompt.interact()?)
+    }
+}
+
+#[cfg(not(feature = "cli"))]
+fn prompt_passphrase(_confirm: bool) -> Result<String> {
+    anyhow::bail!("This build has no terminal prompts, set RUSTQR_PASSPHRASE or pipe the passphrase on stdin")
+}
@@ -0,0 +1,299 @@
+use crate::gs1::GS;
+use crate::symbol::{Symbol, Symbology};
+use anyhow::Result;
+
+/// Square ECC 200 sizes: symbol side, data region side, regions per side,
+/// data codewords, error correction codewords, interleaved blocks
+const SIZES: [(usize, usize, usize, usize, usize, usize); 24] = [
+    (10, 8, 1, 3, 5, 1),
+    (12, 10, 1, 5, 7, 1),
+    (14, 12, 1, 8, 10, 1),
+    (16, 14, 1, 12, 12, 1),
+    (18, 16, 1, 18, 14, 1),
+    (20, 18, 1, 22, 18, 1),
+    (22, 20, 1, 30, 20, 1),
+    (24, 22, 1, 36, 24, 1),
+    (26, 24, 1, 44, 28, 1),
+    (32, 14, 2, 62, 36, 1),
+    (36, 16, 2, 86, 42, 1),
+    (40, 18, 2, 114, 48, 1),
+    (44, 20, 2, 144, 56, 1),
+    (48, 22, 2, 174, 68, 1),
+    (52, 24, 2, 204, 84, 2),
+    (64, 14, 4, 280, 112, 2),
+    (72, 16, 4, 368, 144, 4),
+    (80, 18, 4, 456, 192, 4),
+    (88, 20, 4, 576, 224, 4),
+    (96, 22, 4, 696, 272, 4),
+    (104, 24, 4, 816, 336, 6),
+    (120, 18, 6, 1050, 408, 6),
+    (132, 20, 6, 1304, 496, 8),
+    (144, 22, 6, 1558, 620, 10),
+];
+
+const PAD: u8 = 129;
+const UPPER_SHIFT: u8 = 235;
+const FNC1: u8 = 232;
+
+/// Encodes bytes as the smallest square Data Matrix (ECC 200) that holds them
+pub fn encode(data: &[u8]) -> Result<Symbol> {
+    encode_codewords(ascii_codewords(data))
+}
+
+/// GS1 Data Matrix: FNC1 first, then the element string with every GS separator written as FNC1
+pub fn encode_gs1(data: &[u8]) -> Result<Symbol> {
+    let mut codewords = vec![FNC1];
+    for (i, field) in data.split(|&b| b == GS).enumerate() {
+        if i > 0 {
+            codewords.push(FNC1);
+        }
+        codewords.extend(ascii_codewords(field));
+    }
+    encode_codewords(codewords)
+}
+
+/// Builds a symbol from already encoded data codewords (ASCII mode, FNC1 allowed)
+pub fn encode_codewords(mut codewords: Vec<u8>) -> Result<Symbol> {
+    let &(size, region, regions, data_len, ecc_len, blocks) = SIZES
+        .iter()
+        .find(|s| s.3 >= codewords.len())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Data too long for Data Matrix: {} codewords, the largest symbol (144×144) holds {}",
+                codewords.len(),
+                SIZES[SIZES.len() - 1].3
+            )
+        })?;
+
+    // The first pad is 129, the rest are scrambled so long runs do not form patterns
+    if codewords.len() < data_len {
+        codewords.push(PAD);
+    }
+    while codewords.len() < data_len {
+        let position = codewords.len() + 1;
+        let scrambled = PAD as usize + (149 * position) % 253 + 1;
+        codewords.push(if scrambled > 254 { scrambled - 254 } else { scrambled } as u8);
+    }
+
+    // Codewords are dealt round-robin to the blocks; each block gets its own error correction
+    let ecc_per_block = ecc_len / blocks;
+    let generator = generator_polynomial(ecc_per_block);
+    let mut ecc = vec![0u8; ecc_len];
+    for block in 0..blocks {
+        let block_data: Vec<u8> = codewords.iter().skip(block).step_by(blocks).copied().collect();
+        for (i, value) in reed_solomon(&block_data, &generator).into_iter().enumerate() {
+            ecc[i * blocks + block] = value;
+        }
+    }
+    codewords.extend(ecc);
+
+    let mapping_side = region * regions;
+    let bits = place_codewords(&codewords, mapping_side, mapping_side);
+
+    // Every data region gets a solid L on its left and bottom edge and a clock track on the
+    // opposite two; the mapping matrix fills the insides
+    let mut dark = vec![false; size * size];
+    let cell = region + 2;
+    for y in 0..size {
+        for x in 0..size {
+            let (ly, lx) = (y % cell, x % cell);
+            dark[y * size + x] = if lx == 0 || ly == cell - 1 {
+                true
+            } else if ly == 0 {
+                lx % 2 == 0
+            } else if lx == cell - 1 {
+                ly % 2 == 1
+            } else {
+                let row = y / cell * region + ly - 1;
+                let col = x / cell * region + lx - 1;
+                bits[row * mapping_side + col]
+            };
+        }
+    }
+    Ok(Symbol::from_modules(size, dark, Vec::new(), Symbology::DataMatrix))
+}
+
+/// ASCII encodation: digit pairs take one codeword, bytes above 127 go through Upper Shift
+pub fn ascii_codewords(data: &[u8]) -> Vec<u8> {
+    let mut codewords = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        if byte.is_ascii_digit() && data.get(i + 1).is_some_and(u8::is_ascii_digit) {
+            codewords.push(130 + (byte - b'0') * 10 + (data[i + 1] - b'0'));
+            i += 2;
+            continue;
+        }
+        if byte > 127 {
+            codewords.push(UPPER_SHIFT);
+            codewords.push(byte - 127);
+        } else {
+            codewords.push(byte + 1);
+        }
+        i += 1;
+    }
+    codewords
+}
+
+/// Multiplication in GF(256) with the Data Matrix polynomial x^8 + x^5 + x^3 + x^2 + 1
+fn gf_mul(a: u8, b: u8) -> u8 {
+    let (mut a, mut b, mut product) = (a as u16, b, 0u16);
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a <<= 1;
+        if a & 0x100 != 0 {
+            a ^= 0x12d;
+        }
+        b >>= 1;
+    }
+    product as u8
+}
+
+/// Coefficients of (x - 2^1)(x - 2^2)...(x - 2^n), highest power first
+fn generator_polynomial(degree: usize) -> Vec<u8> {
+    let mut poly = vec![1u8];
+    let mut root = 1u8;
+    for _ in 0..degree {
+        root = gf_mul(root, 2);
+        let mut next = vec![0u8; poly.len() + 1];
+        for (i, &c) in poly.iter().enumerate() {
+            next[i] ^= c;
+            next[i + 1] ^= gf_mul(c, root);
+        }
+        poly = next;
+    }
+    poly
+}
+
+/// Remainder of data × x^n divided by the generator
+fn reed_solomon(data: &[u8], generator: &[u8]) -> Vec<u8> {
+    let degree = generator.len() - 1;
+    let mut remainder = vec![0u8; degree];
+    for &value in data {
+        let factor = value ^ remainder[0];
+        remainder.rotate_left(1);
+        remainder[degree - 1] = 0;
+        for i in 0..degree {
+            remainder[i] ^= gf_mul(generator[i + 1], factor);
+        }
+    }
+    remainder
+}
+
+/// The ECC 200 placement: codewords go into the mapping matrix as 8-module "utah" shapes along
+/// diagonals, with special shapes where they would wrap around a corner
+fn place_codewords(codewords: &[u8], rows: usize, cols: usize) -> Vec<bool> {
+    let mut placement = Placement {
+        grid: vec![None; rows * cols],
+        nrow: rows as isize,
+        ncol: cols as isize,
+        codewords,
+    };
+    let (nrow, ncol) = (placement.nrow, placement.ncol);
+
+    let mut codeword = 0;
+    let (mut row, mut col) = (4isize, 0isize);
+    loop {
+        let corner = if row == nrow && col == 0 {
+            Some([(nrow - 1, 0), (nrow - 1, 1), (nrow - 1, 2), (0, ncol - 2), (0, ncol - 1), (1, ncol - 1), (2, ncol - 1), (3, ncol - 1)])
+        } else if row == nrow - 2 && col == 0 && ncol % 4 != 0 {
+            Some([(nrow - 3, 0), (nrow - 2, 0), (nrow - 1, 0), (0, ncol - 4), (0, ncol - 3), (0, ncol - 2), (0, ncol - 1), (1, ncol - 1)])
+        } else if row == nrow - 2 && col == 0 && ncol % 8 == 4 {
+            Some([(nrow - 3, 0), (nrow - 2, 0), (nrow - 1, 0), (0, ncol - 2), (0, ncol - 1), (1, ncol - 1), (2, ncol - 1), (3, ncol - 1)])
+        } else if row == nrow + 4 && col == 2 && ncol % 8 == 0 {
+            Some([(nrow - 1, 0), (nrow - 1, ncol - 1), (0, ncol - 3), (0, ncol - 2), (0, ncol - 1), (1, ncol - 3), (1, ncol - 2), (1, ncol - 1)])
+        } else {
+            None
+        };
+        if let Some(cells) = corner {
+            placement.shape(cells, codeword);
+            codeword += 1;
+        }
+
+        // Up and to the right, then down and to the left
+        loop {
+            if placement.is_free(row, col) {
+                placement.shape(utah(row, col), codeword);
+                codeword += 1;
+            }
+            row -= 2;
+            col += 2;
+            if row < 0 || col >= ncol {
+                break;
+            }
+        }
+        row += 1;
+        col += 3;
+        loop {
+            if placement.is_free(row, col) {
+                placement.shape(utah(row, col), codeword);
+                codeword += 1;
+            }
+            row += 2;
+            col -= 2;
+            if row >= nrow || col < 0 {
+                break;
+            }
+        }
+        row += 3;
+        col += 1;
+        if row >= nrow && col >= ncol {
+            break;
+        }
+    }
+
+    // Sizes that leave the bottom-right 2×2 unused get a fixed checkerboard there
+    let mut grid = placement.grid;
+    if grid[rows * cols - 1].is_none() {
+        grid[rows * cols - 1] = Some(true);
+        grid[rows * cols - 2] = Some(false);
+        grid[(rows - 1) * cols - 1] = Some(false);
+        grid[(rows - 1) * cols - 2] = Some(true);
+    }
+    grid.into_iter().map(|m| m.unwrap_or(false)).collect()
+}
+
+struct Placement<'a> {
+    grid: Vec<Option<bool>>,
+    nrow: isize,
+    ncol: isize,
+    codewords: &'a [u8],
+}
+
+impl Placement<'_> {
+    fn is_free(&self, row: isize, col: isize) -> bool {
+        (0..self.nrow).contains(&row) && (0..self.ncol).contains(&col) && self.grid[(row * self.ncol + col) as usize].is_none()
+    }
+
+    /// Writes the bits of one codeword, most significant first, wrapping positions off the edge
+    fn shape(&mut self, cells: [(isize, isize); 8], codeword: usize) {
+        let value = self.codewords.get(codeword).copied().unwrap_or(0);
+        for (bit, (mut row, mut col)) in cells.into_iter().enumerate() {
+            if row < 0 {
+                row += self.nrow;
+                col += 4 - ((self.nrow + 4) % 8);
+            }
+            if col < 0 {
+                col += self.ncol;
+                row += 4 - ((self.ncol + 4) % 8);
+            }
+            self.grid[(row * self.ncol + col) as usize] = Some(value >> (7 - bit) & 1 == 1);
+        }
+    }
+}
+
+/// The regular 8-module shape whose last bit sits at (row, col)
+fn utah(row: isize, col: isize) -> [(isize, isize); 8] {
+    [
+        (row - 2, col - 2),
+        (row - 2, col - 1),
+        (row - 1, col - 2),
+        (row - 1, col - 1),
+        (row - 1, col),
+        (row, col - 2),
+        (row, col - 1),
+        (row, col),
+    ]
+}
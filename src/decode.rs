@@ -0,0 +1,1194 @@
+use anyhow::{Context, Result};
+use image::{DynamicImage, GrayImage, ImageReader};
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use qrcode::canvas::{Canvas, Module};
+use qrcode::types::{EcLevel, Version};
+
+/// Error correction bytes per block and block count for versions 1-40 (L, M, Q, H).
+/// ISO/IEC 18004 Table 9; data bytes follow from the module count.
+static EC_BLOCKS: [[(usize, usize); 4]; 40] = [
+    [(7, 1), (10, 1), (13, 1), (17, 1)], // 1
+    [(10, 1), (16, 1), (22, 1), (28, 1)], // 2
+    [(15, 1), (26, 1), (18, 2), (22, 2)], // 3
+    [(20, 1), (18, 2), (26, 2), (16, 4)], // 4
+    [(26, 1), (24, 2), (18, 4), (22, 4)], // 5
+    [(18, 2), (16, 4), (24, 4), (28, 4)], // 6
+    [(20, 2), (18, 4), (18, 6), (26, 5)], // 7
+    [(24, 2), (22, 4), (22, 6), (26, 6)], // 8
+    [(30, 2), (22, 5), (20, 8), (24, 8)], // 9
+    [(18, 4), (26, 5), (24, 8), (28, 8)], // 10
+    [(20, 4), (30, 5), (28, 8), (24, 11)], // 11
+    [(24, 4), (22, 8), (26, 10), (28, 11)], // 12
+    [(26, 4), (22, 9), (24, 12), (22, 16)], // 13
+    [(30, 4), (24, 9), (20, 16), (24, 16)], // 14
+    [(22, 6), (24, 10), (30, 12), (24, 18)], // 15
+    [(24, 6), (28, 10), (24, 17), (30, 16)], // 16
+    [(28, 6), (28, 11), (28, 16), (28, 19)], // 17
+    [(30, 6), (26, 13), (28, 18), (28, 21)], // 18
+    [(28, 7), (26, 14), (26, 21), (26, 25)], // 19
+    [(28, 8), (26, 16), (30, 20), (28, 25)], // 20
+    [(28, 8), (26, 17), (28, 23), (30, 25)], // 21
+    [(28, 9), (28, 17), (30, 23), (24, 34)], // 22
+    [(30, 9), (28, 18), (30, 25), (30, 30)], // 23
+    [(30, 10), (28, 20), (30, 27), (30, 32)], // 24
+    [(26, 12), (28, 21), (30, 29), (30, 35)], // 25
+    [(28, 12), (28, 23), (28, 34), (30, 37)], // 26
+    [(30, 12), (28, 25), (30, 34), (30, 40)], // 27
+    [(30, 13), (28, 26), (30, 35), (30, 42)], // 28
+    [(30, 14), (28, 28), (30, 38), (30, 45)], // 29
+    [(30, 15), (28, 29), (30, 40), (30, 48)], // 30
+    [(30, 16), (28, 31), (30, 43), (30, 51)], // 31
+    [(30, 17), (28, 33), (30, 45), (30, 54)], // 32
+    [(30, 18), (28, 35), (30, 48), (30, 57)], // 33
+    [(30, 19), (28, 37), (30, 51), (30, 60)], // 34
+    [(30, 19), (28, 38), (30, 53), (30, 63)], // 35
+    [(30, 20), (28, 40), (30, 56), (30, 66)], // 36
+    [(30, 21), (28, 43), (30, 59), (30, 70)], // 37
+    [(30, 22), (28, 45), (30, 62), (30, 74)], // 38
+    [(30, 24), (28, 47), (30, 65), (30, 77)], // 39
+    [(30, 25), (28, 49), (30, 68), (30, 81)], // 40
+];
+
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// A QR code read back from an image
+pub struct Decoded {
+    pub data: Vec<u8>,
+    pub version: i16,
+    pub ec_level: EcLevel,
+    pub mask: u8,
+    /// The first ECI designator, naming the character set of the data that follows
+    pub eci: Option<u32>,
+    /// Corners of the symbol in image pixels: top-left, top-right, bottom-right, bottom-left
+    pub bounds: [(f32, f32); 4],
+    /// The sampled module grid, row by row (true = dark), `17 + 4 × version` modules square
+    pub modules: Vec<bool>,
+}
+
+impl Decoded {
+    /// The payload as text; invalid UTF-8 is replaced
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+
+    /// Modules per side
+    pub fn size(&self) -> usize {
+        17 + 4 * self.version as usize
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size() + x]
+    }
+}
+
+/// Opens the image to decode, reading it from stdin for `-`. The format is sniffed from the
+/// bytes, so clipboard dumps and files without (or with the wrong) extension work too.
+pub fn open_image(path: &Path) -> Result<DynamicImage> {
+    if path != Path::new("-") {
+        let reader = ImageReader::open(path)
+            .and_then(ImageReader::with_guessed_format)
+            .with_context(|| format!("Failed to open image: {}", path.display()))?;
+        return reader.decode().with_context(|| format!("Failed to open image: {}", path.display()));
+    }
+
+    if std::io::stdin().is_terminal() {
+        anyhow::bail!("decode - reads the image from stdin, pipe one in: wl-paste | rustqr decode -");
+    }
+    let mut bytes = Vec::new();
+    std::io::stdin().read_to_end(&mut bytes).context("Failed to read the image from stdin")?;
+    if bytes.is_empty() {
+        anyhow::bail!("No image data on stdin");
+    }
+    let format = image::guess_format(&bytes)
+        .context("The data on stdin is not a recognized image format (png, jpg, webp, gif, bmp, ...)")?;
+    image::load_from_memory_with_format(&bytes, format)
+        .with_context(|| format!("Failed to decode the {:?} image from stdin", format))
+}
+
+/// Finds and decodes the first readable QR code in an image
+pub fn decode_image(img: &DynamicImage) -> Result<Decoded> {
+    // The plain pass explains failures best, the blurred ones mostly find nothing at all
+    let mut first_error = None;
+    for bitmap in bitmaps(img) {
+        match decode_bitmap(&bitmap) {
+            Ok(decoded) => return Ok(decoded),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_else(|| anyhow::anyhow!("No QR code found in the image")))
+}
+
+/// Every distinct QR code in an image, such as a screenshot with several codes on it
+pub fn decode_all(img: &DynamicImage) -> Vec<Decoded> {
+    let mut found: Vec<Decoded> = Vec::new();
+    for bitmap in bitmaps(img) {
+        for decoded in decode_bitmap_all(&bitmap) {
+            if !found.iter().any(|f| f.data == decoded.data) {
+                found.push(decoded);
+            }
+        }
+    }
+    found
+}
+
+/// Thresholded versions of the image: plain first, then blurred, each dark-on-light then inverted
+fn bitmaps(img: &DynamicImage) -> impl Iterator<Item = Bitmap> {
+    let gray = to_gray(img);
+
+    // Dotted and outlined styles leave gaps inside the finder patterns;
+    // blurring closes them while the module grid stays readable
+    let short_side = gray.width().min(gray.height()) as f32;
+    let passes = [None, Some(short_side / 150.0), Some(short_side / 75.0)];
+
+    passes.into_iter().flat_map(move |sigma| {
+        let gray = match sigma {
+            Some(sigma) => image::imageops::blur(&gray, sigma),
+            None => gray.clone(),
+        };
+        let threshold = otsu_threshold(&gray);
+        [false, true].map(|inverted| Bitmap::new(&gray, threshold, inverted))
+    })
+}
+
+/// Luminance with transparent areas composited over white
+fn to_gray(img: &DynamicImage) -> GrayImage {
+    let rgba = img.to_rgba8();
+    GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let p = rgba.get_pixel(x, y);
+        let luma = 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32;
+        let alpha = p[3] as f32 / 255.0;
+        image::Luma([(luma * alpha + 255.0 * (1.0 - alpha)).round() as u8])
+    })
+}
+
+fn otsu_threshold(gray: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for p in gray.pixels() {
+        histogram[p[0] as usize] += 1;
+    }
+
+    let total: u64 = histogram.iter().sum();
+    let sum: f64 = histogram.iter().enumerate().map(|(i, &n)| i as f64 * n as f64).sum();
+    let (mut sum_below, mut weight_below) = (0.0, 0u64);
+    let (mut best, mut best_variance) = (127u8, 0.0);
+
+    for (level, &count) in histogram.iter().enumerate() {
+        weight_below += count;
+        if weight_below == 0 || weight_below == total {
+            continue;
+        }
+        sum_below += level as f64 * count as f64;
+        let mean_below = sum_below / weight_below as f64;
+        let mean_above = (sum - sum_below) / (total - weight_below) as f64;
+        let variance = weight_below as f64 * (total - weight_below) as f64 * (mean_below - mean_above).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best = level as u8;
+        }
+    }
+    best
+}
+
+struct Bitmap {
+    width: i32,
+    height: i32,
+    dark: Vec<bool>,
+}
+
+impl Bitmap {
+    fn new(gray: &GrayImage, threshold: u8, inverted: bool) -> Self {
+        Bitmap {
+            width: gray.width() as i32,
+            height: gray.height() as i32,
+            dark: gray.pixels().map(|p| (p[0] <= threshold) != inverted).collect(),
+        }
+    }
+
+    fn get(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height && self.dark[(y * self.width + x) as usize]
+    }
+}
+
+struct Finder {
+    x: f32,
+    y: f32,
+    module: f32,
+    hits: usize,
+}
+
+fn decode_bitmap(bitmap: &Bitmap) -> Result<Decoded> {
+    let finders = find_finders(bitmap);
+    if finders.len() < 3 {
+        anyhow::bail!("No QR code found in the image");
+    }
+
+    let mut last_error = anyhow::anyhow!("No QR code found in the image");
+    for triple in candidate_triples(&finders) {
+        match read_triple(bitmap, triple) {
+            Ok(decoded) => return Ok(decoded),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Every code in the bitmap; once a code is read its finders are not tried with any other
+fn decode_bitmap_all(bitmap: &Bitmap) -> Vec<Decoded> {
+    let finders = find_finders(bitmap);
+    let mut used: Vec<&Finder> = Vec::new();
+    let mut found = Vec::new();
+    for triple in candidate_triples(&finders) {
+        if triple.iter().any(|f| used.iter().any(|u| std::ptr::eq(*u, *f))) {
+            continue;
+        }
+        if let Ok(decoded) = read_triple(bitmap, triple) {
+            used.extend(triple);
+            found.push(decoded);
+        }
+    }
+    found
+}
+
+fn read_triple(bitmap: &Bitmap, [top_left, top_right, bottom_left]: [&Finder; 3]) -> Result<Decoded> {
+    let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+    let span = (distance(top_left, top_right) + distance(top_left, bottom_left)) / 2.0;
+    let estimate = ((span / module + 7.0 - 17.0) / 4.0).round() as i16;
+
+    // The size estimate can be off by one version on skewed or styled codes
+    let mut last_error = anyhow::anyhow!("No QR code found in the image");
+    for version in [estimate, estimate + 1, estimate - 1] {
+        if !(1..=40).contains(&version) {
+            continue;
+        }
+        match read_code(bitmap, top_left, top_right, bottom_left, version) {
+            Ok(decoded) => return Ok(decoded),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Scans rows for the 1:1:3:1:1 finder signature and confirms it vertically
+fn find_finders(bitmap: &Bitmap) -> Vec<Finder> {
+    let mut finders: Vec<Finder> = Vec::new();
+
+    for y in 0..bitmap.height {
+        let runs = row_runs(bitmap, y);
+        for window in runs.windows(5) {
+            if !window[0].2 {
+                continue;
+            }
+            let lengths = [window[0].1, window[1].1, window[2].1, window[3].1, window[4].1];
+            if !finder_ratio(&lengths) {
+                continue;
+            }
+            let center_x = window[2].0 as f32 + window[2].1 as f32 / 2.0;
+            let Some((center_y, vertical)) = cross_check(bitmap, center_x as i32, y, false) else {
+                continue;
+            };
+            let Some((center_x, horizontal)) = cross_check(bitmap, center_x as i32, center_y as i32, true) else {
+                continue;
+            };
+            let module = (vertical + horizontal) / 14.0;
+
+            match finders.iter_mut().find(|f| {
+                (f.x - center_x).abs() < f.module * 2.0
+                    && (f.y - center_y).abs() < f.module * 2.0
+                    && (f.module / module - 1.0).abs() < 0.5
+            }) {
+                Some(f) => {
+                    let n = f.hits as f32;
+                    f.x = (f.x * n + center_x) / (n + 1.0);
+                    f.y = (f.y * n + center_y) / (n + 1.0);
+                    f.module = (f.module * n + module) / (n + 1.0);
+                    f.hits += 1;
+                }
+                None => finders.push(Finder { x: center_x, y: center_y, module, hits: 1 }),
+            }
+        }
+    }
+
+    // Real finders are crossed by several rows; stray matches in data areas are not
+    if finders.iter().filter(|f| f.hits >= 2).count() >= 3 {
+        finders.retain(|f| f.hits >= 2);
+    }
+    // Repetitive data repeats finder-like crosses as often as the real finders occur, so
+    // the whole pattern with its light separator decides first and the hits only break ties
+    let mut ranked: Vec<(usize, Finder)> = finders.into_iter().map(|f| (finder_match(bitmap, &f), f)).collect();
+    ranked.sort_by_key(|(matched, f)| std::cmp::Reverse((*matched, f.hits)));
+    ranked.truncate(12);
+    ranked.into_iter().map(|(_, f)| f).collect()
+}
+
+/// How many of the 9×9 modules around a finder candidate, separator ring included, have the
+/// color of a real finder pattern
+fn finder_match(bitmap: &Bitmap, finder: &Finder) -> usize {
+    let mut matched = 0;
+    for j in -4i32..=4 {
+        for i in -4i32..=4 {
+            let ring = i.abs().max(j.abs());
+            let x = (finder.x + i as f32 * finder.module).floor() as i32;
+            let y = (finder.y + j as f32 * finder.module).floor() as i32;
+            if bitmap.get(x, y) == matches!(ring, 0 | 1 | 3) {
+                matched += 1;
+            }
+        }
+    }
+    matched
+}
+
+/// Runs of equal color in a row as (start, length, dark)
+fn row_runs(bitmap: &Bitmap, y: i32) -> Vec<(i32, i32, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for x in 1..=bitmap.width {
+        if x == bitmap.width || bitmap.get(x, y) != bitmap.get(start, y) {
+            runs.push((start, x - start, bitmap.get(start, y)));
+            start = x;
+        }
+    }
+    runs
+}
+
+fn finder_ratio(lengths: &[i32; 5]) -> bool {
+    let total: i32 = lengths.iter().sum();
+    if total < 7 {
+        return false;
+    }
+    let module = total as f32 / 7.0;
+    let tolerance = module * 0.6;
+    lengths
+        .iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&len, expected)| (len as f32 - module * expected).abs() < tolerance * expected)
+}
+
+/// Measures the finder pattern through (x, y) along one axis.
+/// Returns the refined center on that axis and the pattern's total length.
+fn cross_check(bitmap: &Bitmap, x: i32, y: i32, horizontal: bool) -> Option<(f32, f32)> {
+    let at = |offset: i32| {
+        if horizontal {
+            bitmap.get(x + offset, y)
+        } else {
+            bitmap.get(x, y + offset)
+        }
+    };
+    if !at(0) {
+        return None;
+    }
+
+    // Walk outwards: dark center, light ring, dark ring on each side
+    let mut lengths = [0i32; 5];
+    let mut offset = 0;
+    for (slot, dark) in [(2, true), (1, false), (0, true)] {
+        while at(offset - 1) == dark && lengths[slot] < 2000 {
+            offset -= 1;
+            lengths[slot] += 1;
+        }
+        if slot != 2 && lengths[slot] == 0 {
+            return None;
+        }
+    }
+    let start = offset;
+    lengths[2] += 1;
+    offset = 0;
+    for (slot, dark) in [(2, true), (3, false), (4, true)] {
+        while at(offset + 1) == dark && lengths[slot] < 2000 {
+            offset += 1;
+            lengths[slot] += 1;
+        }
+        if slot != 2 && lengths[slot] == 0 {
+            return None;
+        }
+    }
+
+    if !finder_ratio(&lengths) {
+        return None;
+    }
+    let center_start = start + lengths[0] + lengths[1];
+    let center = center_start as f32 + lengths[2] as f32 / 2.0;
+    let base = if horizontal { x } else { y };
+    Some((base as f32 + center, lengths.iter().sum::<i32>() as f32))
+}
+
+fn distance(a: &Finder, b: &Finder) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// Triples of finders that could be one code, best fit first, as [top-left, top-right, bottom-left]
+fn candidate_triples(finders: &[Finder]) -> Vec<[&Finder; 3]> {
+    let mut triples = Vec::new();
+    for i in 0..finders.len() {
+        for j in i + 1..finders.len() {
+            for k in j + 1..finders.len() {
+                let (a, b, c) = (&finders[i], &finders[j], &finders[k]);
+                let sizes = [a.module, b.module, c.module];
+                let max = sizes.iter().cloned().fold(f32::MIN, f32::max);
+                let min = sizes.iter().cloned().fold(f32::MAX, f32::min);
+                if max / min > 1.5 {
+                    continue;
+                }
+
+                // The corner finder sits opposite the longest side
+                let (ab, bc, ca) = (distance(a, b), distance(b, c), distance(c, a));
+                let (corner, p, q) = if bc >= ab && bc >= ca {
+                    (a, b, c)
+                } else if ca >= ab {
+                    (b, c, a)
+                } else {
+                    (c, a, b)
+                };
+                let (d1, d2) = (distance(corner, p), distance(corner, q));
+                let hypotenuse = distance(p, q);
+                if d1.min(d2) < corner.module * 10.0 {
+                    continue;
+                }
+                let score = (d1 - d2).abs() / d1.max(d2) + (hypotenuse - (d1 * d1 + d2 * d2).sqrt()).abs() / hypotenuse;
+                if score > 0.5 {
+                    continue;
+                }
+
+                // In image coordinates (y down) top-right -> bottom-left turns clockwise
+                let cross = (p.x - corner.x) * (q.y - corner.y) - (p.y - corner.y) * (q.x - corner.x);
+                let triple = if cross > 0.0 { [corner, p, q] } else { [corner, q, p] };
+                triples.push((score, triple));
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    triples.into_iter().map(|(_, t)| t).collect()
+}
+
+fn read_code(bitmap: &Bitmap, top_left: &Finder, top_right: &Finder, bottom_left: &Finder, version: i16) -> Result<Decoded> {
+    let dim = 17 + 4 * version as i32;
+    let far = dim as f32 - 3.5;
+
+    // The fourth reference point is the bottom-right alignment pattern when the
+    // version has one, otherwise the corner that completes the parallelogram
+    let parallelogram = (
+        top_right.x + bottom_left.x - top_left.x,
+        top_right.y + bottom_left.y - top_left.y,
+    );
+    let mut fourths = Vec::new();
+    if version >= 2 {
+        let target = dim as f32 - 6.5;
+        let t = (target - 3.5) / (far - 3.5);
+        let estimate = (
+            top_left.x + (parallelogram.0 - top_left.x) * t,
+            top_left.y + (parallelogram.1 - top_left.y) * t,
+        );
+        let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+        // Data can mimic an alignment pattern, so every nearby match gets a try
+        for found in find_alignments(bitmap, estimate, module) {
+            fourths.push(((target, target), found));
+        }
+        fourths.push(((target, target), estimate));
+    } else {
+        fourths.push(((far, far), parallelogram));
+    }
+
+    let mut last_error = anyhow::anyhow!("Could not map the code grid");
+    for fourth in fourths {
+        let Some(transform) = Homography::from_points(&[
+            ((3.5, 3.5), (top_left.x, top_left.y)),
+            ((far, 3.5), (top_right.x, top_right.y)),
+            ((3.5, far), (bottom_left.x, bottom_left.y)),
+            fourth,
+        ]) else {
+            continue;
+        };
+
+        let mut matrix = vec![false; (dim * dim) as usize];
+        for row in 0..dim {
+            for col in 0..dim {
+                let (x, y) = transform.map(col as f32 + 0.5, row as f32 + 0.5);
+                matrix[(row * dim + col) as usize] = bitmap.get(x.floor() as i32, y.floor() as i32);
+            }
+        }
+
+        let bounds = [(0.0, 0.0), (dim as f32, 0.0), (dim as f32, dim as f32), (0.0, dim as f32)]
+            .map(|(u, v)| transform.map(u, v));
+        match decode_matrix(&matrix, dim, version, bounds) {
+            Ok(decoded) => return Ok(decoded),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Centers of dark-light-dark crosses the size of an alignment pattern near
+/// `estimate`, closest first
+fn find_alignments(bitmap: &Bitmap, estimate: (f32, f32), module: f32) -> Vec<(f32, f32)> {
+    let radius = (module * 8.0).ceil() as i32;
+    let (ex, ey) = (estimate.0 as i32, estimate.1 as i32);
+    let mut found: Vec<(f32, f32)> = Vec::new();
+
+    for y in ey - radius..=ey + radius {
+        for x in ex - radius..=ex + radius {
+            if !bitmap.get(x, y) {
+                continue;
+            }
+            let Some(cx) = alignment_center(bitmap, x, y, module, true) else { continue };
+            let Some(cy) = alignment_center(bitmap, cx as i32, y, module, false) else { continue };
+            if !found.iter().any(|&(fx, fy)| (fx - cx).abs() < module && (fy - cy).abs() < module) {
+                found.push((cx, cy));
+            }
+        }
+    }
+
+    let distance = |&(x, y): &(f32, f32)| (x - estimate.0).powi(2) + (y - estimate.1).powi(2);
+    found.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+    found.truncate(6);
+    found
+}
+
+fn alignment_center(bitmap: &Bitmap, x: i32, y: i32, module: f32, horizontal: bool) -> Option<f32> {
+    let at = |offset: i32| {
+        if horizontal {
+            bitmap.get(x + offset, y)
+        } else {
+            bitmap.get(x, y + offset)
+        }
+    };
+    let limit = (module * 2.0).ceil() as i32;
+    let within = |len: i32| (len as f32 - module).abs() <= module * 0.7;
+
+    let mut back = 0;
+    while at(-back - 1) && back < limit {
+        back += 1;
+    }
+    let mut forward = 0;
+    while at(forward + 1) && forward < limit {
+        forward += 1;
+    }
+    if !within(back + forward + 1) {
+        return None;
+    }
+
+    // Light ring on both sides, then the dark outer ring
+    for direction in [-1, 1] {
+        let mut offset = if direction < 0 { -back - 1 } else { forward + 1 };
+        let mut light = 0;
+        while !at(offset) && light <= limit {
+            light += 1;
+            offset += direction;
+        }
+        if !within(light) || !at(offset) {
+            return None;
+        }
+    }
+
+    let base = if horizontal { x } else { y };
+    Some(base as f32 - back as f32 + (back + forward + 1) as f32 / 2.0)
+}
+
+type Point = (f32, f32);
+
+/// Projective map from module coordinates to image coordinates
+struct Homography([f32; 8]);
+
+impl Homography {
+    /// Builds the map from four (module, image) point pairs
+    fn from_points(points: &[(Point, Point); 4]) -> Option<Self> {
+        // Solve the 8x8 system for h with h8 = 1
+        let mut rows = [[0f64; 9]; 8];
+        for (i, &((u, v), (x, y))) in points.iter().enumerate() {
+            let (u, v, x, y) = (u as f64, v as f64, x as f64, y as f64);
+            rows[2 * i] = [u, v, 1.0, 0.0, 0.0, 0.0, -u * x, -v * x, x];
+            rows[2 * i + 1] = [0.0, 0.0, 0.0, u, v, 1.0, -u * y, -v * y, y];
+        }
+
+        for col in 0..8 {
+            let pivot = (col..8).max_by(|&a, &b| rows[a][col].abs().total_cmp(&rows[b][col].abs()))?;
+            if rows[pivot][col].abs() < 1e-9 {
+                return None;
+            }
+            rows.swap(col, pivot);
+            let pivot_row = rows[col];
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r != col {
+                    let factor = row[col] / pivot_row[col];
+                    for (value, &p) in row.iter_mut().zip(pivot_row.iter()).skip(col) {
+                        *value -= factor * p;
+                    }
+                }
+            }
+        }
+
+        let mut h = [0f32; 8];
+        for (i, value) in h.iter_mut().enumerate() {
+            *value = (rows[i][8] / rows[i][i]) as f32;
+        }
+        Some(Homography(h))
+    }
+
+    fn map(&self, u: f32, v: f32) -> (f32, f32) {
+        let h = &self.0;
+        let w = h[6] * u + h[7] * v + 1.0;
+        ((h[0] * u + h[1] * v + h[2]) / w, (h[3] * u + h[4] * v + h[5]) / w)
+    }
+}
+
+fn decode_matrix(matrix: &[bool], dim: i32, estimated_version: i16, bounds: [(f32, f32); 4]) -> Result<Decoded> {
+    let get = |x: i32, y: i32| matrix[(y * dim + x) as usize];
+
+    let version = if estimated_version >= 7 {
+        read_version(&get, dim).ok_or_else(|| anyhow::anyhow!("Unreadable version information"))?
+    } else {
+        estimated_version
+    };
+    if version != estimated_version {
+        anyhow::bail!("Version information does not match the code size");
+    }
+
+    let (ec_level, mask) = read_format(&get, dim).ok_or_else(|| anyhow::anyhow!("Unreadable format information"))?;
+
+    // Modules outside function patterns hold the codewords
+    let mut canvas = Canvas::new(Version::Normal(version), ec_level);
+    canvas.draw_all_functional_patterns();
+    let is_data = |x: i32, y: i32| canvas.get(x as i16, y as i16) == Module::Empty;
+
+    let mut codewords = Vec::new();
+    let (mut byte, mut bits) = (0u8, 0);
+    let mut upward = true;
+    let mut x = dim - 1;
+    while x > 0 {
+        if x == 6 {
+            x -= 1;
+        }
+        for i in 0..dim {
+            let y = if upward { dim - 1 - i } else { i };
+            for col in [x, x - 1] {
+                if !is_data(col, y) {
+                    continue;
+                }
+                byte = (byte << 1) | (get(col, y) != mask_bit(mask, y, col)) as u8;
+                bits += 1;
+                if bits == 8 {
+                    codewords.push(byte);
+                    byte = 0;
+                    bits = 0;
+                }
+            }
+        }
+        upward = !upward;
+        x -= 2;
+    }
+
+    let data = correct_blocks(&codewords, version, ec_level)?;
+    let (data, eci) = parse_segments(&data, version)?;
+
+    Ok(Decoded { data, version, ec_level, mask, eci, bounds, modules: matrix.to_vec() })
+}
+
+fn mask_bit(mask: u8, row: i32, col: i32) -> bool {
+    let (i, j) = (row, col);
+    match mask {
+        0 => (i + j) % 2 == 0,
+        1 => i % 2 == 0,
+        2 => j % 3 == 0,
+        3 => (i + j) % 3 == 0,
+        4 => (i / 2 + j / 3) % 2 == 0,
+        5 => (i * j) % 2 + (i * j) % 3 == 0,
+        6 => ((i * j) % 2 + (i * j) % 3) % 2 == 0,
+        _ => ((i + j) % 2 + (i * j) % 3) % 2 == 0,
+    }
+}
+
+/// BCH(15,5) format word for 2 EC bits and 3 mask bits, already XOR-masked
+fn format_word(data: u32) -> u32 {
+    let mut rem = data << 10;
+    for i in (0..5).rev() {
+        if rem & (1 << (i + 10)) != 0 {
+            rem ^= 0x537 << i;
+        }
+    }
+    ((data << 10) | rem) ^ 0x5412
+}
+
+/// BCH(18,6) version word
+fn version_word(version: u32) -> u32 {
+    let mut rem = version << 12;
+    for i in (0..6).rev() {
+        if rem & (1 << (i + 12)) != 0 {
+            rem ^= 0x1f25 << i;
+        }
+    }
+    (version << 12) | rem
+}
+
+fn read_format(get: &impl Fn(i32, i32) -> bool, dim: i32) -> Option<(EcLevel, u8)> {
+    let mut first = 0u32;
+    let mut second = 0u32;
+    let push = |word: &mut u32, x: i32, y: i32| *word = (*word << 1) | get(x, y) as u32;
+
+    for x in 0..6 {
+        push(&mut first, x, 8);
+    }
+    push(&mut first, 7, 8);
+    push(&mut first, 8, 8);
+    push(&mut first, 8, 7);
+    for y in (0..6).rev() {
+        push(&mut first, 8, y);
+    }
+    for y in (dim - 7..dim).rev() {
+        push(&mut second, 8, y);
+    }
+    for x in dim - 8..dim {
+        push(&mut second, x, 8);
+    }
+
+    let (data, distance) = (0..32u32)
+        .map(|data| {
+            let word = format_word(data);
+            (data, (word ^ first).count_ones().min((word ^ second).count_ones()))
+        })
+        .min_by_key(|&(_, d)| d)?;
+    if distance > 3 {
+        return None;
+    }
+
+    let ec_level = match data >> 3 {
+        0b01 => EcLevel::L,
+        0b00 => EcLevel::M,
+        0b11 => EcLevel::Q,
+        _ => EcLevel::H,
+    };
+    Some((ec_level, (data & 0b111) as u8))
+}
+
+fn read_version(get: &impl Fn(i32, i32) -> bool, dim: i32) -> Option<i16> {
+    let mut top_right = 0u32;
+    let mut bottom_left = 0u32;
+    for j in (0..6).rev() {
+        for i in (dim - 11..dim - 8).rev() {
+            top_right = (top_right << 1) | get(i, j) as u32;
+            bottom_left = (bottom_left << 1) | get(j, i) as u32;
+        }
+    }
+
+    let (version, distance) = (7..=40u32)
+        .map(|v| {
+            let word = version_word(v);
+            (v, (word ^ top_right).count_ones().min((word ^ bottom_left).count_ones()))
+        })
+        .min_by_key(|&(_, d)| d)?;
+    (distance <= 3).then_some(version as i16)
+}
+
+/// Splits the interleaved codewords into blocks and runs Reed-Solomon on each
+fn correct_blocks(codewords: &[u8], version: i16, ec_level: EcLevel) -> Result<Vec<u8>> {
+    let (ec_len, block_count) = EC_BLOCKS[(version - 1) as usize][ec_level as usize];
+    let data_total = codewords.len() - ec_len * block_count;
+    let short_len = data_total / block_count;
+    let long_blocks = data_total % block_count;
+    let short_blocks = block_count - long_blocks;
+
+    let mut blocks: Vec<Vec<u8>> = (0..block_count)
+        .map(|_| Vec::with_capacity(short_len + 1 + ec_len))
+        .collect::<Vec<_>>();
+    let mut next = codewords.iter();
+    for i in 0..=short_len {
+        for (b, block) in blocks.iter_mut().enumerate() {
+            if i < short_len || b >= short_blocks {
+                block.push(*next.next().unwrap_or(&0));
+            }
+        }
+    }
+    for _ in 0..ec_len {
+        for block in blocks.iter_mut() {
+            block.push(*next.next().unwrap_or(&0));
+        }
+    }
+
+    let gf = Galois::new();
+    let mut data = Vec::with_capacity(data_total);
+    for mut block in blocks {
+        gf.correct(&mut block, ec_len)
+            .ok_or_else(|| anyhow::anyhow!("Too many errors to correct"))?;
+        data.extend_from_slice(&block[..block.len() - ec_len]);
+    }
+    Ok(data)
+}
+
+/// GF(256) with the QR polynomial x^8 + x^4 + x^3 + x^2 + 1
+struct Galois {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+impl Galois {
+    fn new() -> Self {
+        let mut exp = [0u8; 512];
+        let mut log = [0u8; 256];
+        let mut x = 1u16;
+        for (i, value) in exp.iter_mut().take(255).enumerate() {
+            *value = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+        }
+        for i in 255..512 {
+            exp[i] = exp[i - 255];
+        }
+        Galois { exp, log }
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + 255 - self.log[b as usize] as usize]
+        }
+    }
+
+    fn pow(&self, power: usize) -> u8 {
+        self.exp[power % 255]
+    }
+
+    /// Evaluates a polynomial stored lowest degree first
+    fn eval(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+
+    /// Corrects a block in place (first byte = highest degree) and returns
+    /// the number of fixed bytes, or None when it is beyond repair
+    fn correct(&self, block: &mut [u8], ec_len: usize) -> Option<usize> {
+        let n = block.len();
+        let syndromes: Vec<u8> = (0..ec_len)
+            .map(|j| block.iter().fold(0, |acc, &c| self.mul(acc, self.pow(j)) ^ c))
+            .collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(0);
+        }
+
+        // Berlekamp-Massey for the error locator
+        let mut locator = vec![1u8];
+        let mut previous = vec![1u8];
+        let (mut errors, mut shift, mut last_discrepancy) = (0, 1, 1u8);
+        for step in 0..ec_len {
+            let mut discrepancy = syndromes[step];
+            for i in 1..=errors.min(locator.len() - 1) {
+                discrepancy ^= self.mul(locator[i], syndromes[step - i]);
+            }
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let scale = self.div(discrepancy, last_discrepancy);
+            let mut next = locator.clone();
+            if next.len() < previous.len() + shift {
+                next.resize(previous.len() + shift, 0);
+            }
+            for (i, &c) in previous.iter().enumerate() {
+                next[i + shift] ^= self.mul(scale, c);
+            }
+            if 2 * errors <= step {
+                previous = locator;
+                errors = step + 1 - errors;
+                last_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+            locator = next;
+        }
+        if errors * 2 > ec_len {
+            return None;
+        }
+
+        // Error evaluator: S(x) * locator(x) mod x^ec_len
+        let mut evaluator = vec![0u8; ec_len];
+        for (i, &s) in syndromes.iter().enumerate() {
+            for (j, &l) in locator.iter().enumerate() {
+                if i + j < ec_len {
+                    evaluator[i + j] ^= self.mul(s, l);
+                }
+            }
+        }
+        let derivative: Vec<u8> = locator
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+            .collect();
+
+        // Chien search, then Forney for each magnitude
+        let mut fixed = 0;
+        for (pos, byte) in block.iter_mut().enumerate() {
+            let power = n - 1 - pos;
+            let x_inv = self.pow(255 - power % 255);
+            if self.eval(&locator, x_inv) != 0 {
+                continue;
+            }
+            let denominator = self.eval(&derivative, x_inv);
+            if denominator == 0 {
+                return None;
+            }
+            let magnitude = self.mul(self.pow(power), self.div(self.eval(&evaluator, x_inv), denominator));
+            *byte ^= magnitude;
+            fixed += 1;
+        }
+        if fixed != errors {
+            return None;
+        }
+        Some(fixed)
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    fn read(&mut self, count: usize) -> Result<u32> {
+        if count > self.remaining() {
+            anyhow::bail!("Data ends in the middle of a segment");
+        }
+        let mut value = 0u32;
+        for _ in 0..count {
+            let bit = (self.bytes[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Decodes the segment stream back into the original bytes and the first ECI designator
+fn parse_segments(data: &[u8], version: i16) -> Result<(Vec<u8>, Option<u32>)> {
+    let size_class = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let count_bits = |mode: usize| [[10, 12, 14], [9, 11, 13], [8, 16, 16], [8, 10, 12]][mode][size_class];
+
+    let mut reader = BitReader { bytes: data, position: 0 };
+    let mut out = Vec::new();
+    let mut fnc1 = false;
+    let mut eci = None;
+
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0b0000 => break,
+            0b0001 => {
+                let mut count = reader.read(count_bits(0))? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader.read([0, 4, 7, 10][digits])?;
+                    out.extend(format!("{:0width$}", value, width = digits).bytes());
+                    count -= digits;
+                }
+            }
+            0b0010 => {
+                let mut count = reader.read(count_bits(1))? as usize;
+                let mut chars = Vec::with_capacity(count);
+                while count > 0 {
+                    if count >= 2 {
+                        let value = reader.read(11)? as usize;
+                        chars.push(*ALPHANUMERIC.get(value / 45).unwrap_or(&b'?'));
+                        chars.push(*ALPHANUMERIC.get(value % 45).unwrap_or(&b'?'));
+                        count -= 2;
+                    } else {
+                        let value = reader.read(6)? as usize;
+                        chars.push(*ALPHANUMERIC.get(value).unwrap_or(&b'?'));
+                        count -= 1;
+                    }
+                }
+                if fnc1 {
+                    // In GS1 mode '%' stands for the group separator and "%%" for '%'
+                    let mut i = 0;
+                    while i < chars.len() {
+                        if chars[i] == b'%' {
+                            if chars.get(i + 1) == Some(&b'%') {
+                                out.push(b'%');
+                                i += 1;
+                            } else {
+                                out.push(0x1d);
+                            }
+                        } else {
+                            out.push(chars[i]);
+                        }
+                        i += 1;
+                    }
+                } else {
+                    out.extend(chars);
+                }
+            }
+            0b0100 => {
+                let count = reader.read(count_bits(2))?;
+                for _ in 0..count {
+                    out.push(reader.read(8)? as u8);
+                }
+            }
+            0b1000 => {
+                let count = reader.read(count_bits(3))?;
+                for _ in 0..count {
+                    let value = reader.read(13)?;
+                    let word = (value / 0xc0) << 8 | (value % 0xc0);
+                    let word = if word < 0x1f00 { word + 0x8140 } else { word + 0xc140 };
+                    out.push((word >> 8) as u8);
+                    out.push(word as u8);
+                }
+            }
+            0b0111 => {
+                // ECI designator: 1, 2 or 3 bytes; the bytes are passed through as-is
+                let first = reader.read(8)?;
+                let designator = match first {
+                    0x00..=0x7f => first,
+                    0x80..=0xbf => (first & 0x3f) << 8 | reader.read(8)?,
+                    _ => (first & 0x1f) << 16 | reader.read(16)?,
+                };
+                eci.get_or_insert(designator);
+            }
+            0b0011 => {
+                // Structured append header: sequence, total and parity
+                reader.read(16)?;
+            }
+            0b0101 => fnc1 = true,
+            0b1001 => {
+                reader.read(8)?;
+                fnc1 = true;
+            }
+            mode => anyhow::bail!("Unknown segment mode {:#06b}", mode),
+        }
+    }
+
+    Ok((out, eci))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capacity::{Mode, max_chars};
+    use crate::{Args, build_symbol, generate_qr_image};
+    use clap::Parser;
+
+    const LEVELS: [(&str, EcLevel); 4] = [("L", EcLevel::L), ("M", EcLevel::M), ("Q", EcLevel::Q), ("H", EcLevel::H)];
+
+    /// `data` through the regular PNG pipeline with the given options
+    fn render(data: &str, options: &[&str]) -> DynamicImage {
+        let mut argv = vec!["RustQR", "--data", data];
+        argv.extend_from_slice(options);
+        let args = Args::try_parse_from(argv).unwrap();
+        generate_qr_image(&build_symbol(data, &args).unwrap(), &args).unwrap()
+    }
+
+    fn assert_round_trip(data: &str, options: &[&str]) -> Decoded {
+        let decoded = decode_image(&render(data, options))
+            .unwrap_or_else(|e| panic!("{} characters with {:?}: {}", data.len(), options, e));
+        assert_eq!(decoded.text(), data, "{:?}", options);
+        decoded
+    }
+
+    /// `count` characters of `pattern` repeated
+    fn repeat(pattern: &str, count: usize) -> String {
+        pattern.chars().cycle().take(count).collect()
+    }
+
+    #[test]
+    fn round_trips_every_version_level_and_mode() {
+        for version in 1..=40i16 {
+            for (name, level) in LEVELS {
+                let payloads = [
+                    repeat("3141592653589793238462643383279", max_chars(version, level, Mode::Numeric)),
+                    repeat("0", max_chars(version, level, Mode::Numeric)),
+                    repeat("HELLO WORLD $%*+-./: 42", max_chars(version, level, Mode::Alphanumeric)),
+                    repeat("the quick brown fox, jumps over 13 lazy dogs!", max_chars(version, level, Mode::Byte)),
+                    repeat("x", max_chars(version, level, Mode::Byte)),
+                ];
+                // Three pixels per module and no quiet zone, the tightest the default output gets
+                let size = ((17 + 4 * version as u32) * 3).to_string();
+                let version_arg = version.to_string();
+                for data in &payloads {
+                    let decoded = assert_round_trip(data, &["--version", &version_arg, "--error", name, "--size", &size]);
+                    assert_eq!((decoded.version, decoded.ec_level), (version, level));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_repetitive_payloads_at_large_sizes() {
+        for (pattern, count) in [("x", 500), ("x", 700), ("x", 1000), ("0", 300), ("0", 600), ("0", 1200)] {
+            for size in ["2000", "4000"] {
+                assert_round_trip(&repeat(pattern, count), &["--size", size]);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_styled_codes() {
+        let data = "https://example.com/styled";
+        for options in [
+            &["--dot-style", "circle", "--border", "4"][..],
+            &["--dot-style", "rounded", "--eye-style", "circle", "--border", "4"],
+            &["--fg-color", "#ffffff", "--bg-color", "#000000", "--border", "4"],
+        ] {
+            assert_round_trip(data, options);
+        }
+    }
+
+    #[test]
+    fn reed_solomon_corrects_up_to_half_the_ec_codewords() {
+        let gf = Galois::new();
+        let data: Vec<u8> = (0..40u8).map(|i| i.wrapping_mul(37) ^ 0x5a).collect();
+        for ec_len in [7, 10, 18, 30] {
+            let mut block = data.clone();
+            block.extend(qrcode::ec::create_error_correction_code(&data, ec_len));
+            let clean = block.clone();
+            assert_eq!(gf.correct(&mut block.clone(), ec_len), Some(0));
+
+            let mut damaged = block.clone();
+            for i in 0..ec_len / 2 {
+                damaged[i * 3 % block.len()] ^= 0xa5;
+            }
+            assert_eq!(gf.correct(&mut damaged, ec_len), Some(ec_len / 2));
+            assert_eq!(damaged, clean);
+
+            // One error past the limit must not come back as the original block
+            let mut ruined = block;
+            for byte in ruined.iter_mut().take(ec_len / 2 + 1) {
+                *byte ^= 0xff;
+            }
+            let repaired = gf.correct(&mut ruined, ec_len);
+            assert!(repaired.is_none() || ruined != clean);
+        }
+    }
+
+    #[test]
+    fn correct_blocks_deinterleaves_and_repairs() {
+        for version in [1, 5, 10, 20, 40] {
+            for (_, level) in LEVELS {
+                let data_len = qrcode::bits::Bits::new(Version::Normal(version)).max_len(level).unwrap() / 8;
+                let data: Vec<u8> = (0..data_len).map(|i| (i * 7 + 3) as u8).collect();
+                let (data_codewords, ec_codewords) = qrcode::ec::construct_codewords(&data, Version::Normal(version), level).unwrap();
+                let mut codewords = [data_codewords, ec_codewords].concat();
+                // Damage a few codewords spread over different blocks
+                for i in [0, codewords.len() / 3, codewords.len() - 1] {
+                    codewords[i] ^= 0x3c;
+                }
+                assert_eq!(correct_blocks(&codewords, version, level).unwrap(), data, "version {} {:?}", version, level);
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::Args;
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use anyhow::{Context, Result};
+use base64::{Engine as _, engine::general_purpose};
+use serde_json::{Map, Value, json};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A generated file on its way to a webhook, mailbox or chat
+pub struct Delivery {
+    pub name: String,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+    /// File name, format, symbol, pixel size and any --meta entries
+    pub details: Value,
+}
+
+impl Delivery {
+    pub fn new(path: &Path, format: &str, bytes: Vec<u8>, symbol: String, size: (u32, u32), metadata: &[(String, String)]) -> Self {
+        // Streams to stdout have no name of their own
+        let name = match path.file_name() {
+            Some(name) if path != Path::new("-") => name.to_string_lossy().into_owned(),
+            _ => format!("qrcode.{}", format),
+        };
+        let metadata: Map<String, Value> = metadata.iter().map(|(key, value)| (key.clone(), Value::String(value.clone()))).collect();
+        let details = json!({
+            "file": name,
+            "format": format,
+            "symbol": symbol,
+            "width": size.0,
+            "height": size.1,
+            "metadata": metadata,
+        });
+        Delivery { name, content_type: content_type(format), bytes, details }
+    }
+}
+
+/// Whether any delivery option is set, so the file has to be encoded even when nothing is saved
+pub fn requested(args: &Args) -> bool {
+    args.webhook.is_some() || !args.mail_to.is_empty() || args.telegram.is_some()
+}
+
+pub fn send(args: &Args, delivery: &Delivery) -> Result<()> {
+    if let Some(ref url) = args.webhook {
+        webhook(url, &args.webhook_format, delivery)?;
+        log::info!("✓ Posted {} to the webhook", delivery.name);
+    }
+    if !args.mail_to.is_empty() {
+        email(&args.mail_to, &args.mail_subject, delivery)?;
+        log::info!("✓ Emailed {} to {}", delivery.name, args.mail_to.join(", "));
+    }
+    if let Some(ref chat) = args.telegram {
+        telegram(chat, delivery)?;
+        log::info!("✓ Sent {} to Telegram chat {}", delivery.name, chat);
+    }
+    Ok(())
+}
+
+/// POSTs the file as multipart form data (`file` and `metadata` fields) or as JSON with the image in base64
+fn webhook(url: &str, format: &str, delivery: &Delivery) -> Result<()> {
+    let mut cmd = curl();
+    let body = match format {
+        "json" => {
+            let mut body = delivery.details.clone();
+            body["content_type"] = json!(delivery.content_type);
+            body["image"] = json!(general_purpose::STANDARD.encode(&delivery.bytes));
+            cmd.args(["--header", "Content-Type: application/json", "--data-binary", "@-"]);
+            serde_json::to_vec(&body)?
+        }
+        _ => {
+            cmd.arg("--form-string").arg(format!("metadata={}", delivery.details));
+            cmd.arg("--form").arg(format!("file=@-;filename=\"{}\";type={}", quote(&delivery.name), delivery.content_type));
+            delivery.bytes.clone()
+        }
+    };
+    cmd.arg(url);
+    run(cmd, &body).context("Webhook failed")
+}
+
+/// Mails the file as an attachment, with an inline preview for formats mail clients display.
+/// The server comes from RUSTQR_SMTP_URL (smtps://host or smtp://host:587), the login from
+/// RUSTQR_SMTP_USER and RUSTQR_SMTP_PASSWORD, the sender from RUSTQR_SMTP_FROM or the login.
+fn email(recipients: &[String], subject: &str, delivery: &Delivery) -> Result<()> {
+    let setting = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let url = setting("RUSTQR_SMTP_URL").context("Set RUSTQR_SMTP_URL (e.g. smtps://smtp.example.com) to send email")?;
+    let user = setting("RUSTQR_SMTP_USER");
+    let from = setting("RUSTQR_SMTP_FROM")
+        .or_else(|| user.clone())
+        .context("Set RUSTQR_SMTP_FROM to the sender address")?;
+    if recipients.iter().chain([&from]).any(|address| !address.contains('@') || address.contains(['\r', '\n', '<', '>'])) {
+        anyhow::bail!("Email addresses must look like name@example.com");
+    }
+
+    let mut cmd = curl();
+    cmd.arg("--url").arg(&url).arg("--mail-from").arg(&from);
+    for recipient in recipients {
+        cmd.arg("--mail-rcpt").arg(recipient);
+    }
+    cmd.args(["--upload-file", "-"]);
+    // Only a relay on this machine may be reached without TLS
+    let host = url.split("://").nth(1).unwrap_or("").split([':', '/']).next().unwrap_or("");
+    if !matches!(host, "localhost" | "127.0.0.1" | "[::1]") {
+        cmd.arg("--ssl-reqd");
+    }
+
+    // The login goes through a private config file rather than the command line, which would show
+    // the password in the process list
+    let config = match user {
+        Some(ref user) => {
+            let password = std::env::var("RUSTQR_SMTP_PASSWORD").unwrap_or_default();
+            format!("user = \"{}:{}\"\n", quote(user), quote(&password))
+        }
+        None => String::new(),
+    };
+    run_with_config(cmd, &config, mime_message(&from, recipients, subject, delivery).as_bytes()).context("Sending email failed")
+}
+
+/// Sends the file to a Telegram chat as a document, so it arrives uncompressed and still scans.
+/// The bot token comes from RUSTQR_TELEGRAM_TOKEN; RUSTQR_TELEGRAM_API points at a self-hosted Bot API server.
+fn telegram(chat: &str, delivery: &Delivery) -> Result<()> {
+    let token = std::env::var("RUSTQR_TELEGRAM_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .context("Set RUSTQR_TELEGRAM_TOKEN to the bot token from @BotFather to send to Telegram")?;
+    if token.contains(['"', '\\', '/', '\r', '\n']) {
+        anyhow::bail!("RUSTQR_TELEGRAM_TOKEN does not look like a bot token");
+    }
+    let api = std::env::var("RUSTQR_TELEGRAM_API").unwrap_or_else(|_| "https://api.telegram.org".to_string());
+
+    let mut cmd = curl();
+    cmd.arg("--form-string").arg(format!("chat_id={}", chat.trim()));
+    let caption = format!("{}\n{}", delivery.name, delivery.details["symbol"].as_str().unwrap_or_default());
+    cmd.arg("--form-string").arg(format!("caption={}", caption));
+    cmd.arg("--form").arg(format!("document=@-;filename=\"{}\";type={}", quote(&delivery.name), delivery.content_type));
+    // The token is part of the URL, so it goes through the config file like the SMTP login
+    let config = format!("url = \"{}/bot{}/sendDocument\"\n", quote(api.trim_end_matches('/')), token);
+    run_with_config(cmd, &config, &delivery.bytes).context("Sending to Telegram failed")
+}
+
+/// Runs curl with extra options from a private config file, removed afterwards; for secrets that
+/// must not show up in the process list. Stdin already carries the upload, so the options cannot
+/// go through `--config -`.
+fn run_with_config(mut cmd: Command, config: &str, input: &[u8]) -> Result<()> {
+    if config.is_empty() {
+        return run(cmd, input);
+    }
+    // A random name that must not exist yet, so nobody can plant the file or a link in its place
+    let mut random = [0u8; 16];
+    OsRng.fill_bytes(&mut random);
+    let name: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let path = std::env::temp_dir().join(format!("rustqr-curl-{}.conf", name));
+    write_private(&path, config.as_bytes())?;
+    cmd.arg("--config").arg(&path);
+    let result = run(cmd, input);
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Creates a new file only the current user can read; an existing file is an error
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    if let Err(e) = file.write_all(contents) {
+        let _ = std::fs::remove_file(path);
+        return Err(e).with_context(|| format!("Failed to write {}", path.display()));
+    }
+    Ok(())
+}
+
+/// A multipart message: an HTML part with the code shown inline, and the file as an attachment
+fn mime_message(from: &str, recipients: &[String], subject: &str, delivery: &Delivery) -> String {
+    let boundary = format!("rustqr-{}", chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    let encoded = wrap_base64(&delivery.bytes);
+    let inline = matches!(delivery.content_type, "image/png" | "image/jpeg" | "image/gif");
+    let subject = subject.replace(['\r', '\n'], " ");
+    let subject = if subject.is_ascii() { subject } else { format!("=?UTF-8?B?{}?=", general_purpose::STANDARD.encode(subject)) };
+    let name = quote(&delivery.name);
+
+    let mut html = format!("<p>Your QR code is attached as <b>{}</b>.</p>", crate::xml_escape(&delivery.name));
+    if inline {
+        html.push_str("<p><img src=\"cid:qrcode@rustqr\" alt=\"QR code\"></p>");
+    }
+    let mut message = format!(
+        "From: {from}\r\nTo: {}\r\nSubject: {subject}\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: multipart/mixed; boundary=\"{boundary}\"\r\n\r\n\
+         --{boundary}\r\nContent-Type: multipart/related; boundary=\"{boundary}-r\"\r\n\r\n\
+         --{boundary}-r\r\nContent-Type: text/html; charset=utf-8\r\n\r\n{html}\r\n",
+        recipients.join(", "),
+        chrono::Utc::now().to_rfc2822(),
+    );
+    if inline {
+        message.push_str(&format!(
+            "--{boundary}-r\r\nContent-Type: {}\r\nContent-Transfer-Encoding: base64\r\n\
+             Content-ID: <qrcode@rustqr>\r\nContent-Disposition: inline; filename=\"{name}\"\r\n\r\n{encoded}",
+            delivery.content_type
+        ));
+    }
+    message.push_str(&format!(
+        "--{boundary}-r--\r\n--{boundary}\r\nContent-Type: {}; name=\"{name}\"\r\nContent-Transfer-Encoding: base64\r\n\
+         Content-Disposition: attachment; filename=\"{name}\"\r\n\r\n{encoded}--{boundary}--\r\n",
+        delivery.content_type
+    ));
+    message
+}
+
+/// Base64 in 76-character lines, as MIME requires
+fn wrap_base64(bytes: &[u8]) -> String {
+    let encoded = general_purpose::STANDARD.encode(bytes);
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / 76 * 2 + 2);
+    for line in encoded.as_bytes().chunks(76) {
+        wrapped.push_str(std::str::from_utf8(line).unwrap_or_default());
+        wrapped.push_str("\r\n");
+    }
+    wrapped
+}
+
+fn content_type(format: &str) -> &'static str {
+    match format {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "ico" => "image/x-icon",
+        "dxf" => "image/vnd.dxf",
+        "stl" => "model/stl",
+        "3mf" => "model/3mf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Escapes a value for a double-quoted curl or MIME parameter
+fn quote(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn curl() -> Command {
+    let mut cmd = Command::new("curl");
+    cmd.args(["--silent", "--show-error", "--fail-with-body", "--max-time", "60"]);
+    cmd
+}
+
+/// Runs curl with `input` on stdin, failing with its error message and the server's reply
+fn run(mut cmd: Command, input: &[u8]) -> Result<()> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run 'curl'. Is it installed and on the PATH?")?;
+    let mut stdin = child.stdin.take().context("Failed to write to 'curl'")?;
+    // Feed stdin from a thread so a chatty server cannot fill the output pipe and stall both sides
+    let output = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(input));
+        let output = child.wait_with_output();
+        let _ = writer.join();
+        output
+    })
+    .context("Failed to wait for 'curl'")?;
+    if !output.status.success() {
+        let reply = String::from_utf8_lossy(&output.stdout);
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{} {}", error.trim(), reply.trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::decode::Decoded;
+use image::{Rgba, RgbaImage};
+
+/// Pixels per module in the diff image
+const MODULE_PX: u32 = 12;
+/// Light modules around the grid
+const MARGIN: u32 = 4;
+
+const SAME_DARK: Rgba<u8> = Rgba([64, 64, 64, 255]);
+const SAME_LIGHT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+/// Dark only in the first image
+const REMOVED: Rgba<u8> = Rgba([220, 40, 40, 255]);
+/// Dark only in the second image
+const ADDED: Rgba<u8> = Rgba([30, 160, 60, 255]);
+
+/// Number of modules that differ; None when the codes are not the same size
+pub fn count_changes(first: &Decoded, second: &Decoded) -> Option<usize> {
+    (first.size() == second.size()).then(|| first.modules.iter().zip(&second.modules).filter(|(a, b)| a != b).count())
+}
+
+/// Module-by-module comparison: grey and white where the codes agree, red where only the first
+/// is dark and green where only the second is
+pub fn render(first: &Decoded, second: &Decoded) -> Option<RgbaImage> {
+    if first.size() != second.size() {
+        return None;
+    }
+    let size = first.size() as u32;
+    let side = (size + 2 * MARGIN) * MODULE_PX;
+    let mut img = RgbaImage::from_pixel(side, side, SAME_LIGHT);
+    for y in 0..size {
+        for x in 0..size {
+            let color = match (first.is_dark(x as usize, y as usize), second.is_dark(x as usize, y as usize)) {
+                (true, true) => SAME_DARK,
+                (false, false) => continue,
+                (true, false) => REMOVED,
+                (false, true) => ADDED,
+            };
+            let (px, py) = ((x + MARGIN) * MODULE_PX, (y + MARGIN) * MODULE_PX);
+            for dy in 0..MODULE_PX {
+                for dx in 0..MODULE_PX {
+                    img.put_pixel(px + dx, py + dy, color);
+                }
+            }
+        }
+    }
+    Some(img)
+}
@@ -0,0 +1,76 @@
+use crate::symbol::Symbol;
+use anyhow::Result;
+use std::fmt::Write as _;
+
+/// Layer holding the outline of the whole plate, quiet zone included
+const CUT_LAYER: &str = "CUT";
+/// Layer holding the dark modules
+const ENGRAVE_LAYER: &str = "ENGRAVE";
+
+/// Builds an AutoCAD R12 ASCII drawing in millimeters: the plate outline on the CUT layer and the
+/// dark modules, merged into as few rectangles as possible, as closed polylines on ENGRAVE
+pub fn render_dxf(symbol: &Symbol, size_mm: f32, border: u32) -> Result<String> {
+    if size_mm <= 0.0 {
+        anyhow::bail!("DXF size must be greater than 0 mm");
+    }
+    let nx = symbol.width() + 2 * border as usize;
+    let ny = symbol.height() + 2 * border as usize;
+    let cell_mm = size_mm / nx.max(ny) as f32;
+
+    let mut out = String::new();
+    // $INSUNITS 4 = millimeters, so the drawing imports at its real size
+    out.push_str("0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n9\n$INSUNITS\n70\n4\n9\n$MEASUREMENT\n70\n1\n0\nENDSEC\n");
+    out.push_str("0\nSECTION\n2\nTABLES\n");
+    out.push_str("0\nTABLE\n2\nLTYPE\n70\n1\n0\nLTYPE\n2\nCONTINUOUS\n70\n0\n3\nSolid line\n72\n65\n73\n0\n40\n0.0\n0\nENDTAB\n");
+    out.push_str("0\nTABLE\n2\nLAYER\n70\n2\n");
+    // Red for cutting, blue for engraving, the usual laser software convention
+    for (layer, color) in [(CUT_LAYER, 1), (ENGRAVE_LAYER, 5)] {
+        write!(out, "0\nLAYER\n2\n{}\n70\n0\n62\n{}\n6\nCONTINUOUS\n", layer, color)?;
+    }
+    out.push_str("0\nENDTAB\n0\nENDSEC\n");
+
+    out.push_str("0\nSECTION\n2\nENTITIES\n");
+    write_rectangle(&mut out, CUT_LAYER, 0.0, 0.0, nx as f32 * cell_mm, ny as f32 * cell_mm)?;
+    for (x, y, w, h) in merge_modules(symbol) {
+        // DXF Y points up, module rows count down from the top
+        let left = (x + border as usize) as f32 * cell_mm;
+        let bottom = (ny - (y + border as usize) - h) as f32 * cell_mm;
+        write_rectangle(&mut out, ENGRAVE_LAYER, left, bottom, w as f32 * cell_mm, h as f32 * cell_mm)?;
+    }
+    out.push_str("0\nENDSEC\n0\nEOF\n");
+    Ok(out)
+}
+
+/// Covers the dark modules with rectangles: each one grows right as far as the row allows,
+/// then down while the rows below match, which keeps the engraving paths short
+fn merge_modules(symbol: &Symbol) -> Vec<(usize, usize, usize, usize)> {
+    let (width, height) = (symbol.width(), symbol.height());
+    let mut covered = vec![false; width * height];
+    let free = |covered: &[bool], x: usize, y: usize| symbol.is_dark(x, y) && !covered[y * width + x];
+
+    let mut rects = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !free(&covered, x, y) {
+                continue;
+            }
+            let w = (x..width).take_while(|&cx| free(&covered, cx, y)).count();
+            let h = (y..height).take_while(|&cy| (x..x + w).all(|cx| free(&covered, cx, cy))).count();
+            for cy in y..y + h {
+                covered[cy * width + x..cy * width + x + w].fill(true);
+            }
+            rects.push((x, y, w, h));
+        }
+    }
+    rects
+}
+
+/// A closed R12 polyline, counter-clockwise from the bottom-left corner
+fn write_rectangle(out: &mut String, layer: &str, x: f32, y: f32, w: f32, h: f32) -> Result<()> {
+    write!(out, "0\nPOLYLINE\n8\n{}\n66\n1\n70\n1\n10\n0.0\n20\n0.0\n30\n0.0\n", layer)?;
+    for (vx, vy) in [(x, y), (x + w, y), (x + w, y + h), (x, y + h)] {
+        write!(out, "0\nVERTEX\n8\n{}\n10\n{:.4}\n20\n{:.4}\n30\n0.0\n", layer, vx, vy)?;
+    }
+    write!(out, "0\nSEQEND\n8\n{}\n", layer)?;
+    Ok(())
+}
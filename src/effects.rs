@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use image::{Pixel, Rgba, RgbaImage, imageops};
+
+/// Used when `--shadow` is given without a value: 4 px right, 4 px down, 8 px blur
+pub const DEFAULT_SHADOW: &str = "4,4,8";
+
+/// A soft drop shadow cast by the module layer
+pub struct Shadow {
+    pub dx: i32,
+    pub dy: i32,
+    pub blur: f32,
+    pub color: Rgba<u8>,
+}
+
+impl Shadow {
+    /// Parses `DX,DY,BLUR` in pixels; the blur radius may be left out
+    pub fn parse(spec: &str, color: Rgba<u8>) -> Result<Self> {
+        let parts: Vec<&str> = spec.split(',').map(str::trim).collect();
+        let invalid = || anyhow::anyhow!("Shadow must be DX,DY or DX,DY,BLUR in pixels, got '{}'", spec);
+        if !(2..=3).contains(&parts.len()) {
+            return Err(invalid());
+        }
+        let dx = parts[0].parse().map_err(|_| invalid())?;
+        let dy = parts[1].parse().map_err(|_| invalid())?;
+        let blur: f32 = parts.get(2).map_or(Ok(0.0), |b| b.parse()).map_err(|_| invalid())?;
+        if blur < 0.0 {
+            return Err(invalid());
+        }
+        Ok(Shadow { dx, dy, blur, color })
+    }
+
+    /// Gaussian standard deviation for a blur radius (the radius covers about two deviations)
+    pub fn sigma(&self) -> f32 {
+        self.blur / 2.0
+    }
+}
+
+/// Composites the module layer over its own blurred, tinted silhouette on the background
+pub fn cast_shadow(modules: &RgbaImage, shadow: &Shadow, background: RgbaImage) -> RgbaImage {
+    let Rgba([r, g, b, a]) = shadow.color;
+    let mut silhouette = RgbaImage::from_fn(modules.width(), modules.height(), |x, y| {
+        let coverage = modules.get_pixel(x, y)[3] as u32;
+        Rgba([r, g, b, (coverage * a as u32 / 255) as u8])
+    });
+    if shadow.sigma() > 0.0 {
+        silhouette = imageops::blur(&silhouette, shadow.sigma());
+    }
+
+    let mut out = background;
+    imageops::overlay(&mut out, &silhouette, shadow.dx as i64, shadow.dy as i64);
+    imageops::overlay(&mut out, modules, 0, 0);
+    out
+}
+
+/// An outline drawn along the inside edge of the dark modules
+pub struct Stroke {
+    pub width: u32,
+    pub color: Rgba<u8>,
+    /// Outline every module on its own instead of the merged dark region
+    pub per_module: bool,
+}
+
+impl Stroke {
+    pub fn new(width: u32, color: Rgba<u8>, mode: &str) -> Result<Self> {
+        let per_module = match mode.to_lowercase().as_str() {
+            "region" => false,
+            "module" => true,
+            _ => anyhow::bail!("Unknown stroke mode '{}'. Use region or module", mode),
+        };
+        Ok(Stroke { width, color, per_module })
+    }
+}
+
+/// Paints the stroke over the module layer. It stays inside the dark area, so module sizes and
+/// the light gaps between them keep their geometry.
+pub fn apply_stroke(modules: &mut RgbaImage, stroke: &Stroke, module_size: u32) {
+    let (width, height) = modules.dimensions();
+    let dark: Vec<bool> = modules.pixels().map(|p| p[3] >= 128).collect();
+    let is_dark = |x: i64, y: i64| {
+        x >= 0 && y >= 0 && x < width as i64 && y < height as i64 && dark[(y as u32 * width + x as u32) as usize]
+    };
+    let radius = stroke.width as i64;
+    let cell = |v: i64| v / module_size.max(1) as i64;
+
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            if !is_dark(x, y) {
+                continue;
+            }
+            let on_edge = (-radius..=radius).any(|dy| {
+                (-radius..=radius).any(|dx| {
+                    if dx * dx + dy * dy > radius * radius {
+                        return false;
+                    }
+                    let (nx, ny) = (x + dx, y + dy);
+                    let other_module = stroke.per_module && (cell(nx) != cell(x) || cell(ny) != cell(y));
+                    other_module || !is_dark(nx, ny)
+                })
+            });
+            if on_edge {
+                modules.get_pixel_mut(x as u32, y as u32).blend(&stroke.color);
+            }
+        }
+    }
+}
+
+/// Ink texture multiplied over the dark modules
+pub enum Texture {
+    /// Soft, cloudy unevenness like ink soaked into paper
+    Paper,
+    /// Fine per-pixel speckle like a rubber stamp
+    Grain,
+    /// A user image, tiled from the top-left corner
+    Image(RgbaImage),
+}
+
+impl Texture {
+    /// `paper`, `grain`, or a path to an image file
+    pub fn load(spec: &str) -> Result<Self> {
+        match spec.to_lowercase().as_str() {
+            "paper" => Ok(Texture::Paper),
+            "grain" => Ok(Texture::Grain),
+            _ => {
+                let img = image::open(spec)
+                    .with_context(|| format!("Failed to load texture '{}' (use paper, grain or an image file)", spec))?;
+                Ok(Texture::Image(img.to_rgba8()))
+            }
+        }
+    }
+}
+
+/// Multiplies the texture into the module layer; transparent pixels (the background) stay untouched
+pub fn apply_texture(modules: &mut RgbaImage, texture: &Texture, seed: u64) {
+    for (x, y, pixel) in modules.enumerate_pixels_mut() {
+        if pixel[3] == 0 {
+            continue;
+        }
+        let factor = match texture {
+            Texture::Grain => {
+                let f = 0.72 + 0.28 * noise(x, y, seed);
+                [f; 3]
+            }
+            Texture::Paper => {
+                let clouds = 0.6 * smooth_noise(x, y, 24.0, seed) + 0.4 * smooth_noise(x, y, 6.0, seed ^ 0x5eed);
+                let f = 0.7 + 0.3 * clouds;
+                [f; 3]
+            }
+            Texture::Image(img) => {
+                let t = img.get_pixel(x % img.width(), y % img.height());
+                // Transparent texture pixels leave the ink as it is
+                let weight = t[3] as f32 / 255.0;
+                [0, 1, 2].map(|i| 1.0 - weight * (1.0 - t[i] as f32 / 255.0))
+            }
+        };
+        for i in 0..3 {
+            pixel[i] = (pixel[i] as f32 * factor[i]).round() as u8;
+        }
+    }
+}
+
+/// Deterministic white noise in 0..=1
+pub fn noise(x: u32, y: u32, seed: u64) -> f32 {
+    let mut h = seed ^ ((x as u64) << 32 | y as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^= h >> 31;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Value noise: white noise on a coarse grid, smoothly interpolated in between
+fn smooth_noise(x: u32, y: u32, cell: f32, seed: u64) -> f32 {
+    let (fx, fy) = (x as f32 / cell, y as f32 / cell);
+    let (gx, gy) = (fx.floor() as u32, fy.floor() as u32);
+    let ease = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (ease(fx.fract()), ease(fy.fract()));
+    let top = noise(gx, gy, seed) * (1.0 - tx) + noise(gx + 1, gy, seed) * tx;
+    let bottom = noise(gx, gy + 1, seed) * (1.0 - tx) + noise(gx + 1, gy + 1, seed) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
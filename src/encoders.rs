@@ -0,0 +1,460 @@
+use anyhow::{Context, Result};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::tiff::TiffEncoder;
+use image::{DynamicImage, ImageEncoder, Rgb, RgbImage, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use crate::cmyk::to_cmyk;
+use crate::icc::IccProfile;
+use crate::metadata::{jpeg_with_xmp, png_text_chunks};
+use crate::quantize::{Dither, quantize};
+
+pub struct PngOptions {
+    pub compression: u8,
+    pub interlace: bool,
+    pub palette: bool,
+    /// Written as tEXt/iTXt chunks, in order
+    pub metadata: Vec<(String, String)>,
+    /// Written as an iCCP chunk
+    pub icc_profile: Option<IccProfile>,
+}
+
+/// Adam7 passes as (x_start, y_start, x_step, y_step)
+const ADAM7: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+enum PixelLayout {
+    Indexed { palette: Vec<[u8; 4]>, lookup: HashMap<[u8; 4], u8>, depth: u8 },
+    Rgb,
+    Rgba,
+}
+
+pub fn save_png(img: &RgbaImage, path: &Path, options: &PngOptions) -> Result<()> {
+    let bytes = encode_png(img, options)?;
+    let mut file = File::create(path).context("Failed to create PNG file")?;
+    file.write_all(&bytes).context("Failed to write PNG file")?;
+    Ok(())
+}
+
+/// Writes no tIME chunk and text chunks only on request, so the same image always gives the same bytes
+pub fn encode_png(img: &RgbaImage, options: &PngOptions) -> Result<Vec<u8>> {
+    if options.compression > 9 {
+        anyhow::bail!("PNG compression level must be between 0 and 9");
+    }
+
+    let layout = choose_layout(img, options.palette);
+
+    let (color_type, bit_depth) = match &layout {
+        PixelLayout::Indexed { depth, .. } => (3u8, *depth),
+        PixelLayout::Rgb => (2, 8),
+        PixelLayout::Rgba => (6, 8),
+    };
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&img.width().to_be_bytes());
+    ihdr.extend_from_slice(&img.height().to_be_bytes());
+    ihdr.extend_from_slice(&[bit_depth, color_type, 0, 0, options.interlace as u8]);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+
+    // iCCP has to come before PLTE: name, null separator, compression method, zlib stream
+    if let Some(ref profile) = options.icc_profile {
+        let mut iccp = profile.name.as_bytes().to_vec();
+        iccp.extend_from_slice(&[0, 0]);
+        let mut encoder = ZlibEncoder::new(iccp, Compression::best());
+        encoder.write_all(&profile.data).context("Failed to compress color profile")?;
+        write_chunk(&mut out, b"iCCP", &encoder.finish().context("Failed to compress color profile")?);
+    }
+
+    if let PixelLayout::Indexed { palette, .. } = &layout {
+        let plte: Vec<u8> = palette.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+        write_chunk(&mut out, b"PLTE", &plte);
+
+        // tRNS only needs entries up to the last non-opaque color
+        if let Some(last) = palette.iter().rposition(|c| c[3] != 255) {
+            let trns: Vec<u8> = palette[..=last].iter().map(|c| c[3]).collect();
+            write_chunk(&mut out, b"tRNS", &trns);
+        }
+    }
+
+    for (kind, data) in png_text_chunks(&options.metadata) {
+        write_chunk(&mut out, &kind, &data);
+    }
+
+    let mut raw = Vec::new();
+    if options.interlace {
+        for (x0, y0, dx, dy) in ADAM7 {
+            let xs: Vec<u32> = (x0..img.width()).step_by(dx as usize).collect();
+            let ys: Vec<u32> = (y0..img.height()).step_by(dy as usize).collect();
+            if xs.is_empty() || ys.is_empty() {
+                continue;
+            }
+            let rows: Vec<Vec<u8>> = ys
+                .iter()
+                .map(|&y| pack_row(img, &layout, xs.iter().map(|&x| (x, y))))
+                .collect();
+            filter_rows(&mut raw, &rows, &layout);
+        }
+    } else {
+        let rows: Vec<Vec<u8>> = (0..img.height())
+            .map(|y| pack_row(img, &layout, (0..img.width()).map(|x| (x, y))))
+            .collect();
+        filter_rows(&mut raw, &rows, &layout);
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(options.compression as u32));
+    encoder.write_all(&raw).context("Failed to compress PNG data")?;
+    let idat = encoder.finish().context("Failed to compress PNG data")?;
+    write_chunk(&mut out, b"IDAT", &idat);
+
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+fn choose_layout(img: &RgbaImage, palette: bool) -> PixelLayout {
+    if palette {
+        let mut colors: Vec<[u8; 4]> = Vec::new();
+        for p in img.pixels() {
+            if !colors.contains(&p.0) {
+                if colors.len() == 256 {
+                    log::warn!("⚠ Image has more than 256 colors, writing a truecolor PNG instead");
+                    colors.clear();
+                    break;
+                }
+                colors.push(p.0);
+            }
+        }
+
+        if !colors.is_empty() {
+            // Translucent entries first keeps the tRNS chunk short
+            colors.sort_by_key(|c| c[3] == 255);
+            let depth = match colors.len() {
+                0..=2 => 1,
+                3..=4 => 2,
+                5..=16 => 4,
+                _ => 8,
+            };
+            let lookup = colors.iter().enumerate().map(|(i, c)| (*c, i as u8)).collect();
+            return PixelLayout::Indexed { palette: colors, lookup, depth };
+        }
+    }
+
+    if img.pixels().all(|p| p[3] == 255) {
+        PixelLayout::Rgb
+    } else {
+        PixelLayout::Rgba
+    }
+}
+
+fn pack_row(img: &RgbaImage, layout: &PixelLayout, pixels: impl Iterator<Item = (u32, u32)>) -> Vec<u8> {
+    let mut row = Vec::new();
+    match layout {
+        PixelLayout::Indexed { lookup, depth, .. } => {
+            let per_byte = 8 / depth;
+            let mut current = 0u8;
+            let mut filled = 0;
+            for (x, y) in pixels {
+                let index = lookup[&img.get_pixel(x, y).0];
+                current |= index << (8 - depth * (filled + 1));
+                filled += 1;
+                if filled == per_byte {
+                    row.push(current);
+                    current = 0;
+                    filled = 0;
+                }
+            }
+            if filled > 0 {
+                row.push(current);
+            }
+        }
+        PixelLayout::Rgb => {
+            for (x, y) in pixels {
+                row.extend_from_slice(&img.get_pixel(x, y).0[..3]);
+            }
+        }
+        PixelLayout::Rgba => {
+            for (x, y) in pixels {
+                row.extend_from_slice(&img.get_pixel(x, y).0);
+            }
+        }
+    }
+    row
+}
+
+fn filter_rows(raw: &mut Vec<u8>, rows: &[Vec<u8>], layout: &PixelLayout) {
+    let bpp = match layout {
+        PixelLayout::Indexed { .. } => 1,
+        PixelLayout::Rgb => 3,
+        PixelLayout::Rgba => 4,
+    };
+
+    // Palette images compress best unfiltered; truecolor rows use the
+    // minimum-sum-of-absolute-differences heuristic
+    let adaptive = !matches!(layout, PixelLayout::Indexed { .. });
+
+    let empty = vec![0u8; rows.first().map_or(0, |r| r.len())];
+    for (i, row) in rows.iter().enumerate() {
+        let prev = if i == 0 { &empty } else { &rows[i - 1] };
+
+        if !adaptive {
+            raw.push(0);
+            raw.extend_from_slice(row);
+            continue;
+        }
+
+        let best = (0..5u8)
+            .map(|kind| (kind, apply_filter(kind, row, prev, bpp)))
+            .min_by_key(|(_, data)| data.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum::<u64>())
+            .unwrap();
+        raw.push(best.0);
+        raw.extend_from_slice(&best.1);
+    }
+}
+
+fn apply_filter(kind: u8, row: &[u8], prev: &[u8], bpp: usize) -> Vec<u8> {
+    (0..row.len())
+        .map(|i| {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = prev[i];
+            let c = if i >= bpp { prev[i - bpp] } else { 0 };
+            let predictor = match kind {
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => 0,
+            };
+            row[i].wrapping_sub(predictor)
+        })
+        .collect()
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Baseline JPEG, with an XMP packet and a color profile when given
+pub fn encode_jpeg(img: &RgbImage, quality: u8, xmp: Option<&str>, icc_profile: Option<&IccProfile>) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(&mut bytes, validate_quality(quality)?);
+    if let Some(profile) = icc_profile {
+        encoder
+            .set_icc_profile(profile.data.clone())
+            .context("Failed to embed color profile")?;
+    }
+    encoder.encode_image(img).context("Failed to encode JPEG")?;
+    if let Some(xmp) = xmp {
+        bytes = jpeg_with_xmp(&bytes, xmp)?;
+    }
+    Ok(bytes)
+}
+
+pub fn encode_avif(img: &RgbaImage, quality: u8) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let encoder = AvifEncoder::new_with_speed_quality(&mut bytes, 4, validate_quality(quality)?);
+    img.write_with_encoder(encoder).context("Failed to encode AVIF")?;
+    Ok(bytes)
+}
+
+pub fn encode_tiff(img: &DynamicImage, icc_profile: Option<&IccProfile>) -> Result<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut bytes);
+    if let Some(profile) = icc_profile {
+        encoder
+            .set_icc_profile(profile.data.clone())
+            .context("Failed to embed color profile")?;
+    }
+    img.write_with_encoder(encoder).context("Failed to encode TIFF")?;
+    Ok(bytes.into_inner())
+}
+
+/// CMYK TIFF for prepress, PackBits-compressed, with the resolution set when known
+pub fn encode_cmyk_tiff(img: &RgbImage, rich_black: Option<[u8; 4]>, dpi: Option<u32>) -> Result<Vec<u8>> {
+    use tiff::encoder::{Compression as TiffCompression, Rational, TiffEncoder as CmykEncoder, colortype};
+    use tiff::tags::ResolutionUnit;
+
+    let mut bytes = Cursor::new(Vec::new());
+    let mut encoder = CmykEncoder::new(&mut bytes)
+        .context("Failed to encode TIFF")?
+        .with_compression(TiffCompression::Packbits);
+    let mut image = encoder
+        .new_image::<colortype::CMYK8>(img.width(), img.height())
+        .context("Failed to encode TIFF")?;
+    if let Some(dpi) = dpi {
+        image.resolution(ResolutionUnit::Inch, Rational { n: dpi, d: 1 });
+    }
+    image
+        .write_data(&to_cmyk(img, rich_black))
+        .context("Failed to encode TIFF")?;
+    Ok(bytes.into_inner())
+}
+
+/// Single-frame GIF with a palette fitted to the image
+pub fn encode_gif(img: &RgbaImage, dither: Dither) -> Result<Vec<u8>> {
+    let (width, height) = (
+        u16::try_from(img.width()).context("GIF images are at most 65535 pixels wide")?,
+        u16::try_from(img.height()).context("GIF images are at most 65535 pixels tall")?,
+    );
+    let indexed = quantize(img, dither);
+    let palette: Vec<u8> = indexed.palette.concat();
+
+    let mut bytes = Vec::new();
+    let mut encoder = gif::Encoder::new(&mut bytes, width, height, &palette).context("Failed to encode GIF")?;
+    let frame = gif::Frame::from_indexed_pixels(width, height, indexed.pixels, indexed.transparent);
+    encoder.write_frame(&frame).context("Failed to encode GIF")?;
+    drop(encoder);
+    Ok(bytes)
+}
+
+/// Lossless WebP unless a quality is given; lossy ringing around
+/// module edges hurts scan rates, so lossless is the default
+#[cfg(feature = "native")]
+pub fn encode_webp(img: &RgbaImage, quality: Option<u8>) -> Result<Vec<u8>> {
+    let encoder = webp::Encoder::from_rgba(img.as_raw(), img.width(), img.height());
+    let data = match quality {
+        Some(q) => encoder.encode(validate_quality(q)? as f32),
+        None => encoder.encode_lossless(),
+    };
+    Ok(data.to_vec())
+}
+
+#[cfg(not(feature = "native"))]
+pub fn encode_webp(_img: &RgbaImage, _quality: Option<u8>) -> Result<Vec<u8>> {
+    anyhow::bail!("This build has no WebP encoder, --format webp needs the native feature")
+}
+
+/// Writes one page per image, each stretched over a page of the given size in millimeters
+pub fn save_pdf(pages: &[RgbImage], page_size: (f32, f32), path: &Path) -> Result<()> {
+    let bytes = encode_pdf(pages, page_size)?;
+    let mut file = File::create(path).context("Failed to create PDF file")?;
+    file.write_all(&bytes).context("Failed to write PDF file")?;
+    Ok(())
+}
+
+/// Minimal PDF without an info dictionary or file ID, so it carries no creation date
+pub fn encode_pdf(pages: &[RgbImage], page_size: (f32, f32)) -> Result<Vec<u8>> {
+    let width_pt = page_size.0 / 25.4 * 72.0;
+    let height_pt = page_size.1 / 25.4 * 72.0;
+
+    // Objects: 1 catalog, 2 page tree, then (page, content, image) per page
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", 3 + i * 3)).collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+
+    for (i, page) in pages.iter().enumerate() {
+        let page_id = 3 + i * 3;
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents {} 0 R /Resources << /XObject << /Im0 {} 0 R >> >> >>",
+                width_pt,
+                height_pt,
+                page_id + 1,
+                page_id + 2
+            )
+            .into_bytes(),
+        );
+
+        let content = format!("q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q", width_pt, height_pt);
+        objects.push(pdf_stream("", content.as_bytes()));
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(page.as_raw()).context("Failed to compress PDF image")?;
+        let pixels = encoder.finish().context("Failed to compress PDF image")?;
+        let dict = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode",
+            page.width(),
+            page.height()
+        );
+        objects.push(pdf_stream(&dict, &pixels));
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = out.len();
+    out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend_from_slice(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes(),
+    );
+    Ok(out)
+}
+
+fn pdf_stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let entries = [dict.to_string(), format!("/Length {}", data.len())].join(" ");
+    let mut out = format!("<< {} >>\nstream\n", entries.trim()).into_bytes();
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\nendstream");
+    out
+}
+
+/// Composites the image onto an opaque matte for formats without an alpha channel
+pub fn apply_matte(img: &RgbaImage, matte: Rgba<u8>) -> RgbImage {
+    RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let p = img.get_pixel(x, y);
+        let alpha = p[3] as f32 / 255.0;
+        let blend = |c: u8, m: u8| (c as f32 * alpha + m as f32 * (1.0 - alpha)).round() as u8;
+        Rgb([blend(p[0], matte[0]), blend(p[1], matte[1]), blend(p[2], matte[2])])
+    })
+}
+
+fn validate_quality(quality: u8) -> Result<u8> {
+    if !(1..=100).contains(&quality) {
+        anyhow::bail!("Quality must be between 1 and 100");
+    }
+    Ok(quality)
+}
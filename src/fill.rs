@@ -0,0 +1,270 @@
+use crate::parse_color;
+use anyhow::{Context, Result};
+use base64::{Engine as _, engine::general_purpose};
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+/// Brightest luma (Rec. 709, 0-255) an image fill keeps; lighter pixels are darkened to it so the
+/// modules still read as dark against a light background
+const MAX_IMAGE_LUMA: f32 = 100.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// Diagonal bands, two per module
+    Stripes,
+    /// Squares of half a module
+    Checkerboard,
+    /// Dots of the second color on the first, four per module
+    Dots,
+}
+
+/// How dark modules are painted, shared by the raster and SVG renderers
+pub enum Fill {
+    Solid(Rgba<u8>),
+    Pattern { pattern: Pattern, colors: [Rgba<u8>; 2] },
+    /// A left-to-right gradient that runs smoothly across the code rather than stepping per module;
+    /// `border` and `modules` place it in module units
+    Gradient { colors: (Rgba<u8>, Rgba<u8>), border: u32, modules: u32 },
+    /// An image clipped to the modules, already tiled or scaled to the canvas, with the share of its
+    /// pixels that had to be darkened
+    Image { pixels: RgbaImage, darkened: f32 },
+}
+
+impl Fill {
+    /// A color, or `pattern:KIND:COLOR,COLOR` with stripes, checkerboard or dots
+    pub fn parse(spec: &str) -> Result<Self> {
+        let Some(rest) = spec.trim().strip_prefix("pattern:") else {
+            return Ok(Fill::Solid(parse_color(spec)?));
+        };
+        let usage = || format!("Invalid --fill '{}', use pattern:stripes|checkerboard|dots:COLOR,COLOR", spec);
+        let (kind, colors) = rest.split_once(':').with_context(usage)?;
+        let pattern = match kind.to_lowercase().as_str() {
+            "stripes" => Pattern::Stripes,
+            "checkerboard" | "checker" => Pattern::Checkerboard,
+            "dots" => Pattern::Dots,
+            _ => anyhow::bail!("Unknown fill pattern '{}'. Use stripes, checkerboard or dots", kind),
+        };
+        let (first, second) = colors.split_once(',').with_context(usage)?;
+        Ok(Fill::Pattern { pattern, colors: [parse_color(first.trim())?, parse_color(second.trim())?] })
+    }
+
+    /// `--fg-image`: the image tiled at its own size or scaled to the canvas, over `ink` where it is
+    /// transparent, with light areas darkened
+    pub fn image(path: &Path, tile: bool, width: u32, height: u32, ink: Rgba<u8>) -> Result<Self> {
+        let source = image::open(path)
+            .with_context(|| format!("Failed to load foreground image: {}", path.display()))?
+            .to_rgba8();
+        let mut canvas = if tile {
+            RgbaImage::from_fn(width, height, |x, y| *source.get_pixel(x % source.width(), y % source.height()))
+        } else {
+            image::imageops::resize(&source, width, height, FilterType::Triangle)
+        };
+
+        let mut darkened = 0;
+        for pixel in canvas.pixels_mut() {
+            let alpha = pixel[3] as f32 / 255.0;
+            let mut rgb = [0, 1, 2].map(|i| pixel[i] as f32 * alpha + ink[i] as f32 * (1.0 - alpha));
+            let luma = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+            if luma > MAX_IMAGE_LUMA {
+                rgb = rgb.map(|c| c * MAX_IMAGE_LUMA / luma);
+                darkened += 1;
+            }
+            *pixel = Rgba([rgb[0].round() as u8, rgb[1].round() as u8, rgb[2].round() as u8, 255]);
+        }
+        Ok(Fill::Image { pixels: canvas, darkened: darkened as f32 / (width * height).max(1) as f32 })
+    }
+
+    /// The flat colors it paints with; gradients and images are left to their own checks
+    pub fn colors(&self) -> Vec<Rgba<u8>> {
+        match self {
+            Fill::Solid(color) => vec![*color],
+            Fill::Pattern { colors, .. } => colors.to_vec(),
+            Fill::Gradient { .. } | Fill::Image { .. } => Vec::new(),
+        }
+    }
+
+    /// The color at pixel (x, y) of an image with `scale` pixels per module
+    pub fn color_at(&self, x: u32, y: u32, scale: u32) -> Rgba<u8> {
+        let (pattern, colors) = match self {
+            Fill::Solid(color) => return *color,
+            Fill::Gradient { colors, border, modules } => {
+                let t = (x as f32 + 0.5 - (border * scale) as f32) / (modules * scale) as f32;
+                return crate::interpolate_gradient(colors, t.clamp(0.0, 1.0));
+            }
+            Fill::Image { pixels, .. } => return *pixels.get_pixel(x.min(pixels.width() - 1), y.min(pixels.height() - 1)),
+            Fill::Pattern { pattern, colors } => (pattern, colors),
+        };
+        let half = (scale as f32 / 2.0).max(1.0);
+        let (fx, fy) = (x as f32 + 0.5, y as f32 + 0.5);
+        let second = match pattern {
+            // Distance across the bands, which run at 45°
+            Pattern::Stripes => ((fx + fy) / std::f32::consts::SQRT_2 / half) as u32 % 2 == 1,
+            Pattern::Checkerboard => ((fx / half) as u32 + (fy / half) as u32) % 2 == 1,
+            Pattern::Dots => {
+                let (cx, cy) = ((fx / half).fract() - 0.5, (fy / half).fract() - 0.5);
+                cx.hypot(cy) < 0.3
+            }
+        };
+        colors[second as usize]
+    }
+
+    /// Repaints every drawn pixel of a module layer, keeping its coverage
+    pub fn apply(&self, modules: &mut RgbaImage, scale: u32) {
+        for (x, y, pixel) in modules.enumerate_pixels_mut() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            let color = self.color_at(x, y, scale);
+            *pixel = Rgba([color[0], color[1], color[2], (color[3] as u16 * pixel[3] as u16 / 255) as u8]);
+        }
+    }
+
+    /// SVG paint for `fill=`, referring to the pattern written by `svg_defs` under the same id
+    pub fn svg_paint(&self, id: &str) -> String {
+        match self {
+            Fill::Solid(color) => svg_color(*color),
+            Fill::Gradient { .. } | Fill::Pattern { .. } | Fill::Image { .. } => format!("url(#{})", id),
+        }
+    }
+
+    /// A `<pattern>` matching `color_at`, for `scale` SVG units per module on a canvas of `size` units
+    pub fn svg_defs(&self, id: &str, scale: u32, size: (f32, f32)) -> Result<Option<String>> {
+        let (pattern, colors) = match self {
+            Fill::Solid(_) => return Ok(None),
+            Fill::Gradient { colors, border, modules } => {
+                return Ok(Some(format!(
+                    r#"  <defs>
+    <linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="0" x2="{}" y2="0">
+      <stop offset="0%" stop-color="{}"/>
+      <stop offset="100%" stop-color="{}"/>
+    </linearGradient>
+  </defs>
+"#,
+                    id,
+                    border * scale,
+                    (border + modules) * scale,
+                    svg_color(colors.0),
+                    svg_color(colors.1),
+                )));
+            }
+            Fill::Image { pixels, .. } => {
+                let mut png = std::io::Cursor::new(Vec::new());
+                pixels.write_to(&mut png, image::ImageFormat::Png)?;
+                return Ok(Some(format!(
+                    r#"  <defs>
+    <pattern id="{}" patternUnits="userSpaceOnUse" width="{w}" height="{h}">
+      <image width="{w}" height="{h}" preserveAspectRatio="none" href="data:image/png;base64,{}"/>
+    </pattern>
+  </defs>
+"#,
+                    id,
+                    general_purpose::STANDARD.encode(png.into_inner()),
+                    w = size.0,
+                    h = size.1,
+                )));
+            }
+            Fill::Pattern { pattern, colors } => (pattern, colors),
+        };
+        let half = scale as f32 / 2.0;
+        let [first, second] = colors.map(svg_color);
+        let body = match pattern {
+            Pattern::Stripes => format!(
+                r#"<pattern id="{id}" patternUnits="userSpaceOnUse" width="{w}" height="{w}" patternTransform="rotate(45)">
+      <rect width="{half}" height="{w}" fill="{first}"/>
+      <rect x="{half}" width="{half}" height="{w}" fill="{second}"/>
+    </pattern>"#,
+                w = scale,
+            ),
+            Pattern::Checkerboard => format!(
+                r#"<pattern id="{id}" patternUnits="userSpaceOnUse" width="{w}" height="{w}">
+      <rect width="{w}" height="{w}" fill="{first}"/>
+      <rect x="{half}" width="{half}" height="{half}" fill="{second}"/>
+      <rect y="{half}" width="{half}" height="{half}" fill="{second}"/>
+    </pattern>"#,
+                w = scale,
+            ),
+            Pattern::Dots => format!(
+                r#"<pattern id="{id}" patternUnits="userSpaceOnUse" width="{half}" height="{half}">
+      <rect width="{half}" height="{half}" fill="{first}"/>
+      <circle cx="{c}" cy="{c}" r="{r}" fill="{second}"/>
+    </pattern>"#,
+                c = half / 2.0,
+                r = half * 0.3,
+            ),
+        };
+        Ok(Some(format!("  <defs>\n    {}\n  </defs>\n", body)))
+    }
+}
+
+/// `--bg-gradient`: a linear background gradient at an angle, clockwise from left to right
+pub struct BgGradient {
+    pub colors: [Rgba<u8>; 2],
+    pub angle: f32,
+}
+
+impl BgGradient {
+    /// `COLOR,COLOR[,ANGLE]` with the angle in degrees, 0 running left to right and 90 top to bottom
+    pub fn parse(spec: &str) -> Result<Self> {
+        let parts: Vec<&str> = spec.split(',').map(str::trim).collect();
+        let angle = match parts.len() {
+            2 => 0.0,
+            3 => parts[2]
+                .trim_end_matches("deg")
+                .parse()
+                .with_context(|| format!("Invalid --bg-gradient angle '{}', use degrees such as 45", parts[2]))?,
+            _ => anyhow::bail!("Invalid --bg-gradient '{}', use COLOR,COLOR or COLOR,COLOR,ANGLE", spec),
+        };
+        Ok(BgGradient { colors: [parse_color(parts[0])?, parse_color(parts[1])?], angle })
+    }
+
+    /// The gradient line through the middle of a width x height area, reaching its far corners
+    fn line(&self, width: f32, height: f32) -> ((f32, f32), (f32, f32)) {
+        let (dy, dx) = self.angle.to_radians().sin_cos();
+        let half = (width * dx.abs() + height * dy.abs()) / 2.0;
+        let (cx, cy) = (width / 2.0, height / 2.0);
+        ((cx - dx * half, cy - dy * half), (cx + dx * half, cy + dy * half))
+    }
+
+    pub fn render(&self, width: u32, height: u32) -> RgbaImage {
+        let ((x1, y1), (x2, y2)) = self.line(width as f32, height as f32);
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length = (dx * dx + dy * dy).max(f32::EPSILON);
+        let [start, end] = self.colors;
+        RgbaImage::from_fn(width, height, |x, y| {
+            let t = (((x as f32 + 0.5 - x1) * dx + (y as f32 + 0.5 - y1) * dy) / length).clamp(0.0, 1.0);
+            Rgba([0, 1, 2, 3].map(|i| (start[i] as f32 + (end[i] as f32 - start[i] as f32) * t).round() as u8))
+        })
+    }
+
+    /// A `<linearGradient>` matching `render` over a width x height area in SVG units
+    pub fn svg_defs(&self, id: &str, width: f32, height: f32) -> String {
+        let ((x1, y1), (x2, y2)) = self.line(width, height);
+        // Adding zero turns the -0 left by rounding into 0
+        let round = |v: f32| (v * 100.0).round() / 100.0 + 0.0;
+        format!(
+            r#"  <defs>
+    <linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">
+      <stop offset="0%" stop-color="{}"/>
+      <stop offset="100%" stop-color="{}"/>
+    </linearGradient>
+  </defs>
+"#,
+            id,
+            round(x1),
+            round(y1),
+            round(x2),
+            round(y2),
+            svg_color(self.colors[0]),
+            svg_color(self.colors[1]),
+        )
+    }
+}
+
+pub fn svg_color(Rgba([r, g, b, a]): Rgba<u8>) -> String {
+    if a == 255 {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    } else {
+        format!("rgba({},{},{},{:.3})", r, g, b, a as f32 / 255.0)
+    }
+}
@@ -0,0 +1,184 @@
+use crate::text::{Align, draw_text, fit_text_size, line_height};
+use ab_glyph::FontArc;
+use image::{Rgba, RgbaImage};
+
+pub enum FrameStyle {
+    /// Outline around the code with the text on the background
+    Border,
+    /// Outline joined to a solid band that holds the text
+    Banner,
+    /// Outline plus a separate speech bubble pointing at the code
+    Bubble,
+}
+
+pub enum FramePosition {
+    Top,
+    Bottom,
+}
+
+pub struct FrameOptions {
+    pub style: FrameStyle,
+    pub position: FramePosition,
+    pub text: String,
+    pub color: Rgba<u8>,
+    pub text_color: Rgba<u8>,
+    pub background: Rgba<u8>,
+    /// Text size in pixels; the band grows to fit it. Defaults to a size relative to the code.
+    pub font_size: Option<f32>,
+}
+
+impl FrameStyle {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "border" => Some(FrameStyle::Border),
+            "banner" => Some(FrameStyle::Banner),
+            "bubble" => Some(FrameStyle::Bubble),
+            _ => None,
+        }
+    }
+}
+
+impl FramePosition {
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "top" => FramePosition::Top,
+            _ => FramePosition::Bottom,
+        }
+    }
+}
+
+/// Places the code on a larger canvas with a frame and call-to-action text.
+/// All proportions follow the code size so frames scale with `--size`.
+pub fn apply_frame(code: &RgbaImage, frame: &FrameOptions, font: &FontArc) -> RgbaImage {
+    let side = code.width() as f32;
+    let stroke = (side * 0.03).max(2.0).round();
+    let pad = (side * 0.05).round();
+    let radius = side * 0.06;
+    let band = match frame.font_size {
+        Some(size) => (size * 1.8).round(),
+        None => (side * 0.2).round(),
+    };
+    let gap = match frame.style {
+        FrameStyle::Bubble => (side * 0.06).round(),
+        _ => 0.0,
+    };
+
+    // Linear barcodes are wider than tall, the box follows the code's shape
+    let box_w = side + 2.0 * (pad + stroke);
+    let box_h = code.height() as f32 + 2.0 * (pad + stroke);
+    let width = box_w.ceil() as u32;
+    let height = (box_h + gap + band).ceil() as u32;
+    let mut canvas = RgbaImage::from_pixel(width, height, frame.background);
+
+    let (box_y, band_y) = match frame.position {
+        FramePosition::Bottom => (0.0, box_h + gap),
+        FramePosition::Top => (band + gap, 0.0),
+    };
+
+    // Outline: solid rounded rectangle with the inside punched back out
+    fill_rounded_rect(&mut canvas, 0.0, box_y, box_w, box_h, radius, frame.color);
+    fill_rounded_rect(
+        &mut canvas,
+        stroke,
+        box_y + stroke,
+        box_w - 2.0 * stroke,
+        box_h - 2.0 * stroke,
+        (radius - stroke).max(0.0),
+        frame.background,
+    );
+    let offset = (stroke + pad) as i64;
+    image::imageops::overlay(&mut canvas, code, offset, box_y as i64 + offset);
+
+    let text_color = match frame.style {
+        FrameStyle::Border => frame.color,
+        FrameStyle::Banner | FrameStyle::Bubble => frame.text_color,
+    };
+
+    match frame.style {
+        FrameStyle::Border => {}
+        FrameStyle::Banner => {
+            // Overlap the outline so band and frame read as one shape
+            let (y, h) = match frame.position {
+                FramePosition::Bottom => (band_y - radius, band + radius),
+                FramePosition::Top => (band_y, band + radius),
+            };
+            fill_rounded_rect(&mut canvas, 0.0, y, box_w, h, radius, frame.color);
+        }
+        FrameStyle::Bubble => {
+            let inset = box_w * 0.1;
+            fill_rounded_rect(&mut canvas, inset, band_y, box_w - 2.0 * inset, band, band / 2.0, frame.color);
+
+            // Pointer from the bubble towards the code
+            let center = box_w / 2.0;
+            let (tip, base) = match frame.position {
+                FramePosition::Bottom => (band_y - gap, band_y + 1.0),
+                FramePosition::Top => (band_y + band + gap, band_y + band - 1.0),
+            };
+            fill_triangle(&mut canvas, (center, tip), (center - gap, base), (center + gap, base), frame.color);
+        }
+    }
+
+    let text_w = box_w - 4.0 * pad;
+    let size = fit_text_size(font, frame.font_size.unwrap_or(band * 0.55), &frame.text, text_w);
+    let text_y = band_y + (band - line_height(font, size)) / 2.0;
+    draw_text(&mut canvas, font, size, text_color, &frame.text, 2.0 * pad, text_y, text_w, Align::Center);
+
+    canvas
+}
+
+/// Rounded rectangle with a one pixel anti-aliased edge. Pixels are replaced
+/// rather than blended, so a transparent fill cuts a hole.
+pub fn fill_rounded_rect(img: &mut RgbaImage, x: f32, y: f32, w: f32, h: f32, radius: f32, color: Rgba<u8>) {
+    let radius = radius.min(w / 2.0).min(h / 2.0);
+    let x0 = x.floor().max(0.0) as u32;
+    let y0 = y.floor().max(0.0) as u32;
+    let x1 = ((x + w).ceil() as u32).min(img.width());
+    let y1 = ((y + h).ceil() as u32).min(img.height());
+
+    for py in y0..y1 {
+        for px in x0..x1 {
+            let cx = px as f32 + 0.5;
+            let cy = py as f32 + 0.5;
+            // Distance outside the shape, measured from the nearest corner circle
+            let dx = (x + radius - cx).max(cx - (x + w - radius)).max(0.0);
+            let dy = (y + radius - cy).max(cy - (y + h - radius)).max(0.0);
+            let edge = if radius > 0.0 {
+                (dx * dx + dy * dy).sqrt() - radius
+            } else {
+                (x - cx).max(cx - (x + w)).max(y - cy).max(cy - (y + h))
+            };
+            let coverage = (0.5 - edge).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                mix_pixel(img, px, py, color, coverage);
+            }
+        }
+    }
+}
+
+fn fill_triangle(img: &mut RgbaImage, a: (f32, f32), b: (f32, f32), c: (f32, f32), color: Rgba<u8>) {
+    let x0 = a.0.min(b.0).min(c.0).floor().max(0.0) as u32;
+    let x1 = (a.0.max(b.0).max(c.0).ceil() as u32).min(img.width());
+    let y0 = a.1.min(b.1).min(c.1).floor().max(0.0) as u32;
+    let y1 = (a.1.max(b.1).max(c.1).ceil() as u32).min(img.height());
+
+    let side = |p: (f32, f32), q: (f32, f32), r: (f32, f32)| (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0);
+    for py in y0..y1 {
+        for px in x0..x1 {
+            let p = (px as f32 + 0.5, py as f32 + 0.5);
+            let (d1, d2, d3) = (side(a, b, p), side(b, c, p), side(c, a, p));
+            let has_neg = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+            let has_pos = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+            if !(has_neg && has_pos) {
+                img.put_pixel(px, py, color);
+            }
+        }
+    }
+}
+
+/// Linear mix of every channel including alpha
+fn mix_pixel(img: &mut RgbaImage, x: u32, y: u32, color: Rgba<u8>, amount: f32) {
+    let dst = img.get_pixel_mut(x, y);
+    for i in 0..4 {
+        dst[i] = (dst[i] as f32 + (color[i] as f32 - dst[i] as f32) * amount).round() as u8;
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+
+/// Group separator that ends variable-length fields; symbologies encode it as FNC1 or GS
+pub const GS: u8 = 0x1d;
+
+/// One Application Identifier and its value
+pub struct Element {
+    pub ai: String,
+    pub value: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Check {
+    None,
+    /// Mod-10 check digit in the last position (GTIN, SSCC, GLN)
+    Mod10,
+    /// YYMMDD, where day 00 means the end of the month
+    Date,
+}
+
+/// (AI, minimum length, maximum length, digits only, check)
+type Spec = (&'static str, usize, usize, bool, Check);
+
+/// The AIs in everyday use on retail, logistics and healthcare labels
+const SPECS: [Spec; 32] = [
+    ("00", 18, 18, true, Check::Mod10),
+    ("01", 14, 14, true, Check::Mod10),
+    ("02", 14, 14, true, Check::Mod10),
+    ("10", 1, 20, false, Check::None),
+    ("11", 6, 6, true, Check::Date),
+    ("12", 6, 6, true, Check::Date),
+    ("13", 6, 6, true, Check::Date),
+    ("15", 6, 6, true, Check::Date),
+    ("16", 6, 6, true, Check::Date),
+    ("17", 6, 6, true, Check::Date),
+    ("20", 2, 2, true, Check::None),
+    ("21", 1, 20, false, Check::None),
+    ("22", 1, 20, false, Check::None),
+    ("30", 1, 8, true, Check::None),
+    ("37", 1, 8, true, Check::None),
+    ("240", 1, 30, false, Check::None),
+    ("241", 1, 30, false, Check::None),
+    ("250", 1, 30, false, Check::None),
+    ("400", 1, 30, false, Check::None),
+    ("410", 13, 13, true, Check::Mod10),
+    ("414", 13, 13, true, Check::Mod10),
+    ("420", 1, 20, false, Check::None),
+    ("422", 3, 3, true, Check::None),
+    ("7003", 10, 10, true, Check::None),
+    ("8005", 6, 6, true, Check::None),
+    ("90", 1, 30, false, Check::None),
+    ("91", 1, 90, false, Check::None),
+    ("92", 1, 90, false, Check::None),
+    ("93", 1, 90, false, Check::None),
+    ("94", 1, 90, false, Check::None),
+    ("95", 1, 90, false, Check::None),
+    ("99", 1, 90, false, Check::None),
+];
+
+/// AIs starting with these digits have a predefined length and never need a separator
+const PREDEFINED: [&str; 22] = [
+    "00", "01", "02", "03", "04", "11", "12", "13", "14", "15", "16", "17", "18", "19", "20", "23", "31", "32", "33",
+    "34", "35", "41",
+];
+
+fn spec(ai: &str) -> Option<Spec> {
+    if let Some(&spec) = SPECS.iter().find(|s| s.0 == ai) {
+        return Some(spec);
+    }
+    // Trade measures such as 3103 (net weight in kg, 3 decimals) are all six digits
+    let measure = ai.len() == 4 && ai.bytes().all(|b| b.is_ascii_digit()) && ("3100".."3700").contains(&ai);
+    measure.then_some(("3nnn", 6, 6, true, Check::None))
+}
+
+/// Parses "(01)09506000134352(17)261231(10)ABC" and validates every element
+pub fn parse(text: &str) -> Result<Vec<Element>> {
+    let mut elements: Vec<Element> = Vec::new();
+    let mut rest = text.trim();
+    if !rest.starts_with('(') {
+        anyhow::bail!("GS1 data must be written as (AI)value pairs, e.g. (01)09506000134352(10)ABC");
+    }
+    while let Some(after) = rest.strip_prefix('(') {
+        let (ai, after) = after.split_once(')').context("Unclosed '(' in GS1 data")?;
+        let end = after.find('(').unwrap_or(after.len());
+        let element = Element { ai: ai.to_string(), value: after[..end].to_string() };
+        validate(&element)?;
+        if elements.iter().any(|e| e.ai == element.ai) {
+            anyhow::bail!("GS1 AI ({}) appears twice", element.ai);
+        }
+        elements.push(element);
+        rest = &after[end..];
+    }
+    Ok(elements)
+}
+
+/// Checks the AI is known and its value has the right length, characters and check digit or date
+pub fn validate(element: &Element) -> Result<()> {
+    let (ai, value) = (&element.ai, &element.value);
+    let (_, min, max, numeric, check) =
+        spec(ai).with_context(|| format!("Unknown or unsupported GS1 Application Identifier ({})", ai))?;
+
+    if value.len() < min || value.len() > max {
+        let expected = if min == max { format!("{}", max) } else { format!("{} to {}", min, max) };
+        anyhow::bail!("GS1 AI ({}) needs {} characters, got {} in '{}'", ai, expected, value.len(), value);
+    }
+    if numeric && !value.bytes().all(|b| b.is_ascii_digit()) {
+        anyhow::bail!("GS1 AI ({}) holds digits only, got '{}'", ai, value);
+    }
+    // GS1's 82-character set: letters, digits and a few punctuation marks
+    if let Some(c) = value.chars().find(|&c| !c.is_ascii_alphanumeric() && !"!\"%&'()*+,-./:;<=>?_".contains(c)) {
+        anyhow::bail!("GS1 AI ({}) cannot hold '{}'", ai, c);
+    }
+
+    match check {
+        Check::None => {}
+        Check::Mod10 => {
+            let expected = check_digit(&value[..value.len() - 1]);
+            let actual = (value.as_bytes()[value.len() - 1] - b'0') as u32;
+            if actual != expected {
+                anyhow::bail!("GS1 AI ({}) check digit should be {}, not {}", ai, expected, actual);
+            }
+        }
+        Check::Date => {
+            let month: u32 = value[2..4].parse()?;
+            let day: u32 = value[4..6].parse()?;
+            if !(1..=12).contains(&month) || day > 31 {
+                anyhow::bail!("GS1 AI ({}) date '{}' is not a valid YYMMDD date", ai, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// GS1 mod-10: weights 3 and 1 alternating from the rightmost digit
+pub fn check_digit(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| (b - b'0') as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+/// Elements from the `--gtin`, `--expiry`, `--lot` and `--serial` flags, in the usual label order
+pub fn from_flags(gtin: Option<&str>, expiry: Option<&str>, lot: Option<&str>, serial: Option<&str>) -> Result<Vec<Element>> {
+    let mut elements = Vec::new();
+    if let Some(gtin) = gtin {
+        // GTIN-8, -12 and -13 are zero-padded to 14 digits
+        let gtin = gtin.trim();
+        if !matches!(gtin.len(), 8 | 12 | 13 | 14) {
+            anyhow::bail!("GTIN must have 8, 12, 13 or 14 digits, got {}", gtin.len());
+        }
+        elements.push(Element { ai: "01".to_string(), value: format!("{:0>14}", gtin) });
+    }
+    if let Some(expiry) = expiry {
+        // Accept ISO dates as well as GS1's own YYMMDD
+        let expiry = expiry.trim();
+        let value = match expiry.split('-').collect::<Vec<_>>()[..] {
+            [year, month, day] if year.len() == 4 => format!("{}{}{}", &year[2..], month, day),
+            _ => expiry.to_string(),
+        };
+        elements.push(Element { ai: "17".to_string(), value });
+    }
+    if let Some(lot) = lot {
+        elements.push(Element { ai: "10".to_string(), value: lot.to_string() });
+    }
+    if let Some(serial) = serial {
+        elements.push(Element { ai: "21".to_string(), value: serial.to_string() });
+    }
+    for element in &elements {
+        validate(element)?;
+    }
+    Ok(elements)
+}
+
+/// Human-readable form, e.g. "(01)09506000134352(10)ABC"
+pub fn element_string(elements: &[Element]) -> String {
+    elements.iter().map(|e| format!("({}){}", e.ai, e.value)).collect()
+}
+
+/// The encoded data without the leading FNC1: AIs and values back to back, with a GS after
+/// every variable-length value that is not the last one
+pub fn encode(elements: &[Element]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (i, element) in elements.iter().enumerate() {
+        out.extend_from_slice(element.ai.as_bytes());
+        out.extend_from_slice(element.value.as_bytes());
+        let predefined = PREDEFINED.iter().any(|prefix| element.ai.starts_with(prefix));
+        if !predefined && i + 1 < elements.len() {
+            out.push(GS);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_digits_match_published_examples() {
+        // GTIN 09506000134352 and SSCC 106141411234567897 from the GS1 General Specifications
+        assert_eq!(check_digit("0950600013435"), 2);
+        assert_eq!(check_digit("10614141123456789"), 7);
+        assert_eq!(check_digit("400638133393"), 1);
+    }
+
+    #[test]
+    fn validates_elements() {
+        assert!(parse("(01)09506000134352(17)261231(10)ABC").is_ok());
+        for text in [
+            "(01)09506000134353",
+            "(01)0950600013435",
+            "(17)261331",
+            "(10)ABC(10)DEF",
+            "(10)AB~C",
+            "(999)1",
+            "01)09506000134352",
+        ] {
+            assert!(parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn flags_pad_gtins_and_convert_iso_dates() {
+        let elements = from_flags(Some("9506000134352"), Some("2026-12-31"), Some("ABC"), None).unwrap();
+        assert_eq!(element_string(&elements), "(01)09506000134352(17)261231(10)ABC");
+        assert!(from_flags(Some("12345"), None, None, None).is_err());
+    }
+
+    #[test]
+    fn separates_only_variable_length_values() {
+        let elements = parse("(01)09506000134352(10)ABC(21)X7").unwrap();
+        assert_eq!(encode(&elements), b"010950600013435210ABC\x1d21X7");
+    }
+}
@@ -0,0 +1,170 @@
+use crate::preset::option_groups;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// One generated code as it was recorded
+pub struct Entry {
+    pub id: i64,
+    pub created: DateTime<Utc>,
+    /// None when only the hash was kept (--record hash)
+    pub payload: Option<Vec<u8>>,
+    pub sha256: String,
+    /// The command line without the payload; None when it could not be kept
+    pub settings: Option<Vec<String>>,
+    pub output: Option<String>,
+    pub format: String,
+    pub symbol: String,
+}
+
+impl Entry {
+    /// The payload as text, for display
+    pub fn payload_text(&self) -> Option<String> {
+        self.payload.as_ref().map(|bytes| match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            Err(_) => format!("<{} bytes>", bytes.len()),
+        })
+    }
+}
+
+/// A recorded code, before it has an ID
+pub struct Record<'a> {
+    pub payload: &'a [u8],
+    /// Keep only the payload's hash
+    pub hash_only: bool,
+    pub settings: Option<&'a [String]>,
+    pub output: Option<String>,
+    pub format: &'a str,
+    pub symbol: String,
+}
+
+/// Which entries `History::entries` returns, newest first
+pub struct Filter {
+    /// Text to find in the payload, output path or command line, or the start of a payload hash
+    pub text: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: u32,
+}
+
+/// The database in RUSTQR_HISTORY, or history.sqlite3 in the user's data directory
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    pub fn open() -> Result<Self> {
+        let path = database_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let conn = Connection::open(&path).with_context(|| format!("Failed to open history {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS codes (
+                 id INTEGER PRIMARY KEY,
+                 created TEXT NOT NULL,
+                 payload,
+                 sha256 TEXT NOT NULL,
+                 settings TEXT,
+                 output TEXT,
+                 format TEXT NOT NULL,
+                 symbol TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS codes_created ON codes (created);",
+        )
+        .with_context(|| format!("Failed to set up history {}", path.display()))?;
+        Ok(History { conn })
+    }
+
+    pub fn record(&self, record: &Record) -> Result<i64> {
+        // Text payloads stay searchable, anything else is kept as a blob
+        let payload = match (record.hash_only, std::str::from_utf8(record.payload)) {
+            (true, _) => Value::Null,
+            (false, Ok(text)) => Value::Text(text.to_string()),
+            (false, Err(_)) => Value::Blob(record.payload.to_vec()),
+        };
+        let settings = record.settings.map(serde_json::to_string).transpose()?;
+        self.conn.execute(
+            "INSERT INTO codes (created, payload, sha256, settings, output, format, symbol) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![timestamp(Utc::now()), payload, sha256(record.payload), settings, record.output, record.format, record.symbol],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn entries(&self, filter: &Filter) -> Result<Vec<Entry>> {
+        let pattern = filter.text.as_ref().map(|text| format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+        let hash_prefix = filter.text.as_ref().map(|text| format!("{}%", text.to_lowercase()));
+        let mut statement = self.conn.prepare(
+            "SELECT id, created, payload, sha256, settings, output, format, symbol FROM codes
+             WHERE (?1 IS NULL OR CAST(payload AS TEXT) LIKE ?1 ESCAPE '\\' OR output LIKE ?1 ESCAPE '\\'
+                    OR settings LIKE ?1 ESCAPE '\\' OR sha256 LIKE ?2)
+               AND (?3 IS NULL OR created >= ?3) AND (?4 IS NULL OR created < ?4)
+             ORDER BY id DESC LIMIT ?5",
+        )?;
+        let rows = statement.query_map(
+            params![pattern, hash_prefix, filter.since.map(timestamp), filter.until.map(timestamp), filter.limit],
+            entry,
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn entry(&self, id: i64) -> Result<Entry> {
+        self.conn
+            .query_row("SELECT id, created, payload, sha256, settings, output, format, symbol FROM codes WHERE id = ?1", [id], entry)
+            .optional()?
+            .with_context(|| format!("There is no history entry {}", id))
+    }
+}
+
+fn entry(row: &Row) -> rusqlite::Result<Entry> {
+    let payload = match row.get::<_, Value>(2)? {
+        Value::Text(text) => Some(text.into_bytes()),
+        Value::Blob(bytes) => Some(bytes),
+        _ => None,
+    };
+    let created: String = row.get(1)?;
+    let settings: Option<String> = row.get(4)?;
+    Ok(Entry {
+        id: row.get(0)?,
+        created: DateTime::parse_from_rfc3339(&created).map(|time| time.with_timezone(&Utc)).unwrap_or_default(),
+        payload,
+        sha256: row.get(3)?,
+        settings: settings.and_then(|settings| serde_json::from_str(&settings).ok()),
+        output: row.get(5)?,
+        format: row.get(6)?,
+        symbol: row.get(7)?,
+    })
+}
+
+/// Fixed-width UTC, so timestamps compare correctly as text
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+pub fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn database_path() -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os("RUSTQR_HISTORY").filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let dir = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local/share")))
+    };
+    let dir = dir.context("Cannot find a data directory for the history, set RUSTQR_HISTORY to a file path")?;
+    Ok(dir.join("rustqr").join("history.sqlite3"))
+}
+
+/// The command line with the --data value left out, since the payload is stored on its own
+pub fn strip_data(arguments: &[String]) -> Vec<String> {
+    option_groups(arguments).into_iter().filter(|(id, _)| id.as_deref() != Some("data")).flat_map(|(_, tokens)| tokens).collect()
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+/// The Fluent messages of every language, English first as the fallback for missing ones
+const LOCALES: [(&str, &str); 3] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fa", include_str!("../locales/fa.ftl")),
+];
+
+struct Messages {
+    chosen: FluentBundle<FluentResource>,
+    english: FluentBundle<FluentResource>,
+}
+
+static MESSAGES: OnceLock<Messages> = OnceLock::new();
+
+/// Picks the language of prompts and status messages: `--lang` when given, otherwise the
+/// first of LC_ALL, LC_MESSAGES and LANG that names a language RustQR speaks, otherwise English
+pub fn init(lang: Option<&str>) -> Result<()> {
+    let code = match lang {
+        Some(lang) => supported(lang).ok_or_else(|| {
+            let codes: Vec<&str> = LOCALES.iter().map(|(code, _)| *code).collect();
+            anyhow::anyhow!("Unknown language '{}', RustQR speaks {}", lang, codes.join(", "))
+        })?,
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+            .find_map(|value| supported(&value))
+            .unwrap_or("en"),
+    };
+    let _ = MESSAGES.set(Messages { chosen: bundle(code), english: bundle("en") });
+    Ok(())
+}
+
+/// The code of a supported language from a tag or locale name such as `fa`, `es-MX` or `es_ES.UTF-8`
+fn supported(name: &str) -> Option<&'static str> {
+    let tag = name.split(['.', '@']).next().unwrap_or_default().replace('_', "-");
+    let id: LanguageIdentifier = tag.parse().ok()?;
+    LOCALES.iter().map(|(code, _)| *code).find(|code| *code == id.language.as_str())
+}
+
+fn bundle(code: &str) -> FluentBundle<FluentResource> {
+    let (_, source) = LOCALES.iter().find(|(name, _)| *name == code).unwrap_or(&LOCALES[0]);
+    let resource = FluentResource::try_new(source.to_string()).expect("bundled .ftl files parse");
+    let mut bundle = FluentBundle::new_concurrent(vec![code.parse().expect("bundled language codes are valid")]);
+    // Bidi isolation marks show up as stray characters in most terminals
+    bundle.set_use_isolating(false);
+    bundle.add_resource(resource).expect("bundled .ftl files have no duplicate messages");
+    bundle
+}
+
+/// The message `id` in the chosen language, falling back to English and then to the id itself
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let messages = MESSAGES.get_or_init(|| Messages { chosen: bundle("en"), english: bundle("en") });
+    for bundle in [&messages.chosen, &messages.english] {
+        if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+            let mut errors = Vec::new();
+            return bundle.format_pattern(pattern, args, &mut errors).into_owned();
+        }
+    }
+    id.to_string()
+}
+
+/// `tr!("saved", kind = "qr", path = path.display().to_string())` formats a translated message with its arguments
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), fluent_bundle::FluentValue::from($value));)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+
+pub(crate) use tr;
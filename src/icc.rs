@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// A color profile to embed, with the name PNG stores next to it
+#[derive(Debug, Clone)]
+pub struct IccProfile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// D50 white, the profile connection space illuminant
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// sRGB primaries adapted to D50, as found in the reference sRGB profiles
+const SRGB_PRIMARIES: [[f64; 3]; 3] = [
+    [0.4361, 0.2225, 0.0139],
+    [0.3851, 0.7169, 0.0971],
+    [0.1431, 0.0606, 0.7141],
+];
+
+/// Resolves `--icc-profile`: `sRGB` for the built-in profile, otherwise a path to an RGB .icc file
+pub fn load(spec: &str) -> Result<IccProfile> {
+    if spec.eq_ignore_ascii_case("srgb") {
+        return Ok(IccProfile {
+            name: "sRGB".to_string(),
+            data: srgb_profile(),
+        });
+    }
+
+    let path = Path::new(spec);
+    let data = std::fs::read(path).with_context(|| format!("Failed to read color profile: {}", spec))?;
+    if data.len() < 132 || &data[36..40] != b"acsp" {
+        anyhow::bail!("{} is not an ICC color profile", spec);
+    }
+    if &data[16..20] != b"RGB " {
+        anyhow::bail!(
+            "{} is a {} profile, RGB output needs an RGB profile",
+            spec,
+            String::from_utf8_lossy(&data[16..20]).trim()
+        );
+    }
+
+    // PNG profile names are 1-79 Latin-1 characters
+    let name: String = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().chars().filter(|c| c.is_ascii_graphic() || *c == ' ').take(79).collect())
+        .unwrap_or_default();
+    Ok(IccProfile {
+        name: if name.is_empty() { "ICC profile".to_string() } else { name },
+        data,
+    })
+}
+
+/// A compact ICC v2 display profile for sRGB (tone curves sampled at 256 points)
+fn srgb_profile() -> Vec<u8> {
+    let trc = curve_tag(256, |v| {
+        if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+    });
+    let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"desc", desc_tag("sRGB IEC61966-2.1 (RustQR)")),
+        (b"cprt", text_tag("No copyright, use freely")),
+        (b"wtpt", xyz_tag(D50)),
+        (b"rXYZ", xyz_tag(SRGB_PRIMARIES[0])),
+        (b"gXYZ", xyz_tag(SRGB_PRIMARIES[1])),
+        (b"bXYZ", xyz_tag(SRGB_PRIMARIES[2])),
+        (b"rTRC", trc.clone()),
+        (b"gTRC", trc.clone()),
+        (b"bTRC", trc),
+    ];
+
+    // Tag data follows the header and the tag table, each element 4-byte aligned
+    let data_start = 128 + 4 + tags.len() * 12;
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut body = Vec::new();
+    for (signature, data) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&((data_start + body.len()) as u32).to_be_bytes());
+        table.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        body.extend_from_slice(data);
+        body.resize(body.len().next_multiple_of(4), 0);
+    }
+
+    let size = 128 + table.len() + body.len();
+    let mut header = Vec::with_capacity(128);
+    header.extend_from_slice(&(size as u32).to_be_bytes());
+    header.extend_from_slice(&[0; 4]); // preferred CMM
+    header.extend_from_slice(&[2, 0x10, 0, 0]); // version 2.1
+    header.extend_from_slice(b"mntrRGB XYZ ");
+    header.extend_from_slice(&[0; 12]); // creation date, left empty so output stays reproducible
+    header.extend_from_slice(b"acsp");
+    header.extend_from_slice(&[0; 24]); // platform, flags, manufacturer, model, attributes
+    header.extend_from_slice(&[0; 4]); // perceptual intent
+    for value in D50 {
+        header.extend_from_slice(&s15_fixed16(value));
+    }
+    header.resize(128, 0);
+
+    [header, table, body].concat()
+}
+
+fn s15_fixed16(value: f64) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for value in xyz {
+        tag.extend_from_slice(&s15_fixed16(value));
+    }
+    tag
+}
+
+fn curve_tag(points: usize, transfer: impl Fn(f64) -> f64) -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(points as u32).to_be_bytes());
+    for i in 0..points {
+        let value = transfer(i as f64 / (points - 1) as f64);
+        tag.extend_from_slice(&((value * 65535.0).round() as u16).to_be_bytes());
+    }
+    tag
+}
+
+fn text_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"text\0\0\0\0".to_vec();
+    tag.extend_from_slice(text.as_bytes());
+    tag.push(0);
+    tag
+}
+
+/// textDescriptionType: ASCII description, then empty Unicode and ScriptCode parts
+fn desc_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+    tag.extend_from_slice(text.as_bytes());
+    tag.push(0);
+    tag.extend_from_slice(&[0; 8]); // Unicode language code and count
+    tag.extend_from_slice(&[0; 3]); // ScriptCode code and count
+    tag.extend_from_slice(&[0; 67]);
+    tag
+}
@@ -0,0 +1,302 @@
+use url::Url;
+
+/// A decoded payload in a well-known format, split into labeled fields for reading
+pub struct Breakdown {
+    /// Short identifier for JSON output: wifi, mecard, vcard, otpauth, geo or epc
+    pub format: &'static str,
+    pub title: &'static str,
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl Breakdown {
+    /// The title followed by one indented `Label: value` line per field
+    pub fn render(&self) -> String {
+        let width = self.fields.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0) + 1;
+        let mut out = self.title.to_string();
+        for (label, value) in &self.fields {
+            out.push_str(&format!("\n  {:<width$} {}", format!("{}:", label), value, width = width));
+        }
+        out
+    }
+}
+
+/// Recognizes Wi-Fi, MECARD, vCard, otpauth, geo and EPC payloads; None for anything else
+pub fn breakdown(text: &str) -> Option<Breakdown> {
+    let upper = text.get(..11).unwrap_or(text).to_ascii_uppercase();
+    let (format, title, fields) = if upper.starts_with("WIFI:") {
+        ("wifi", "Wi-Fi network", wifi(&text[5..]))
+    } else if upper.starts_with("MECARD:") {
+        ("mecard", "Contact (MECARD)", mecard(&text[7..]))
+    } else if upper.starts_with("BEGIN:VCARD") {
+        ("vcard", "Contact (vCard)", vcard(text))
+    } else if upper.starts_with("OTPAUTH://") {
+        ("otpauth", "One-time password", otpauth(text)?)
+    } else if upper.starts_with("GEO:") {
+        ("geo", "Location", geo(&text[4..])?)
+    } else if text.starts_with("BCD\n") || text.starts_with("BCD\r\n") {
+        ("epc", "SEPA credit transfer", epc(text)?)
+    } else {
+        return None;
+    };
+    (!fields.is_empty()).then_some(Breakdown { format, title, fields })
+}
+
+/// Splits on `separator` where it is not escaped with a backslash, removing the escapes
+fn split_escaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => parts.last_mut().unwrap().extend(chars.next()),
+            c if c == separator => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// `KEY:value` pairs of the `;`-separated WIFI and MECARD formats
+fn key_values(body: &str) -> Vec<(String, String)> {
+    split_escaped(body, ';')
+        .into_iter()
+        .filter_map(|part| {
+            let (key, value) = part.split_once(':')?;
+            Some((key.trim().to_ascii_uppercase(), value.to_string()))
+        })
+        .collect()
+}
+
+fn wifi(body: &str) -> Vec<(&'static str, String)> {
+    let pairs = key_values(body);
+    let get = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+    let mut fields = Vec::new();
+    if let Some(ssid) = get("S") {
+        fields.push(("SSID", ssid));
+    }
+    let security = match get("T").unwrap_or_default().to_ascii_uppercase().as_str() {
+        "" | "NOPASS" => "None (open network)".to_string(),
+        "WPA" => "WPA/WPA2".to_string(),
+        "SAE" => "WPA3".to_string(),
+        "WPA2-EAP" => "WPA2-Enterprise".to_string(),
+        other => other.to_string(),
+    };
+    fields.push(("Security", security));
+    if let Some(password) = get("P").filter(|p| !p.is_empty()) {
+        fields.push(("Password", password));
+    }
+    if get("H").is_some_and(|h| h.eq_ignore_ascii_case("true")) {
+        fields.push(("Hidden", "yes".to_string()));
+    }
+    for (key, label) in [("E", "EAP method"), ("PH2", "Phase 2"), ("A", "Anonymous identity"), ("I", "Identity")] {
+        if let Some(value) = get(key) {
+            fields.push((label, value));
+        }
+    }
+    fields
+}
+
+fn mecard(body: &str) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+    for (key, value) in key_values(body) {
+        let label = match key.as_str() {
+            // N is "Last,First"
+            "N" => {
+                let name = split_escaped(&value, ',');
+                fields.push(("Name", name.iter().rev().map(String::as_str).collect::<Vec<_>>().join(" ")));
+                continue;
+            }
+            "ORG" => "Organization",
+            "TEL" => "Phone",
+            "EMAIL" => "Email",
+            "URL" => "Website",
+            "ADR" => "Address",
+            "BDAY" => "Birthday",
+            "NOTE" => "Note",
+            "NICKNAME" => "Nickname",
+            _ => continue,
+        };
+        fields.push((label, value));
+    }
+    fields
+}
+
+fn vcard(text: &str) -> Vec<(&'static str, String)> {
+    // Lines starting with a space or tab continue the previous one
+    let unfolded = text.replace("\r\n", "\n").replace("\n ", "").replace("\n\t", "");
+
+    let mut name = None;
+    let mut fields = Vec::new();
+    for line in unfolded.lines() {
+        let Some((property, value)) = line.split_once(':') else { continue };
+        let mut params = property.split(';');
+        let key = params.next().unwrap_or_default().to_ascii_uppercase();
+        // TYPE=work,voice or the bare vCard 2.1 form ;WORK
+        let kinds: Vec<String> = params
+            .map(|param| param.split_once('=').map_or(param, |(_, types)| types).to_lowercase())
+            .filter(|kind| !kind.is_empty() && kind != "pref" && kind != "internet")
+            .collect();
+        let parts = vcard_parts(value);
+        let joined = |separator: &str| {
+            let parts: Vec<&str> = parts.iter().map(|part| part.trim()).filter(|part| !part.is_empty()).collect();
+            parts.join(separator)
+        };
+        let label = match key.as_str() {
+            "FN" => {
+                name = Some(parts.join(";"));
+                continue;
+            }
+            // N is Family;Given;Additional;Prefix;Suffix, used when FN is missing
+            "N" => {
+                let [family, given] = [0, 1].map(|i| parts.get(i).map(String::as_str).unwrap_or_default());
+                name.get_or_insert_with(|| format!("{} {}", given, family).trim().to_string());
+                continue;
+            }
+            "ORG" => {
+                fields.push(("Organization", joined(", ")));
+                continue;
+            }
+            "ADR" => {
+                fields.push(("Address", joined(", ")));
+                continue;
+            }
+            "TITLE" => "Title",
+            "TEL" => "Phone",
+            "EMAIL" => "Email",
+            "URL" => "Website",
+            "BDAY" => "Birthday",
+            "NOTE" => "Note",
+            _ => continue,
+        };
+        let value = parts.join(";");
+        if kinds.is_empty() || !matches!(label, "Phone" | "Email") {
+            fields.push((label, value));
+        } else {
+            fields.push((label, format!("{} ({})", value, kinds.join(", "))));
+        }
+    }
+    if let Some(name) = name.filter(|name| !name.is_empty()) {
+        fields.insert(0, ("Name", name));
+    }
+    fields
+}
+
+/// The `;`-separated components of a vCard value with `\n`, `\,`, `\;` and `\\` unescaped
+fn vcard_parts(value: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().unwrap();
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => part.push('\n'),
+                Some(escaped) => part.push(escaped),
+                None => {}
+            },
+            ';' => parts.push(String::new()),
+            c => part.push(c),
+        }
+    }
+    parts
+}
+
+fn otpauth(text: &str) -> Option<Vec<(&'static str, String)>> {
+    let url = Url::parse(text).ok()?;
+    let kind = url.host_str()?.to_ascii_uppercase();
+    let query = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.into_owned());
+
+    // The label is "Issuer:account" or just the account
+    let label = url.path().trim_start_matches('/');
+    let label = percent_decode(label);
+    let (label_issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+        None => (None, label.trim().to_string()),
+    };
+
+    let mut fields = vec![("Type", kind.clone())];
+    if let Some(issuer) = query("issuer").or(label_issuer) {
+        fields.push(("Issuer", issuer));
+    }
+    fields.push(("Account", account));
+    fields.push(("Secret", query("secret")?));
+    fields.push(("Algorithm", query("algorithm").unwrap_or_else(|| "SHA1".to_string()).to_uppercase()));
+    fields.push(("Digits", query("digits").unwrap_or_else(|| "6".to_string())));
+    match kind.as_str() {
+        "HOTP" => fields.push(("Counter", query("counter").unwrap_or_else(|| "0".to_string()))),
+        _ => fields.push(("Period", format!("{} s", query("period").unwrap_or_else(|| "30".to_string())))),
+    }
+    Some(fields)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `geo:lat,lon[,alt][;params][?q=query]`
+fn geo(body: &str) -> Option<Vec<(&'static str, String)>> {
+    let (location, query) = match body.split_once('?') {
+        Some((location, query)) => (location, Some(query)),
+        None => (body, None),
+    };
+    let mut coords = location.split(';').next()?.split(',');
+    let lat: f64 = coords.next()?.trim().parse().ok()?;
+    let lon: f64 = coords.next()?.trim().parse().ok()?;
+
+    let mut fields = vec![("Latitude", lat.to_string()), ("Longitude", lon.to_string())];
+    if let Some(altitude) = coords.next().and_then(|alt| alt.trim().parse::<f64>().ok()) {
+        fields.push(("Altitude", format!("{} m", altitude)));
+    }
+    let search = query.and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes()).find(|(key, _)| key == "q").map(|(_, value)| value.into_owned())
+    });
+    if let Some(search) = search {
+        fields.push(("Search", search));
+    }
+    fields.push(("Map", format!("https://www.openstreetmap.org/?mlat={}&mlon={}#map=16/{}/{}", lat, lon, lat, lon)));
+    Some(fields)
+}
+
+/// EPC069-12: BCD, version, charset, SCT, BIC, name, IBAN, amount, purpose, reference, text, info
+fn epc(text: &str) -> Option<Vec<(&'static str, String)>> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    if lines.get(3) != Some(&"SCT") {
+        return None;
+    }
+    let line = |i: usize| lines.get(i).copied().filter(|line| !line.is_empty());
+
+    let mut fields = vec![("Recipient", line(5)?.to_string())];
+    let iban = line(6)?.replace(' ', "");
+    let grouped: Vec<String> = iban.as_bytes().chunks(4).map(|chunk| String::from_utf8_lossy(chunk).into_owned()).collect();
+    fields.push(("IBAN", grouped.join(" ")));
+    if let Some(bic) = line(4) {
+        fields.push(("BIC", bic.to_string()));
+    }
+    if let Some(amount) = line(7) {
+        let amount = match amount.strip_prefix("EUR") {
+            Some(value) => format!("{} EUR", value),
+            None => amount.to_string(),
+        };
+        fields.push(("Amount", amount));
+    }
+    for (i, label) in [(8, "Purpose"), (9, "Reference"), (10, "Remittance text"), (11, "Information")] {
+        if let Some(value) = line(i) {
+            fields.push((label, value.to_string()));
+        }
+    }
+    Some(fields)
+}
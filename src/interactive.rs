@@ -0,0 +1,429 @@
+use anyhow::Result;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input, Select};
+use image::Rgba;
+use std::path::PathBuf;
+
+use crate::i18n::tr;
+use crate::tui::{Preview, render_preview};
+use crate::{Args, payload, preset};
+
+/// A setting the menu lists with its current value
+#[derive(Clone, Copy, PartialEq)]
+enum Setting {
+    Data,
+    FgColor,
+    BgColor,
+    Gradient,
+    EyeGradient,
+    DotStyle,
+    EyeStyle,
+    Logo,
+    Error,
+    Version,
+    Border,
+    Size,
+    Encode,
+    Format,
+    Output,
+    Copy,
+    Show,
+}
+
+const SETTINGS: [Setting; 17] = [
+    Setting::Data,
+    Setting::FgColor,
+    Setting::BgColor,
+    Setting::Gradient,
+    Setting::EyeGradient,
+    Setting::DotStyle,
+    Setting::EyeStyle,
+    Setting::Logo,
+    Setting::Error,
+    Setting::Version,
+    Setting::Border,
+    Setting::Size,
+    Setting::Encode,
+    Setting::Format,
+    Setting::Output,
+    Setting::Copy,
+    Setting::Show,
+];
+
+const DOT_STYLES: [&str; 4] = ["square", "circle", "rounded", "diamond"];
+const EYE_STYLES: [&str; 3] = ["square", "circle", "frame"];
+const EC_LEVELS: [&str; 4] = ["L", "M", "Q", "H"];
+const FORMATS: [&str; 7] = ["png", "jpg", "svg", "webp", "bmp", "ico", "tiff"];
+
+impl Setting {
+    fn label(&self) -> String {
+        match self {
+            Setting::Data => tr!("setting-data"),
+            Setting::FgColor => tr!("setting-fg-color"),
+            Setting::BgColor => tr!("setting-bg-color"),
+            Setting::Gradient => tr!("setting-gradient"),
+            Setting::EyeGradient => tr!("setting-eye-gradient"),
+            Setting::DotStyle => tr!("setting-dot-style"),
+            Setting::EyeStyle => tr!("setting-eye-style"),
+            Setting::Logo => tr!("setting-logo"),
+            Setting::Error => tr!("setting-error"),
+            Setting::Version => tr!("setting-version"),
+            Setting::Border => tr!("setting-border"),
+            Setting::Size => tr!("setting-size"),
+            Setting::Encode => tr!("setting-encode"),
+            Setting::Format => tr!("setting-format"),
+            Setting::Output => tr!("setting-output"),
+            Setting::Copy => tr!("setting-copy"),
+            Setting::Show => tr!("setting-show"),
+        }
+    }
+
+    /// The clap ID of the option the setting stands for
+    fn id(&self) -> &'static str {
+        match self {
+            Setting::Data => "data",
+            Setting::FgColor => "fg_color",
+            Setting::BgColor => "bg_color",
+            Setting::Gradient => "gradient",
+            Setting::EyeGradient => "eye_gradient",
+            Setting::DotStyle => "dot_style",
+            Setting::EyeStyle => "eye_style",
+            Setting::Logo => "logo",
+            Setting::Error => "error",
+            Setting::Version => "version",
+            Setting::Border => "border",
+            Setting::Size => "size",
+            Setting::Encode => "encode",
+            Setting::Format => "format",
+            Setting::Output => "output",
+            Setting::Copy => "copy",
+            Setting::Show => "show",
+        }
+    }
+
+    fn value(&self, args: &Args) -> String {
+        let none = || tr!("value-none");
+        let flag = |on: bool| if on { tr!("value-yes") } else { tr!("value-no") };
+        match self {
+            Setting::Data => match (&args.data, &args.data_file) {
+                (Some(data), _) => data.clone(),
+                (None, Some(path)) => path.display().to_string(),
+                (None, None) => none(),
+            },
+            Setting::FgColor => args.fg_color.clone(),
+            Setting::BgColor => args.bg_color.clone(),
+            Setting::Gradient => args.gradient.clone().unwrap_or_else(none),
+            Setting::EyeGradient => args.eye_gradient.clone().unwrap_or_else(none),
+            Setting::DotStyle => args.dot_style.clone(),
+            Setting::EyeStyle => args.eye_style.clone(),
+            Setting::Logo => match args.logo {
+                Some(ref path) => format!("{} ({:.2})", path.display(), args.logo_size),
+                None => none(),
+            },
+            Setting::Error => args.error.clone().unwrap_or_else(|| tr!("value-auto")),
+            Setting::Version => args.version.map(|v| v.to_string()).unwrap_or_else(|| tr!("value-auto")),
+            Setting::Border => args.border.to_string(),
+            Setting::Size => args.size.to_string(),
+            Setting::Encode => flag(args.encode),
+            Setting::Format => args.format.clone(),
+            Setting::Output => output_path(args).display().to_string(),
+            Setting::Copy => flag(args.copy),
+            Setting::Show => flag(args.show),
+        }
+    }
+
+    /// The setting as command-line arguments for a preset; the data and output belong to one run
+    fn arguments(&self, args: &Args) -> Vec<String> {
+        let option = |name: &str, value: String| vec![format!("--{}", name), value];
+        let flag = |name: &str, on: bool| if on { vec![format!("--{}", name)] } else { Vec::new() };
+        match self {
+            Setting::Data | Setting::Output => Vec::new(),
+            Setting::FgColor => option("fg-color", args.fg_color.clone()),
+            Setting::BgColor => option("bg-color", args.bg_color.clone()),
+            Setting::Gradient => args.gradient.clone().map(|g| option("gradient", g)).unwrap_or_default(),
+            Setting::EyeGradient => args.eye_gradient.clone().map(|g| option("eye-gradient", g)).unwrap_or_default(),
+            Setting::DotStyle => option("dot-style", args.dot_style.clone()),
+            Setting::EyeStyle => option("eye-style", args.eye_style.clone()),
+            Setting::Logo => match args.logo {
+                Some(ref path) => [option("logo", path.display().to_string()), option("logo-size", args.logo_size.to_string())].concat(),
+                None => Vec::new(),
+            },
+            Setting::Error => args.error.clone().map(|level| option("error", level)).unwrap_or_default(),
+            Setting::Version => args.version.map(|v| option("version", v.to_string())).unwrap_or_default(),
+            Setting::Border => option("border", args.border.to_string()),
+            Setting::Size => option("size", args.size.to_string()),
+            Setting::Encode => flag("encode", args.encode),
+            Setting::Format => option("format", args.format.clone()),
+            Setting::Copy => flag("copy", args.copy),
+            Setting::Show => flag("show", args.show),
+        }
+    }
+
+    /// Asks for a new value; Esc in a list keeps the old one
+    fn edit(&self, theme: &ColorfulTheme, args: &mut Args) -> Result<()> {
+        match self {
+            Setting::Data => ask_data(theme, args)?,
+            Setting::FgColor => args.fg_color = text(theme, tr!("prompt-fg-color"), &args.fg_color)?,
+            Setting::BgColor => args.bg_color = text(theme, tr!("prompt-bg-color"), &args.bg_color)?,
+            Setting::Gradient => args.gradient = optional(theme, tr!("prompt-gradient"), args.gradient.as_deref())?,
+            Setting::EyeGradient => args.eye_gradient = optional(theme, tr!("prompt-eye-gradient"), args.eye_gradient.as_deref())?,
+            Setting::DotStyle => {
+                if let Some(style) = choose(theme, tr!("prompt-dot-style"), &DOT_STYLES, &args.dot_style)? {
+                    args.dot_style = style;
+                }
+            }
+            Setting::EyeStyle => {
+                if let Some(style) = choose(theme, tr!("prompt-eye-style"), &EYE_STYLES, &args.eye_style)? {
+                    args.eye_style = style;
+                }
+            }
+            Setting::Logo => {
+                let current = args.logo.as_ref().map(|path| path.display().to_string());
+                args.logo = optional(theme, tr!("prompt-logo-path"), current.as_deref())?.map(PathBuf::from);
+                if args.logo.is_some() {
+                    args.logo_size = Input::with_theme(theme)
+                        .with_prompt(tr!("prompt-logo-size"))
+                        .default(args.logo_size)
+                        .interact_text()?;
+                }
+            }
+            Setting::Error => {
+                let current = args.error.clone().unwrap_or_else(|| "M".to_string());
+                if let Some(level) = choose(theme, tr!("prompt-error-level"), &EC_LEVELS, &current)? {
+                    args.error = Some(level);
+                }
+            }
+            Setting::Version => {
+                let current = args.version.map(|v| v.to_string()).unwrap_or_else(|| "auto".to_string());
+                let version: String = Input::with_theme(theme)
+                    .with_prompt(tr!("prompt-version"))
+                    .default(current)
+                    .validate_with(|input: &String| parse_version(input).map(|_| ()))
+                    .interact_text()?;
+                args.version = parse_version(&version).unwrap_or_default();
+            }
+            Setting::Border => {
+                args.border = Input::with_theme(theme).with_prompt(tr!("prompt-border")).default(args.border).interact_text()?;
+            }
+            Setting::Size => {
+                args.size = Input::with_theme(theme).with_prompt(tr!("prompt-size")).default(args.size).interact_text()?;
+            }
+            Setting::Format => {
+                if let Some(format) = choose(theme, tr!("prompt-format"), &FORMATS, &args.format)? {
+                    args.format = format;
+                }
+            }
+            Setting::Output => {
+                let output = text(theme, tr!("prompt-output"), &output_path(args).display().to_string())?;
+                args.output = Some(PathBuf::from(output));
+            }
+            Setting::Encode => args.encode = !args.encode,
+            Setting::Copy => args.copy = !args.copy,
+            Setting::Show => args.show = !args.show,
+        }
+        Ok(())
+    }
+}
+
+/// Shows every setting in a menu until the user saves a previewed code or cancels; returns true
+/// to save, with the arguments prepared for generating
+pub fn run(args: &mut Args) -> Result<bool> {
+    let theme = ColorfulTheme::default();
+
+    // A payload subcommand already provides the data
+    let settings: Vec<Setting> = SETTINGS
+        .into_iter()
+        .filter(|setting| args.command.is_none() || *setting != Setting::Data)
+        .collect();
+    let has_data = |args: &Args| args.data.is_some() || args.data_file.is_some() || args.command.is_some();
+    if !has_data(args) {
+        ask_data(&theme, args)?;
+    }
+
+    let labels: Vec<String> = settings.iter().map(Setting::label).collect();
+    let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or_default();
+    let mut selected = settings.len();
+    loop {
+        let mut items: Vec<String> = settings
+            .iter()
+            .zip(&labels)
+            .map(|(setting, label)| {
+                let padding = " ".repeat(width - label.chars().count());
+                format!("{}{}  {}", label, padding, setting.value(args))
+            })
+            .collect();
+        items.push(tr!("menu-generate"));
+        items.push(tr!("menu-cancel"));
+
+        let choice = Select::with_theme(&theme)
+            .with_prompt(tr!("menu-prompt"))
+            .default(selected)
+            .items(&items)
+            .interact_opt()?;
+        match choice {
+            Some(choice) if choice < settings.len() => {
+                settings[choice].edit(&theme, args)?;
+                selected = choice;
+            }
+            Some(choice) if choice == settings.len() => {
+                if !has_data(args) {
+                    log::warn!("⚠ {}", tr!("menu-needs-data"));
+                    selected = 0;
+                    continue;
+                }
+                let mut prepared = args.clone();
+                prepared.output = Some(output_path(args));
+                match review(&theme, &mut prepared) {
+                    Ok(Review::Save) => {
+                        offer_preset(&theme, args)?;
+                        *args = prepared;
+                        return Ok(true);
+                    }
+                    Ok(Review::Adjust) => {}
+                    Ok(Review::Cancel) => return Ok(false),
+                    // A bad color or a payload that does not fit goes back to the menu to be fixed
+                    Err(e) => log::warn!("⚠ {:#}", e),
+                }
+            }
+            // Cancel or Esc
+            _ => return Ok(false),
+        }
+    }
+}
+
+enum Review {
+    Save,
+    Adjust,
+    Cancel,
+}
+
+/// Prepares the arguments as a normal run would, shows the code and asks whether to save it
+fn review(theme: &ColorfulTheme, args: &mut Args) -> Result<Review> {
+    crate::validate_format(&args.format)?;
+    crate::prepare_args(args)?;
+    // A batch has no single code to show
+    let batch = args.batch.is_some() || args.batch_sql.is_some() || args.sequence.is_some() || args.generate.is_some();
+    if !batch && args.binary_data.is_none() {
+        print_preview(&render_preview(args)?);
+    }
+
+    let choices = [tr!("review-save"), tr!("review-adjust"), tr!("review-cancel")];
+    let choice = Select::with_theme(theme).with_prompt(tr!("review-prompt")).default(0).items(&choices).interact_opt()?;
+    Ok(match choice {
+        Some(0) => Review::Save,
+        Some(2) => Review::Cancel,
+        // Esc goes back to the menu rather than throwing the settings away
+        _ => Review::Adjust,
+    })
+}
+
+/// Offers to keep the answers as a preset, along with the command line's other styling options
+fn offer_preset(theme: &ColorfulTheme, args: &Args) -> Result<()> {
+    if !Confirm::with_theme(theme).with_prompt(tr!("preset-offer")).default(false).interact()? {
+        return Ok(());
+    }
+    let name: String = Input::with_theme(theme)
+        .with_prompt(tr!("preset-name"))
+        .validate_with(|name: &String| {
+            let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if valid { Ok(()) } else { Err(tr!("preset-name-invalid")) }
+        })
+        .interact_text()?;
+
+    // Options the menu covers come from the answers, per-run ones such as --force stay out
+    const LEFT_OUT: [&str; 18] = [
+        "data", "data_file", "output", "interactive", "preset", "force", "no_clobber", "dry_run", "lang", "verbose", "quiet",
+        "log_format", "print", "webhook", "mail_to", "telegram", "logo_size", "record",
+    ];
+    let mut arguments: Vec<String> = preset::option_groups(args.invocation.as_deref().unwrap_or_default())
+        .into_iter()
+        .filter(|(id, _)| id.as_deref().is_some_and(|id| !LEFT_OUT.contains(&id) && !SETTINGS.iter().any(|setting| setting.id() == id)))
+        .flat_map(|(_, tokens)| tokens)
+        .collect();
+    arguments.extend(SETTINGS.iter().flat_map(|setting| setting.arguments(args)));
+
+    let path = preset::save(&name, &arguments)?;
+    log::info!("{}", tr!("preset-saved", name = name, path = path.display().to_string()));
+    Ok(())
+}
+
+/// Prints two modules per character cell as upper half blocks, in the image's own colors
+fn print_preview(preview: &Preview) {
+    let mut out = String::from("\n");
+    for rows in preview.modules.chunks(2) {
+        for (x, &top) in rows[0].iter().enumerate() {
+            let bottom = rows.get(1).map_or(top, |row| row[x]);
+            let ([r, g, b], [br, bg, bb]) = (blend(top), blend(bottom));
+            out.push_str(&format!("\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m▀", r, g, b, br, bg, bb));
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    println!("{}{}\n", out, preview.summary);
+}
+
+/// Blends transparency over white
+fn blend(pixel: Rgba<u8>) -> [u8; 3] {
+    let alpha = pixel[3] as u16;
+    let blend = |c: u8| ((c as u16 * alpha + 255 * (255 - alpha)) / 255) as u8;
+    [blend(pixel[0]), blend(pixel[1]), blend(pixel[2])]
+}
+
+/// Asks what to encode, plain text or an SMS; Esc keeps the current data
+fn ask_data(theme: &ColorfulTheme, args: &mut Args) -> Result<()> {
+    let kinds = [tr!("kind-text"), tr!("kind-sms")];
+    let Some(kind) = Select::with_theme(theme).with_prompt(tr!("prompt-kind")).default(0).items(&kinds).interact_opt()? else {
+        return Ok(());
+    };
+
+    args.data = Some(match kind {
+        1 => {
+            let to: String = Input::with_theme(theme).with_prompt(tr!("prompt-phone")).interact_text()?;
+            let message: String = Input::with_theme(theme)
+                .with_prompt(tr!("prompt-message"))
+                .allow_empty(true)
+                .interact_text()?;
+            payload::sms_payload(&to, &message)?
+        }
+        _ => Input::with_theme(theme)
+            .with_prompt(tr!("prompt-text"))
+            .with_initial_text(args.data.clone().unwrap_or_default())
+            .interact_text()?,
+    });
+    args.data_file = None;
+    Ok(())
+}
+
+fn text(theme: &ColorfulTheme, prompt: String, current: &str) -> Result<String> {
+    Ok(Input::with_theme(theme).with_prompt(prompt).default(current.to_string()).interact_text()?)
+}
+
+/// Text that may be left empty to clear the setting
+fn optional(theme: &ColorfulTheme, prompt: String, current: Option<&str>) -> Result<Option<String>> {
+    let value: String = Input::with_theme(theme)
+        .with_prompt(prompt)
+        .with_initial_text(current.unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()?;
+    Ok((!value.trim().is_empty()).then(|| value.trim().to_string()))
+}
+
+/// Picks one of `options` starting at the current one, None on Esc
+fn choose(theme: &ColorfulTheme, prompt: String, options: &[&str], current: &str) -> Result<Option<String>> {
+    let index = options.iter().position(|option| option.eq_ignore_ascii_case(current)).unwrap_or(0);
+    let choice = Select::with_theme(theme).with_prompt(prompt).default(index).items(options).interact_opt()?;
+    Ok(choice.map(|choice| options[choice].to_string()))
+}
+
+fn parse_version(input: &str) -> Result<Option<i16>, String> {
+    match input.trim() {
+        "" | "auto" => Ok(None),
+        text => match text.parse::<i16>() {
+            Ok(version @ 1..=40) => Ok(Some(version)),
+            _ => Err(tr!("version-range")),
+        },
+    }
+}
+
+fn output_path(args: &Args) -> PathBuf {
+    args.output.clone().unwrap_or_else(|| PathBuf::from(format!("qrcode.{}", args.format)))
+}
@@ -0,0 +1,102 @@
+use crate::layout::mm_to_px;
+use anyhow::Result;
+use crate::symbol::Symbol;
+use std::io::Write;
+
+pub enum LabelLanguage {
+    Epl,
+    Tspl,
+}
+
+pub struct LabelOptions {
+    pub width_mm: f32,
+    pub height_mm: f32,
+    pub dpi: u32,
+    pub darkness: u8,
+    pub border: u32,
+}
+
+impl LabelLanguage {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "epl" => Some(LabelLanguage::Epl),
+            "tspl" => Some(LabelLanguage::Tspl),
+            _ => None,
+        }
+    }
+}
+
+/// Builds a printer program that prints the module grid as a 1-bit bitmap,
+/// centered on the label with an integer number of dots per module
+pub fn render_label(symbol: &Symbol, language: &LabelLanguage, options: &LabelOptions) -> Result<Vec<u8>> {
+    if options.darkness > 15 {
+        anyhow::bail!("Darkness must be between 0 and 15");
+    }
+
+    let label_w = mm_to_px(options.width_mm, options.dpi);
+    let label_h = mm_to_px(options.height_mm, options.dpi);
+    let columns = symbol.width() as u32 + 2 * options.border;
+    let rows = symbol.height() as u32 + 2 * options.border;
+    let dots_per_module = (label_w / columns).min(label_h / rows);
+    if dots_per_module == 0 {
+        anyhow::bail!(
+            "Label is too small: {}x{} modules need at least {}x{} dots, label has {}x{}",
+            columns,
+            rows,
+            columns,
+            rows,
+            label_w,
+            label_h
+        );
+    }
+
+    let (width, height) = (columns * dots_per_module, rows * dots_per_module);
+    let x = (label_w - width) / 2;
+    let y = (label_h - height) / 2;
+    let (width_bytes, bitmap) = pack_bitmap(symbol, options.border, dots_per_module);
+
+    let mut out = Vec::new();
+    match language {
+        LabelLanguage::Epl => {
+            write!(out, "\nN\nq{}\nQ{},24\nD{}\n", label_w, label_h, options.darkness)?;
+            write!(out, "GW{},{},{},{},", x, y, width_bytes, height)?;
+            out.extend_from_slice(&bitmap);
+            write!(out, "\nP1\n")?;
+        }
+        LabelLanguage::Tspl => {
+            write!(out, "SIZE {} mm,{} mm\r\n", options.width_mm, options.height_mm)?;
+            write!(out, "GAP 2 mm,0 mm\r\nDENSITY {}\r\nCLS\r\n", options.darkness)?;
+            write!(out, "BITMAP {},{},{},{},0,", x, y, width_bytes, height)?;
+            out.extend_from_slice(&bitmap);
+            write!(out, "\r\nPRINT 1\r\n")?;
+        }
+    }
+    Ok(out)
+}
+
+/// Both languages expect MSB-first rows where a 0 bit prints a dot
+fn pack_bitmap(symbol: &Symbol, border: u32, dots_per_module: u32) -> (u32, Vec<u8>) {
+    let (qr_width, qr_height) = (symbol.width() as u32, symbol.height() as u32);
+    let width = (qr_width + 2 * border) * dots_per_module;
+    let height = (qr_height + 2 * border) * dots_per_module;
+    let width_bytes = width.div_ceil(8);
+    let mut bitmap = vec![0xffu8; (width_bytes * height) as usize];
+
+    for py in 0..height {
+        for px in 0..width {
+            let mx = (px / dots_per_module) as i64 - border as i64;
+            let my = (py / dots_per_module) as i64 - border as i64;
+            let dark = mx >= 0
+                && my >= 0
+                && (mx as u32) < qr_width
+                && (my as u32) < qr_height
+                && symbol.is_dark(mx as usize, my as usize);
+            if dark {
+                let index = (py * width_bytes + px / 8) as usize;
+                bitmap[index] &= !(0x80 >> (px % 8));
+            }
+        }
+    }
+
+    (width_bytes, bitmap)
+}
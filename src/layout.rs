@@ -0,0 +1,362 @@
+use crate::text::{Align, draw_text, fit_text_size, line_height};
+use ab_glyph::FontArc;
+use anyhow::{Context, Result};
+use image::{Rgba, RgbaImage};
+
+/// Inner padding kept clear along each label edge, in millimeters
+const CELL_PADDING: f32 = 1.5;
+
+/// Label templates, all measurements in millimeters
+const LABEL_PRESETS: &[(&str, SheetLayout)] = &[
+    ("avery-5160", SheetLayout {
+        page_width: 215.9,
+        page_height: 279.4,
+        columns: 3,
+        rows: 10,
+        cell_width: 66.675,
+        cell_height: 25.4,
+        left: 4.7625,
+        top: 12.7,
+        pitch_x: 69.85,
+        pitch_y: 25.4,
+    }),
+    ("avery-5163", SheetLayout {
+        page_width: 215.9,
+        page_height: 279.4,
+        columns: 2,
+        rows: 5,
+        cell_width: 101.6,
+        cell_height: 50.8,
+        left: 3.96875,
+        top: 12.7,
+        pitch_x: 104.775,
+        pitch_y: 50.8,
+    }),
+    ("avery-5167", SheetLayout {
+        page_width: 215.9,
+        page_height: 279.4,
+        columns: 4,
+        rows: 20,
+        cell_width: 44.45,
+        cell_height: 12.7,
+        left: 7.62,
+        top: 12.7,
+        pitch_x: 52.07,
+        pitch_y: 12.7,
+    }),
+    ("avery-l7160", SheetLayout {
+        page_width: 210.0,
+        page_height: 297.0,
+        columns: 3,
+        rows: 7,
+        cell_width: 63.5,
+        cell_height: 38.1,
+        left: 7.2,
+        top: 15.15,
+        pitch_x: 66.0,
+        pitch_y: 38.1,
+    }),
+    ("avery-l7163", SheetLayout {
+        page_width: 210.0,
+        page_height: 297.0,
+        columns: 2,
+        rows: 7,
+        cell_width: 99.1,
+        cell_height: 38.1,
+        left: 4.65,
+        top: 15.15,
+        pitch_x: 101.6,
+        pitch_y: 38.1,
+    }),
+    ("avery-l7651", SheetLayout {
+        page_width: 210.0,
+        page_height: 297.0,
+        columns: 5,
+        rows: 13,
+        cell_width: 38.1,
+        cell_height: 21.2,
+        left: 4.75,
+        top: 10.7,
+        pitch_x: 40.6,
+        pitch_y: 21.2,
+    }),
+    ("herma-4360", SheetLayout {
+        page_width: 210.0,
+        page_height: 297.0,
+        columns: 3,
+        rows: 8,
+        cell_width: 70.0,
+        cell_height: 36.0,
+        left: 0.0,
+        top: 4.5,
+        pitch_x: 70.0,
+        pitch_y: 36.0,
+    }),
+];
+
+/// A rendered code and the text printed next to it in its cell
+pub struct SheetItem {
+    pub image: RgbaImage,
+    pub caption: Option<String>,
+}
+
+/// Physical placement of equally sized cells on a page, in millimeters
+#[derive(Clone, Copy)]
+pub struct SheetLayout {
+    pub page_width: f32,
+    pub page_height: f32,
+    pub columns: u32,
+    pub rows: u32,
+    pub cell_width: f32,
+    pub cell_height: f32,
+    pub left: f32,
+    pub top: f32,
+    pub pitch_x: f32,
+    pub pitch_y: f32,
+}
+
+impl SheetLayout {
+    /// Evenly divides the printable area into a grid separated by gutters
+    pub fn grid(page: (f32, f32), columns: u32, rows: u32, margin: f32, gutter: f32) -> Result<Self> {
+        if columns == 0 || rows == 0 {
+            anyhow::bail!("Sheet needs at least one row and one column");
+        }
+        let cell_width = (page.0 - 2.0 * margin - (columns - 1) as f32 * gutter) / columns as f32;
+        let cell_height = (page.1 - 2.0 * margin - (rows - 1) as f32 * gutter) / rows as f32;
+        if cell_width <= 0.0 || cell_height <= 0.0 {
+            anyhow::bail!("Margins and gutters leave no room for {}x{} cells", columns, rows);
+        }
+
+        Ok(SheetLayout {
+            page_width: page.0,
+            page_height: page.1,
+            columns,
+            rows,
+            cell_width,
+            cell_height,
+            left: margin,
+            top: margin,
+            pitch_x: cell_width + gutter,
+            pitch_y: cell_height + gutter,
+        })
+    }
+
+    /// Looks up a label template such as `avery-5160`
+    pub fn preset(name: &str) -> Result<Self> {
+        LABEL_PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, layout)| *layout)
+            .with_context(|| format!("Unknown label template '{}'. Available: {}", name, preset_names().join(", ")))
+    }
+
+    pub fn page_size(&self) -> (f32, f32) {
+        (self.page_width, self.page_height)
+    }
+
+    pub fn cells_per_page(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    /// Wide cells put captions beside the code, others below it
+    fn caption_beside(&self) -> bool {
+        self.cell_width >= 1.5 * self.cell_height
+    }
+
+    /// Largest square (in pixels) that fits every cell, leaving room for captions
+    pub fn code_size(&self, dpi: u32, captions: bool) -> u32 {
+        let width = self.cell_width - 2.0 * CELL_PADDING;
+        let height = self.cell_height - 2.0 * CELL_PADDING;
+        let side = match (captions, self.caption_beside()) {
+            (false, _) => width.min(height),
+            (true, true) => height.min(width / 2.0),
+            (true, false) => width.min(height * 0.8),
+        };
+        mm_to_px(side.max(0.0), dpi)
+    }
+
+    /// Top-left pixel of a cell, filled row by row
+    pub fn cell_origin(&self, index: usize, dpi: u32) -> (u32, u32) {
+        let column = (index % self.columns as usize) as f32;
+        let row = (index / self.columns as usize) as f32;
+        (
+            mm_to_px(self.left + column * self.pitch_x, dpi),
+            mm_to_px(self.top + row * self.pitch_y, dpi),
+        )
+    }
+}
+
+/// Accepts a named paper size or a custom `WIDTHxHEIGHT` in millimeters
+pub fn parse_page_size(s: &str) -> Result<(f32, f32)> {
+    match s.to_lowercase().as_str() {
+        "a3" => Ok((297.0, 420.0)),
+        "a4" => Ok((210.0, 297.0)),
+        "a5" => Ok((148.0, 210.0)),
+        "letter" => Ok((215.9, 279.4)),
+        "legal" => Ok((215.9, 355.6)),
+        custom => {
+            let (w, h) = custom
+                .split_once('x')
+                .context("Page size must be a4, a3, a5, letter, legal or WIDTHxHEIGHT in mm")?;
+            let w: f32 = w.trim().parse().context("Invalid page width")?;
+            let h: f32 = h.trim().parse().context("Invalid page height")?;
+            if w <= 0.0 || h <= 0.0 {
+                anyhow::bail!("Page size must be positive");
+            }
+            Ok((w, h))
+        }
+    }
+}
+
+pub fn preset_names() -> Vec<&'static str> {
+    LABEL_PRESETS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Places each code in its cell, starting a new page whenever one fills up.
+/// Without a caption the code is centered; with one, the code and text share the cell.
+pub fn compose_sheets(items: &[SheetItem], layout: &SheetLayout, dpi: u32, font: &FontArc, text_color: Rgba<u8>) -> Vec<RgbaImage> {
+    let page_w = mm_to_px(layout.page_width, dpi);
+    let page_h = mm_to_px(layout.page_height, dpi);
+    let cell_w = mm_to_px(layout.cell_width, dpi);
+    let cell_h = mm_to_px(layout.cell_height, dpi);
+    let padding = mm_to_px(CELL_PADDING, dpi);
+
+    items
+        .chunks(layout.cells_per_page())
+        .map(|page_items| {
+            let mut page = RgbaImage::from_pixel(page_w, page_h, Rgba([255, 255, 255, 255]));
+            for (i, item) in page_items.iter().enumerate() {
+                let (cell_x, cell_y) = layout.cell_origin(i, dpi);
+                let code = &item.image;
+
+                let Some(ref caption) = item.caption else {
+                    let x = cell_x + cell_w.saturating_sub(code.width()) / 2;
+                    let y = cell_y + cell_h.saturating_sub(code.height()) / 2;
+                    image::imageops::overlay(&mut page, code, x as i64, y as i64);
+                    continue;
+                };
+
+                if layout.caption_beside() {
+                    let x = cell_x + padding;
+                    let y = cell_y + cell_h.saturating_sub(code.height()) / 2;
+                    image::imageops::overlay(&mut page, code, x as i64, y as i64);
+
+                    let text_x = (x + code.width() + padding) as f32;
+                    let text_w = (cell_x + cell_w).saturating_sub(x + code.width() + 2 * padding) as f32;
+                    let size = fit_text_size(font, cell_h as f32 * 0.25, caption, text_w);
+                    let text_y = cell_y as f32 + (cell_h as f32 - line_height(font, size)) / 2.0;
+                    draw_text(&mut page, font, size, text_color, caption, text_x, text_y, text_w, Align::Left);
+                } else {
+                    let text_w = cell_w.saturating_sub(2 * padding) as f32;
+                    let size = fit_text_size(font, cell_h as f32 * 0.12, caption, text_w);
+                    let block_h = code.height() as f32 + line_height(font, size);
+                    let top = cell_y as f32 + (cell_h as f32 - block_h) / 2.0;
+
+                    let x = cell_x + cell_w.saturating_sub(code.width()) / 2;
+                    image::imageops::overlay(&mut page, code, x as i64, top as i64);
+                    let text_y = top + code.height() as f32;
+                    draw_text(&mut page, font, size, text_color, caption, (cell_x + padding) as f32, text_y, text_w, Align::Center);
+                }
+            }
+            page
+        })
+        .collect()
+}
+
+pub fn mm_to_px(mm: f32, dpi: u32) -> u32 {
+    (mm / 25.4 * dpi as f32).round() as u32
+}
+
+/// Safe margin kept clear of the card trim edge, in millimeters
+const CARD_MARGIN: f32 = 5.0;
+
+pub struct CardOptions {
+    /// Trim size in millimeters
+    pub size: (f32, f32),
+    /// Front carries the text, back carries the code
+    pub two_sided: bool,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub lines: Vec<String>,
+    pub background: Rgba<u8>,
+    pub text_color: Rgba<u8>,
+}
+
+impl CardOptions {
+    /// Side of the square code in pixels
+    pub fn code_size(&self, dpi: u32) -> u32 {
+        let height = self.size.1 - 2.0 * CARD_MARGIN;
+        let side = if self.two_sided { height * 0.8 } else { height.min(self.size.0 * 0.4) };
+        mm_to_px(side, dpi)
+    }
+}
+
+/// Accepts a named card size or a custom `WIDTHxHEIGHT` in millimeters
+pub fn parse_card_size(s: &str) -> Result<(f32, f32)> {
+    match s.to_lowercase().as_str() {
+        "eu" => Ok((85.0, 55.0)),
+        "us" => Ok((88.9, 50.8)),
+        "jp" => Ok((91.0, 55.0)),
+        custom => parse_page_size(custom).context("Card size must be eu, us, jp or WIDTHxHEIGHT in mm"),
+    }
+}
+
+/// Lays out the card: text block on the left of the front, code either on the
+/// right of the same side or centered on a separate back page
+pub fn compose_card(code: &RgbaImage, card: &CardOptions, dpi: u32, font: &FontArc) -> Vec<RgbaImage> {
+    let width = mm_to_px(card.size.0, dpi);
+    let height = mm_to_px(card.size.1, dpi);
+    let margin = mm_to_px(CARD_MARGIN, dpi);
+
+    let mut front = RgbaImage::from_pixel(width, height, card.background);
+    let text_right = if card.two_sided {
+        width - margin
+    } else {
+        let x = width - margin - code.width();
+        let y = (height - code.height()) / 2;
+        image::imageops::overlay(&mut front, code, x as i64, y as i64);
+        x - margin
+    };
+
+    // (text, size relative to card height) for each line, top to bottom
+    let h = height as f32;
+    let mut lines: Vec<(&str, f32)> = Vec::new();
+    if let Some(ref name) = card.name {
+        lines.push((name, h * 0.11));
+    }
+    if let Some(ref title) = card.title {
+        lines.push((title, h * 0.065));
+    }
+    for line in &card.lines {
+        lines.push((line, h * 0.055));
+    }
+
+    let text_w = text_right.saturating_sub(margin) as f32;
+    let sized: Vec<(&str, f32)> = lines
+        .iter()
+        .map(|(text, size)| (*text, fit_text_size(font, *size, text, text_w)))
+        .collect();
+
+    // A small gap separates the name/title from the contact lines
+    let gap = if card.lines.is_empty() { 0.0 } else { h * 0.05 };
+    let block_h: f32 = sized.iter().map(|(_, size)| line_height(font, *size)).sum::<f32>() + gap;
+    let mut y = (h - block_h) / 2.0;
+    let contact_start = sized.len() - card.lines.len();
+    for (i, (text, size)) in sized.iter().enumerate() {
+        if i == contact_start && i > 0 {
+            y += gap;
+        }
+        draw_text(&mut front, font, *size, card.text_color, text, margin as f32, y, text_w, Align::Left);
+        y += line_height(font, *size);
+    }
+
+    if !card.two_sided {
+        return vec![front];
+    }
+
+    let mut back = RgbaImage::from_pixel(width, height, card.background);
+    let x = (width - code.width()) / 2;
+    let y = (height - code.height()) / 2;
+    image::imageops::overlay(&mut back, code, x as i64, y as i64);
+    vec![front, back]
+}
@@ -0,0 +1,4185 @@
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage};
+use qrcode::bits::Bits;
+use qrcode::types::QrError;
+use qrcode::{EcLevel, QrCode, Version};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use base64::{Engine as _, engine::general_purpose};
+use i18n::tr;
+
+mod address;
+mod archive;
+mod aztec;
+mod barcode;
+mod batch;
+mod capacity;
+mod canvas;
+mod cmyk;
+mod compress;
+mod crypto;
+mod datamatrix;
+mod decode;
+mod deliver;
+mod diff;
+mod dxf;
+mod effects;
+mod encoders;
+mod fill;
+mod frame;
+mod gs1;
+#[cfg(feature = "history")]
+mod history;
+mod i18n;
+mod icc;
+mod inspect;
+#[cfg(feature = "cli")]
+mod interactive;
+mod label;
+mod layout;
+mod logging;
+mod manifest;
+mod metadata;
+mod model3d;
+mod mockup;
+#[cfg(feature = "node")]
+mod node;
+mod palette;
+mod payload;
+mod plugin;
+mod preset;
+#[cfg(feature = "preview")]
+mod preview;
+mod print;
+mod quantize;
+mod reserve;
+mod scan;
+mod script;
+mod sign;
+mod styles;
+mod symbol;
+mod template;
+mod text;
+mod themes;
+#[cfg(feature = "cli")]
+mod tui;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use archive::ZipWriter;
+use barcode::BarcodeOptions;
+use batch::read_batch;
+use canvas::{Canvas, OuterBorder, clip_to_circle};
+use capacity::{Mode, too_long_error};
+use compress::Compressor;
+use decode::{Decoded, decode_all, decode_image, open_image};
+use dxf::render_dxf;
+use effects::{Shadow, Stroke, Texture, apply_stroke, apply_texture, cast_shadow};
+use encoders::{PngOptions, apply_matte, encode_avif, encode_cmyk_tiff, encode_gif, encode_jpeg, encode_png, encode_tiff, encode_webp, save_pdf, save_png};
+use fill::{BgGradient, Fill};
+use frame::{FrameOptions, FramePosition, FrameStyle, apply_frame, fill_rounded_rect};
+use label::{LabelLanguage, LabelOptions, render_label};
+use mockup::Mockup;
+use model3d::{ModelFormat, ModelOptions, render_model};
+use layout::{CardOptions, SheetItem, SheetLayout, compose_card, compose_sheets, parse_card_size, parse_page_size};
+use payload::{Payload, normalize_url};
+use print::{PrintJob, send_to_printer};
+use text::{Align, add_caption, draw_text, fit_text_size, load_font};
+use reserve::Reservation;
+use scan::{FrameStream, capture_screens};
+use script::{ModuleStyle, StyleScript};
+use symbol::{Pattern, Symbol, Symbology};
+use styles::{DotPattern, DotStyle, EyeStyle, apply_dot_style, apply_eye_style, parse_gradient};
+
+pub use plugin::{ModuleRenderer, Registry};
+
+#[derive(Subcommand, Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+enum Command {
+    /// Read a QR code from an image and print its contents
+    Decode(DecodeArgs),
+    /// Compare the QR codes in two images: payload, version, level, mask and a module diff image
+    Diff(DiffArgs),
+    /// Watch a camera, or grab the screen, and print the QR codes found
+    Scan(ScanArgs),
+    /// Report which versions and error correction levels fit the data, without rendering
+    Analyze,
+    /// Print how many characters fit in each version, marking where --data lands
+    Capacity(CapacityArgs),
+    /// Render every dot style × eye style combination onto one labeled sheet
+    Styles(StylesArgs),
+    /// Time encoding, rendering and file writing across sizes and dot styles
+    Bench(BenchArgs),
+    /// List the built-in themes for --theme
+    Themes,
+    /// Browse the local record of generated codes
+    History(HistoryArgs),
+    #[command(flatten)]
+    Payload(Payload),
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct DecodeArgs {
+    /// Image containing the QR code (png, jpg, webp, ...), or - to read it from stdin
+    image: PathBuf,
+
+    /// Decrypt a payload written with --encrypt
+    #[arg(long)]
+    decrypt: bool,
+
+    /// Check a payload written with --sign against this Ed25519 public key (PEM)
+    #[arg(long, value_name = "KEY")]
+    verify_signature: Option<PathBuf>,
+
+    /// Read every code in the image, not just the first one found
+    #[arg(long)]
+    all: bool,
+
+    /// text prints the payloads, json adds bounds, version, level, mask and ECI for each code
+    #[arg(long, value_parser = ["text", "json"], default_value = "text")]
+    output_format: String,
+
+    /// Write the payload byte for byte to this file; with --all, later codes get -2, -3, ... suffixes
+    #[arg(long, value_name = "FILE")]
+    raw_bytes: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct CapacityArgs {
+    /// Encoding mode to list; defaults to the mode --data needs, or byte
+    #[arg(long, value_parser = ["numeric", "alphanumeric", "byte"])]
+    mode: Option<String>,
+
+    /// Only this error correction level; on its own it lists all three modes side by side
+    #[arg(long, ignore_case = true, value_parser = ["L", "M", "Q", "H"])]
+    ec: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct BenchArgs {
+    /// Runs per size and style; the table shows the median
+    #[arg(long, default_value = "10")]
+    iterations: u32,
+
+    /// Image sizes in pixels, comma separated
+    #[arg(long, value_delimiter = ',', default_value = "250,500,1000,2000")]
+    sizes: Vec<u32>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct DiffArgs {
+    /// The original image
+    first: PathBuf,
+
+    /// The image to compare against it; the diff is written to --output (default diff.png)
+    second: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct ScanArgs {
+    /// Camera to read: an index such as 0 or a device path (a device name on Windows); needs ffmpeg
+    #[arg(long, value_name = "DEVICE")]
+    camera: Option<String>,
+
+    /// Read every code currently visible on the screen(s), e.g. in a video call
+    #[arg(long, conflicts_with = "camera")]
+    screen: bool,
+
+    /// Exit after the first code
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct HistoryArgs {
+    #[command(subcommand)]
+    action: HistoryAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum HistoryAction {
+    /// The most recent codes, newest first
+    List(HistoryFilter),
+    /// Codes whose payload, output path or command line contains the text, or whose payload hash starts with it
+    Search {
+        text: String,
+        #[command(flatten)]
+        filter: HistoryFilter,
+    },
+    /// Everything recorded about one code
+    Show { id: i64 },
+    /// Generate a recorded code again; options given here (e.g. --format svg --size 2000) replace the recorded ones
+    Regen { id: i64 },
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct HistoryFilter {
+    /// Show at most this many codes
+    #[arg(long, default_value = "20")]
+    limit: u32,
+
+    /// Only codes generated on or after this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    since: Option<chrono::NaiveDate>,
+
+    /// Only codes generated on or before this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    until: Option<chrono::NaiveDate>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct StylesArgs {
+    /// Where to write the preview sheet (PNG)
+    #[arg(default_value = "styles.png")]
+    sheet: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone, serde::Serialize)]
+#[command(name = "qrcode")]
+#[command(about = "Generate QR codes with custom styling", long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    #[serde(skip)]
+    command: Option<Command>,
+
+    /// Text or URL to encode; {env:VAR} and {date} are filled in, and {col:NAME} from each --batch row
+    #[arg(short = 'd', long, global = true)]
+    data: Option<String>,
+
+    /// Read the payload from a file (UTF-8 text unless --binary)
+    #[arg(long, conflicts_with = "data", global = true)]
+    data_file: Option<PathBuf>,
+
+    /// Encode --data-file as raw bytes in byte mode, without any text handling
+    #[arg(
+        long,
+        requires = "data_file",
+        conflicts_with_all = ["url", "encode", "encrypt", "compress", "sign", "batch", "batch_sql", "sequence", "generate", "sheet", "card"],
+        global = true
+    )]
+    binary: bool,
+
+    /// Bytes of --data-file in binary mode, loaded at startup
+    #[arg(skip)]
+    binary_data: Option<Vec<u8>>,
+
+    /// Output file path
+    #[arg(short = 'o', long, global = true)]
+    output: Option<PathBuf>,
+
+    /// Never overwrite: save as name-2.png, name-3.png, ... when the file exists
+    #[arg(long, conflicts_with = "force", global = true)]
+    no_clobber: bool,
+
+    /// Overwrite existing files without a warning
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Name each file from a hash of its payload and settings, in DIR (default: the current
+    /// directory), and skip codes whose file already exists
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = ".", conflicts_with_all = ["output", "archive", "sheet", "card"], global = true)]
+    name_by_hash: Option<PathBuf>,
+
+    /// Seed for everything random (encryption, textures, random dot styles), so identical input always gives byte-identical output
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Metadata written into the file, e.g. title=..., author=..., comment=... (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", global = true)]
+    meta: Vec<String>,
+
+    /// Also record the payload and the style settings as metadata
+    #[arg(long, global = true)]
+    embed_settings: bool,
+
+    /// Color profile embedded in PNG, JPEG and TIFF output: sRGB or a path to an .icc file
+    #[arg(long, value_name = "sRGB|FILE", global = true)]
+    icc_profile: Option<String>,
+
+    /// Profile loaded from --icc-profile
+    #[arg(skip)]
+    #[serde(skip)]
+    color_profile: Option<icc::IccProfile>,
+
+    /// Convert colors to CMYK and write a CMYK TIFF for prepress
+    #[arg(long, global = true)]
+    cmyk: bool,
+
+    /// Print black as a rich black ink mix in CMYK output, as C,M,Y,K percentages
+    #[arg(long, value_name = "C,M,Y,K", num_args = 0..=1, default_missing_value = cmyk::DEFAULT_RICH_BLACK, requires = "cmyk", global = true)]
+    rich_black: Option<String>,
+
+    /// Ink values parsed from --rich-black
+    #[arg(skip)]
+    rich_black_inks: Option<[u8; 4]>,
+
+    /// Check data, capacity and styles and report what would be written, without writing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Check data capacity, colors, the logo and output directories, list every problem and generate nothing
+    #[arg(long, conflicts_with_all = ["dry_run", "interactive", "tui"], global = true)]
+    validate: bool,
+
+    /// Output format (png, jpg, jpeg, svg, webp, tiff, tif, ico, bmp, gif, tga, avif, qoi, epl, tspl, stl, 3mf, dxf)
+    #[arg(short = 'f', long, default_value = "png", global = true)]
+    format: String,
+
+    /// Code type to generate (qr, datamatrix, aztec, code128, ean13)
+    #[arg(long, default_value = "qr", global = true)]
+    symbology: String,
+
+    /// Bar height in modules for linear barcodes (default: a quarter of the width, 69 for EAN-13)
+    #[arg(long, global = true)]
+    bar_height: Option<usize>,
+
+    /// Light modules left and right of linear barcodes (default: 10, EAN-13 uses 11 and 7)
+    #[arg(long, global = true)]
+    quiet_zone: Option<usize>,
+
+    /// Encode --data as a GS1 element string such as "(01)09506000134352(17)261231(10)A1"
+    #[arg(long, global = true)]
+    gs1: bool,
+
+    /// GS1 GTIN, AI (01): 8, 12, 13 or 14 digits with check digit (implies --gs1)
+    #[arg(long, global = true)]
+    gtin: Option<String>,
+
+    /// GS1 expiry date, AI (17): YYMMDD or YYYY-MM-DD (implies --gs1)
+    #[arg(long, global = true)]
+    expiry: Option<String>,
+
+    /// GS1 batch or lot number, AI (10) (implies --gs1)
+    #[arg(long, global = true)]
+    lot: Option<String>,
+
+    /// GS1 serial number, AI (21) (implies --gs1)
+    #[arg(long, global = true)]
+    serial: Option<String>,
+
+    /// Built-in theme setting colors, gradient, dot and eye styles (see the themes subcommand); explicit options win
+    #[arg(long, global = true)]
+    theme: Option<String>,
+
+    /// JSON file of named colors, e.g. {"primary": "#1a73e8"}, usable in every color option
+    #[arg(long, global = true)]
+    palette: Option<PathBuf>,
+
+    /// Background color (hex format: #ffffff or 'transparent')
+    #[arg(long, default_value = "transparent", global = true)]
+    bg_color: String,
+
+    /// Background gradient behind the code (format: #ffffff,#dde7f0[,ANGLE]); frames and captions keep --bg-color
+    #[arg(long, global = true)]
+    bg_gradient: Option<String>,
+
+    /// Foreground color (hex format: #000000)
+    #[arg(long, default_value = "#000000", global = true)]
+    fg_color: String,
+
+    /// Gradient colors (format: #ff0000,#0000ff)
+    #[arg(short = 'g', long, global = true)]
+    gradient: Option<String>,
+
+    /// How the gradient is applied: module (one color per module) or continuous (smooth across pixels)
+    #[arg(long, default_value = "module", value_parser = ["module", "continuous"], global = true)]
+    gradient_mapping: String,
+
+    /// Separate gradient for the finder patterns, running across each one (format: #aa0000,#ff8800)
+    #[arg(long, global = true)]
+    eye_gradient: Option<String>,
+
+    /// Paint dark modules with a two-color pattern: pattern:stripes|checkerboard|dots:COLOR,COLOR
+    #[arg(long, conflicts_with = "gradient", global = true)]
+    fill: Option<String>,
+
+    /// Paint dark modules with an image, darkened where it is too light to scan
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["gradient", "fill"], global = true)]
+    fg_image: Option<PathBuf>,
+
+    /// How --fg-image covers the code: scale (stretch over it once) or tile (repeat at its own size)
+    #[arg(long, default_value = "scale", value_parser = ["scale", "tile"], global = true)]
+    fg_image_mode: String,
+
+    /// Dot style (square, circle, rounded, diamond), or random:STYLE,STYLE,... for a seeded mix
+    #[arg(long, default_value = "square", global = true)]
+    dot_style: String,
+
+    /// Draw each dark module at this fraction of its cell (0.1 to 1.0), leaving gaps between dots
+    #[arg(long, default_value_t = 1.0, value_name = "FRACTION", global = true)]
+    dot_scale: f32,
+
+    /// Eye style (square, circle, frame)
+    #[arg(long, default_value = "square", global = true)]
+    eye_style: String,
+
+    /// Dot style of the timing lines between the eyes, drawn whole (default: --dot-style); square keeps them plain
+    #[arg(long, value_name = "STYLE", global = true)]
+    timing_style: Option<String>,
+
+    /// Dot style of the alignment patterns, drawn whole (default: --dot-style); square keeps them plain
+    #[arg(long, value_name = "STYLE", global = true)]
+    alignment_style: Option<String>,
+
+    /// Color of the timing lines (default: the foreground)
+    #[arg(long, global = true)]
+    timing_color: Option<String>,
+
+    /// Color of the alignment patterns (default: the foreground)
+    #[arg(long, global = true)]
+    alignment_color: Option<String>,
+
+    /// Style plugin (shared library) whose shapes become --dot-style and --eye-style names; repeatable
+    #[arg(long = "plugin", global = true)]
+    plugins: Vec<PathBuf>,
+
+    /// Rhai script whose style(x, y, is_dark, is_eye, neighbors) picks each module's shape and color
+    #[arg(long, global = true)]
+    style_script: Option<PathBuf>,
+
+    /// Styles registered by --plugin, loaded at startup
+    #[arg(skip)]
+    #[serde(skip)]
+    style_plugins: plugin::Registry,
+
+    /// Rotate the finished image clockwise (90, 180, 270); codes scan at any angle
+    #[arg(long, global = true)]
+    rotate: Option<u16>,
+
+    /// Mirror the finished image (h, v), after any rotation
+    #[arg(long, global = true)]
+    flip: Option<String>,
+
+    /// Place the code in a presentation scene (laptop-screen, bottle-label, table-card), or at
+    /// four corners given as fractions of the image: X,Y;X,Y;X,Y;X,Y clockwise from top-left
+    #[arg(long, value_name = "PRESET|CORNERS", global = true)]
+    mockup: Option<String>,
+
+    /// Drop shadow beneath the modules as DX,DY,BLUR in pixels
+    #[arg(long, value_name = "DX,DY,BLUR", num_args = 0..=1, default_missing_value = effects::DEFAULT_SHADOW, global = true)]
+    shadow: Option<String>,
+
+    /// Shadow color, alpha included (e.g. #00000066)
+    #[arg(long, default_value = "#00000066", global = true)]
+    shadow_color: String,
+
+    /// Ink texture multiplied over the dark modules (paper, grain, or an image file)
+    #[arg(long, global = true)]
+    texture: Option<String>,
+
+    /// Outline the modules in this color, a dark edge keeps light brand colors scannable
+    #[arg(long, global = true)]
+    stroke_color: Option<String>,
+
+    /// Outline width in pixels
+    #[arg(long, default_value_t = 2, requires = "stroke_color", global = true)]
+    stroke_width: u32,
+
+    /// Outline the merged dark region or every module on its own (region, module)
+    #[arg(long, default_value = "region", requires = "stroke_color", global = true)]
+    stroke_mode: String,
+
+    /// Logo file path
+    #[arg(short = 'l', long, global = true)]
+    logo: Option<PathBuf>,
+
+    /// Logo size ratio (0.0 to 1.0)
+    #[arg(long, default_value = "0.2", global = true)]
+    logo_size: f32,
+
+    /// Plate behind the logo: auto adds one when the logo blends into the modules beneath it (auto, on, off)
+    #[arg(long, default_value = "auto", value_parser = ["auto", "on", "off"], global = true)]
+    logo_plate: String,
+
+    /// Color of the logo plate (default: the background, or white when it is transparent)
+    #[arg(long, global = true)]
+    logo_plate_color: Option<String>,
+
+    /// Leave a blank region of WxH modules for artwork, centred or at @X,Y (e.g. 9x7 or 9x7@12,14)
+    #[arg(long, global = true)]
+    reserve: Option<String>,
+
+    /// Error correction level (L, M, Q, H) [default: M, or H with --logo or --reserve]
+    #[arg(short, long, global = true)]
+    error: Option<String>,
+
+    /// QR code size in pixels
+    #[arg(short = 's', long, default_value = "500", global = true)]
+    size: u32,
+
+    /// Border size (quiet zone)
+    #[arg(short = 'b', long, default_value = "0", global = true)]
+    border: u32,
+
+    /// Outline of the code: square, or circle for round badges (widens --border until the corners fit)
+    #[arg(long, default_value = "square", value_parser = ["square", "circle"], global = true)]
+    shape: String,
+
+    /// Quiet zone color (defaults to the background color)
+    #[arg(long, global = true)]
+    border_color: Option<String>,
+
+    /// Show QR in terminal
+    #[arg(long, global = true)]
+    show: bool,
+
+    /// Copy to clipboard
+    #[arg(long, global = true)]
+    copy: bool,
+
+    /// Base64 encode data before generating QR
+    #[arg(long, global = true)]
+    encode: bool,
+
+    /// Encrypt the data with a passphrase (AES-256-GCM, base45 output)
+    #[arg(long, conflicts_with = "encode", global = true)]
+    encrypt: bool,
+
+    /// Passphrase for --encrypt, read at startup
+    #[arg(skip)]
+    #[serde(skip)]
+    passphrase: Option<String>,
+
+    /// Compress the data (deflate, brotli) before base45, or base64 with --encode
+    #[arg(long, num_args = 0..=1, default_missing_value = "deflate", conflicts_with = "encrypt", global = true)]
+    compress: Option<String>,
+
+    /// Sign the data with an Ed25519 private key (PEM) as a compact JWS
+    #[arg(long, value_name = "KEY", conflicts_with = "encode", global = true)]
+    sign: Option<PathBuf>,
+
+    /// Key for --sign, loaded at startup
+    #[arg(skip)]
+    #[serde(skip)]
+    signing_key: Option<ed25519_dalek::SigningKey>,
+
+    /// Validate and normalize the data as a URL
+    #[arg(long, global = true)]
+    url: bool,
+
+    /// Accept this URL scheme besides http and https with --url (repeatable), e.g. mailto
+    #[arg(long, value_name = "SCHEME", requires = "url", global = true)]
+    allow_scheme: Vec<String>,
+
+    /// Append utm_source to the URL
+    #[arg(long, requires = "url", global = true)]
+    utm_source: Option<String>,
+
+    /// Append utm_medium to the URL
+    #[arg(long, requires = "url", global = true)]
+    utm_medium: Option<String>,
+
+    /// Append utm_campaign to the URL
+    #[arg(long, requires = "url", global = true)]
+    utm_campaign: Option<String>,
+
+    /// Append utm_term to the URL
+    #[arg(long, requires = "url", global = true)]
+    utm_term: Option<String>,
+
+    /// Append utm_content to the URL
+    #[arg(long, requires = "url", global = true)]
+    utm_content: Option<String>,
+
+    /// QR version (1-40)
+    #[arg(short = 'V', long, global = true)]
+    version: Option<i16>,
+
+    /// Raise the error correction level as far as the chosen version allows
+    #[arg(long, global = true)]
+    boost_ecc: bool,
+
+    /// Smallest QR version to use (1-40)
+    #[arg(long, conflicts_with = "version", value_parser = clap::value_parser!(i16).range(1..=40), global = true)]
+    min_version: Option<i16>,
+
+    /// Largest QR version to use (1-40), fails if the data needs a bigger code
+    #[arg(long, conflicts_with = "version", value_parser = clap::value_parser!(i16).range(1..=40), global = true)]
+    max_version: Option<i16>,
+
+    /// Quality for lossy formats (1-100, used by jpg, avif and lossy webp)
+    #[arg(long, default_value = "90", global = true)]
+    quality: u8,
+
+    /// Matte color that replaces transparency in formats without alpha (jpg, bmp, CMYK tiff)
+    #[arg(long, default_value = "#ffffff", global = true)]
+    matte: String,
+
+    /// Dithering for GIF palettes (none, ordered, floyd-steinberg); module edges are never dithered
+    #[arg(long, default_value = "none", global = true)]
+    dither: String,
+
+    /// Encode WebP losslessly (use --webp-lossless false for lossy output)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
+    webp_lossless: bool,
+
+    /// PNG compression level (0-9)
+    #[arg(long, default_value = "9", global = true)]
+    png_compression: u8,
+
+    /// Write an interlaced (Adam7) PNG
+    #[arg(long, global = true)]
+    png_interlace: bool,
+
+    /// Write an indexed-color PNG with the smallest bit depth that fits
+    #[arg(long, global = true)]
+    png_palette: bool,
+
+    /// Label width in millimeters (epl, tspl)
+    #[arg(long, default_value = "50", global = true)]
+    label_width: f32,
+
+    /// Label height in millimeters (epl, tspl)
+    #[arg(long, default_value = "50", global = true)]
+    label_height: f32,
+
+    /// Printer resolution in dots per inch (default: 203 for epl/tspl, 300 for sheets; also tags CMYK TIFFs)
+    #[arg(long, global = true)]
+    dpi: Option<u32>,
+
+    /// Print darkness (0-15, epl, tspl)
+    #[arg(long, default_value = "8", global = true)]
+    darkness: u8,
+
+    /// Side length in millimeters, quiet zone included (stl, 3mf, dxf)
+    #[arg(long, default_value = "50", global = true)]
+    model_size: f32,
+
+    /// Thickness of the base plate in millimeters (stl, 3mf)
+    #[arg(long, default_value = "2", global = true)]
+    model_base: f32,
+
+    /// How far dark modules rise above the base in millimeters (stl, 3mf)
+    #[arg(long, default_value = "1", global = true)]
+    model_height: f32,
+
+    /// How far the finder patterns rise above the base (stl, 3mf; default: --model-height)
+    #[arg(long, global = true)]
+    model_eye_height: Option<f32>,
+
+    /// Send the output straight to a printer (default printer if no name is given)
+    #[arg(long, num_args = 0..=1, default_missing_value = "", global = true)]
+    print: Option<String>,
+
+    /// Number of copies to print
+    #[arg(long, default_value = "1", global = true)]
+    copies: u32,
+
+    /// Printer media size (e.g. A4, Letter, Custom.50x50mm)
+    #[arg(long, global = true)]
+    media: Option<String>,
+
+    /// CSV or spreadsheet (xlsx, xls, ods) with a 'data' column (and optional 'output' column), one code per row
+    #[arg(long, global = true)]
+    batch: Option<PathBuf>,
+
+    /// Database to read batch rows from, as sqlite://PATH; the rows come from --batch-query
+    #[arg(long, value_name = "URL", requires = "batch_query", conflicts_with_all = ["batch", "sequence", "generate"], global = true)]
+    batch_sql: Option<String>,
+
+    /// SQL query for --batch-sql, one code per result row, e.g. "SELECT sku AS output, url AS data FROM items"
+    #[arg(long, value_name = "SQL", requires = "batch_sql", global = true)]
+    batch_query: Option<String>,
+
+    /// After a batch, list every row with its file, QR version and any error in this CSV or JSON
+    /// file; failed rows no longer stop the batch, but the run still fails at the end
+    #[arg(long, value_name = "FILE", global = true)]
+    manifest: Option<PathBuf>,
+
+    /// Worksheet of a spreadsheet --batch to read (default: the first)
+    #[arg(long, value_name = "NAME", requires = "batch", global = true)]
+    worksheet: Option<String>,
+
+    /// Column of --batch or --batch-query holding the payload
+    #[arg(long, value_name = "NAME", default_value = "data", global = true)]
+    data_column: String,
+
+    /// Column of --batch or --batch-query naming each output file
+    #[arg(long, value_name = "NAME", default_value = "output", global = true)]
+    output_column: String,
+
+    /// Column of --batch or --batch-query captioning each code on sheets
+    #[arg(long, value_name = "NAME", default_value = "caption", global = true)]
+    caption_column: String,
+
+    /// Generate a numbered series instead of reading a CSV: START..END, both ends included (e.g. 1..5000)
+    #[arg(long, value_name = "START..END", requires = "template", conflicts_with_all = ["batch", "data", "data_file"], global = true)]
+    sequence: Option<String>,
+
+    /// Payload for each number of --sequence, with {n} or zero-padded {n:06} standing for the number
+    #[arg(long, requires = "sequence", global = true)]
+    template: Option<String>,
+
+    /// Count --sequence up in steps of this size
+    #[arg(long, default_value_t = 1, requires = "sequence", global = true)]
+    step: u64,
+
+    /// Encode a freshly generated identifier instead of --data: uuid (v4) or ulid, one per code
+    #[arg(long, value_name = "KIND", value_parser = ["uuid", "ulid"], conflicts_with_all = ["data", "data_file", "batch", "sequence"], global = true)]
+    generate: Option<String>,
+
+    /// How many codes --generate makes, each with its own identifier
+    #[arg(long, default_value_t = 1, requires = "generate", global = true)]
+    count: usize,
+
+    /// CSV file that maps each --generate identifier to the file encoding it
+    #[arg(long, value_name = "CSV", default_value = "ids.csv", requires = "generate", global = true)]
+    id_manifest: PathBuf,
+
+    /// POST every generated file to this URL along with its details
+    #[arg(long, value_name = "URL", global = true)]
+    webhook: Option<String>,
+
+    /// Webhook body: multipart (file and metadata fields) or json (base64 image and details)
+    #[arg(long, default_value = "multipart", value_parser = ["multipart", "json"], global = true)]
+    webhook_format: String,
+
+    /// Email every generated file to this address (repeatable); the server is set with RUSTQR_SMTP_* variables
+    #[arg(long, value_name = "ADDRESS", global = true)]
+    mail_to: Vec<String>,
+
+    /// Subject line for --mail-to
+    #[arg(long, default_value = "Your QR code", global = true)]
+    mail_subject: String,
+
+    /// Send every generated file to this Telegram chat ID or @channel; the bot token is read from RUSTQR_TELEGRAM_TOKEN
+    #[arg(long, value_name = "CHAT_ID", allow_hyphen_values = true, global = true)]
+    telegram: Option<String>,
+
+    /// Write the batch into one ZIP archive instead of a file per row
+    #[arg(long, value_name = "ZIP", global = true)]
+    archive: Option<PathBuf>,
+
+    /// Store a manifest of the batch inside the --archive under this name (e.g. manifest.csv or
+    /// manifest.json); like --manifest, failed rows no longer stop the batch
+    #[arg(long, value_name = "NAME", requires = "archive", global = true)]
+    archive_manifest: Option<PathBuf>,
+
+    /// Tile codes onto printable pages and save them to this PNG or PDF file
+    #[arg(long, global = true)]
+    sheet: Option<PathBuf>,
+
+    /// Sheet page size (a4, a3, a5, letter, legal or WIDTHxHEIGHT in mm)
+    #[arg(long, default_value = "a4", global = true)]
+    page_size: String,
+
+    /// Codes per row on a sheet
+    #[arg(long, default_value = "3", global = true)]
+    columns: u32,
+
+    /// Rows of codes on a sheet
+    #[arg(long, default_value = "4", global = true)]
+    rows: u32,
+
+    /// Page margin in millimeters
+    #[arg(long, default_value = "10", global = true)]
+    margin: f32,
+
+    /// Space between codes in millimeters
+    #[arg(long, default_value = "5", global = true)]
+    gutter: f32,
+
+    /// Label template for the sheet (e.g. avery-5160, avery-l7160, herma-4360)
+    #[arg(long, requires = "sheet", global = true)]
+    labels: Option<String>,
+
+    /// How many copies of --data to place on a sheet (default: fill one page)
+    #[arg(long, global = true)]
+    sheet_count: Option<usize>,
+
+    /// Compose a business card around the code and save it as PNG or PDF
+    #[arg(long, global = true)]
+    card: Option<PathBuf>,
+
+    /// Card size (eu, us, jp or WIDTHxHEIGHT in mm)
+    #[arg(long, default_value = "eu", global = true)]
+    card_size: String,
+
+    /// Card layout: 'back' puts the code on its own back side, 'side' next to the text
+    #[arg(long, default_value = "back", global = true)]
+    card_layout: String,
+
+    /// Name printed on the card
+    #[arg(long, global = true)]
+    card_name: Option<String>,
+
+    /// Job title printed under the name
+    #[arg(long, global = true)]
+    card_title: Option<String>,
+
+    /// Contact line (repeatable, e.g. phone, email, website)
+    #[arg(long, global = true)]
+    card_line: Vec<String>,
+
+    /// Call-to-action frame around the code (border, banner, bubble)
+    #[arg(long, global = true)]
+    frame: Option<String>,
+
+    /// Text shown in the frame
+    #[arg(long, default_value = "SCAN ME", global = true)]
+    frame_text: String,
+
+    /// Frame color (defaults to the foreground color)
+    #[arg(long, global = true)]
+    frame_color: Option<String>,
+
+    /// Frame text color for banner and bubble frames
+    #[arg(long, default_value = "#ffffff", global = true)]
+    frame_text_color: String,
+
+    /// Frame text position (top, bottom)
+    #[arg(long, default_value = "bottom", global = true)]
+    frame_position: String,
+
+    /// Caption text rendered beneath the code
+    #[arg(long, global = true)]
+    caption: Option<String>,
+
+    /// Caption font size in pixels (defaults to 8% of --size)
+    #[arg(long, global = true)]
+    caption_size: Option<f32>,
+
+    /// Caption color (defaults to the foreground color)
+    #[arg(long, global = true)]
+    caption_color: Option<String>,
+
+    /// Caption alignment (left, center, right)
+    #[arg(long, default_value = "center", global = true)]
+    caption_align: String,
+
+    /// TTF/OTF font for captions, frames, labels and cards (defaults to the bundled DejaVu Sans)
+    #[arg(long, global = true)]
+    font: Option<PathBuf>,
+
+    /// Font size in pixels for captions and frame text
+    #[arg(long, global = true)]
+    font_size: Option<f32>,
+
+    /// Round the corners of the whole output by this many pixels, clear outside
+    #[arg(long, default_value_t = 0, global = true)]
+    canvas_radius: u32,
+
+    /// Canvas space in pixels around everything else, outside the quiet zone
+    #[arg(long, default_value_t = 0, global = true)]
+    canvas_padding: u32,
+
+    /// Canvas color for the padding (defaults to the background color)
+    #[arg(long, global = true)]
+    canvas_color: Option<String>,
+
+    /// Decorative line around the output, outside the quiet zone (solid, dashed, double)
+    #[arg(long, global = true)]
+    outer_border: Option<String>,
+
+    /// Outer border color (defaults to the foreground color)
+    #[arg(long, global = true)]
+    outer_border_color: Option<String>,
+
+    /// Outer border width in pixels
+    #[arg(long, default_value_t = 8, global = true)]
+    outer_border_width: u32,
+
+    /// Interactive mode
+    #[arg(short = 'i', long, global = true)]
+    interactive: bool,
+
+    /// Start from the options saved as preset NAME, e.g. at the end of --interactive
+    #[arg(long, value_name = "NAME", global = true)]
+    preset: Option<String>,
+
+    /// Print debug details, twice (-vv) for traces
+    #[arg(short = 'v', long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only print errors
+    #[arg(long, conflicts_with = "verbose", global = true)]
+    quiet: bool,
+
+    /// How status lines, warnings and errors are printed: text, or json lines on stderr
+    #[arg(long, default_value = "text", value_parser = ["text", "json"], global = true)]
+    log_format: String,
+
+    /// Language of prompts and status messages (en, es, fa) [default: from LC_ALL, LC_MESSAGES or LANG]
+    #[arg(long, global = true)]
+    lang: Option<String>,
+
+    /// Show the rendered image in a window and ask before saving
+    #[cfg(feature = "preview")]
+    #[arg(long, global = true)]
+    preview: bool,
+
+    /// Full-screen editor with a live preview
+    #[arg(long, conflicts_with_all = ["interactive", "binary", "batch", "batch_sql", "sequence", "sheet", "card"], global = true)]
+    tui: bool,
+
+    /// What the local history keeps of each code: full, hash (the payload's SHA-256 only) or off;
+    /// set RUSTQR_HISTORY_RECORD to record every run without the flag
+    #[arg(long, env = "RUSTQR_HISTORY_RECORD", default_value = "off", value_parser = ["full", "hash", "off"], global = true)]
+    record: String,
+
+    /// The command line for the history; None when RustQR is embedded
+    #[arg(skip)]
+    #[serde(skip)]
+    invocation: Option<Vec<String>>,
+
+    /// Whether `{env:VAR}` and `{date}` in --data are filled in; only the command line asks for it,
+    /// so embedders never leak their environment into a code
+    #[arg(skip)]
+    #[serde(skip)]
+    expand_data: bool,
+}
+
+/// The command-line entry point
+pub fn run() -> Result<()> {
+    let mut args = Args::parse();
+    let mut invocation: Vec<String> = std::env::args_os().skip(1).map(|arg| arg.to_string_lossy().into_owned()).collect();
+    // The preset's options are spelled out, so the history records what was actually used
+    if let Some(ref name) = args.preset {
+        invocation = preset::apply(name, &invocation)?;
+        args = Args::try_parse_from(std::iter::once("RustQR".to_string()).chain(invocation.iter().cloned()))
+            .with_context(|| format!("Failed to apply preset {}", name))?;
+    }
+    args.invocation = Some(invocation);
+    args.expand_data = true;
+    // Decoded payloads and -o - files own stdout, so status lines move to stderr
+    let stdout_taken = args.output.as_deref() == Some(Path::new("-")) || matches!(args.command, Some(Command::Decode(_) | Command::Scan(_)));
+    logging::init(args.verbose, args.quiet, &args.log_format, stdout_taken);
+    i18n::init(args.lang.as_deref())?;
+
+    if let Some(Command::Decode(ref decode_args)) = args.command {
+        return run_decode(decode_args);
+    }
+
+    if let Some(Command::Diff(ref diff_args)) = args.command {
+        return run_diff(&args, diff_args);
+    }
+
+    if let Some(Command::Scan(ref scan_args)) = args.command {
+        return run_scan(scan_args);
+    }
+
+    if let Some(Command::Themes) = args.command {
+        run_themes();
+        return Ok(());
+    }
+
+    // Regenerating swaps in the recorded arguments and carries on like any other run
+    if let Some(Command::History(ref history_args)) = args.command {
+        let HistoryAction::Regen { id } = history_args.action else {
+            return run_history(history_args);
+        };
+        args = replay_args(id, args.invocation.as_deref().unwrap_or_default())?;
+    }
+
+    if args.validate {
+        return run_validate(&args);
+    }
+
+    // The prompts fill in and prepare the arguments, cancelling leaves before anything is generated
+    #[cfg(feature = "cli")]
+    if args.interactive && !interactive::run(&mut args)? {
+        log::info!("{}", tr!("cancelled"));
+        return Ok(());
+    }
+    #[cfg(not(feature = "cli"))]
+    if args.interactive {
+        anyhow::bail!("This build has no terminal prompts, --interactive needs the cli feature");
+    }
+
+    if !args.interactive {
+        validate_format(&args.format)?;
+        prepare_args(&mut args)?;
+    }
+
+    // The editor tweaks the arguments, saving falls through to the normal single-code path
+    #[cfg(feature = "cli")]
+    if args.tui && !tui::run(&mut args)? {
+        return Ok(());
+    }
+    #[cfg(not(feature = "cli"))]
+    if args.tui {
+        anyhow::bail!("This build has no terminal editor, --tui needs the cli feature");
+    }
+
+    if let Some(Command::Analyze) = args.command {
+        return run_analyze(&args);
+    }
+
+    if let Some(Command::Capacity(ref capacity_args)) = args.command {
+        return run_capacity(&args, capacity_args);
+    }
+
+    if let Some(Command::Styles(ref styles_args)) = args.command {
+        return run_styles(&args, &styles_args.sheet);
+    }
+
+    if let Some(Command::Bench(ref bench_args)) = args.command {
+        return run_bench(&args, bench_args);
+    }
+
+    // Card mode composes a business card around the code
+    if let Some(ref card_path) = args.card {
+        return run_card(&args, card_path);
+    }
+
+    // Sheet mode tiles every code onto printable pages
+    if let Some(ref sheet_path) = args.sheet {
+        return run_sheet(&args, sheet_path);
+    }
+
+    if let Some(ref dir) = args.name_by_hash
+        && !args.dry_run
+    {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    // Batch mode generates one file per CSV row, sequence number or generated identifier
+    if let Some(rows) = batch_rows(&args)? {
+        log::debug!("Batch of {} rows", rows.len());
+        // Names taken from a column such as a SKU get the format as their extension
+        let output_path = |row: &batch::BatchRow| match row.output {
+            Some(ref path) if path.extension().is_none() => path.with_extension(&args.format),
+            Some(ref path) => path.clone(),
+            None => PathBuf::from(format!("qrcode-{}.{}", row.index, args.format)),
+        };
+
+        // Archives are streamed entry by entry, so only the file being compressed is held in memory.
+        // Entry names are checked up front so a bad row does not leave half an archive behind.
+        let mut archive = None;
+        // Removes the archive again if the batch stops before it is finished; declared first so
+        // the archive's file is closed by then
+        let mut unfinished = None;
+        if let Some(ref path) = args.archive {
+            let mut entries = HashSet::new();
+            if let Some(ref name) = args.archive_manifest {
+                entries.insert(archive_entry_name(name)?);
+            }
+            for row in &rows {
+                if !entries.insert(archive_entry_name(&output_path(row))?) {
+                    anyhow::bail!("Batch row {} repeats the archive entry {}", row.index, output_path(row).display());
+                }
+            }
+            let (path, out): (PathBuf, Box<dyn Write>) = if args.dry_run {
+                (path.clone(), Box::new(std::io::sink()))
+            } else {
+                let path = claim_output_path(path, &args);
+                let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+                unfinished = Some(PartialFile(Some(path.clone())));
+                (path, Box::new(BufWriter::new(file)))
+            };
+            archive = Some((ZipWriter::new(out), path));
+        }
+
+        let mut up_to_date = 0;
+        let mut generated = Vec::new();
+        // With a manifest a failed row is recorded and the batch carries on
+        let mut manifest = (args.manifest.is_some() || args.archive_manifest.is_some()).then(Vec::new);
+        let mut failed = 0;
+        for row in &rows {
+            log::trace!("Batch row {}: {}", row.index, row.data);
+            let save = args.print.is_none() || row.output.is_some();
+            let mut row_args = args.clone();
+            if let Some(ref symbology) = row.symbology {
+                row_args.symbology = symbology.clone();
+            }
+            let output_path = match args.name_by_hash {
+                Some(ref dir) if row.output.is_none() => {
+                    let path = hashed_output_path(&row_args, &row.data, dir)?;
+                    if path.exists() {
+                        log::info!("{}", tr!("up-to-date", path = path.display().to_string()));
+                        up_to_date += 1;
+                        if let Some(ref mut manifest) = manifest {
+                            manifest.push(manifest::Entry {
+                                row: row.index,
+                                data: row.data.clone(),
+                                file: Some(path),
+                                symbol: None,
+                                version: None,
+                                status: manifest::Status::UpToDate,
+                            });
+                        }
+                        continue;
+                    }
+                    path
+                }
+                _ => output_path(row),
+            };
+            let output = match archive {
+                Some((ref mut zip, _)) => Output::Archive(zip, &output_path),
+                None if save => Output::File(&output_path),
+                None => Output::Discard,
+            };
+            let archived = matches!(output, Output::Archive(..));
+            let code = match (generate_one(&row_args, &row.data, output), manifest.as_mut()) {
+                (Ok(code), _) => code,
+                (Err(e), Some(manifest)) => {
+                    log::error!("{}", tr!("batch-row-failed", row = row.index, error = format!("{:#}", e)));
+                    failed += 1;
+                    manifest.push(manifest::Entry {
+                        row: row.index,
+                        data: row.data.clone(),
+                        file: None,
+                        symbol: None,
+                        version: None,
+                        status: manifest::Status::Failed(format!("{:#}", e)),
+                    });
+                    continue;
+                }
+                (Err(e), None) => return Err(e.context(format!("Batch row {} failed", row.index))),
+            };
+            let file = code.path.clone().or_else(|| archived.then(|| output_path.clone()));
+            if let Some(ref mut manifest) = manifest {
+                manifest.push(manifest::Entry {
+                    row: row.index,
+                    data: row.data.clone(),
+                    file: file.clone(),
+                    symbol: Some(code.symbol),
+                    version: code.version,
+                    status: manifest::Status::Generated,
+                });
+            }
+            if args.generate.is_some() {
+                generated.push((row.data.as_str(), file.unwrap_or(output_path)));
+            }
+        }
+        if args.generate.is_some() && !args.dry_run {
+            write_id_manifest(&generated, &args)?;
+        }
+        let made = rows.len() - up_to_date - failed;
+        if args.dry_run {
+            log::info!("{}", tr!("batch-checked", count = made));
+        } else if let Some((mut zip, path)) = archive {
+            if let (Some(name), Some(entries)) = (&args.archive_manifest, &manifest) {
+                zip.add(&archive_entry_name(name)?, &manifest::encode(name, entries)?)?;
+            }
+            zip.finish()?.flush().with_context(|| format!("Failed to write {}", path.display()))?;
+            if let Some(file) = unfinished.take() {
+                file.keep();
+            }
+            log::info!("{}", tr!("batch-archived", count = made, path = path.display().to_string()));
+        } else if up_to_date > 0 {
+            log::info!("{}", tr!("batch-up-to-date", count = made, current = up_to_date));
+        } else {
+            log::info!("{}", tr!("batch-generated", count = made));
+        }
+        if let (Some(path), Some(entries)) = (&args.manifest, manifest)
+            && !args.dry_run
+        {
+            let path = claim_output_path(path, &args);
+            manifest::write(&path, &entries)?;
+            log::info!("{}", tr!("manifest-saved", path = path.display().to_string()));
+        }
+        if failed > 0 {
+            anyhow::bail!("{} of {} batch rows failed, see the manifest for details", failed, rows.len());
+        }
+        return Ok(());
+    }
+
+    if args.archive.is_some() {
+        anyhow::bail!("--archive collects the files of a batch, add --batch or --sequence");
+    }
+
+    // Validate required data, binary payloads travel in args.binary_data instead
+    let data = match args.binary_data {
+        Some(_) => String::new(),
+        None => args.data.as_ref().context("Data is required. Use --data or --interactive")?.clone(),
+    };
+
+    // Determine output path with correct extension
+    let output_path = if let Some(ref dir) = args.name_by_hash {
+        // The same payload and settings always give the same file, so an existing one is kept
+        let path = hashed_output_path(&args, &data, dir)?;
+        if path.exists() {
+            log::info!("{}", tr!("up-to-date", path = path.display().to_string()));
+            if args.copy {
+                copy_to_clipboard(&path);
+            }
+            return Ok(());
+        }
+        path
+    } else if let Some(ref path) = args.output {
+        path.clone()
+    } else {
+        PathBuf::from(format!("qrcode.{}", args.format))
+    };
+
+    // When printing without --output nothing is written to disk
+    let save = args.print.is_none() || args.output.is_some() || args.name_by_hash.is_some();
+    let output = if save { Output::File(&output_path) } else { Output::Discard };
+    let code = generate_one(&args, &data, output)?;
+
+    // Copy to clipboard if requested
+    if let Some(saved_path) = code.path.filter(|_| args.copy) {
+        copy_to_clipboard(&saved_path);
+    }
+
+    Ok(())
+}
+
+/// Renders one code from command-line arguments (without the program name) to the bytes of
+/// `format`, for callers that embed RustQR instead of running it
+pub fn render(arguments: &[String], format: &str) -> Result<Vec<u8>> {
+    RenderOptions::from_args(arguments.iter().cloned())?.render_to_bytes(format)
+}
+
+/// Everything that shapes one code, for rendering in memory without temporary files. The common
+/// options have setters; any other command-line option can be added with [`RenderOptions::args`].
+///
+/// ```no_run
+/// let png = rustqr::RenderOptions::new("https://example.com")
+///     .size(800)
+///     .dot_style("rounded")
+///     .args(["--eye-style", "circle"])?
+///     .render_to_bytes("png")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// The command line without the program name; parsed again for each render
+    arguments: Vec<String>,
+    /// Styles registered in code, alongside any loaded with --plugin
+    styles: Registry,
+}
+
+impl RenderOptions {
+    /// Defaults as on the command line, encoding `data`
+    pub fn new(data: impl Into<String>) -> Self {
+        RenderOptions { arguments: vec![format!("--data={}", data.into())], styles: Registry::default() }
+    }
+
+    /// Options as on the command line, without the program name
+    pub fn from_args<I, S>(arguments: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let options = RenderOptions { arguments: arguments.into_iter().map(Into::into).collect(), styles: Registry::default() };
+        options.parse()?;
+        Ok(options)
+    }
+
+    /// Adds command-line options, e.g. `["--gradient", "#ff0000,#0000ff"]`; an option that was
+    /// already set is replaced
+    pub fn args<I, S>(mut self, arguments: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let arguments: Vec<String> = arguments.into_iter().map(Into::into).collect();
+        self.merge(&arguments);
+        self.parse()?;
+        Ok(self)
+    }
+
+    /// Image size in pixels (`--size`)
+    pub fn size(mut self, size: u32) -> Self {
+        self.merge(&[format!("--size={}", size)]);
+        self
+    }
+
+    /// Quiet zone in modules (`--border`)
+    pub fn border(mut self, modules: u32) -> Self {
+        self.merge(&[format!("--border={}", modules)]);
+        self
+    }
+
+    /// Error correction level: L, M, Q or H (`--error`)
+    pub fn error_level(mut self, level: impl Into<String>) -> Self {
+        self.merge(&[format!("--error={}", level.into())]);
+        self
+    }
+
+    /// Module color (`--fg-color`)
+    pub fn fg_color(mut self, color: impl Into<String>) -> Self {
+        self.merge(&[format!("--fg-color={}", color.into())]);
+        self
+    }
+
+    /// Background color, or transparent (`--bg-color`)
+    pub fn bg_color(mut self, color: impl Into<String>) -> Self {
+        self.merge(&[format!("--bg-color={}", color.into())]);
+        self
+    }
+
+    /// Dot style, e.g. square, circle or rounded (`--dot-style`)
+    pub fn dot_style(mut self, style: impl Into<String>) -> Self {
+        self.merge(&[format!("--dot-style={}", style.into())]);
+        self
+    }
+
+    /// Finder pattern style (`--eye-style`)
+    pub fn eye_style(mut self, style: impl Into<String>) -> Self {
+        self.merge(&[format!("--eye-style={}", style.into())]);
+        self
+    }
+
+    /// Module styles implemented in code, usable by name in `dot_style`, `eye_style` and the
+    /// other style options
+    pub fn styles(mut self, styles: Registry) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    /// The encoded file in any format the CLI writes, e.g. png, svg or webp
+    pub fn render_to_bytes(&self, format: &str) -> Result<Vec<u8>> {
+        validate_format(format)?;
+        let (args, data, symbol) = self.prepare()?;
+        let img = generate_qr_image(&symbol, &args)?;
+        let metadata = metadata::collect(&args, Some(&data))?;
+        render_to_bytes(&symbol, &img, &args, &metadata, format)
+    }
+
+    /// The code as an SVG document
+    pub fn render_svg_string(&self) -> Result<String> {
+        let (args, data, symbol) = self.prepare()?;
+        let metadata = metadata::collect(&args, Some(&data))?;
+        render_svg_string(&symbol, &args, &metadata)
+    }
+
+    fn prepare(&self) -> Result<(Args, String, Symbol)> {
+        let mut args = self.parse()?;
+        prepare_args(&mut args)?;
+        let data = match args.binary_data {
+            Some(_) => String::new(),
+            None => args.data.clone().context("Data is required. Use --data")?,
+        };
+        let symbol = build_symbol(&data, &args)?;
+        Ok((args, data, symbol))
+    }
+
+    fn parse(&self) -> Result<Args> {
+        let mut args = Args::try_parse_from(std::iter::once("RustQR").chain(self.arguments.iter().map(String::as_str)))?;
+        args.style_plugins = self.styles.clone();
+        Ok(args)
+    }
+
+    /// Appends `arguments`, dropping earlier occurrences of the options they set
+    fn merge(&mut self, arguments: &[String]) {
+        let added = preset::option_groups(arguments);
+        let given: HashSet<&str> = added.iter().filter_map(|(id, _)| id.as_deref()).collect();
+        self.arguments = preset::option_groups(&self.arguments)
+            .into_iter()
+            .filter(|(id, _)| id.as_deref().is_none_or(|id| !given.contains(id)))
+            .chain(added.iter().cloned())
+            .flat_map(|(_, tokens)| tokens)
+            .collect();
+    }
+}
+
+#[cfg(feature = "cli")]
+fn copy_to_clipboard(path: &Path) {
+    match cli_clipboard::set_contents(path.to_string_lossy().to_string()) {
+        Ok(_) => log::info!("{}", tr!("path-copied")),
+        Err(e) => log::warn!("⚠ Failed to copy to clipboard: {}", e),
+    }
+}
+
+#[cfg(not(feature = "cli"))]
+fn copy_to_clipboard(_path: &Path) {
+    log::warn!("⚠ This build has no clipboard support, --copy needs the cli feature");
+}
+
+/// Turns the parsed flags into what rendering needs: payload data, GS1 elements, error level
+/// defaults, color profiles and keys
+fn prepare_args(args: &mut Args) -> Result<()> {
+    // Batch rows fill in --data themselves, one row at a time
+    if args.expand_data
+        && args.batch.is_none()
+        && args.batch_sql.is_none()
+        && let Some(ref data) = args.data
+    {
+        args.data = Some(template::expand_data(data, None)?);
+    }
+
+    // A theme fills in whatever is still at its default
+    if let Some(ref name) = args.theme {
+        let theme = themes::find(name).with_context(|| {
+            let names: Vec<&str> = themes::THEMES.iter().map(|theme| theme.name).collect();
+            format!("Unknown theme '{}'. Use {}", name, names.join(", "))
+        })?;
+        if args.fg_color == "#000000" {
+            args.fg_color = theme.fg_color.to_string();
+            if args.gradient.is_none() {
+                args.gradient = theme.gradient.map(str::to_string);
+            }
+        }
+        if args.bg_color == "transparent" {
+            args.bg_color = theme.bg_color.to_string();
+        }
+        if args.dot_style == "square" {
+            args.dot_style = theme.dot_style.to_string();
+        }
+        if args.eye_style == "square" {
+            args.eye_style = theme.eye_style.to_string();
+        }
+    }
+
+    // Palette names stand in for colors in every color option
+    if let Some(ref path) = args.palette {
+        let palette = palette::Palette::load(path)?;
+        for color in [&mut args.fg_color, &mut args.bg_color, &mut args.shadow_color, &mut args.matte, &mut args.frame_text_color] {
+            *color = palette.resolve(color);
+        }
+        for color in [&mut args.stroke_color, &mut args.frame_color, &mut args.caption_color, &mut args.canvas_color, &mut args.border_color, &mut args.outer_border_color]
+            .into_iter()
+            .flatten()
+        {
+            *color = palette.resolve(color);
+        }
+        if let Some(ref mut gradient) = args.gradient {
+            *gradient = palette.resolve_list(gradient);
+        }
+        if let Some(ref mut gradient) = args.eye_gradient {
+            *gradient = palette.resolve_list(gradient);
+        }
+        if let Some(ref mut gradient) = args.bg_gradient {
+            *gradient = palette.resolve_list(gradient);
+        }
+        if let Some(ref mut fill) = args.fill {
+            *fill = match fill.rsplit_once(':') {
+                Some((pattern, colors)) => format!("{}:{}", pattern, palette.resolve_list(colors)),
+                None => palette.resolve(fill),
+            };
+        }
+    }
+
+    // Payload builders produce the data to encode
+    if let Some(Command::Payload(ref payload)) = args.command {
+        if args.data.is_some() {
+            anyhow::bail!("--data cannot be combined with a payload subcommand");
+        }
+        args.data = Some(payload.payload()?);
+
+        if let Some(level) = payload.error_level() {
+            if args.error.as_deref().is_some_and(|e| !e.eq_ignore_ascii_case(level)) {
+                log::warn!("⚠ This payload requires error correction level {}, ignoring --error", level);
+            }
+            if args.boost_ecc {
+                log::warn!("⚠ This payload requires error correction level {}, ignoring --boost-ecc", level);
+                args.boost_ecc = false;
+            }
+            args.error = Some(level.to_string());
+        }
+    }
+
+    if let Some(ref path) = args.data_file {
+        if matches!(args.command, Some(Command::Payload(_))) {
+            anyhow::bail!("--data-file cannot be combined with a payload subcommand");
+        }
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read data file: {}", path.display()))?;
+        if args.binary {
+            args.binary_data = Some(bytes);
+        } else {
+            let text = String::from_utf8(bytes)
+                .map_err(|_| anyhow::anyhow!("{} is not UTF-8 text, add --binary to encode it as raw bytes", path.display()))?;
+            args.data = Some(text);
+        }
+    }
+
+    // Checked here so a bad --rotate or --flip fails before any warning about it
+    let orientation = Orientation::from_args(args)?;
+    if orientation.flip_horizontal || orientation.flip_vertical {
+        log::warn!("⚠ Mirrored codes are not read by every scanner, test one before printing");
+    }
+
+    let symbology = Symbology::from_str(&args.symbology)
+        .ok_or_else(|| anyhow::anyhow!("Unknown symbology '{}'. Use qr, datamatrix, aztec, code128 or ean13", args.symbology))?;
+    let qr_only = args.error.is_some() || args.version.is_some() || args.min_version.is_some() || args.max_version.is_some() || args.boost_ecc;
+    if symbology != Symbology::Qr && qr_only {
+        log::warn!("⚠ --error, --version and --boost-ecc only apply to QR codes, other symbologies pick their own size and error correction");
+    }
+    if symbology.is_linear() && (args.dot_style != "square" || args.dot_scale < 1.0) {
+        log::warn!("⚠ Dot styles break bars into separate modules, scanners expect --dot-style square for barcodes");
+    }
+    if !(0.1..=1.0).contains(&args.dot_scale) {
+        anyhow::bail!("--dot-scale must be between 0.1 and 1.0");
+    }
+    if args.dot_scale < 0.6 {
+        log::warn!("⚠ Dots under 60% of a module leave little ink for scanners, test the code before printing");
+    }
+    // Padding wider than the code itself only grows the image, until it no longer fits in memory
+    if args.canvas_padding > args.size {
+        anyhow::bail!("--canvas-padding {} is wider than the code itself, use at most --size ({})", args.canvas_padding, args.size);
+    }
+    let pattern_options = [&args.timing_style, &args.alignment_style, &args.timing_color, &args.alignment_color];
+    if symbology != Symbology::Qr && pattern_options.iter().any(|option| option.is_some()) {
+        log::warn!("⚠ --timing-style, --timing-color, --alignment-style and --alignment-color only apply to QR codes");
+    }
+    if !symbology.is_linear() && (args.bar_height.is_some() || args.quiet_zone.is_some()) {
+        log::warn!("⚠ --bar-height and --quiet-zone only apply to linear barcodes, use --border for 2D codes");
+    }
+    if symbology.is_linear() && args.shape == "circle" {
+        anyhow::bail!("--shape circle needs a square symbol, {} barcodes cannot be round", args.symbology);
+    }
+
+    // GS1 flags add elements after any --gs1 data; parsing checks the result as a whole
+    let gs1_flags = gs1::from_flags(args.gtin.as_deref(), args.expiry.as_deref(), args.lot.as_deref(), args.serial.as_deref())?;
+    if !gs1_flags.is_empty() {
+        if args.data.is_some() && !args.gs1 {
+            anyhow::bail!("--gtin, --expiry, --lot and --serial build the data themselves, add --gs1 to combine them with --data");
+        }
+        let data = format!("{}{}", args.data.as_deref().unwrap_or(""), gs1::element_string(&gs1_flags));
+        gs1::parse(&data)?;
+        args.data = Some(data);
+        args.gs1 = true;
+    }
+    if args.gs1 {
+        if args.binary_data.is_some() {
+            anyhow::bail!("--gs1 encodes a text element string, it cannot be combined with --binary");
+        }
+        if args.url || args.sign.is_some() || args.encrypt || args.compress.is_some() || args.encode {
+            anyhow::bail!("GS1 data is encoded as is, it cannot be combined with --url, --sign, --encrypt, --compress or --encode");
+        }
+    }
+
+    if symbology != Symbology::Qr && args.reserve.is_some() {
+        anyhow::bail!("--reserve relies on QR error correction, use --symbology qr");
+    }
+
+    // A logo or a reserved region hides modules, so use the strongest level unless one was pinned
+    if (args.logo.is_some() || args.reserve.is_some()) && args.error.is_none() {
+        args.error = Some("H".to_string());
+    }
+
+    for path in &args.plugins {
+        args.style_plugins.load(path)?;
+    }
+
+    if let Some(ref spec) = args.icc_profile {
+        args.color_profile = Some(icc::load(spec)?);
+    }
+    if let Some(ref spec) = args.rich_black {
+        args.rich_black_inks = Some(cmyk::parse_rich_black(spec)?);
+    }
+
+    if let Some(ref key_path) = args.sign {
+        args.signing_key = Some(sign::load_signing_key(key_path)?);
+    }
+
+    // Ask once, every code in the run uses the same passphrase
+    if args.encrypt {
+        args.passphrase = Some(crypto::read_passphrase(true)?);
+    }
+    Ok(())
+}
+
+/// Builds the code in the symbology chosen with `--symbology`
+fn build_symbol(data: &str, args: &Args) -> Result<Symbol> {
+    let symbology = Symbology::from_str(&args.symbology)
+        .ok_or_else(|| anyhow::anyhow!("Unknown symbology '{}'. Use qr, datamatrix, aztec, code128 or ean13", args.symbology))?;
+    if args.gs1 {
+        let encoded = gs1::encode(&gs1::parse(data)?);
+        return match symbology {
+            Symbology::Qr => build_qr_symbol(args, |args| build_gs1_qr(&encoded, args)),
+            Symbology::DataMatrix => datamatrix::encode_gs1(&encoded),
+            Symbology::Code128 => barcode::encode_gs1_128(&encoded, &barcode_options(args)),
+            Symbology::Aztec | Symbology::Ean13 => anyhow::bail!("GS1 data needs --symbology qr, datamatrix or code128 (GS1-128)"),
+        };
+    }
+    match symbology {
+        Symbology::Qr => build_qr_symbol(args, |args| build_qr(data, args)),
+        Symbology::DataMatrix => datamatrix::encode(&symbol_payload(data, args)?),
+        Symbology::Aztec => aztec::encode(&symbol_payload(data, args)?),
+        Symbology::Code128 => barcode::encode_code128(&symbol_payload(data, args)?, &barcode_options(args)),
+        Symbology::Ean13 => barcode::encode_ean13(&prepare_payload(data, args)?, &barcode_options(args)),
+    }
+}
+
+/// Builds a QR code, growing the version until a `--reserve` region can be cleared safely
+fn build_qr_symbol(args: &Args, build: impl Fn(&Args) -> Result<QrCode>) -> Result<Symbol> {
+    let Some(ref spec) = args.reserve else {
+        return Ok(Symbol::from_qr(build(args)?));
+    };
+    let region = reserve::Region::parse(spec)?;
+    let mut last_error = None;
+    for v in version_range(args)? {
+        let mut version_args = args.clone();
+        (version_args.version, version_args.min_version, version_args.max_version) = (Some(v), None, None);
+        // Versions too small for the data are skipped
+        let Ok(qr) = build(&version_args) else { continue };
+        match reserve::check(&qr, &region) {
+            Ok(reservation) => {
+                let mut symbol = Symbol::from_qr(qr);
+                symbol.reserve(reservation);
+                return Ok(symbol);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) => Err(e.context("No QR version in range leaves room for --reserve, make the region smaller or allow larger versions")),
+        None => Ok(Symbol::from_qr(build(args)?)),
+    }
+}
+
+fn barcode_options(args: &Args) -> BarcodeOptions {
+    BarcodeOptions { height: args.bar_height, quiet_zone: args.quiet_zone }
+}
+
+/// Bytes for symbologies without QR's encoding modes: raw binary input or the prepared text
+fn symbol_payload(data: &str, args: &Args) -> Result<Vec<u8>> {
+    match args.binary_data {
+        Some(ref bytes) => Ok(bytes.clone()),
+        None => Ok(prepare_payload(data, args)?.into_bytes()),
+    }
+}
+
+fn build_qr(data: &str, args: &Args) -> Result<QrCode> {
+    // Raw bytes skip every text transformation
+    if let Some(ref bytes) = args.binary_data {
+        return build_binary_qr(bytes, args);
+    }
+
+    let final_data = prepare_payload(data, args)?;
+    let ec_level = parse_ec_level(args.error.as_deref().unwrap_or("M"));
+
+    // Create QR code
+    let qr = if let Some(v) = args.version {
+        QrCode::with_version(&final_data, Version::Normal(v), ec_level)
+    } else if args.min_version.is_some() || args.max_version.is_some() {
+        version_range(args)?
+            .map(|v| QrCode::with_version(&final_data, Version::Normal(v), ec_level))
+            .find(|qr| !matches!(qr, Err(QrError::DataTooLong)))
+            .unwrap_or(Err(QrError::DataTooLong))
+    } else {
+        QrCode::with_error_correction_level(&final_data, ec_level)
+    };
+    match qr {
+        Ok(qr) if args.boost_ecc => {
+            let version = qr.version();
+            Ok(boost_ec_level(qr, |level| QrCode::with_version(&final_data, version, level)))
+        }
+        Ok(qr) => Ok(qr),
+        Err(QrError::DataTooLong) => {
+            let bytes = final_data.as_bytes();
+            Err(too_long_error(bytes, Mode::detect(bytes), ec_level, *version_range(args)?.end()))
+        }
+        Err(e) if args.version.is_some() => Err(e).context("Failed to create QR code with specified version"),
+        Err(e) => Err(e).context("Failed to create QR code"),
+    }
+}
+
+/// Applies the text transformations (URL checks, signing, encryption, compression, base64)
+fn prepare_payload(data: &str, args: &Args) -> Result<String> {
+    // Check and normalize URLs before anything else touches them
+    let data = if args.url {
+        let utm = [
+            ("utm_source", &args.utm_source),
+            ("utm_medium", &args.utm_medium),
+            ("utm_campaign", &args.utm_campaign),
+            ("utm_term", &args.utm_term),
+            ("utm_content", &args.utm_content),
+        ];
+        let utm: Vec<(&str, &str)> = utm
+            .iter()
+            .filter_map(|(key, value)| value.as_deref().map(|v| (*key, v)))
+            .collect();
+        normalize_url(data, &utm, &args.allow_scheme)?
+    } else {
+        data.to_string()
+    };
+
+    // Sign first so an encrypted code still carries the signature inside
+    let data = match args.signing_key {
+        Some(ref key) => sign::sign(&data, key),
+        None => data,
+    };
+
+    let data = match args.passphrase {
+        Some(ref passphrase) => crypto::encrypt(&data, passphrase, args.seed)?,
+        None => data,
+    };
+
+    // Compression does its own text encoding, --encode only picks base64 over base45
+    let final_data = if let Some(ref name) = args.compress {
+        let compressor = Compressor::from_str(name)
+            .with_context(|| format!("Unknown compression: {} (use deflate or brotli)", name))?;
+        let compressed = compress::compress(&data, compressor, args.encode)?;
+        if compressed.len() >= data.len() {
+            log::warn!("⚠ Compression made the payload larger ({} → {} bytes)", data.len(), compressed.len());
+        }
+        compressed
+    } else if args.encode {
+        general_purpose::STANDARD.encode(&data)
+    } else {
+        data
+    };
+    Ok(final_data)
+}
+
+/// Pre-flight check for `--validate`: runs every check instead of stopping at the first failure
+fn run_validate(args: &Args) -> Result<()> {
+    let mut problems: Vec<String> = Vec::new();
+    let mut check = |what: &str, result: Result<()>| {
+        if let Err(e) = result {
+            problems.push(format!("{}: {:#}", what, e));
+        }
+    };
+
+    check("--format", validate_format(&args.format));
+    // Later checks see palette colors and payloads resolved, or the raw options if that failed
+    let mut prepared = args.clone();
+    let ready = match prepare_args(&mut prepared) {
+        Ok(()) => true,
+        Err(e) => {
+            check("Options", Err(e));
+            prepared = args.clone();
+            false
+        }
+    };
+
+    let colors = [
+        ("--fg-color", Some(&prepared.fg_color)),
+        ("--bg-color", Some(&prepared.bg_color)),
+        ("--shadow-color", Some(&prepared.shadow_color)),
+        ("--matte", Some(&prepared.matte)),
+        ("--frame-text-color", Some(&prepared.frame_text_color)),
+        ("--stroke-color", prepared.stroke_color.as_ref()),
+        ("--border-color", prepared.border_color.as_ref()),
+        ("--frame-color", prepared.frame_color.as_ref()),
+        ("--caption-color", prepared.caption_color.as_ref()),
+        ("--canvas-color", prepared.canvas_color.as_ref()),
+        ("--outer-border-color", prepared.outer_border_color.as_ref()),
+        ("--timing-color", prepared.timing_color.as_ref()),
+        ("--alignment-color", prepared.alignment_color.as_ref()),
+    ];
+    for (flag, color) in colors {
+        if let Some(color) = color {
+            check(flag, parse_color(color).map(|_| ()));
+        }
+    }
+    for (flag, gradient) in [("--gradient", &prepared.gradient), ("--eye-gradient", &prepared.eye_gradient)] {
+        if let Some(gradient) = gradient {
+            check(flag, parse_gradient(gradient).map(|_| ()));
+        }
+    }
+    if let Some(ref gradient) = prepared.bg_gradient {
+        check("--bg-gradient", BgGradient::parse(gradient).map(|_| ()));
+    }
+    check("--dot-style", DotPattern::from_str(&prepared.dot_style, &prepared.style_plugins).map(|_| ()));
+    for (flag, style) in [("--timing-style", &prepared.timing_style), ("--alignment-style", &prepared.alignment_style)] {
+        if let Some(style) = style {
+            check(flag, DotPattern::parse(style, &prepared.style_plugins).map(|_| ()));
+        }
+    }
+
+    // The logo has to open, and its share of the code has to stay within what error correction recovers
+    let mut logo = None;
+    if let Some(ref path) = prepared.logo {
+        let opened = image::open(path).with_context(|| format!("Cannot read {}", path.display()));
+        let size_ok = if prepared.logo_size > 0.0 && prepared.logo_size <= 1.0 {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("{} is outside 0.0 to 1.0", prepared.logo_size))
+        };
+        check("--logo-size", size_ok);
+        match opened {
+            Ok(opened) => logo = Some(opened.to_rgba8()),
+            Err(e) => check("--logo", Err(e)),
+        }
+    }
+
+    // Capacity: every batch row, or the single payload, at the requested version and level
+    if ready {
+        match batch_rows(&prepared) {
+            Ok(Some(rows)) => {
+                for row in &rows {
+                    let mut row_args = prepared.clone();
+                    if let Some(ref symbology) = row.symbology {
+                        row_args.symbology = symbology.clone();
+                    }
+                    check(&format!("Batch row {}", row.index), build_symbol(&row.data, &row_args).map(|_| ()));
+                }
+            }
+            Ok(None) => {
+                let data = match prepared.binary_data {
+                    Some(_) => Ok(String::new()),
+                    None => prepared.data.clone().context("Data is required. Use --data or --interactive"),
+                };
+                match data.and_then(|data| build_symbol(&data, &prepared)) {
+                    Ok(symbol) => {
+                        let vector = matches!(prepared.format.to_lowercase().as_str(), "svg" | "epl" | "tspl" | "stl" | "3mf" | "dxf");
+                        if !vector {
+                            check("--size", module_scale(&symbol, &shaped_args(&symbol, &prepared)).map(|_| ()));
+                        }
+                        // Without rendering, only a plate that is always on counts towards the hidden modules
+                        if let Some(ref logo) = logo {
+                            let args = shaped_args(&symbol, &prepared);
+                            check("--logo", place_logo(&symbol, &args, logo, |_, _, _| args.logo_plate == "on").map(|_| ()));
+                        }
+                    }
+                    Err(e) => check("Data", Err(e)),
+                }
+            }
+            Err(e) => check("Batch", Err(e)),
+        }
+    }
+
+    // Output directories have to exist, or be creatable for --name-by-hash, and accept files
+    let files = [&prepared.output, &prepared.archive, &prepared.manifest, &prepared.sheet, &prepared.card];
+    let mut dirs: Vec<PathBuf> = files
+        .into_iter()
+        .flatten()
+        .filter(|path| path.as_path() != Path::new("-"))
+        .map(|path| path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf())
+        .collect();
+    if let Some(ref dir) = prepared.name_by_hash {
+        dirs.push(dir.ancestors().find(|dir| dir.exists()).unwrap_or(Path::new(".")).to_path_buf());
+    }
+    dirs.dedup();
+    for dir in dirs {
+        check("Output", writable_dir(&dir));
+    }
+
+    if problems.is_empty() {
+        log::info!("{}", tr!("validate-ok"));
+        return Ok(());
+    }
+    for problem in &problems {
+        log::error!("✗ {}", problem);
+    }
+    anyhow::bail!("{} problem(s) found, nothing was generated", problems.len())
+}
+
+/// Checks that `dir` is a directory files can be created in
+fn writable_dir(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+    let probe = dir.join(format!(".rustqr-validate-{}", std::process::id()));
+    File::create(&probe).with_context(|| format!("Cannot write to {}", dir.display()))?;
+    let _ = std::fs::remove_file(probe);
+    Ok(())
+}
+
+fn run_analyze(args: &Args) -> Result<()> {
+    let (payload, binary) = match args.binary_data {
+        Some(ref bytes) => (bytes.clone(), true),
+        None => {
+            let data = args.data.as_ref().context("Data is required. Use --data or --data-file")?;
+            (prepare_payload(data, args)?.into_bytes(), false)
+        }
+    };
+
+    let mode = if binary { Mode::Byte } else { Mode::detect(&payload) };
+    println!("Payload: {} bytes, {} mode", payload.len(), format!("{:?}", mode).to_lowercase());
+    let versions = version_range(args)?;
+    match args.version {
+        Some(v) => println!("Fit in version {} ({}×{} modules):\n", v, v * 4 + 17, v * 4 + 17),
+        None if versions != (1..=40) => println!(
+            "Smallest version between {} and {} per error correction level:\n",
+            versions.start(),
+            versions.end()
+        ),
+        None => println!("Smallest version per error correction level:\n"),
+    }
+
+    println!("{:<6} {:<8} {:<10} {:<16} Headroom", "Level", "Version", "Modules", "Used");
+    for ec_level in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+        let found = capacity::smallest_fit(&payload, binary, ec_level, versions.clone());
+        match found {
+            Some(fit) => println!(
+                "{:<6} {:<8} {:<10} {:<16} {} bits (~{} bytes)",
+                format!("{:?}", ec_level),
+                fit.version,
+                format!("{}×{}", fit.modules(), fit.modules()),
+                format!("{}/{} bits", fit.used_bits, fit.capacity_bits),
+                fit.headroom_bits(),
+                fit.headroom_bits() / 8
+            ),
+            None => {
+                let needed = capacity::smallest_fit(&payload, binary, ec_level, 1..=40)
+                    .map(|fit| format!("needs version {}", fit.version))
+                    .unwrap_or_else(|| "too long for any version".to_string());
+                println!("{:<6} -        does not fit, {}", format!("{:?}", ec_level), needed);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the capacity table for the versions in range. Columns are the four levels of one
+/// mode, or the three modes of one level with `--ec` alone; with a payload, each column marks
+/// the smallest version it fits in
+fn run_capacity(args: &Args, capacity_args: &CapacityArgs) -> Result<()> {
+    let payload = match (&args.binary_data, &args.data) {
+        (Some(bytes), _) => Some((bytes.clone(), true)),
+        (None, Some(data)) => Some((prepare_payload(data, args)?.into_bytes(), false)),
+        (None, None) => None,
+    };
+    let payload_mode = payload
+        .as_ref()
+        .map(|(bytes, binary)| if *binary { Mode::Byte } else { Mode::detect(bytes) });
+    let mode = match capacity_args.mode.as_deref() {
+        Some("numeric") => Some(Mode::Numeric),
+        Some("alphanumeric") => Some(Mode::Alphanumeric),
+        Some(_) => Some(Mode::Byte),
+        None => None,
+    };
+    let levels = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+    let columns: Vec<(Mode, EcLevel)> = match (mode, capacity_args.ec.as_deref().map(parse_ec_level)) {
+        (None, Some(ec_level)) => [Mode::Numeric, Mode::Alphanumeric, Mode::Byte]
+            .into_iter()
+            .map(|mode| (mode, ec_level))
+            .collect(),
+        (Some(mode), Some(ec_level)) => vec![(mode, ec_level)],
+        (mode, None) => {
+            let mode = mode.or(payload_mode).unwrap_or(Mode::Byte);
+            levels.into_iter().map(|ec_level| (mode, ec_level)).collect()
+        }
+    };
+
+    let versions = version_range(args)?;
+    // The version each level's column lands on, found the way the generator encodes
+    let landing: Vec<Option<i16>> = columns
+        .iter()
+        .map(|&(mode, ec_level)| {
+            let (bytes, binary) = payload.as_ref()?;
+            (Some(mode) == payload_mode)
+                .then(|| capacity::smallest_fit(bytes, *binary, ec_level, versions.clone()))
+                .flatten()
+                .map(|fit| fit.version)
+        })
+        .collect();
+
+    if let [(mode, _), ..] = columns[..]
+        && columns.iter().all(|&(other, _)| other == mode)
+    {
+        println!("Capacity in {} ({} mode):\n", mode.unit(), format!("{:?}", mode).to_lowercase());
+    } else {
+        println!("Capacity per mode at error correction level {:?}:\n", columns[0].1);
+    }
+    let header: Vec<String> = columns
+        .iter()
+        .map(|&(mode, ec_level)| {
+            if columns.iter().all(|&(other, _)| other == mode) {
+                format!("{:?}", ec_level)
+            } else {
+                format!("{:?}", mode)
+            }
+        })
+        .collect();
+    let header: String = header.iter().map(|h| format!("{:<14}", h)).collect();
+    println!("{:<8} {:<10} {}", "Version", "Modules", header.trim_end());
+    for version in versions.clone() {
+        let modules = version * 4 + 17;
+        let cells: String = columns
+            .iter()
+            .zip(&landing)
+            .map(|(&(mode, ec_level), landed)| {
+                let mark = if *landed == Some(version) { " ◀" } else { "" };
+                format!("{:<14}", format!("{}{}", capacity::max_chars(version, ec_level, mode), mark))
+            })
+            .collect();
+        println!("{:<8} {:<10} {}", version, format!("{}×{}", modules, modules), cells.trim_end());
+    }
+
+    if let (Some((bytes, binary)), Some(payload_mode)) = (&payload, payload_mode) {
+        println!(
+            "\n◀ Payload: {} bytes, {} mode",
+            bytes.len(),
+            format!("{:?}", payload_mode).to_lowercase()
+        );
+        for (&(mode, ec_level), landed) in columns.iter().zip(&landing) {
+            if mode != payload_mode {
+                continue;
+            }
+            match landed {
+                Some(version) => {
+                    let fit = capacity::smallest_fit(bytes, *binary, ec_level, *version..=*version)
+                        .context("A landing version always fits")?;
+                    println!(
+                        "  {:?}: version {}, {}/{} bits used, {} bits (~{} bytes) to spare",
+                        ec_level,
+                        version,
+                        fit.used_bits,
+                        fit.capacity_bits,
+                        fit.headroom_bits(),
+                        fit.headroom_bits() / 8
+                    );
+                }
+                None => println!("  {:?}: does not fit in versions {}-{}", ec_level, versions.start(), versions.end()),
+            }
+        }
+        if !columns.iter().any(|&(mode, _)| mode == payload_mode) {
+            println!("  Not listed, the payload needs {} mode", format!("{:?}", payload_mode).to_lowercase());
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_ec_level(level: &str) -> EcLevel {
+    match level.to_uppercase().as_str() {
+        "L" => EcLevel::L,
+        "M" => EcLevel::M,
+        "Q" => EcLevel::Q,
+        "H" => EcLevel::H,
+        _ => EcLevel::M,
+    }
+}
+
+/// Puts raw bytes into a single byte-mode segment, picking the smallest version that fits
+fn build_binary_qr(bytes: &[u8], args: &Args) -> Result<QrCode> {
+    build_qr_from_bits(bytes, Mode::Byte, args, |bits| bits.push_byte_data(bytes))
+}
+
+/// GS1 QR: FNC1 in first position, then the element string with GS separators
+fn build_gs1_qr(data: &[u8], args: &Args) -> Result<QrCode> {
+    build_qr_from_bits(data, Mode::detect(data), args, |bits| {
+        bits.push_fnc1_first_position()?;
+        // Under FNC1 an alphanumeric '%' reads as GS, so a literal one keeps everything in byte mode
+        if data.contains(&b'%') {
+            bits.push_byte_data(data)
+        } else {
+            bits.push_optimal_data(data)
+        }
+    })
+}
+
+/// Builds the code from hand-written segments, picking the smallest version that fits
+fn build_qr_from_bits(
+    data: &[u8],
+    mode: Mode,
+    args: &Args,
+    push: impl Fn(&mut Bits) -> qrcode::types::QrResult<()>,
+) -> Result<QrCode> {
+    let ec_level = parse_ec_level(args.error.as_deref().unwrap_or("M"));
+    let versions = version_range(args)?;
+    let encode = |version: Version, level: EcLevel| {
+        let mut bits = Bits::new(version);
+        push(&mut bits)?;
+        bits.push_terminator(level)?;
+        QrCode::with_bits(bits, level)
+    };
+
+    for v in versions.clone() {
+        if let Ok(qr) = encode(Version::Normal(v), ec_level) {
+            if args.boost_ecc {
+                return Ok(boost_ec_level(qr, |level| encode(Version::Normal(v), level)));
+            }
+            return Ok(qr);
+        }
+    }
+
+    Err(too_long_error(data, mode, ec_level, *versions.end()))
+}
+
+/// Re-encodes at the strongest level that still fits the same version (like qrencode's -8)
+fn boost_ec_level(qr: QrCode, encode: impl Fn(EcLevel) -> qrcode::types::QrResult<QrCode>) -> QrCode {
+    let current = qr.error_correction_level();
+    [EcLevel::H, EcLevel::Q, EcLevel::M]
+        .into_iter()
+        .take_while(|level| *level > current)
+        .find_map(|level| encode(level).ok())
+        .unwrap_or(qr)
+}
+
+/// Versions allowed by --version or --min-version/--max-version
+fn version_range(args: &Args) -> Result<RangeInclusive<i16>> {
+    if let Some(v) = args.version {
+        return Ok(v..=v);
+    }
+    let (min, max) = (args.min_version.unwrap_or(1), args.max_version.unwrap_or(40));
+    if min > max {
+        anyhow::bail!("--min-version {} is larger than --max-version {}", min, max);
+    }
+    Ok(min..=max)
+}
+
+/// Decodes frames until the stream ends, printing each code once when it appears
+fn run_scan(scan_args: &ScanArgs) -> Result<()> {
+    if scan_args.screen {
+        let mut found: Vec<Decoded> = Vec::new();
+        for decoded in capture_screens()?.iter().flat_map(decode_all) {
+            if !found.iter().any(|f| f.data == decoded.data) {
+                found.push(decoded);
+            }
+        }
+        if found.is_empty() {
+            anyhow::bail!("No QR code found on the screen");
+        }
+        let count = if scan_args.once { 1 } else { found.len() };
+        for decoded in &found[..count] {
+            println!("{}", decoded.text());
+        }
+        return Ok(());
+    }
+
+    let device = scan_args
+        .camera
+        .as_deref()
+        .context("Choose what to scan with --camera <DEVICE> (e.g. --camera 0) or --screen")?;
+    let mut stream = FrameStream::camera(device)?;
+    log::info!("Scanning camera {}, press Ctrl+C to stop", device);
+
+    // The same code stays in view for many frames, so only a change is reported
+    let mut last: Option<Vec<u8>> = None;
+    while let Some(frame) = stream.next_frame()? {
+        let Ok(decoded) = decode_image(&frame) else { continue };
+        if last.as_ref() == Some(&decoded.data) {
+            continue;
+        }
+        println!("{}", decoded.text());
+        std::io::stdout().flush()?;
+        if scan_args.once {
+            return Ok(());
+        }
+        last = Some(decoded.data);
+    }
+    Ok(())
+}
+
+/// Reports what differs between the codes in two images and writes a module-by-module diff
+fn run_diff(args: &Args, diff_args: &DiffArgs) -> Result<()> {
+    let read = |path: &Path| -> Result<Decoded> {
+        let img = image::open(path).with_context(|| format!("Failed to open image: {}", path.display()))?;
+        decode_image(&img).with_context(|| format!("No readable QR code in {}", path.display()))
+    };
+    let (first, second) = (read(&diff_args.first)?, read(&diff_args.second)?);
+    let name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let (first_name, second_name) = (name(&diff_args.first), name(&diff_args.second));
+
+    let row = |label: &str, a: String, b: String| {
+        println!("{:<10} {:<24} {}{}", label, a, b, if a == b { "" } else { "  ≠" });
+    };
+    println!("{:<10} {:<24} {}", "", first_name, second_name);
+    row("Version", first.version.to_string(), second.version.to_string());
+    row("Level", format!("{:?}", first.ec_level), format!("{:?}", second.ec_level));
+    row("Mask", first.mask.to_string(), second.mask.to_string());
+    row("Payload", format!("{} bytes", first.data.len()), format!("{} bytes", second.data.len()));
+    match diff::count_changes(&first, &second) {
+        Some(changed) => {
+            let total = first.modules.len();
+            println!("{:<10} {} of {} differ ({:.0}%)", "Modules", changed, total, changed as f32 * 100.0 / total as f32);
+        }
+        None => println!("{:<10} different sizes, no module diff", "Modules"),
+    }
+    println!();
+
+    if first.data == second.data {
+        println!("✓ Payloads are identical");
+    } else {
+        println!("✗ Payloads differ");
+        println!("  {}: {}", first_name, first.text());
+        println!("  {}: {}", second_name, second.text());
+    }
+
+    if let Some(img) = diff::render(&first, &second) {
+        let output_path = claim_output_path(args.output.as_deref().unwrap_or(Path::new("diff.png")), args);
+        img.save(&output_path).with_context(|| format!("Failed to save {}", output_path.display()))?;
+        log::info!("✓ Diff saved to: {} (red: dark only in {}, green: dark only in {})", output_path.display(), first_name, second_name);
+    }
+    Ok(())
+}
+
+fn run_themes() {
+    println!("{:<11} {:<32} {:<8} {:<7}", "Theme", "Colors", "Dots", "Eyes");
+    for theme in &themes::THEMES {
+        let colors = match theme.gradient {
+            Some(gradient) => format!("{} on {}", gradient.replace(',', " → "), theme.bg_color),
+            None => format!("{} on {}", theme.fg_color, theme.bg_color),
+        };
+        println!("{:<11} {:<32} {:<8} {:<7}", theme.name, colors, theme.dot_style, theme.eye_style);
+        println!("{:<11} {}", "", theme.description);
+    }
+}
+
+/// Adds a generated code to the history; a failure is only a warning, the code itself was made
+#[cfg(feature = "history")]
+fn record_history(args: &Args, data: &str, output: Option<&Path>, symbol: String) {
+    let Some(ref invocation) = args.invocation else {
+        return;
+    };
+    if args.record == "off" {
+        return;
+    }
+    // Even a hash of the plaintext would let anyone with the database confirm a guess at it
+    if args.encrypt {
+        log::debug!("Encrypted payloads are not recorded in the history");
+        return;
+    }
+    // OTP seeds are secret, so only their hash is kept
+    let secret = matches!(args.command, Some(Command::Payload(ref payload)) if payload.is_secret());
+    let hash_only = args.record == "hash" || secret;
+    // Payload builders and GS1 flags carry the payload on the command line itself
+    let payload_flags = matches!(args.command, Some(Command::Payload(_)))
+        || [&args.gtin, &args.expiry, &args.lot, &args.serial].iter().any(|flag| flag.is_some());
+    // Prompts and the editor choose settings the command line does not show
+    let kept = !(args.interactive || args.tui || (hash_only && payload_flags));
+    let settings = kept.then(|| history::strip_data(invocation));
+    let record = history::Record {
+        payload: args.binary_data.as_deref().unwrap_or(data.as_bytes()),
+        hash_only,
+        settings: settings.as_deref(),
+        output: output.map(|path| path.display().to_string()),
+        format: &output_format(args, output.unwrap_or(Path::new(""))),
+        symbol,
+    };
+    log::trace!("Recording {} in the history ({})", record.format, if hash_only { "hash only" } else { "full" });
+    if let Err(e) = history::History::open().and_then(|history| history.record(&record)) {
+        log::warn!("⚠ Could not record this code in the history: {:#}", e);
+    }
+}
+
+#[cfg(feature = "history")]
+fn run_history(history_args: &HistoryArgs) -> Result<()> {
+    let history = history::History::open()?;
+    let (text, filter) = match history_args.action {
+        HistoryAction::List(ref filter) => (None, filter),
+        HistoryAction::Search { ref text, ref filter } => (Some(text.clone()), filter),
+        HistoryAction::Show { id } => {
+            print_history_entry(&history.entry(id)?);
+            return Ok(());
+        }
+        HistoryAction::Regen { .. } => unreachable!("regen goes through the normal generation path"),
+    };
+
+    // Days are given in local time, the history keeps UTC
+    let day_start = |day: chrono::NaiveDate| {
+        day.and_hms_opt(0, 0, 0)
+            .and_then(|start| start.and_local_timezone(chrono::Local).earliest())
+            .map(|start| start.with_timezone(&chrono::Utc))
+    };
+    let filter = history::Filter {
+        text,
+        since: filter.since.and_then(day_start),
+        until: filter.until.and_then(|day| day.succ_opt()).and_then(day_start),
+        limit: filter.limit,
+    };
+    let entries = history.entries(&filter)?;
+    if entries.is_empty() {
+        println!("No matching codes in the history");
+        return Ok(());
+    }
+    println!("{:>5}  {:<16}  {:<28}  Payload", "ID", "Generated", "Output");
+    for entry in &entries {
+        let output = entry.output.as_deref().map(Path::new).and_then(Path::file_name).map(|name| name.to_string_lossy().into_owned());
+        let payload = match entry.payload_text() {
+            Some(text) => shorten(text.lines().next().unwrap_or(""), 60),
+            None => format!("sha256:{}", &entry.sha256[..12]),
+        };
+        let created = entry.created.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+        println!("{:>5}  {:<16}  {:<28}  {}", entry.id, created, shorten(output.as_deref().unwrap_or("-"), 28), payload);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "history"))]
+fn run_history(_history_args: &HistoryArgs) -> Result<()> {
+    anyhow::bail!("This build has no history, rustqr history needs the history feature")
+}
+
+/// Options that choose a run's input, output, delivery and logging rather than the code itself:
+/// `history regen` does not repeat them and `--name-by-hash` leaves them out of the name
+const RUN_ONLY: [&str; 41] = [
+    "data", "data_file", "interactive", "tui", "batch", "batch_sql", "batch_query", "worksheet", "data_column",
+    "output_column", "caption_column", "sequence", "template", "step", "generate", "count", "id_manifest", "manifest",
+    "archive", "archive_manifest", "output", "name_by_hash", "force", "no_clobber", "dry_run", "validate", "print",
+    "copies", "media", "copy", "show", "webhook", "mail_to", "telegram", "record", "preset", "lang", "verbose", "quiet",
+    "log_format", "preview",
+];
+
+/// The arguments that generate history entry `id` again: its recorded command line and payload,
+/// with the options on the `history regen` command line (`invocation`) replacing recorded ones
+#[cfg(feature = "history")]
+fn replay_args(id: i64, invocation: &[String]) -> Result<Args> {
+    let entry = history::History::open()?.entry(id)?;
+    let settings = entry.settings.as_ref().with_context(|| {
+        format!("History entry {} kept no command line (made interactively, in the editor or from a hashed payload builder), so it cannot be regenerated", id)
+    })?;
+
+    // Saving, printing, delivery and batch options belong to the original run; they only apply
+    // again when given to regen
+    let overrides: Vec<(Option<String>, Vec<String>)> = preset::option_groups(invocation).into_iter().filter(|(id, _)| id.is_some()).collect();
+    let given: HashSet<&str> = overrides.iter().filter_map(|(id, _)| id.as_deref()).collect();
+    let mut replay: Vec<String> = preset::option_groups(settings)
+        .into_iter()
+        .filter(|(id, _)| id.as_deref().is_none_or(|id| !given.contains(id) && !RUN_ONLY.contains(&id)))
+        .chain(overrides.iter().cloned())
+        .flat_map(|(_, tokens)| tokens)
+        .collect();
+    let mut args = Args::try_parse_from(std::iter::once("RustQR".to_string()).chain(replay.iter().cloned()))
+        .with_context(|| format!("Failed to replay history entry {}", id))?;
+
+    // The recorded payload stands in for whatever produced it the first time
+    if let Some(Command::Payload(ref payload)) = args.command {
+        if let Some(level) = payload.error_level() {
+            args.error = Some(level.to_string());
+        }
+        args.command = None;
+    }
+    if [&args.gtin, &args.expiry, &args.lot, &args.serial].iter().any(|flag| flag.is_some()) {
+        (args.gtin, args.expiry, args.lot, args.serial) = (None, None, None, None);
+        args.gs1 = true;
+    }
+    match entry.payload {
+        Some(ref payload) if args.binary => {
+            args.binary_data = Some(payload.clone());
+            args.data_file = None;
+        }
+        Some(ref payload) => {
+            args.data = Some(String::from_utf8(payload.clone()).context("The recorded payload is not UTF-8 text")?);
+            args.data_file = None;
+        }
+        // Only the hash was kept, so the payload has to come again and match it
+        None => {
+            let payload = match (&args.data, &args.data_file) {
+                (Some(data), _) => data.clone().into_bytes(),
+                (None, Some(path)) => std::fs::read(path).with_context(|| format!("Failed to read data file: {}", path.display()))?,
+                (None, None) => anyhow::bail!("History entry {} kept only a hash of its payload, give the payload again with --data", id),
+            };
+            if history::sha256(&payload) != entry.sha256 {
+                anyhow::bail!("The payload does not match the one recorded for history entry {}", id);
+            }
+        }
+    }
+
+    // Reprints go next to the original under its name, in the new format, and never replace it
+    // unless --force is given
+    if !given.contains("output")
+        && let Some(ref output) = entry.output
+    {
+        let mut path = PathBuf::from(output);
+        if given.contains("format") && path != Path::new("-") {
+            path.set_extension(args.format.to_lowercase());
+        }
+        replay.extend(["--output".to_string(), path.display().to_string()]);
+        args.output = Some(path);
+        args.no_clobber = !args.force;
+    }
+    args.invocation = Some(replay);
+    Ok(args)
+}
+
+#[cfg(not(feature = "history"))]
+fn replay_args(_id: i64, _invocation: &[String]) -> Result<Args> {
+    anyhow::bail!("This build has no history, rustqr history needs the history feature")
+}
+
+#[cfg(feature = "history")]
+fn print_history_entry(entry: &history::Entry) {
+    println!("ID         {}", entry.id);
+    println!("Generated  {}", entry.created.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S %:z"));
+    println!("Payload    {}", entry.payload_text().as_deref().unwrap_or("(only the hash was kept)"));
+    println!("SHA-256    {}", entry.sha256);
+    println!("Symbol     {}", entry.symbol);
+    println!("Format     {}", entry.format);
+    println!("Output     {}", entry.output.as_deref().unwrap_or("(not saved)"));
+    match entry.settings {
+        Some(ref settings) => {
+            let quoted: Vec<String> = settings.iter().map(|arg| shell_quote(arg)).collect();
+            println!("Command    RustQR {}", quoted.join(" "));
+        }
+        None => println!("Command    (not kept)"),
+    }
+}
+
+/// `text` cut to `max` characters, marking the cut with an ellipsis
+#[cfg(feature = "history")]
+fn shorten(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max.saturating_sub(1)) {
+        Some((end, _)) if text.chars().count() > max => format!("{}…", &text[..end]),
+        _ => text.to_string(),
+    }
+}
+
+/// Quotes an argument for a POSIX shell when it needs it
+#[cfg(feature = "history")]
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,@%+#".contains(c));
+    if plain { arg.to_string() } else { format!("'{}'", arg.replace('\'', "'\\''")) }
+}
+
+fn run_decode(decode_args: &DecodeArgs) -> Result<()> {
+    let img = open_image(&decode_args.image)?;
+    let mut codes = if decode_args.all { decode_all(&img) } else { Vec::new() };
+    if codes.is_empty() {
+        // Also explains why nothing was found
+        codes.push(decode_image(&img)?);
+    }
+    log::debug!("Found {} QR code(s)", codes.len());
+
+    if let Some(ref path) = decode_args.raw_bytes {
+        for (i, decoded) in codes.iter().enumerate() {
+            let file = if i == 0 {
+                path.clone()
+            } else {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let name = match path.extension() {
+                    Some(ext) => format!("{}-{}.{}", stem, i + 1, ext.to_string_lossy()),
+                    None => format!("{}-{}", stem, i + 1),
+                };
+                path.with_file_name(name)
+            };
+            std::fs::write(&file, &decoded.data).with_context(|| format!("Failed to write {}", file.display()))?;
+            log::info!("{}", tr!("payload-saved", path = file.display().to_string()));
+        }
+        if decode_args.output_format == "text" {
+            return Ok(());
+        }
+    }
+
+    let passphrase = if decode_args.decrypt { Some(crypto::read_passphrase(false)?) } else { None };
+    let key = match decode_args.verify_signature {
+        Some(ref key_path) => Some(sign::load_verifying_key(key_path)?),
+        None => None,
+    };
+
+    let mut reports = Vec::new();
+    for decoded in &codes {
+        // Binary payloads (--binary) have no text to unwrap
+        let text = if std::str::from_utf8(&decoded.data).is_err() && passphrase.is_none() && key.is_none() {
+            None
+        } else {
+            Some(payload_text(decoded, passphrase.as_deref(), key.as_ref())?)
+        };
+        let breakdown = text.as_deref().and_then(inspect::breakdown);
+        if decode_args.output_format == "json" {
+            let fields = breakdown.as_ref().map(|breakdown| {
+                breakdown
+                    .fields
+                    .iter()
+                    .map(|(label, value)| serde_json::json!({ "label": label, "value": value }))
+                    .collect::<Vec<_>>()
+            });
+            let corner = |(x, y): (f32, f32)| [(x * 10.0).round() / 10.0, (y * 10.0).round() / 10.0];
+            reports.push(serde_json::json!({
+                "payload": text,
+                "payload_base64": text.is_none().then(|| general_purpose::STANDARD.encode(&decoded.data)),
+                "bytes": decoded.data.len(),
+                "version": decoded.version,
+                "ec_level": format!("{:?}", decoded.ec_level),
+                "mask": decoded.mask,
+                "eci": decoded.eci,
+                "bounds": decoded.bounds.map(corner),
+                "format": breakdown.as_ref().map(|breakdown| breakdown.format),
+                "fields": fields,
+            }));
+            continue;
+        }
+        match text {
+            Some(text) => {
+                println!("{}", text);
+                // A status line, so it lands on stderr, $(rustqr decode ...) still captures just the
+                // payload and --quiet hides it
+                if let Some(breakdown) = breakdown {
+                    log::info!("\n{}", breakdown.render());
+                }
+            }
+            // Written to stdout byte for byte
+            None => std::io::stdout().write_all(&decoded.data)?,
+        }
+    }
+
+    if decode_args.output_format == "json" {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    }
+    Ok(())
+}
+
+/// The decoded text with --decrypt, compression and --verify-signature undone
+fn payload_text(decoded: &Decoded, passphrase: Option<&str>, key: Option<&ed25519_dalek::VerifyingKey>) -> Result<String> {
+    let mut text = decoded.text();
+
+    if let Some(passphrase) = passphrase {
+        text = crypto::decrypt(&text, passphrase)?;
+    } else if crypto::is_encrypted(&text) {
+        log::warn!("⚠ This payload is encrypted, add --decrypt to read it");
+    }
+
+    if compress::is_compressed(&text) {
+        text = compress::decompress(&text)?;
+    }
+
+    if let Some(key) = key {
+        text = sign::verify(&text, key)?;
+        log::info!("✓ Signature verified");
+    } else if sign::is_signed(&text) {
+        log::warn!("⚠ This payload is signed, add --verify-signature <KEY> to check it");
+    }
+
+    Ok(text)
+}
+
+/// Saves which file encodes which `--generate` identifier, as an `id,file` CSV
+fn write_id_manifest(generated: &[(&str, PathBuf)], args: &Args) -> Result<()> {
+    let path = claim_output_path(&args.id_manifest, args);
+    let mut writer = csv::Writer::from_path(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+    writer.write_record(["id", "file"])?;
+    for (id, file) in generated {
+        writer.write_record([*id, &file.display().to_string()])?;
+    }
+    writer.flush().with_context(|| format!("Failed to write {}", path.display()))?;
+    log::info!("{}", tr!("ids-saved", path = path.display().to_string()));
+    Ok(())
+}
+
+/// The rows of `--batch`, `--batch-sql`, `--sequence` or `--generate`, None when a single code is generated
+fn batch_rows(args: &Args) -> Result<Option<Vec<batch::BatchRow>>> {
+    let options = batch::BatchOptions {
+        data_template: args.data.as_deref(),
+        worksheet: args.worksheet.as_deref(),
+        data_column: &args.data_column,
+        output_column: &args.output_column,
+        caption_column: &args.caption_column,
+    };
+    if let Some(ref path) = args.batch {
+        return read_batch(path, &options).map(Some);
+    }
+    if let (Some(url), Some(query)) = (&args.batch_sql, &args.batch_query) {
+        return batch::read_sql(url, query, &options).map(Some);
+    }
+    if let Some(ref kind) = args.generate {
+        return batch::generated_rows(kind, args.count, &args.format, args.output.as_deref()).map(Some);
+    }
+    match args.sequence {
+        Some(ref range) => batch::sequence_rows(range, args.template.as_deref().unwrap_or_default(), args.step, args.output.as_deref(), &args.format).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// A file being written that is deleted again when dropped, unless `keep` is called first
+struct PartialFile(Option<PathBuf>);
+
+impl PartialFile {
+    fn keep(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Where `generate_one` writes the encoded code
+enum Output<'a> {
+    /// Nothing is written, e.g. when only printing
+    Discard,
+    File(&'a Path),
+    /// An entry of a `--archive`, named like the file it stands for
+    Archive(&'a mut ZipWriter<Box<dyn Write>>, &'a Path),
+}
+
+impl Output<'_> {
+    fn path(&self) -> Option<&Path> {
+        match self {
+            Output::Discard => None,
+            Output::File(path) | Output::Archive(_, path) => Some(path),
+        }
+    }
+}
+
+/// What `generate_one` made
+struct Generated {
+    /// Where the file was saved, if anywhere
+    path: Option<PathBuf>,
+    /// Symbology, version and level, as `Symbol::describe` puts them
+    symbol: String,
+    /// QR version, None for other symbologies
+    version: Option<i16>,
+}
+
+/// Renders one code and reports where it was saved, if anywhere
+fn generate_one(args: &Args, data: &str, output: Output) -> Result<Generated> {
+    let output_path = output.path();
+    let symbol = build_symbol(data, args)?;
+    log::debug!("Encoded {} bytes as {}", data.len(), symbol.describe());
+    let mut generated = Generated {
+        path: None,
+        symbol: symbol.describe(),
+        version: symbol.qr().and_then(|qr| match qr.version() {
+            Version::Normal(v) => Some(v),
+            Version::Micro(_) => None,
+        }),
+    };
+
+    // Show in terminal if requested
+    if args.show {
+        print_qr_terminal(&symbol);
+    }
+
+    // Generate image
+    let img = generate_qr_image(&symbol, args)?;
+    log::debug!("Rendered {}×{} px", img.width(), img.height());
+    if let Some(reservation) = symbol.reserved() {
+        report_reservation(&symbol, reservation, args);
+    }
+    let metadata = metadata::collect(args, Some(data))?;
+    if args.cmyk && output_path.is_some_and(|path| !matches!(output_format(args, path).as_str(), "tif" | "tiff")) {
+        anyhow::bail!("--cmyk writes TIFF files, use --format tiff or a .tif/.tiff output path");
+    }
+    if args.mockup.is_some()
+        && output_path.is_some_and(|path| matches!(output_format(args, path).as_str(), "svg" | "epl" | "tspl" | "stl" | "3mf" | "dxf"))
+    {
+        anyhow::bail!("--mockup renders a raster scene, use a PNG, JPEG or other image output");
+    }
+
+    // Everything has been validated by now, report instead of writing
+    if args.dry_run {
+        let details = format!("{}, {}×{} px", symbol.describe(), img.width(), img.height());
+        if let Some(ref printer) = args.print {
+            log::info!("✓ Would print on {}: {}", if printer.is_empty() { "default" } else { printer }, details);
+        }
+        match output {
+            Output::Discard => {}
+            Output::File(path) => log::info!("✓ Would save {}: {}", claim_output_path(path, args).display(), details),
+            Output::Archive(_, name) => log::info!("✓ Would archive {}: {}", name.display(), details),
+        }
+        return Ok(generated);
+    }
+
+    #[cfg(feature = "preview")]
+    if args.preview && !preview::confirm(&img)? {
+        log::info!("{}", tr!("discarded"));
+        return Ok(generated);
+    }
+
+    // Print directly if requested
+    if let Some(ref printer) = args.print {
+        let job = if matches!(args.format.to_lowercase().as_str(), "epl" | "tspl") {
+            let language = LabelLanguage::from_str(&args.format).context("Unknown label language")?;
+            PrintJob::Raw(render_label(&symbol, &language, &label_options(args))?)
+        } else {
+            let options = PngOptions {
+                compression: args.png_compression,
+                interlace: false,
+                palette: false,
+                metadata: Vec::new(),
+                icc_profile: None,
+            };
+            PrintJob::Png(encode_png(&img.to_rgba8(), &options)?)
+        };
+        let printer = (!printer.is_empty()).then_some(printer.as_str());
+        send_to_printer(&job, printer, args.copies, args.media.as_deref())?;
+        log::info!("{}", tr!("sent-to-printer", printer = printer.unwrap_or("default")));
+    }
+
+    // Save based on format
+    let mut saved_path = None;
+    #[cfg(feature = "history")]
+    let archived = match output {
+        Output::Archive(_, name) => Some(name.to_path_buf()),
+        _ => None,
+    };
+    let encoded = match output {
+        Output::Discard if deliver::requested(args) => {
+            let name = PathBuf::from(format!("qrcode.{}", args.format));
+            Some((encode_output(&symbol, &img, args, &metadata, &name)?, name))
+        }
+        Output::Discard => None,
+        Output::File(path) => {
+            let output_path = claim_output_path(path, args);
+            let bytes = encode_output(&symbol, &img, args, &metadata, &output_path)?;
+            write_output(&output_path, &bytes)?;
+            if output_path != Path::new("-") {
+                log::info!("{}", tr!("saved", kind = symbol.symbology().message_kind(), path = output_path.display().to_string()));
+            }
+            saved_path = Some(output_path.clone());
+            Some((bytes, output_path))
+        }
+        Output::Archive(zip, name) => {
+            let bytes = encode_output(&symbol, &img, args, &metadata, name)?;
+            zip.add(&archive_entry_name(name)?, &bytes)?;
+            Some((bytes, name.to_path_buf()))
+        }
+    };
+
+    #[cfg(feature = "history")]
+    if encoded.is_some() || args.print.is_some() {
+        let kept = match saved_path {
+            Some(ref path) if path != Path::new("-") => Some(std::path::absolute(path).unwrap_or_else(|_| path.clone())),
+            Some(ref path) => Some(path.clone()),
+            None => archived,
+        };
+        record_history(args, data, kept.as_deref(), symbol.describe());
+    }
+
+    // Hand the finished file on to webhooks, mailboxes and chats
+    if let Some((bytes, path)) = encoded
+        && deliver::requested(args)
+    {
+        let size = (img.width(), img.height());
+        let delivery = deliver::Delivery::new(&path, &output_format(args, &path), bytes, symbol.describe(), size, &metadata);
+        deliver::send(args, &delivery)?;
+    }
+    generated.path = saved_path;
+    Ok(generated)
+}
+
+fn run_sheet(args: &Args, sheet_path: &Path) -> Result<()> {
+    let layout = if let Some(ref preset) = args.labels {
+        SheetLayout::preset(preset)?
+    } else {
+        let page = parse_page_size(&args.page_size)?;
+        SheetLayout::grid(page, args.columns, args.rows, args.margin, args.gutter)?
+    };
+    let dpi = args.dpi.unwrap_or(300);
+
+    let entries: Vec<(String, Option<String>, Option<String>)> = if let Some(rows) = batch_rows(args)? {
+        rows.into_iter()
+            .map(|row| (row.data, row.caption.or_else(|| args.caption.clone()), row.symbology))
+            .collect()
+    } else {
+        let data = args.data.as_ref().context("Data is required. Use --data, --batch, --sequence or --interactive")?;
+        let count = args.sheet_count.unwrap_or(layout.cells_per_page());
+        vec![(data.clone(), args.caption.clone(), None); count]
+    };
+
+    // Render each code directly at cell resolution so modules stay crisp
+    let captions = entries.iter().any(|(_, caption, _)| caption.is_some());
+    let mut cell_args = args.clone();
+    cell_args.size = layout.code_size(dpi, captions);
+    let items = entries
+        .into_iter()
+        .map(|(data, caption, symbology)| {
+            let mut cell_args = cell_args.clone();
+            if let Some(symbology) = symbology {
+                cell_args.symbology = symbology;
+            }
+            let image = render_code(&data, &cell_args)?;
+            Ok(SheetItem { image, caption })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let text_color = parse_color(&args.fg_color)?;
+    let pages = compose_sheets(&items, &layout, dpi, &load_font(args.font.as_deref())?, text_color);
+    let sheet_path = &claim_output_path(sheet_path, args);
+    if args.dry_run {
+        log::info!("✓ Would save {} page(s) with {} codes to: {}", pages.len(), items.len(), sheet_path.display());
+        return Ok(());
+    }
+    save_pages(&pages, layout.page_size(), sheet_path, args, |i| (i + 1).to_string())?;
+    log::info!("{}", tr!("sheet-saved", count = items.len(), path = sheet_path.display().to_string()));
+    Ok(())
+}
+
+fn run_card(args: &Args, card_path: &Path) -> Result<()> {
+    let data = args.data.as_ref().context("Data is required. Use --data or --interactive")?;
+    let dpi = args.dpi.unwrap_or(300);
+    let size = parse_card_size(&args.card_size)?;
+    let two_sided = match args.card_layout.to_lowercase().as_str() {
+        "side" => false,
+        "back" => true,
+        other => anyhow::bail!("Unknown card layout '{}'. Use side or back", other),
+    };
+
+    let background = match parse_color(&args.bg_color)? {
+        color if color[3] == 0 => Rgba([255, 255, 255, 255]),
+        color => color,
+    };
+    let card = CardOptions {
+        size,
+        two_sided,
+        name: args.card_name.clone(),
+        title: args.card_title.clone(),
+        lines: args.card_line.clone(),
+        background,
+        text_color: parse_color(&args.fg_color)?,
+    };
+
+    let mut code_args = args.clone();
+    code_args.size = card.code_size(dpi);
+    let code = render_code(data, &code_args)?;
+
+    let pages = compose_card(&code, &card, dpi, &load_font(args.font.as_deref())?);
+    let card_path = &claim_output_path(card_path, args);
+    if args.dry_run {
+        log::info!("✓ Would save business card ({} page(s)) to: {}", pages.len(), card_path.display());
+        return Ok(());
+    }
+    save_pages(&pages, size, card_path, args, |_| "back".to_string())?;
+    log::info!("{}", tr!("card-saved", path = card_path.display().to_string()));
+    Ok(())
+}
+
+/// Lays out one sample code per dot style (rows) and eye style (columns), each captioned
+const DOT_STYLES: [&str; 4] = ["square", "circle", "rounded", "diamond"];
+
+fn run_styles(args: &Args, sheet_path: &Path) -> Result<()> {
+    const EYE_STYLES: [&str; 3] = ["square", "circle", "frame"];
+    const CELL: u32 = 300;
+    const GAP: u32 = 40;
+    const LABEL: u32 = 36;
+
+    let data = args.data.as_deref().unwrap_or("https://github.com/amirroox/RustQR");
+    let font = load_font(args.font.as_deref())?;
+    let text_color = parse_color(&args.fg_color)?;
+    let label_color = if text_color[3] == 0 { Rgba([0, 0, 0, 255]) } else { text_color };
+
+    let columns = EYE_STYLES.len() as u32;
+    let rows = DOT_STYLES.len() as u32;
+    let width = GAP + columns * (CELL + GAP);
+    let height = GAP + rows * (CELL + LABEL + GAP);
+    let mut sheet: RgbaImage = ImageBuffer::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+
+    for (row, dot_style) in DOT_STYLES.iter().enumerate() {
+        for (column, eye_style) in EYE_STYLES.iter().enumerate() {
+            let mut cell_args = args.clone();
+            cell_args.dot_style = dot_style.to_string();
+            cell_args.eye_style = eye_style.to_string();
+            cell_args.size = CELL;
+            if cell_args.border == 0 {
+                cell_args.border = 2;
+            }
+            let code = render_code(data, &cell_args)?;
+
+            let x = GAP + column as u32 * (CELL + GAP);
+            let y = GAP + row as u32 * (CELL + LABEL + GAP);
+            let offset = (CELL - code.width()) / 2;
+            image::imageops::overlay(&mut sheet, &code, (x + offset) as i64, (y + offset) as i64);
+
+            let label = format!("dot {} · eye {}", dot_style, eye_style);
+            let size = fit_text_size(&font, 22.0, &label, CELL as f32);
+            draw_text(&mut sheet, &font, size, label_color, &label, x as f32, (y + CELL + 8) as f32, CELL as f32, Align::Center);
+        }
+    }
+
+    let options = PngOptions {
+        compression: args.png_compression,
+        interlace: args.png_interlace,
+        palette: false,
+        metadata: Vec::new(),
+        icc_profile: args.color_profile.clone(),
+    };
+    let sheet_path = &claim_output_path(sheet_path, args);
+    if args.dry_run {
+        log::info!("✓ Would save style sheet ({}×{} px) to: {}", sheet.width(), sheet.height(), sheet_path.display());
+        return Ok(());
+    }
+    save_png(&sheet, sheet_path, &options)?;
+    log::info!("✓ Style sheet saved to: {}", sheet_path.display());
+    Ok(())
+}
+
+/// Times each stage of `generate_one` separately, keeping the other options (colors, gradient,
+/// effects, --format) so a user can measure the styles they actually use
+fn run_bench(args: &Args, bench_args: &BenchArgs) -> Result<()> {
+    if bench_args.iterations == 0 {
+        anyhow::bail!("--iterations must be at least 1");
+    }
+    let data = args.data.as_deref().unwrap_or("https://github.com/amirroox/RustQR");
+    let path = std::env::temp_dir().join(format!("rustqr-bench-{}.{}", std::process::id(), args.format));
+    let median = |mut times: Vec<f64>| {
+        times.sort_by(f64::total_cmp);
+        times[times.len() / 2]
+    };
+
+    println!("{} runs each, median milliseconds, writing {}\n", bench_args.iterations, args.format);
+    println!("{:<7} {:<9} {:>9} {:>9} {:>9} {:>9}", "Size", "Style", "Encode", "Render", "Write", "Total");
+    for &size in &bench_args.sizes {
+        for dot_style in DOT_STYLES {
+            let mut run_args = args.clone();
+            run_args.size = size;
+            run_args.dot_style = dot_style.to_string();
+
+            let (mut encode, mut render, mut write) = (Vec::new(), Vec::new(), Vec::new());
+            for _ in 0..bench_args.iterations {
+                let start = std::time::Instant::now();
+                let symbol = build_symbol(data, &run_args)?;
+                encode.push(start.elapsed().as_secs_f64() * 1000.0);
+
+                let start = std::time::Instant::now();
+                let img = generate_qr_image(&symbol, &run_args)?;
+                render.push(start.elapsed().as_secs_f64() * 1000.0);
+
+                let start = std::time::Instant::now();
+                save_output(&symbol, &img, &run_args, &[], &path)?;
+                write.push(start.elapsed().as_secs_f64() * 1000.0);
+            }
+            let (encode, render, write) = (median(encode), median(render), median(write));
+            println!(
+                "{:<7} {:<9} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+                size,
+                dot_style,
+                encode,
+                render,
+                write,
+                encode + render + write
+            );
+        }
+    }
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
+/// Applies --no-clobber and --force to a path that is about to be written.
+/// Without either flag an existing file is overwritten with a warning.
+fn claim_output_path(path: &Path, args: &Args) -> PathBuf {
+    if args.force || !path.exists() || path == Path::new("-") {
+        return path.to_path_buf();
+    }
+    if !args.no_clobber {
+        log::warn!("⚠ Overwriting {}", path.display());
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("some numbered file name is free")
+}
+
+/// `DIR/<hash>.<format>` for `--name-by-hash`: a short SHA-256 of the payload, every option that
+/// changes the file and the contents of the files they name, so options like --force or
+/// --webhook leave the name alone while an edited logo gets a new one
+fn hashed_output_path(args: &Args, data: &str, dir: &Path) -> Result<PathBuf> {
+    use sha2::{Digest, Sha256};
+
+    // Files whose contents end up in the code; --texture, --mockup and --icc-profile also take
+    // built-in names, which are not files
+    const READ_FILES: [&str; 8] = ["logo", "palette", "font", "fg_image", "texture", "style_script", "icc_profile", "mockup"];
+
+    // A JSON object keeps its keys sorted, so the name only changes with the settings
+    let serde_json::Value::Object(mut settings) = serde_json::to_value(args)? else {
+        anyhow::bail!("Settings did not serialize to an object");
+    };
+    settings.retain(|key, _| !RUN_ONLY.contains(&key.as_str()));
+
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update((data.len() as u64).to_be_bytes());
+    hasher.update(data.as_bytes());
+    hasher.update(serde_json::to_vec(&settings)?);
+    let named = READ_FILES.iter().filter_map(|key| settings.get(*key)?.as_str()).chain(args.plugins.iter().filter_map(|path| path.to_str()));
+    for path in named.map(Path::new).filter(|path| path.is_file()) {
+        let contents = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        hasher.update(Sha256::digest(&contents));
+    }
+    let hash: String = hasher.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(dir.join(format!("{}.{}", hash, args.format.to_lowercase())))
+}
+
+/// Renders a code at `args.size` and trims the unused remainder so the
+/// modules fill the image exactly, which keeps it centered when composed
+fn render_code(data: &str, args: &Args) -> Result<RgbaImage> {
+    let mut args = args.clone();
+    args.frame = None;
+    args.caption = None;
+    let args = &args;
+
+    let symbol = build_symbol(data, args)?;
+    let mut image = generate_qr_image(&symbol, args)?.to_rgba8();
+    let (columns, rows) = (symbol.width() as u32 + 2 * args.border, symbol.height() as u32 + 2 * args.border);
+    let scale = args.size / columns.max(rows);
+    Ok(image::imageops::crop(&mut image, 0, 0, scale * columns, scale * rows).to_image())
+}
+
+/// Writes composed pages as one PDF, or as PNG files where pages after the
+/// first get a suffix: sheet.png, sheet-2.png, ...
+fn save_pages(
+    pages: &[RgbaImage],
+    page_size: (f32, f32),
+    path: &Path,
+    args: &Args,
+    suffix: impl Fn(usize) -> String,
+) -> Result<()> {
+    let is_pdf = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+
+    if is_pdf {
+        let pages: Vec<_> = pages.iter().map(|p| DynamicImage::ImageRgba8(p.clone()).to_rgb8()).collect();
+        return save_pdf(&pages, page_size, path);
+    }
+
+    let options = PngOptions {
+        compression: args.png_compression,
+        interlace: args.png_interlace,
+        palette: args.png_palette,
+        metadata: metadata::collect(args, None)?,
+        icc_profile: args.color_profile.clone(),
+    };
+    for (i, page) in pages.iter().enumerate() {
+        let page_path = if i == 0 {
+            path.to_path_buf()
+        } else {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            path.with_file_name(format!("{}-{}.png", stem, suffix(i)))
+        };
+        save_png(page, &page_path, &options)?;
+        if pages.len() > 1 {
+            log::info!("{}", tr!("page-saved", path = page_path.display().to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn save_output(
+    symbol: &Symbol,
+    img: &DynamicImage,
+    args: &Args,
+    metadata: &[(String, String)],
+    output_path: &Path,
+) -> Result<()> {
+    let bytes = encode_output(symbol, img, args, metadata, output_path)?;
+    write_output(output_path, &bytes)
+}
+
+fn write_output(output_path: &Path, bytes: &[u8]) -> Result<()> {
+    // "-" streams the file to stdout, e.g. into a web server or an image viewer
+    if output_path == Path::new("-") {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(bytes).and_then(|_| stdout.flush()).context("Failed to write to stdout")?;
+        return Ok(());
+    }
+    std::fs::write(output_path, bytes).with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// A batch output path as a ZIP entry name: relative, with forward slashes
+fn archive_entry_name(path: &Path) -> Result<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            std::path::Component::CurDir => {}
+            _ => anyhow::bail!("Archive entries must be relative paths without '..', got {}", path.display()),
+        }
+    }
+    if parts.is_empty() {
+        anyhow::bail!("Archive entry name is empty");
+    }
+    Ok(parts.join("/"))
+}
+
+/// Encodes the code in the format `output_path` asks for, warning about options that format drops
+fn encode_output(
+    symbol: &Symbol,
+    img: &DynamicImage,
+    args: &Args,
+    metadata: &[(String, String)],
+    output_path: &Path,
+) -> Result<Vec<u8>> {
+    let format = output_format(args, output_path);
+    if !metadata.is_empty() && !matches!(format.as_str(), "png" | "jpg" | "jpeg" | "svg") {
+        log::warn!("⚠ Metadata is only written to PNG, JPEG and SVG, {} output carries none", format);
+    }
+    if args.color_profile.is_some() && (args.cmyk || !matches!(format.as_str(), "png" | "jpg" | "jpeg" | "tif" | "tiff")) {
+        let kind = if args.cmyk { "CMYK" } else { format.as_str() };
+        log::warn!("⚠ Color profiles are only embedded in RGB PNG, JPEG and TIFF, {} output is untagged", kind);
+    }
+
+    match format.as_str() {
+        "svg" => {
+            if args.frame.is_some() {
+                log::warn!("⚠ Frames are only drawn in raster formats, SVG output has no frame");
+            }
+            if args.texture.is_some() {
+                log::warn!("⚠ Textures are only drawn in raster formats, SVG output has flat modules");
+            }
+            if args.style_script.is_some() {
+                log::warn!("⚠ Style scripts are only applied in raster formats, SVG output ignores --style-script");
+            }
+            if DotPattern::from_str(&args.dot_style, &args.style_plugins).is_ok_and(|pattern| pattern.uses_plugin()) {
+                log::warn!("⚠ Plugin styles are only drawn in raster formats, SVG output uses square modules");
+            }
+        }
+        "epl" | "tspl" if args.rotate.is_some() || args.flip.is_some() => {
+            log::warn!("⚠ Label printers draw the code themselves, --rotate and --flip are ignored");
+        }
+        "stl" | "3mf" if args.rotate.is_some() || args.flip.is_some() || args.logo.is_some() => {
+            log::warn!("⚠ 3D models only carry the module grid, --rotate, --flip and --logo are ignored");
+        }
+        "dxf" if args.rotate.is_some() || args.flip.is_some() || args.logo.is_some() => {
+            log::warn!("⚠ DXF output only carries the module grid, --rotate, --flip and --logo are ignored");
+        }
+        _ => {}
+    }
+
+    render_to_bytes(symbol, img, args, metadata, &format)
+}
+
+/// Encodes the rendered code in memory, so nothing has to go through a temporary file
+fn render_to_bytes(symbol: &Symbol, img: &DynamicImage, args: &Args, metadata: &[(String, String)], format: &str) -> Result<Vec<u8>> {
+    match format {
+        "svg" => Ok(render_svg_string(symbol, args, metadata)?.into_bytes()),
+        "epl" | "tspl" => {
+            let language = LabelLanguage::from_str(format).context("Unknown label language")?;
+            render_label(symbol, &language, &label_options(args))
+        }
+        "stl" | "3mf" => {
+            let model_format = ModelFormat::from_str(format).context("Unknown model format")?;
+            render_model(symbol, &model_format, &model_options(args))
+        }
+        "dxf" => Ok(render_dxf(symbol, args.model_size, args.border)?.into_bytes()),
+        "png" => {
+            let options = PngOptions {
+                compression: args.png_compression,
+                interlace: args.png_interlace,
+                palette: args.png_palette,
+                metadata: metadata.to_vec(),
+                icc_profile: args.color_profile.clone(),
+            };
+            encode_png(&img.to_rgba8(), &options)
+        }
+        "jpg" | "jpeg" => {
+            let flattened = flatten_onto_matte(img, args, "JPEG")?;
+            let xmp = (!metadata.is_empty()).then(|| metadata::xmp_packet(metadata));
+            encode_jpeg(&flattened, args.quality, xmp.as_deref(), args.color_profile.as_ref())
+        }
+        "avif" => encode_avif(&img.to_rgba8(), args.quality),
+        "webp" => {
+            let quality = if args.webp_lossless { None } else { Some(args.quality) };
+            encode_webp(&img.to_rgba8(), quality)
+        }
+        "gif" => {
+            let dither = quantize::Dither::from_str(&args.dither)
+                .ok_or_else(|| anyhow::anyhow!("Unknown dither '{}'. Use none, ordered or floyd-steinberg", args.dither))?;
+            encode_gif(&img.to_rgba8(), dither)
+        }
+        "tif" | "tiff" if args.cmyk => {
+            let flattened = flatten_onto_matte(img, args, "CMYK TIFF")?;
+            encode_cmyk_tiff(&flattened, args.rich_black_inks, args.dpi)
+        }
+        "tif" | "tiff" => encode_tiff(img, args.color_profile.as_ref()),
+        _ => {
+            let image_format = image::ImageFormat::from_extension(format)
+                .with_context(|| format!("Unsupported output format '{}'", format))?;
+            // 32-bit BMPs are shown on black by most viewers, so write plain 24-bit
+            let img = match image_format {
+                image::ImageFormat::Bmp => &DynamicImage::ImageRgb8(flatten_onto_matte(img, args, "BMP")?),
+                _ => img,
+            };
+            let mut bytes = std::io::Cursor::new(Vec::new());
+            img.write_to(&mut bytes, image_format).context("Failed to encode QR code image")?;
+            Ok(bytes.into_inner())
+        }
+    }
+}
+
+/// Composites the image onto `--matte` for formats without transparency, warning when that hides any
+fn flatten_onto_matte(img: &DynamicImage, args: &Args, format: &str) -> Result<image::RgbImage> {
+    let matte = parse_color(&args.matte)?;
+    if matte[3] != 255 {
+        anyhow::bail!("--matte must be an opaque color, {} cannot store transparency", format);
+    }
+    let rgba = img.to_rgba8();
+    if rgba.pixels().any(|p| p[3] != 255) {
+        log::warn!(
+            "⚠ {} has no transparency, transparent areas are filled with {} (change it with --matte)",
+            format, args.matte
+        );
+    }
+    Ok(apply_matte(&rgba, matte))
+}
+
+fn label_options(args: &Args) -> LabelOptions {
+    LabelOptions {
+        width_mm: args.label_width,
+        height_mm: args.label_height,
+        dpi: args.dpi.unwrap_or(203),
+        darkness: args.darkness,
+        border: args.border,
+    }
+}
+
+fn model_options(args: &Args) -> ModelOptions {
+    ModelOptions {
+        size_mm: args.model_size,
+        base_mm: args.model_base,
+        height_mm: args.model_height,
+        eye_height_mm: args.model_eye_height.unwrap_or(args.model_height),
+        border: args.border,
+    }
+}
+
+/// The arguments with the quiet zone `--shape circle` needs: wide enough that the symbol's
+/// corners sit inside the circle with half a module to spare
+fn shaped_args<'a>(symbol: &Symbol, args: &'a Args) -> Cow<'a, Args> {
+    if args.shape != "circle" {
+        return Cow::Borrowed(args);
+    }
+    let side = symbol.width().max(symbol.height()) as f32;
+    let needed = ((side * std::f32::consts::SQRT_2 - side) / 2.0 + 0.5).ceil() as u32;
+    if args.border >= needed {
+        return Cow::Borrowed(args);
+    }
+    log::debug!("Widened the quiet zone to {} modules for --shape circle", needed);
+    Cow::Owned(Args { border: needed, ..args.clone() })
+}
+
+fn generate_qr_image(symbol: &Symbol, args: &Args) -> Result<DynamicImage> {
+    let args = &*shaped_args(symbol, args);
+    let qr_width = symbol.width();
+    let (columns, rows) = (qr_width as u32 + 2 * args.border, symbol.height() as u32 + 2 * args.border);
+    let scale = module_scale(symbol, args)?;
+    // The longer side gets --size, a linear barcode is only as tall as its bars
+    let img_width = if columns >= rows { args.size } else { scale * columns };
+    let img_height = if rows >= columns { args.size } else { scale * rows };
+
+    // Parse colors
+    let bg_color = parse_color(&args.bg_color)?;
+    let fg_color = parse_color(&args.fg_color)?;
+
+    // Check for gradient
+    let gradient_colors = if let Some(ref g) = args.gradient {
+        Some(parse_gradient(g)?)
+    } else {
+        None
+    };
+    let eye_gradient = args.eye_gradient.as_deref().map(parse_gradient).transpose()?;
+    if eye_gradient.is_some_and(|(start, end)| is_light(start) || is_light(end)) {
+        log::warn!("⚠ Light --eye-gradient colors can keep scanners from finding the code, keep both dark");
+    }
+    for (flag, color) in [("--timing-color", &args.timing_color), ("--alignment-color", &args.alignment_color)] {
+        if color.as_deref().map(parse_color).transpose()?.is_some_and(is_light) {
+            log::warn!("⚠ A light {} makes scanners misjudge the module grid, keep it dark", flag);
+        }
+    }
+
+    let shadow = match args.shadow {
+        Some(ref spec) => Some(Shadow::parse(spec, parse_color(&args.shadow_color)?)?),
+        None => None,
+    };
+
+    let stroke = match args.stroke_color {
+        Some(ref color) => Some(Stroke::new(args.stroke_width, parse_color(color)?, &args.stroke_mode)?),
+        None => None,
+    };
+
+    let texture = args.texture.as_deref().map(Texture::load).transpose()?;
+
+    let fill = module_fill(args, symbol, img_width, img_height)?;
+    if let Some(ref fill) = fill {
+        if fill.colors().into_iter().any(is_light) {
+            log::warn!("⚠ Light fill colors read as background to scanners, keep every --fill color dark");
+        }
+        if let (Fill::Image { darkened, .. }, Some(path)) = (fill, &args.fg_image)
+            && *darkened > 0.0
+        {
+            log::warn!("⚠ Darkened {:.0}% of {} so the modules stay dark enough to scan", (darkened * 100.0).max(1.0), path.display());
+        }
+    }
+
+    // Create image; effects need the modules on a clear layer of their own first
+    let bg_gradient = args.bg_gradient.as_deref().map(BgGradient::parse).transpose()?;
+    let border_color = args.border_color.as_deref().map(parse_color).transpose()?;
+    if border_color.is_some() && args.border == 0 {
+        log::warn!("⚠ --border-color needs a quiet zone, add --border");
+    }
+    let layered =
+        shadow.is_some() || stroke.is_some() || texture.is_some() || fill.is_some() || bg_gradient.is_some() || border_color.is_some();
+    let canvas = if layered { Rgba([0, 0, 0, 0]) } else { bg_color };
+    let mut img: RgbaImage = ImageBuffer::from_pixel(img_width, img_height, canvas);
+
+    // Parse styles
+    let dot_pattern = DotPattern::from_str(&args.dot_style, &args.style_plugins)?;
+    let eye_style = EyeStyle::from_str(&args.eye_style, &args.style_plugins);
+
+    let mut script = args.style_script.as_deref().map(StyleScript::load).transpose()?;
+
+    let timing = PatternLook::from_args(args, Pattern::Timing)?;
+    let alignment = PatternLook::from_args(args, Pattern::Alignment)?;
+    let look = |x, y| match symbol.pattern_at(x, y) {
+        Some(Pattern::Timing) => Some(&timing),
+        Some(Pattern::Alignment) => Some(&alignment),
+        None => None,
+    };
+
+    // Dots shrink around the cell's center, finder patterns and restyled patterns stay whole
+    let dot = ((scale as f32 * args.dot_scale).round() as u32).clamp(1, scale);
+    let inset = (scale - dot) / 2;
+
+    // Draw QR code with styles
+    for y in 0..symbol.height() {
+        for x in 0..qr_width {
+            let custom = match script {
+                Some(ref mut script) => script.style(symbol, x, y)?,
+                None => ModuleStyle::default(),
+            };
+            if custom.draw.unwrap_or_else(|| symbol.is_dark(x, y)) {
+                let look = look(x, y);
+                let color = if let Some(ref color) = custom.color {
+                    parse_color(color)?
+                } else if let Some(color) = look.and_then(|look| look.color) {
+                    color
+                } else if let Some(color) = eye_gradient.as_ref().and_then(|grad| eye_gradient_color(symbol, grad, x, y)) {
+                    color
+                } else if let Some(ref grad) = gradient_colors {
+                    interpolate_gradient(grad, x as f32 / qr_width as f32)
+                } else {
+                    fg_color
+                };
+
+                // Check if in eye area
+                let in_eye = symbol.in_eye(x, y);
+
+                let px = (x as u32 + args.border) * scale;
+                let py = (y as u32 + args.border) * scale;
+
+                let restyled = look.and_then(|look| look.style.as_ref());
+                let (px, py, size) = if in_eye || restyled.is_some() { (px, py, scale) } else { (px + inset, py + inset, dot) };
+
+                if let Some(ref shape) = custom.shape {
+                    apply_dot_style(&mut img, px, py, size, color, &DotStyle::from_str(shape, &args.style_plugins));
+                } else if in_eye {
+                    apply_eye_style(&mut img, px, py, scale, color, &eye_style);
+                } else if let Some(style) = restyled {
+                    apply_dot_style(&mut img, px, py, scale, color, style.pick(x, y, args.seed.unwrap_or(0)));
+                } else {
+                    let dot_style = dot_pattern.pick(x, y, args.seed.unwrap_or(0));
+                    apply_dot_style(&mut img, px, py, size, color, dot_style);
+                }
+            }
+        }
+    }
+
+    if let Some(ref fill) = fill {
+        // Finder patterns with their own gradient keep it, timing and alignment modules their own color
+        let crop = |x: usize, y: usize, side: usize| {
+            let (px, py) = ((x as u32 + args.border) * scale, (y as u32 + args.border) * scale);
+            (px, py, image::imageops::crop_imm(&img, px, py, side as u32 * scale, side as u32 * scale).to_image())
+        };
+        let mut kept: Vec<_> = match eye_gradient {
+            Some(_) => symbol.eyes().iter().map(|&(ex, ey, side)| crop(ex, ey, side)).collect(),
+            None => Vec::new(),
+        };
+        for &(_, px, py, width, height) in symbol.patterns() {
+            for y in py..py + height {
+                for x in px..px + width {
+                    if look(x, y).is_some_and(|look| look.color.is_some()) {
+                        kept.push(crop(x, y, 1));
+                    }
+                }
+            }
+        }
+        fill.apply(&mut img, scale);
+        for (px, py, cells) in kept {
+            image::imageops::replace(&mut img, &cells, px as i64, py as i64);
+        }
+    }
+    if let Some(ref texture) = texture {
+        apply_texture(&mut img, texture, args.seed.unwrap_or(0));
+    }
+    if let Some(ref stroke) = stroke {
+        apply_stroke(&mut img, stroke, scale);
+    }
+    let background = || {
+        let mut background = match bg_gradient {
+            Some(ref gradient) => gradient.render(img_width, img_height),
+            None => ImageBuffer::from_pixel(img_width, img_height, bg_color),
+        };
+        // The quiet zone is everything outside the code area, including any remainder of --size
+        if let Some(color) = border_color {
+            let (left, top) = (args.border * scale, args.border * scale);
+            let (right, bottom) = (left + qr_width as u32 * scale, top + symbol.height() as u32 * scale);
+            for (x, y, pixel) in background.enumerate_pixels_mut() {
+                if x < left || x >= right || y < top || y >= bottom {
+                    *pixel = color;
+                }
+            }
+        }
+        background
+    };
+    if let Some(ref shadow) = shadow {
+        img = cast_shadow(&img, shadow, background());
+    } else if layered {
+        let mut background = background();
+        image::imageops::overlay(&mut background, &img, 0, 0);
+        img = background;
+    }
+
+    // Add logo if provided
+    if let Some(ref logo_path) = args.logo {
+        let source = image::open(logo_path).context("Failed to open logo file")?.to_rgba8();
+        let PlacedLogo { image: logo, x, y, plate, size } = place_logo(symbol, args, &source, |logo, x, y| {
+            match args.logo_plate.as_str() {
+                "on" => true,
+                "off" => false,
+                _ => logo_blends_in(&img, logo, x, y, bg_color),
+            }
+        })?;
+        if size < args.logo_size.clamp(0.1, 0.4) {
+            log::warn!("⚠ Shrank the logo to --logo-size {:.2} so it leaves the code's patterns and enough error correction intact", size);
+        }
+        if let Some(pad) = plate {
+            let color = match args.logo_plate_color {
+                Some(ref color) => parse_color(color)?,
+                None if bg_color[3] == 255 => bg_color,
+                None => Rgba([255, 255, 255, 255]),
+            };
+            let (width, height) = (logo.width() + 2 * pad, logo.height() + 2 * pad);
+            let (left, top) = ((x - pad) as f32, (y - pad) as f32);
+            fill_rounded_rect(&mut img, left, top, width as f32, height as f32, pad as f32, color);
+            log::info!("{}", tr!("logo-plate", modules = width.div_ceil(scale) * height.div_ceil(scale)));
+        }
+        image::imageops::overlay(&mut img, &logo, x as i64, y as i64);
+        if symbol.qr().is_none() {
+            log::warn!("⚠ Logos rely on QR error correction levels, check that the code still scans");
+        }
+    }
+
+    // Round badges drop everything outside the circle, the quiet zone keeps the symbol inside it
+    if args.shape == "circle" {
+        clip_to_circle(&mut img, scale * columns);
+    }
+
+    // Decorations lay out around the code itself, so trim the unused remainder first
+    if args.frame.is_some() || args.caption.is_some() {
+        img = image::imageops::crop(&mut img, 0, 0, scale * columns, scale * rows).to_image();
+    }
+
+    // Wrap in a call-to-action frame
+    if let Some(ref style) = args.frame {
+        let style = FrameStyle::from_str(style)
+            .with_context(|| format!("Unknown frame style '{}'. Use border, banner or bubble", style))?;
+        let frame = FrameOptions {
+            style,
+            position: FramePosition::from_str(&args.frame_position),
+            text: args.frame_text.clone(),
+            color: parse_color(args.frame_color.as_deref().unwrap_or(&args.fg_color))?,
+            text_color: parse_color(&args.frame_text_color)?,
+            background: bg_color,
+            font_size: args.font_size,
+        };
+        img = apply_frame(&img, &frame, &load_font(args.font.as_deref())?);
+    }
+
+    // Caption line beneath everything else
+    if let Some(ref caption) = args.caption {
+        let color = parse_color(args.caption_color.as_deref().unwrap_or(&args.fg_color))?;
+        let align = Align::from_str(&args.caption_align);
+        let font = load_font(args.font.as_deref())?;
+        img = add_caption(&img, caption, &font, caption_size(args), color, align, bg_color);
+    }
+
+    let outer_border = OuterBorder::from_args(args)?;
+    if let Some(ref border) = outer_border {
+        img = border.apply(&img);
+    }
+    if let Some(canvas) = Canvas::from_args(args)? {
+        let ring = outer_border.as_ref().map_or(0, |border| border.width);
+        if args.frame.is_none() && canvas.corner_inset() > (canvas.padding + ring + args.border * scale) as f32 {
+            log::warn!("⚠ --canvas-radius cuts into the corners of the code, add --canvas-padding or --border");
+        }
+        img = canvas.apply(&img);
+    }
+
+    let img = Orientation::from_args(args)?.apply(img);
+    if let Some(ref spec) = args.mockup {
+        // Scenes are twice as wide as the code so it keeps its resolution at an angle
+        let img = mockup::render(&img, &Mockup::from_str(spec)?, args.size * 2)?;
+        return Ok(DynamicImage::ImageRgba8(img));
+    }
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+/// Rotation and mirroring applied to the finished image
+struct Orientation {
+    /// Clockwise quarter turns
+    turns: u8,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+}
+
+impl Orientation {
+    fn from_args(args: &Args) -> Result<Self> {
+        let turns = match args.rotate.unwrap_or(0) {
+            0 => 0,
+            90 => 1,
+            180 => 2,
+            270 => 3,
+            other => anyhow::bail!("--rotate must be 90, 180 or 270, got {}", other),
+        };
+        let (flip_horizontal, flip_vertical) = match args.flip.as_deref().map(str::to_lowercase).as_deref() {
+            None => (false, false),
+            Some("h" | "horizontal") => (true, false),
+            Some("v" | "vertical") => (false, true),
+            Some(other) => anyhow::bail!("--flip must be h or v, got '{}'", other),
+        };
+        Ok(Orientation { turns, flip_horizontal, flip_vertical })
+    }
+
+    /// Rotates first, then mirrors along the axes of the rotated image
+    fn apply(&self, img: RgbaImage) -> RgbaImage {
+        use image::imageops::{flip_horizontal, flip_vertical, rotate90, rotate180, rotate270};
+        let img = match self.turns {
+            1 => rotate90(&img),
+            2 => rotate180(&img),
+            3 => rotate270(&img),
+            _ => img,
+        };
+        if self.flip_horizontal {
+            flip_horizontal(&img)
+        } else if self.flip_vertical {
+            flip_vertical(&img)
+        } else {
+            img
+        }
+    }
+
+    /// The same as an SVG transform for content of the given size, and whether width and height swap
+    fn svg_transform(&self, width: f32, height: f32) -> (String, bool) {
+        let swapped = self.turns % 2 == 1;
+        let (out_width, out_height) = if swapped { (height, width) } else { (width, height) };
+        // Transform lists apply right to left, so the mirror is written first
+        let mut transform = String::new();
+        if self.flip_horizontal {
+            transform.push_str(&format!("translate({} 0) scale(-1 1) ", out_width));
+        } else if self.flip_vertical {
+            transform.push_str(&format!("translate(0 {}) scale(1 -1) ", out_height));
+        }
+        match self.turns {
+            1 => transform.push_str(&format!("translate({} 0) rotate(90)", height)),
+            2 => transform.push_str(&format!("translate({} {}) rotate(180)", width, height)),
+            3 => transform.push_str(&format!("translate(0 {}) rotate(270)", width)),
+            _ => {}
+        }
+        (transform.trim_end().to_string(), swapped)
+    }
+}
+
+fn caption_size(args: &Args) -> f32 {
+    args.caption_size
+        .or(args.font_size)
+        .unwrap_or(args.size as f32 * 0.08)
+}
+
+fn parse_color(hex: &str) -> Result<Rgba<u8>> {
+    if hex.to_lowercase() == "transparent" {
+        return Ok(Rgba([0, 0, 0, 0])); // Fully transparent
+    }
+
+    let color = csscolorparser::parse(hex)
+        .context("Invalid color format")?;
+    Ok(Rgba([
+        (color.r * 255.0) as u8,
+        (color.g * 255.0) as u8,
+        (color.b * 255.0) as u8,
+        (color.a * 255.0) as u8,
+    ]))
+}
+
+/// The --fill, --fg-image or continuous --gradient paint for dark modules on a canvas of width x
+/// height pixels; per-module gradients are colored module by module instead
+fn module_fill(args: &Args, symbol: &Symbol, width: u32, height: u32) -> Result<Option<Fill>> {
+    if let Some(ref path) = args.fg_image {
+        let tile = args.fg_image_mode == "tile";
+        return Ok(Some(Fill::image(path, tile, width, height, parse_color(&args.fg_color)?)?));
+    }
+    if let Some(ref gradient) = args.gradient
+        && args.gradient_mapping == "continuous"
+    {
+        return Ok(Some(Fill::Gradient { colors: parse_gradient(gradient)?, border: args.border, modules: symbol.width() as u32 }));
+    }
+    args.fill.as_deref().map(Fill::parse).transpose()
+}
+
+/// Whether a module color is light enough for scanners to take it for background
+fn is_light(Rgba([r, g, b, _]): Rgba<u8>) -> bool {
+    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32 > 128.0
+}
+
+/// The --eye-gradient color of module (x, y) when it lies in a finder pattern
+/// Dot style and color given to the timing lines or the alignment patterns, each falling back to
+/// the rest of the code's when not set
+struct PatternLook {
+    style: Option<DotPattern>,
+    color: Option<Rgba<u8>>,
+}
+
+impl PatternLook {
+    fn from_args(args: &Args, pattern: Pattern) -> Result<Self> {
+        let (style, color, flag) = match pattern {
+            Pattern::Timing => (&args.timing_style, &args.timing_color, "--timing-style"),
+            Pattern::Alignment => (&args.alignment_style, &args.alignment_color, "--alignment-style"),
+        };
+        Ok(PatternLook {
+            style: style
+                .as_deref()
+                .map(|style| DotPattern::parse(style, &args.style_plugins).with_context(|| format!("Invalid {}", flag)))
+                .transpose()?,
+            color: color.as_deref().map(parse_color).transpose()?,
+        })
+    }
+}
+
+fn eye_gradient_color(symbol: &Symbol, gradient: &(Rgba<u8>, Rgba<u8>), x: usize, y: usize) -> Option<Rgba<u8>> {
+    let (ex, _, side) = symbol.eye_at(x, y)?;
+    Some(interpolate_gradient(gradient, (x - ex) as f32 / side.saturating_sub(1).max(1) as f32))
+}
+
+fn interpolate_gradient(colors: &(Rgba<u8>, Rgba<u8>), t: f32) -> Rgba<u8> {
+    let (c1, c2) = colors;
+    Rgba([
+        (c1[0] as f32 + (c2[0] as f32 - c1[0] as f32) * t) as u8,
+        (c1[1] as f32 + (c2[1] as f32 - c1[1] as f32) * t) as u8,
+        (c1[2] as f32 + (c2[2] as f32 - c1[2] as f32) * t) as u8,
+        255,
+    ])
+}
+
+/// Scales the logo to `size_ratio` of the image's shorter side
+fn scale_logo(logo: &RgbaImage, img_size: u32, size_ratio: f32) -> RgbaImage {
+    let max_logo_size = (img_size as f32 * size_ratio.clamp(0.1, 0.4)) as u32;
+
+    // Calculate new dimensions while preserving aspect ratio
+    let logo_width = logo.width();
+    let logo_height = logo.height();
+
+    let (new_width, new_height) = if logo_width > logo_height {
+        // Landscape or square - fit width
+        let new_width = max_logo_size;
+        let new_height = (logo_height as f32 * (max_logo_size as f32 / logo_width as f32)) as u32;
+        (new_width, new_height)
+    } else {
+        // Portrait - fit height
+        let new_height = max_logo_size;
+        let new_width = (logo_width as f32 * (max_logo_size as f32 / logo_height as f32)) as u32;
+        (new_width, new_height)
+    };
+
+    image::imageops::resize(
+        logo,
+        new_width,
+        new_height,
+        image::imageops::FilterType::Lanczos3
+    )
+}
+
+/// A logo scaled and centered on the code image
+struct PlacedLogo {
+    image: RgbaImage,
+    x: u32,
+    y: u32,
+    /// Padding of the plate behind it, if it gets one
+    plate: Option<u32>,
+    /// The --logo-size it ended up with
+    size: f32,
+}
+
+/// Scales the logo to --logo-size and centers it, shrinking it until the modules it hides (plate
+/// included) spare the finder, timing and outer alignment patterns and leave every block a quarter
+/// of its error correction. `plate` decides from the scaled logo and its position whether it needs one.
+fn place_logo(symbol: &Symbol, args: &Args, source: &RgbaImage, plate: impl Fn(&RgbaImage, u32, u32) -> bool) -> Result<PlacedLogo> {
+    // The same layout as generate_qr_image; any remainder of --size lies right of and below the code
+    let (columns, rows) = (symbol.width() as u32 + 2 * args.border, symbol.height() as u32 + 2 * args.border);
+    let scale = module_scale(symbol, args)?;
+    let side = if columns == rows { args.size } else { scale * columns.min(rows) };
+    let (width, height) = (scale * columns, scale * rows);
+
+    let mut size = args.logo_size.clamp(0.1, 0.4);
+    loop {
+        let image = scale_logo(source, side, size);
+        let (x, y) = ((width - image.width()) / 2, (height - image.height()) / 2);
+        // Half a module of padding keeps module edges from touching the logo
+        let pad = plate(&image, x, y).then(|| (scale / 2).max(2).min(x).min(y));
+        let placed = PlacedLogo { image, x, y, plate: pad, size };
+        let Some(qr) = symbol.qr() else { return Ok(placed) };
+        match check_logo_footprint(qr, args.border, scale, &placed) {
+            Ok(()) => return Ok(placed),
+            Err(_) if size > 0.1 => size = (size - 0.02).max(0.1),
+            Err(e) => {
+                let hint = if qr.error_correction_level() == EcLevel::H { "" } else { " or use --error H" };
+                return Err(e.context(format!("The logo hides too much of the code even at --logo-size 0.1, raise --min-version{}", hint)));
+            }
+        }
+    }
+}
+
+/// Checks the modules under the logo the way --reserve checks its region
+fn check_logo_footprint(qr: &QrCode, border: u32, scale: u32, logo: &PlacedLogo) -> Result<()> {
+    let pad = logo.plate.unwrap_or(0);
+    let size = qr.width() as u32;
+    let first = |px: u32| (px / scale).saturating_sub(border).min(size) as usize;
+    let end = |px: u32| px.div_ceil(scale).saturating_sub(border).min(size) as usize;
+    let (left, top) = (first(logo.x - pad), first(logo.y - pad));
+    let (right, bottom) = (end(logo.x + logo.image.width() + pad), end(logo.y + logo.image.height() + pad));
+    if right <= left || bottom <= top {
+        return Ok(());
+    }
+    let region = reserve::Region { width: right - left, height: bottom - top, position: Some((left, top)) };
+    let reservation = reserve::check(qr, &region)?;
+    if reserve::covers_outer_alignment(qr, &reservation) {
+        anyhow::bail!("The logo covers alignment patterns away from the middle of the code");
+    }
+    Ok(())
+}
+
+/// Whether more than a fifth of the logo's outline over modules is hard to tell apart from
+/// them, so readers would take the logo's edge for part of the code
+fn logo_blends_in(img: &RgbaImage, logo: &RgbaImage, x: u32, y: u32, background: Rgba<u8>) -> bool {
+    let opaque = |lx: i64, ly: i64| {
+        lx >= 0 && ly >= 0 && lx < logo.width() as i64 && ly < logo.height() as i64 && logo.get_pixel(lx as u32, ly as u32)[3] >= 128
+    };
+    let (mut over_modules, mut blending) = (0u32, 0u32);
+    for (lx, ly, pixel) in logo.enumerate_pixels() {
+        let (cx, cy) = (lx as i64, ly as i64);
+        // Only the outline meets the modules, the inside is the logo's own business
+        let outline = opaque(cx, cy) && [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| !opaque(cx + dx, cy + dy));
+        let under = img.get_pixel(x + lx, y + ly);
+        // Light gaps between modules (transparent with the default background) show any logo fine
+        let light = under[3] < 128 || (background[3] >= 128 && contrast_ratio(*under, background) < 1.5);
+        if !outline || light {
+            continue;
+        }
+        over_modules += 1;
+        if contrast_ratio(*pixel, *under) < 2.0 {
+            blending += 1;
+        }
+    }
+    blending * 5 > over_modules && over_modules > 0
+}
+
+/// WCAG contrast ratio of two colors, from 1 (identical) to 21 (black on white)
+fn contrast_ratio(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    let luminance = |c: Rgba<u8>| {
+        let channel = |v: u8| {
+            let v = v as f32 / 255.0;
+            if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+        };
+        0.2126 * channel(c[0]) + 0.7152 * channel(c[1]) + 0.0722 * channel(c[2])
+    };
+    let (la, lb) = (luminance(a), luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Tells where artwork can go: the module rectangle and, on the undecorated image, its pixels
+fn report_reservation(symbol: &Symbol, reservation: &Reservation, args: &Args) {
+    let Reservation { x, y, width, height, .. } = *reservation;
+    log::info!(
+        "✓ Modules x {}-{}, y {}-{} ({}x{}) are blank and safe to draw over, {}",
+        x,
+        x + width - 1,
+        y,
+        y + height - 1,
+        width,
+        height,
+        symbol.describe()
+    );
+    log::info!(
+        "  The worst block loses {} of the {} codewords it can correct",
+        reservation.damaged, reservation.correctable
+    );
+    let decorated = args.frame.is_some()
+        || args.caption.is_some()
+        || args.canvas_padding > 0
+        || args.outer_border.is_some()
+        || args.rotate.is_some()
+        || args.flip.is_some()
+        || args.mockup.is_some()
+        || args.shape == "circle";
+    if !decorated {
+        let side = symbol.width() as u32 + 2 * args.border;
+        let scale = args.size / side;
+        let (left, top) = ((x as u32 + args.border) * scale, (y as u32 + args.border) * scale);
+        log::info!(
+            "  In the image: pixels {},{} to {},{} ({}x{} px)",
+            left,
+            top,
+            left + width as u32 * scale,
+            top + height as u32 * scale,
+            width as u32 * scale,
+            height as u32 * scale
+        );
+    }
+    if reservation.covers_alignment {
+        log::warn!("⚠ The region covers an alignment pattern, readers cope but may struggle with curved or tilted prints");
+    }
+}
+
+fn print_qr_terminal(symbol: &Symbol) {
+    // Bars only need a few rows and single-width cells to fit the terminal
+    let linear = symbol.symbology().is_linear();
+    let rows = if linear { symbol.height().min(6) } else { symbol.height() };
+    println!("\nQR Code:");
+    for y in 0..rows {
+        for x in 0..symbol.width() {
+            let c = match (symbol.is_dark(x, y), linear) {
+                (true, true) => "█",
+                (false, true) => " ",
+                (true, false) => "██",
+                (false, false) => "  ",
+            };
+            print!("{}", c);
+        }
+        println!();
+    }
+    println!();
+}
+
+/// The code as an SVG document, the vector counterpart of `generate_qr_image`
+fn render_svg_string(symbol: &Symbol, args: &Args, metadata: &[(String, String)]) -> Result<String> {
+    let args = &*shaped_args(symbol, args);
+    let qr_width = symbol.width();
+    let scale = 10; // SVG units per module
+    let _border = args.border * scale;
+    let svg_size = (qr_width as u32 + 2 * args.border) * scale;
+    let code_height = (symbol.height() as u32 + 2 * args.border) * scale;
+
+    // Caption band below the code, converted from pixels to SVG units
+    let units_per_px = svg_size as f32 / args.size as f32;
+    let caption_band = if args.caption.is_some() { caption_size(args) * 1.6 * units_per_px } else { 0.0 };
+    let svg_height = code_height as f32 + caption_band;
+    let px_height = args.size as f32 * code_height as f32 / svg_size as f32 + caption_band / units_per_px;
+
+    // Parse colors for SVG
+    let bg_color = if args.bg_color.to_lowercase() == "transparent" {
+        "none".to_string()
+    } else {
+        args.bg_color.clone()
+    };
+
+    let fg_color = &args.fg_color;
+
+    let (transform, swapped) = Orientation::from_args(args)?.svg_transform(svg_size as f32, svg_height.round());
+    let (mut view_box, mut px_size) = ((svg_size as f32, svg_height.round()), (args.size as f32, px_height.round()));
+    if swapped {
+        view_box = (view_box.1, view_box.0);
+        px_size = (px_size.1, px_size.0);
+    }
+    let canvas = Canvas::from_args(args)?;
+    let outer_border = OuterBorder::from_args(args)?;
+    let ring = outer_border.as_ref().map_or(0, |border| border.width);
+    let pad = canvas.as_ref().map_or(0, |canvas| canvas.padding) + ring;
+
+    let mut svg = String::new();
+    svg.push_str(&format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" version="1.1" viewBox="0 0 {} {}" width="{}" height="{}">
+"#,
+        view_box.0 + 2.0 * pad as f32 * units_per_px,
+        view_box.1 + 2.0 * pad as f32 * units_per_px,
+        px_size.0 + 2.0 * pad as f32,
+        px_size.1 + 2.0 * pad as f32
+    ));
+
+    // Metadata: a title element for viewers, the full set as XMP
+    if !metadata.is_empty() {
+        if let Some((_, title)) = metadata.iter().find(|(key, _)| key == "Title") {
+            svg.push_str(&format!("  <title>{}</title>\n", xml_escape(title)));
+        }
+        svg.push_str(&format!("  <metadata>\n{}\n  </metadata>\n", metadata::xmp_packet(metadata)));
+    }
+
+    // The card: clipped to its rounded corners, with the code moved in by the padding
+    if let Some(ref canvas) = canvas {
+        let (width, height) = (view_box.0 + 2.0 * pad as f32 * units_per_px, view_box.1 + 2.0 * pad as f32 * units_per_px);
+        svg.push_str(&format!(
+            r#"  <defs>
+    <clipPath id="qrCanvas"><rect width="{}" height="{}" rx="{}"/></clipPath>
+  </defs>
+  <g clip-path="url(#qrCanvas)">
+  <rect width="{}" height="{}" fill="{}"/>
+  <g transform="translate({p},{p})">
+"#,
+            width,
+            height,
+            canvas.radius as f32 * units_per_px,
+            width,
+            height,
+            fill::svg_color(canvas.color),
+            p = canvas.padding as f32 * units_per_px
+        ));
+    }
+
+    // Outer border around the output, with the code moved in by its width
+    if let Some(ref border) = outer_border {
+        let w = ring as f32 * units_per_px;
+        let (width, height) = (view_box.0 + 2.0 * w, view_box.1 + 2.0 * w);
+        if border.background[3] > 0 {
+            svg.push_str(&format!("  <rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", width, height, fill::svg_color(border.background)));
+        }
+        svg.push_str(&border.svg(width, height, w));
+        svg.push_str(&format!("  <g transform=\"translate({w},{w})\">\n"));
+    }
+
+    // Background, a disc for round badges
+    let background = |fill: &str| match args.shape.as_str() {
+        "circle" => format!("  <circle cx=\"{r}\" cy=\"{r}\" r=\"{r}\" fill=\"{}\"/>\n", fill, r = svg_size as f32 / 2.0),
+        _ => format!("  <rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", view_box.0, view_box.1, fill),
+    };
+    if let Some(ref gradient) = args.bg_gradient {
+        svg.push_str(&BgGradient::parse(gradient)?.svg_defs("qrBackground", view_box.0, view_box.1));
+        svg.push_str(&background("url(#qrBackground)"));
+    } else if bg_color != "none" {
+        svg.push_str(&background(&bg_color));
+    }
+
+    // Rotation and mirroring wrap everything drawn on top of the background
+    if !transform.is_empty() {
+        svg.push_str(&format!("  <g transform=\"{}\">\n", transform));
+    }
+
+    // Quiet zone ring: the code's outline with the module area cut out
+    if let Some(ref color) = args.border_color
+        && args.shape == "circle"
+    {
+        let (inner, outer, r) = (args.border * scale, svg_size - args.border * scale, svg_size as f32 / 2.0);
+        svg.push_str(&format!(
+            r#"  <path d="M0 {r}A{r} {r} 0 1 0 {d} {r}A{r} {r} 0 1 0 0 {r}Z M{i} {i}V{o}H{o}V{i}Z" fill-rule="evenodd" fill="{}"/>
+"#,
+            color,
+            d = svg_size,
+            i = inner,
+            o = outer
+        ));
+    } else if let Some(ref color) = args.border_color {
+        let (inner, right, bottom) = (args.border * scale, svg_size - args.border * scale, code_height - args.border * scale);
+        svg.push_str(&format!(
+            r#"  <path d="M0 0H{}V{}H0Z M{i} {i}V{}H{}V{i}Z" fill-rule="evenodd" fill="{}"/>
+"#,
+            svg_size,
+            code_height,
+            bottom,
+            right,
+            color,
+            i = inner
+        ));
+    }
+
+    // Pattern fills and continuous gradients are defined once and referenced by every module
+    let fill = module_fill(args, symbol, args.size, (args.size as f32 * code_height as f32 / svg_size as f32).round() as u32)?;
+    if let Some(ref fill) = fill
+        && let Some(defs) = fill.svg_defs("qrFill", scale, (svg_size as f32, code_height as f32))?
+    {
+        svg.push_str(&defs);
+    }
+
+    // Drop shadow as an SVG 1.1 filter chain, in SVG units
+    if let Some(ref spec) = args.shadow {
+        let shadow = Shadow::parse(spec, parse_color(&args.shadow_color)?)?;
+        let Rgba([r, g, b, a]) = shadow.color;
+        svg.push_str(&format!(
+            r#"  <defs>
+    <filter id="qrShadow" x="-20%" y="-20%" width="140%" height="140%">
+      <feGaussianBlur in="SourceAlpha" stdDeviation="{:.2}"/>
+      <feOffset dx="{:.2}" dy="{:.2}" result="offsetBlur"/>
+      <feFlood flood-color="rgb({},{},{})" flood-opacity="{:.3}"/>
+      <feComposite in2="offsetBlur" operator="in"/>
+      <feMerge><feMergeNode/><feMergeNode in="SourceGraphic"/></feMerge>
+    </filter>
+  </defs>
+  <g filter="url(#qrShadow)">
+"#,
+            shadow.sigma() * units_per_px,
+            shadow.dx as f32 * units_per_px,
+            shadow.dy as f32 * units_per_px,
+            r,
+            g,
+            b,
+            a as f32 / 255.0
+        ));
+    }
+
+    // Inner outline: the shape minus its eroded self, filled with the stroke color
+    let stroke = match args.stroke_color {
+        Some(ref color) => Some(Stroke::new(args.stroke_width, parse_color(color)?, &args.stroke_mode)?),
+        None => None,
+    };
+    if let Some(ref stroke) = stroke {
+        let Rgba([r, g, b, a]) = stroke.color;
+        svg.push_str(&format!(
+            r#"  <defs>
+    <filter id="qrStroke">
+      <feMorphology in="SourceAlpha" operator="erode" radius="{:.2}" result="inner"/>
+      <feComposite in="SourceAlpha" in2="inner" operator="out" result="edge"/>
+      <feFlood flood-color="rgb({},{},{})" flood-opacity="{:.3}"/>
+      <feComposite in2="edge" operator="in" result="outline"/>
+      <feMerge><feMergeNode in="SourceGraphic"/><feMergeNode in="outline"/></feMerge>
+    </filter>
+  </defs>
+"#,
+            stroke.width as f32 * units_per_px,
+            r,
+            g,
+            b,
+            a as f32 / 255.0
+        ));
+        if !stroke.per_module {
+            svg.push_str("  <g filter=\"url(#qrStroke)\">\n");
+        }
+    }
+
+    // QR modules
+    let filter_attr = if stroke.as_ref().is_some_and(|stroke| stroke.per_module) { r#" filter="url(#qrStroke)""# } else { "" };
+    let stepped = match args.gradient {
+        Some(ref gradient) if fill.is_none() => Some(parse_gradient(gradient)?),
+        _ => None,
+    };
+    let eye_gradient = args.eye_gradient.as_deref().map(parse_gradient).transpose()?;
+    // Per-module gradients step like the raster output, one flat color per column of modules
+    let timing = PatternLook::from_args(args, Pattern::Timing)?;
+    let alignment = PatternLook::from_args(args, Pattern::Alignment)?;
+    let look = |x, y| match symbol.pattern_at(x, y) {
+        Some(Pattern::Timing) => Some(&timing),
+        Some(Pattern::Alignment) => Some(&alignment),
+        None => None,
+    };
+    let paint = |x: usize, y: usize| {
+        if let Some(color) = look(x, y).and_then(|look| look.color) {
+            return fill::svg_color(color);
+        }
+        if let Some(color) = eye_gradient.as_ref().and_then(|grad| eye_gradient_color(symbol, grad, x, y)) {
+            return fill::svg_color(color);
+        }
+        match (&fill, &stepped) {
+            (Some(fill), _) => fill.svg_paint("qrFill"),
+            (None, Some(gradient)) => fill::svg_color(interpolate_gradient(gradient, x as f32 / qr_width as f32)),
+            (None, None) => fg_color.to_string(),
+        }
+    };
+    let dot_pattern = DotPattern::from_str(&args.dot_style, &args.style_plugins)?;
+    // Same shrinking as the raster output, in whole SVG units so insets stay at halves
+    let dot = ((scale as f32 * args.dot_scale).round() as u32).clamp(1, scale);
+
+    for y in 0..symbol.height() {
+        for x in 0..qr_width {
+            if symbol.is_dark(x, y) {
+                let restyled = look(x, y).and_then(|look| look.style.as_ref());
+                let size = if symbol.in_eye(x, y) || restyled.is_some() { scale } else { dot };
+                let inset = (scale - size) as f32 / 2.0;
+                let px = ((x as u32 + args.border) * scale) as f32 + inset;
+                let py = ((y as u32 + args.border) * scale) as f32 + inset;
+                let fill_attr = format!(r#"fill="{}"{}"#, paint(x, y), filter_attr);
+
+                match restyled.unwrap_or(&dot_pattern).pick(x, y, args.seed.unwrap_or(0)) {
+                    DotStyle::Circle => {
+                        let r = size as f32 / 2.0;
+                        svg.push_str(&format!(
+                            r#"  <circle cx="{}" cy="{}" r="{}" {}/>
+"#,
+                            px + r, py + r, r, fill_attr
+                        ));
+                    }
+                    DotStyle::Rounded => {
+                        let rx = size / 3;
+                        svg.push_str(&format!(
+                            r#"  <rect x="{}" y="{}" width="{}" height="{}" rx="{}" {}/>
+"#,
+                            px, py, size, size, rx, fill_attr
+                        ));
+                    }
+                    DotStyle::Diamond => {
+                        let (half, size) = (size as f32 / 2.0, size as f32);
+                        svg.push_str(&format!(
+                            r#"  <polygon points="{},{} {},{} {},{} {},{}" {}/>
+"#,
+                            px + half, py, px + size, py + half, px + half, py + size, px, py + half, fill_attr
+                        ));
+                    }
+                    // Plugin shapes are raster only, SVG falls back to squares
+                    DotStyle::Square | DotStyle::Plugin(_) => {
+                        svg.push_str(&format!(
+                            r#"  <rect x="{}" y="{}" width="{}" height="{}" {}/>
+"#,
+                            px, py, size, size, fill_attr
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    if stroke.as_ref().is_some_and(|stroke| !stroke.per_module) {
+        svg.push_str("  </g>\n");
+    }
+    if args.shadow.is_some() {
+        svg.push_str("  </g>\n");
+    }
+
+    if let Some(ref caption) = args.caption {
+        // Embed a custom font so the caption renders the same without it installed
+        let family = if let Some(ref font_path) = args.font {
+            let bytes = std::fs::read(font_path)
+                .with_context(|| format!("Failed to read font file {}", font_path.display()))?;
+            svg.push_str(&format!(
+                r#"  <style>@font-face {{ font-family: "RustQRCaption"; src: url(data:font/ttf;base64,{}); }}</style>
+"#,
+                general_purpose::STANDARD.encode(bytes)
+            ));
+            "RustQRCaption, DejaVu Sans, Verdana, sans-serif"
+        } else {
+            "DejaVu Sans, Verdana, sans-serif"
+        };
+        let align = Align::from_str(&args.caption_align);
+        let margin = caption_size(args) * 0.3 * units_per_px;
+        let x = match align {
+            Align::Left => margin,
+            Align::Center => svg_size as f32 / 2.0,
+            Align::Right => svg_size as f32 - margin,
+        };
+        let color = args.caption_color.as_deref().unwrap_or(&args.fg_color);
+        svg.push_str(&format!(
+            r#"  <text x="{:.1}" y="{:.1}" font-family="{}" font-size="{:.1}" fill="{}" text-anchor="{}" dominant-baseline="middle">{}</text>
+"#,
+            x,
+            code_height as f32 + caption_band / 2.0,
+            family,
+            caption_size(args) * units_per_px,
+            color,
+            align.svg_anchor(),
+            xml_escape(caption)
+        ));
+    }
+
+    if !transform.is_empty() {
+        svg.push_str("  </g>\n");
+    }
+    if outer_border.is_some() {
+        svg.push_str("  </g>\n");
+    }
+    if canvas.is_some() {
+        svg.push_str("  </g>\n  </g>\n");
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Pixels per module at --size, with the quiet zone counted in; every module needs at least one
+fn module_scale(symbol: &Symbol, args: &Args) -> Result<u32> {
+    let modules = (symbol.width().max(symbol.height()) as u32) + 2 * args.border;
+    if args.size < modules {
+        anyhow::bail!(
+            "--size {} is too small for {} modules (quiet zone included), use --size {} or more",
+            args.size,
+            modules,
+            modules
+        );
+    }
+    Ok(args.size / modules)
+}
+
+/// Raster formats follow the output file extension (as `image::save` does),
+/// falling back to `--format` when the path has none
+fn output_format(args: &Args, output_path: &Path) -> String {
+    let format = args.format.to_lowercase();
+    if matches!(format.as_str(), "svg" | "epl" | "tspl" | "stl" | "3mf" | "dxf") {
+        return format;
+    }
+    output_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or(format)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn validate_format(format: &str) -> Result<()> {
+    let valid = ["png", "jpg", "jpeg", "svg", "webp", "tiff", "tif", "ico", "bmp", "gif", "tga", "avif", "qoi", "epl", "tspl", "stl", "3mf", "dxf"];
+    if !valid.contains(&format.to_lowercase().as_str()) {
+        anyhow::bail!(
+            "Unsupported format '{}'. Valid formats: {}",
+            format,
+            valid.join(", ")
+        );
+    }
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,57 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::io::Write;
+
+/// Prints status lines, warnings and errors from the `log` macros. Text keeps the familiar
+/// `✓`/`⚠`/`✗` lines with status on stdout (stderr when the file itself goes to stdout), JSON writes one object per record to stderr so
+/// supervisors can collect warnings alongside their own logs.
+struct Logger {
+    level: LevelFilter,
+    json: bool,
+    stdout_taken: bool,
+}
+
+/// Installs the logger: errors only with `quiet`, otherwise status and warnings, plus
+/// debug details for each `--verbose` and traces for two. `stdout_taken` is set for `-o -`,
+/// where a status line would end up inside the file.
+pub fn init(verbose: u8, quiet: bool, format: &str, stdout_taken: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    if log::set_boxed_logger(Box::new(Logger { level, json: format == "json", stdout_taken })).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependencies only get a say when something went wrong
+        metadata.level() <= self.level && (metadata.target().starts_with("rustqr") || metadata.level() <= Level::Warn)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        if self.json {
+            let line = serde_json::json!({
+                "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                "level": record.level().as_str().to_lowercase(),
+                "target": record.target(),
+                "message": message.trim_start_matches(['✓', '⚠', '✗']).trim_start(),
+            });
+            let _ = writeln!(std::io::stderr(), "{}", line);
+        } else if record.level() == Level::Info && !self.stdout_taken {
+            let _ = writeln!(std::io::stdout(), "{}", message);
+        } else {
+            let _ = writeln!(std::io::stderr(), "{}", message);
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stdout().flush();
+    }
+}
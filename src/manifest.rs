@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// What happened to one batch row
+pub enum Status {
+    Generated,
+    /// `--name-by-hash` found the file already there
+    UpToDate,
+    Failed(String),
+}
+
+/// One line of a `--manifest`
+pub struct Entry {
+    pub row: usize,
+    pub data: String,
+    pub file: Option<PathBuf>,
+    /// Symbology, version and level, as `Symbol::describe` puts them
+    pub symbol: Option<String>,
+    /// QR version, None for other symbologies
+    pub version: Option<i16>,
+    pub status: Status,
+}
+
+impl Entry {
+    fn status(&self) -> &'static str {
+        match self.status {
+            Status::Generated => "ok",
+            Status::UpToDate => "up-to-date",
+            Status::Failed(_) => "failed",
+        }
+    }
+
+    fn error(&self) -> Option<&str> {
+        match self.status {
+            Status::Failed(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the manifest as JSON when the path ends in .json, as CSV otherwise
+pub fn write(path: &Path, entries: &[Entry]) -> Result<()> {
+    let bytes = encode(path, entries)?;
+    std::fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The manifest as `write` would save it at `path`, for storing it inside an archive
+pub fn encode(path: &Path, entries: &[Entry]) -> Result<Vec<u8>> {
+    let json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let file = |entry: &Entry| entry.file.as_ref().map(|file| file.display().to_string());
+    if json {
+        let rows: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "row": entry.row,
+                    "data": entry.data,
+                    "file": file(entry),
+                    "symbol": entry.symbol,
+                    "version": entry.version,
+                    "status": entry.status(),
+                    "error": entry.error(),
+                })
+            })
+            .collect();
+        return Ok((serde_json::to_string_pretty(&rows)? + "\n").into_bytes());
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["row", "data", "file", "symbol", "version", "status", "error"])?;
+    for entry in entries {
+        writer.write_record([
+            entry.row.to_string(),
+            entry.data.clone(),
+            file(entry).unwrap_or_default(),
+            entry.symbol.clone().unwrap_or_default(),
+            entry.version.map(|version| version.to_string()).unwrap_or_default(),
+            entry.status().to_string(),
+            entry.error().unwrap_or_default().to_string(),
+        ])?;
+    }
+    writer.into_inner().map_err(|e| anyhow::anyhow!("Failed to write the manifest: {}", e.error()))
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+
+use crate::{Args, xml_escape};
+
+/// Keywords with a predefined meaning in PNG, matched case-insensitively so `--meta title=...` works
+const PNG_KEYWORDS: [&str; 10] = [
+    "Title", "Author", "Description", "Copyright", "Creation Time",
+    "Software", "Disclaimer", "Warning", "Source", "Comment",
+];
+
+/// Parses the `--meta key=value` entries and, with `--embed-settings`, appends the style and the
+/// payload (sheets and cards hold many codes, they pass no payload)
+pub fn collect(args: &Args, data: Option<&str>) -> Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for entry in &args.meta {
+        let (key, value) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Metadata '{}' must be written as key=value", entry))?;
+        let key = key.trim();
+        if key.is_empty() || key.len() > 79 || !key.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+            anyhow::bail!("Metadata key '{}' must be 1-79 printable ASCII characters", key);
+        }
+        let key = PNG_KEYWORDS
+            .iter()
+            .find(|k| k.eq_ignore_ascii_case(key))
+            .map_or(key, |k| *k);
+        entries.push((key.to_string(), value.to_string()));
+    }
+
+    if args.embed_settings {
+        entries.push(("Software".to_string(), format!("RustQR {}", env!("CARGO_PKG_VERSION"))));
+        if let Some(data) = data {
+            entries.push(("Source".to_string(), data.to_string()));
+        }
+        entries.push(("Settings".to_string(), settings_summary(args)));
+    }
+    Ok(entries)
+}
+
+fn settings_summary(args: &Args) -> String {
+    let mut summary = format!(
+        "fg={} bg={} dots={} eyes={} level={} size={} border={}",
+        args.fg_color,
+        args.bg_color,
+        args.dot_style,
+        args.eye_style,
+        args.error.as_deref().unwrap_or("M"),
+        args.size,
+        args.border
+    );
+    if args.dot_scale < 1.0 {
+        summary.push_str(&format!(" dot-scale={}", args.dot_scale));
+    }
+    if let Some(ref gradient) = args.gradient {
+        summary.push_str(&format!(" gradient={}", gradient));
+    }
+    if let Some(ref logo) = args.logo {
+        summary.push_str(&format!(" logo={}", logo.display()));
+    }
+    summary
+}
+
+/// PNG text chunks: tEXt for Latin-1 values, uncompressed iTXt (UTF-8) for everything else
+pub fn png_text_chunks(entries: &[(String, String)]) -> Vec<([u8; 4], Vec<u8>)> {
+    entries
+        .iter()
+        .map(|(key, value)| {
+            let mut chunk = key.as_bytes().to_vec();
+            chunk.push(0);
+            if value.chars().all(|c| (c as u32) < 256) {
+                chunk.extend(value.chars().map(|c| c as u8));
+                (*b"tEXt", chunk)
+            } else {
+                // compression flag, compression method, empty language tag, empty translated keyword
+                chunk.extend_from_slice(&[0, 0, 0, 0]);
+                chunk.extend_from_slice(value.as_bytes());
+                (*b"iTXt", chunk)
+            }
+        })
+        .collect()
+}
+
+/// An XMP packet; title, author and description map to Dublin Core, the rest to an `rqr:` property each
+pub fn xmp_packet(entries: &[(String, String)]) -> String {
+    let mut properties = String::new();
+    for (key, value) in entries {
+        let value = xml_escape(value);
+        let property = match key.as_str() {
+            "Title" => format!("<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>", value),
+            "Description" | "Comment" => format!(
+                "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>",
+                value
+            ),
+            "Author" => format!("<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>", value),
+            "Copyright" => format!("<dc:rights><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:rights>", value),
+            "Software" => format!("<xmp:CreatorTool>{}</xmp:CreatorTool>", value),
+            _ => {
+                let name: String = key.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+                format!("<rqr:{0}>{1}</rqr:{0}>", if name.is_empty() { "Value".to_string() } else { name }, value)
+            }
+        };
+        properties.push_str("\n      ");
+        properties.push_str(&property);
+    }
+
+    format!(
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+    <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmlns:rqr="https://github.com/amirroox/RustQR/ns/1.0/">{}
+    </rdf:Description>
+  </rdf:RDF>
+</x:xmpmeta>"#,
+        properties
+    )
+}
+
+/// Inserts an XMP APP1 segment after the JFIF header of an encoded JPEG
+pub fn jpeg_with_xmp(jpeg: &[u8], xmp: &str) -> Result<Vec<u8>> {
+    const XMP_NAMESPACE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+    let segment_len = 2 + XMP_NAMESPACE.len() + xmp.len();
+    if segment_len > u16::MAX as usize {
+        anyhow::bail!("Metadata is too large for a JPEG file ({} bytes, at most 65 KB)", xmp.len());
+    }
+    if !jpeg.starts_with(&[0xff, 0xd8]) {
+        anyhow::bail!("Encoded JPEG has no start-of-image marker");
+    }
+
+    // SOI, then the APP0 (JFIF) segment if present
+    let mut insert_at = 2;
+    if jpeg.get(2..4) == Some(&[0xff, 0xe0]) {
+        let app0_len = u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+        insert_at += 2 + app0_len;
+    }
+
+    let mut out = Vec::with_capacity(jpeg.len() + segment_len + 2);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xff, 0xe1]);
+    out.extend_from_slice(&(segment_len as u16).to_be_bytes());
+    out.extend_from_slice(XMP_NAMESPACE);
+    out.extend_from_slice(xmp.as_bytes());
+    out.extend_from_slice(&jpeg[insert_at..]);
+    Ok(out)
+}
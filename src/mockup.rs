@@ -0,0 +1,308 @@
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+
+/// Where the code goes in a presentation image
+pub enum Mockup {
+    /// On the screen of a laptop seen from the front left
+    LaptopScreen,
+    /// On a label wrapped around a bottle
+    BottleLabel,
+    /// On a card lying on a table
+    TableCard,
+    /// Corners of the code (top-left, top-right, bottom-right, bottom-left) as fractions of the image
+    Quad([(f32, f32); 4]),
+}
+
+impl Mockup {
+    /// A preset name, or four corners like `0.1,0.2;0.9,0.1;0.85,0.9;0.15,0.8`
+    pub fn from_str(spec: &str) -> Result<Self> {
+        match spec.to_lowercase().as_str() {
+            "laptop-screen" | "laptop" => return Ok(Mockup::LaptopScreen),
+            "bottle-label" | "bottle" => return Ok(Mockup::BottleLabel),
+            "table-card" | "table" => return Ok(Mockup::TableCard),
+            _ => {}
+        }
+
+        let invalid = || {
+            anyhow::anyhow!(
+                "Unknown mockup '{}'. Use laptop-screen, bottle-label, table-card or four corners like 0.1,0.2;0.9,0.1;0.85,0.9;0.15,0.8",
+                spec
+            )
+        };
+        let corners: Vec<(f32, f32)> = spec
+            .split(';')
+            .map(|corner| {
+                let (x, y) = corner.split_once(',')?;
+                Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        let corners: [(f32, f32); 4] = corners.try_into().map_err(|_| invalid())?;
+        if corners.iter().any(|&(x, y)| !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y)) {
+            anyhow::bail!("Mockup corners must lie between 0 and 1, got '{}'", spec);
+        }
+        Ok(Mockup::Quad(corners))
+    }
+}
+
+/// Renders the presentation image; `size` is the width of the finished picture
+pub fn render(code: &RgbaImage, mockup: &Mockup, size: u32) -> Result<RgbaImage> {
+    let img = match mockup {
+        Mockup::Quad(corners) => {
+            let (w, h) = (code.width() as f32, code.height() as f32);
+            let quad = corners.map(|(x, y)| (x * w, y * h));
+            let mut canvas = RgbaImage::new(code.width(), code.height());
+            warp_into_quad(&mut canvas, code, quad)?;
+            canvas
+        }
+        Mockup::LaptopScreen => laptop_screen(code, size)?,
+        Mockup::BottleLabel => bottle_label(code, size),
+        Mockup::TableCard => table_card(code, size)?,
+    };
+    Ok(img)
+}
+
+fn laptop_screen(code: &RgbaImage, size: u32) -> Result<RgbaImage> {
+    let (w, h) = (size as f32, size as f32 * 0.625);
+    let mut canvas = backdrop(size, h as u32, [236, 239, 244], [206, 212, 222]);
+    let at = |x: f32, y: f32| (x * w, y * h);
+
+    // Keyboard deck, lid with bezel, then the screen
+    fill_polygon(&mut canvas, &[at(0.10, 0.80), at(0.86, 0.76), at(0.97, 0.90), at(0.05, 0.96)], Rgba([196, 200, 207, 255]));
+    fill_polygon(&mut canvas, &[at(0.05, 0.96), at(0.97, 0.90), at(0.97, 0.92), at(0.06, 0.98)], Rgba([150, 155, 163, 255]));
+    fill_polygon(&mut canvas, &[at(0.16, 0.08), at(0.82, 0.12), at(0.86, 0.76), at(0.10, 0.80)], Rgba([32, 34, 38, 255]));
+    let screen = [at(0.18, 0.12), at(0.80, 0.155), at(0.835, 0.72), at(0.125, 0.755)];
+
+    // The code sits centered on a white screen, 16:10 like the display
+    let screen_w = (code.width().max(code.height()) as f32 * 1.9) as u32;
+    let screen_h = (screen_w as f32 * 0.625) as u32;
+    let mut content = RgbaImage::from_pixel(screen_w, screen_h, Rgba([255, 255, 255, 255]));
+    image::imageops::overlay(
+        &mut content,
+        code,
+        ((screen_w - code.width()) / 2) as i64,
+        ((screen_h - code.height()) / 2) as i64,
+    );
+    warp_into_quad(&mut canvas, &content, screen)?;
+    Ok(canvas)
+}
+
+fn table_card(code: &RgbaImage, size: u32) -> Result<RgbaImage> {
+    let (w, h) = (size as f32, size as f32 * 0.75);
+    let mut canvas = backdrop(size, h as u32, [196, 160, 122], [150, 112, 78]);
+    let at = |x: f32, y: f32| (x * w, y * h);
+
+    // A card tilted back on the table, with a soft contact shadow
+    let card = [at(0.30, 0.14), at(0.74, 0.18), at(0.84, 0.86), at(0.18, 0.82)];
+    let shadow = card.map(|(x, y)| (x + w * 0.012, y + h * 0.02));
+    fill_polygon(&mut canvas, &shadow, Rgba([60, 40, 25, 90]));
+
+    let margin = code.width().max(code.height()) / 10;
+    let mut content = RgbaImage::from_pixel(code.width() + 2 * margin, code.height() + 2 * margin, Rgba([255, 255, 255, 255]));
+    image::imageops::overlay(&mut content, code, margin as i64, margin as i64);
+    warp_into_quad(&mut canvas, &content, card)?;
+    Ok(canvas)
+}
+
+fn bottle_label(code: &RgbaImage, size: u32) -> RgbaImage {
+    let (w, h) = (size as f32, size as f32 * 1.25);
+    let mut canvas = backdrop(size, h as u32, [244, 241, 236], [222, 216, 206]);
+    let center = w / 2.0;
+    let radius = w * 0.26;
+    let (label_top, label_bottom) = (h * 0.34, h * 0.80);
+
+    // Neck and body of the bottle
+    let glass = Rgba([46, 102, 72, 255]);
+    fill_polygon(&mut canvas, &[(center - w * 0.07, h * 0.04), (center + w * 0.07, h * 0.04), (center + w * 0.07, h * 0.16), (center - w * 0.07, h * 0.16)], glass);
+    fill_polygon(&mut canvas, &[(center - w * 0.07, h * 0.16), (center + w * 0.07, h * 0.16), (center + radius, h * 0.28), (center - radius, h * 0.28)], glass);
+    fill_polygon(&mut canvas, &[(center - radius, h * 0.28), (center + radius, h * 0.28), (center + radius, h * 0.94), (center - radius, h * 0.94)], glass);
+
+    // The label wraps the front 100° of the cylinder; the code takes its middle
+    let half_angle = 50f32.to_radians();
+    let label_height = label_bottom - label_top;
+    let code_side = (label_height * 0.8).min(radius * 2.0 * half_angle.sin() * 0.8);
+    let (sw, sh) = (code.width() as f32, code.height() as f32);
+
+    for y in label_top as u32..label_bottom as u32 {
+        for x in (center - radius) as u32..(center + radius) as u32 {
+            let offset = (x as f32 + 0.5 - center) / radius;
+            if offset.abs() > half_angle.sin() {
+                continue;
+            }
+            // Arc length along the label, measured from its middle
+            let arc = offset.asin() * radius;
+            let shade = 0.55 + 0.45 * (1.0 - offset * offset).sqrt();
+            let mut pixel = Rgba([250, 248, 240, 255]);
+
+            let (u, v) = ((arc + code_side / 2.0) / code_side, (y as f32 + 0.5 - (label_top + label_bottom) / 2.0 + code_side / 2.0) / code_side);
+            if (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) {
+                let sample = bilinear(code, u * sw, v * sh);
+                pixel = blend_over(pixel, sample);
+            }
+            for i in 0..3 {
+                pixel[i] = (pixel[i] as f32 * shade) as u8;
+            }
+            canvas.put_pixel(x, y, pixel);
+        }
+    }
+
+    // A highlight streak sells the glass
+    fill_polygon(&mut canvas, &[(center - radius * 0.78, h * 0.30), (center - radius * 0.68, h * 0.30), (center - radius * 0.68, h * 0.92), (center - radius * 0.78, h * 0.92)], Rgba([255, 255, 255, 40]));
+    canvas
+}
+
+/// A soft vertical gradient to set the scene on
+fn backdrop(width: u32, height: u32, top: [u8; 3], bottom: [u8; 3]) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |_, y| {
+        let t = y as f32 / height.max(1) as f32;
+        let mix = |i: usize| (top[i] as f32 * (1.0 - t) + bottom[i] as f32 * t) as u8;
+        Rgba([mix(0), mix(1), mix(2), 255])
+    })
+}
+
+/// Draws `src` into the quad (top-left, top-right, bottom-right, bottom-left) with a perspective
+/// transform, sampling 2×2 points per pixel so the edges stay smooth
+fn warp_into_quad(canvas: &mut RgbaImage, src: &RgbaImage, quad: [(f32, f32); 4]) -> Result<()> {
+    let to_quad = square_to_quad(quad);
+    let from_quad = invert(&to_quad).ok_or_else(|| anyhow::anyhow!("Mockup corners must not lie on one line"))?;
+    let (sw, sh) = (src.width() as f32, src.height() as f32);
+
+    let min_x = quad.iter().map(|p| p.0).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let max_x = quad.iter().map(|p| p.0).fold(0.0, f32::max).ceil().min(canvas.width() as f32) as u32;
+    let min_y = quad.iter().map(|p| p.1).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let max_y = quad.iter().map(|p| p.1).fold(0.0, f32::max).ceil().min(canvas.height() as f32) as u32;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let mut sum = [0f32; 4];
+            for (ox, oy) in [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)] {
+                let (u, v) = project(&from_quad, x as f32 + ox, y as f32 + oy);
+                if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                    continue;
+                }
+                let sample = bilinear(src, u * sw, v * sh);
+                let alpha = sample[3] as f32 / 255.0;
+                for i in 0..3 {
+                    sum[i] += sample[i] as f32 * alpha;
+                }
+                sum[3] += alpha;
+            }
+            if sum[3] == 0.0 {
+                continue;
+            }
+            let coverage = sum[3] / 4.0;
+            let color = Rgba([
+                (sum[0] / sum[3]) as u8,
+                (sum[1] / sum[3]) as u8,
+                (sum[2] / sum[3]) as u8,
+                (coverage * 255.0).round() as u8,
+            ]);
+            let under = *canvas.get_pixel(x, y);
+            canvas.put_pixel(x, y, blend_over(under, color));
+        }
+    }
+    Ok(())
+}
+
+/// Fills a convex polygon, anti-aliased with 4×4 samples per pixel
+fn fill_polygon(canvas: &mut RgbaImage, points: &[(f32, f32)], color: Rgba<u8>) {
+    let inside = |px: f32, py: f32| {
+        let mut sign = 0f32;
+        for i in 0..points.len() {
+            let (ax, ay) = points[i];
+            let (bx, by) = points[(i + 1) % points.len()];
+            let cross = (bx - ax) * (py - ay) - (by - ay) * (px - ax);
+            if cross != 0.0 {
+                if sign != 0.0 && cross.signum() != sign {
+                    return false;
+                }
+                sign = cross.signum();
+            }
+        }
+        true
+    };
+
+    let min_x = points.iter().map(|p| p.0).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let max_x = points.iter().map(|p| p.0).fold(0.0, f32::max).ceil().min(canvas.width() as f32) as u32;
+    let min_y = points.iter().map(|p| p.1).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let max_y = points.iter().map(|p| p.1).fold(0.0, f32::max).ceil().min(canvas.height() as f32) as u32;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let hits = (0..16)
+                .filter(|i| inside(x as f32 + (i % 4) as f32 / 4.0 + 0.125, y as f32 + (i / 4) as f32 / 4.0 + 0.125))
+                .count();
+            if hits > 0 {
+                let mut painted = color;
+                painted[3] = (color[3] as u32 * hits as u32 / 16) as u8;
+                let under = *canvas.get_pixel(x, y);
+                canvas.put_pixel(x, y, blend_over(under, painted));
+            }
+        }
+    }
+}
+
+/// Porter-Duff "over" for straight (non-premultiplied) alpha
+fn blend_over(under: Rgba<u8>, over: Rgba<u8>) -> Rgba<u8> {
+    let (ao, au) = (over[3] as f32 / 255.0, under[3] as f32 / 255.0);
+    let alpha = ao + au * (1.0 - ao);
+    if alpha == 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let mix = |i: usize| ((over[i] as f32 * ao + under[i] as f32 * au * (1.0 - ao)) / alpha).round() as u8;
+    Rgba([mix(0), mix(1), mix(2), (alpha * 255.0).round() as u8])
+}
+
+fn bilinear(img: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let (x, y) = ((x - 0.5).max(0.0), (y - 0.5).max(0.0));
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(img.width() - 1), (y0 + 1).min(img.height() - 1));
+    let (x0, y0) = (x0.min(img.width() - 1), y0.min(img.height() - 1));
+    let (tx, ty) = (x.fract(), y.fract());
+    let (a, b, c, d) = (img.get_pixel(x0, y0), img.get_pixel(x1, y0), img.get_pixel(x0, y1), img.get_pixel(x1, y1));
+    Rgba([0, 1, 2, 3].map(|i| {
+        let top = a[i] as f32 * (1.0 - tx) + b[i] as f32 * tx;
+        let bottom = c[i] as f32 * (1.0 - tx) + d[i] as f32 * tx;
+        (top * (1.0 - ty) + bottom * ty).round() as u8
+    }))
+}
+
+type Matrix = [[f32; 3]; 3];
+
+/// Projective map from the unit square onto the quad (Heckbert's square-to-quad)
+fn square_to_quad(q: [(f32, f32); 4]) -> Matrix {
+    let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] = q;
+    let (sx, sy) = (x0 - x1 + x2 - x3, y0 - y1 + y2 - y3);
+    if sx.abs() < 1e-6 && sy.abs() < 1e-6 {
+        // Parallelogram, an affine map is enough
+        return [[x1 - x0, x3 - x0, x0], [y1 - y0, y3 - y0, y0], [0.0, 0.0, 1.0]];
+    }
+    let (dx1, dx2, dy1, dy2) = (x1 - x2, x3 - x2, y1 - y2, y3 - y2);
+    let det = dx1 * dy2 - dx2 * dy1;
+    let g = (sx * dy2 - dx2 * sy) / det;
+    let h = (dx1 * sy - sx * dy1) / det;
+    [
+        [x1 - x0 + g * x1, x3 - x0 + h * x3, x0],
+        [y1 - y0 + g * y1, y3 - y0 + h * y3, y0],
+        [g, h, 1.0],
+    ]
+}
+
+fn invert(m: &Matrix) -> Option<Matrix> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < 1e-9 {
+        return None;
+    }
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    Some([
+        [cofactor(1, 2, 1, 2) / det, -cofactor(0, 2, 1, 2) / det, cofactor(0, 1, 1, 2) / det],
+        [-cofactor(1, 2, 0, 2) / det, cofactor(0, 2, 0, 2) / det, -cofactor(0, 1, 0, 2) / det],
+        [cofactor(1, 2, 0, 1) / det, -cofactor(0, 2, 0, 1) / det, cofactor(0, 1, 0, 1) / det],
+    ])
+}
+
+fn project(m: &Matrix, x: f32, y: f32) -> (f32, f32) {
+    let w = m[2][0] * x + m[2][1] * y + m[2][2];
+    ((m[0][0] * x + m[0][1] * y + m[0][2]) / w, (m[1][0] * x + m[1][1] * y + m[1][2]) / w)
+}
@@ -0,0 +1,230 @@
+use crate::archive::ZipWriter;
+use crate::symbol::Symbol;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+pub enum ModelFormat {
+    Stl,
+    ThreeMf,
+}
+
+pub struct ModelOptions {
+    /// Longer side of the whole plate, quiet zone included
+    pub size_mm: f32,
+    pub base_mm: f32,
+    /// How far dark modules rise above the base
+    pub height_mm: f32,
+    /// How far the finder patterns rise above the base
+    pub eye_height_mm: f32,
+    pub border: u32,
+}
+
+impl ModelFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "stl" => Some(ModelFormat::Stl),
+            "3mf" => Some(ModelFormat::ThreeMf),
+            _ => None,
+        }
+    }
+}
+
+/// A closed triangle mesh in millimeters
+struct Mesh {
+    vertices: Vec<[f32; 3]>,
+    triangles: Vec<[u32; 3]>,
+}
+
+pub fn render_model(symbol: &Symbol, format: &ModelFormat, options: &ModelOptions) -> Result<Vec<u8>> {
+    let mesh = build_mesh(symbol, options)?;
+    let mut out = Vec::new();
+    match format {
+        ModelFormat::Stl => write_stl(&mesh, &mut out)?,
+        ModelFormat::ThreeMf => write_3mf(&mesh, &mut out)?,
+    }
+    Ok(out)
+}
+
+/// Turns the module grid into one solid: a base plate with dark modules and finder patterns
+/// raised as columns. Every cell gets its own top and bottom face and neighboring cells of
+/// different height are joined by a wall, so the surface is closed. Modules that touch only at a
+/// corner share that vertical edge, which slicers handle fine.
+fn build_mesh(symbol: &Symbol, options: &ModelOptions) -> Result<Mesh> {
+    if options.size_mm <= 0.0 || options.base_mm <= 0.0 {
+        anyhow::bail!("Model size and base height must be greater than 0 mm");
+    }
+    if options.height_mm <= 0.0 || options.eye_height_mm < 0.0 {
+        anyhow::bail!("Module height must be greater than 0 mm and eye height cannot be negative");
+    }
+
+    let (qr_width, qr_height) = (symbol.width(), symbol.height());
+    let nx = qr_width + 2 * options.border as usize;
+    let ny = qr_height + 2 * options.border as usize;
+    let cell_mm = options.size_mm / nx.max(ny) as f32;
+
+    // Heights by level, so equal heights share vertices; flat eyes stay on the base
+    let mut levels = vec![0.0, options.base_mm, options.base_mm + options.height_mm];
+    let eye_level = if options.eye_height_mm == 0.0 {
+        1
+    } else if options.eye_height_mm == options.height_mm {
+        2
+    } else {
+        levels.push(options.base_mm + options.eye_height_mm);
+        3
+    };
+    let mut by_height: Vec<usize> = (0..levels.len()).collect();
+    by_height.sort_by(|&a, &b| levels[a].total_cmp(&levels[b]));
+
+    let mut grid = vec![1usize; nx * ny];
+    for y in 0..qr_height {
+        for x in 0..qr_width {
+            if !symbol.is_dark(x, y) {
+                continue;
+            }
+            let (gx, gy) = (x + options.border as usize, y + options.border as usize);
+            grid[gy * nx + gx] = if symbol.in_eye(x, y) { eye_level } else { 2 };
+        }
+    }
+    // Outside the plate counts as level 0, so the rim gets walls down to the bed
+    let level_at = |x: isize, y: isize| {
+        if x < 0 || y < 0 || x >= nx as isize || y >= ny as isize { 0 } else { grid[y as usize * nx + x as usize] }
+    };
+
+    let mut mesh = Mesh { vertices: Vec::new(), triangles: Vec::new() };
+    let mut index: HashMap<(usize, usize, usize), u32> = HashMap::new();
+    // Grid corners count rows from the top of the code, model Y points up so the print is not mirrored
+    let mut vertex = |mesh: &mut Mesh, cx: usize, cy: usize, level: usize| {
+        *index.entry((cx, cy, level)).or_insert_with(|| {
+            mesh.vertices.push([cx as f32 * cell_mm, (ny - cy) as f32 * cell_mm, levels[level]]);
+            (mesh.vertices.len() - 1) as u32
+        })
+    };
+    // Corners in counter-clockwise order seen from outside the solid
+    let quad = |mesh: &mut Mesh, corners: [u32; 4]| {
+        mesh.triangles.push([corners[0], corners[1], corners[2]]);
+        mesh.triangles.push([corners[0], corners[2], corners[3]]);
+    };
+
+    for y in 0..ny {
+        for x in 0..nx {
+            let level = grid[y * nx + x];
+            // Top faces up, bottom faces down (the model's Y axis runs opposite to rows)
+            let top = [
+                vertex(&mut mesh, x, y + 1, level),
+                vertex(&mut mesh, x + 1, y + 1, level),
+                vertex(&mut mesh, x + 1, y, level),
+                vertex(&mut mesh, x, y, level),
+            ];
+            quad(&mut mesh, top);
+            let bottom = [
+                vertex(&mut mesh, x, y + 1, 0),
+                vertex(&mut mesh, x, y, 0),
+                vertex(&mut mesh, x + 1, y, 0),
+                vertex(&mut mesh, x + 1, y + 1, 0),
+            ];
+            quad(&mut mesh, bottom);
+
+            // Walls on the right and bottom edge of this cell, and on the rim's left and top edges
+            let (xi, yi) = (x as isize, y as isize);
+            let mut edges = vec![
+                ((x + 1, y), (x + 1, y + 1), level, level_at(xi + 1, yi)),
+                ((x + 1, y + 1), (x, y + 1), level, level_at(xi, yi + 1)),
+            ];
+            if x == 0 {
+                edges.push(((x, y + 1), (x, y), level, 0));
+            }
+            if y == 0 {
+                edges.push(((x, y), (x + 1, y), level, 0));
+            }
+            for ((ax, ay), (bx, by), here, there) in edges {
+                if here == there {
+                    continue;
+                }
+                // Split the wall at every height in between, so walls meeting at a corner share
+                // their vertices there
+                let (low, high) = if levels[here] < levels[there] { (here, there) } else { (there, here) };
+                let steps: Vec<usize> = by_height
+                    .iter()
+                    .copied()
+                    .filter(|&l| levels[l] >= levels[low] && levels[l] <= levels[high])
+                    .collect();
+                for pair in steps.windows(2) {
+                    let (from, to) = if here == high { (pair[1], pair[0]) } else { (pair[0], pair[1]) };
+                    // Edges run clockwise around the cell seen from above, so this winding faces
+                    // out of the cell when it is the taller one and into it otherwise
+                    let corners = [
+                        vertex(&mut mesh, ax, ay, from),
+                        vertex(&mut mesh, bx, by, from),
+                        vertex(&mut mesh, bx, by, to),
+                        vertex(&mut mesh, ax, ay, to),
+                    ];
+                    quad(&mut mesh, corners);
+                }
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+/// Binary STL: an 80-byte header, the triangle count, then normal and corners per triangle
+fn write_stl(mesh: &Mesh, out: &mut impl Write) -> Result<()> {
+    let mut header = [0u8; 80];
+    let title = b"RustQR QR code model";
+    header[..title.len()].copy_from_slice(title);
+    out.write_all(&header)?;
+    out.write_all(&(mesh.triangles.len() as u32).to_le_bytes())?;
+
+    for triangle in &mesh.triangles {
+        let [a, b, c] = triangle.map(|i| mesh.vertices[i as usize]);
+        let (u, v) = ([0, 1, 2].map(|i| b[i] - a[i]), [0, 1, 2].map(|i| c[i] - a[i]));
+        let normal = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+        let length = normal.iter().map(|n| n * n).sum::<f32>().sqrt().max(f32::EPSILON);
+        for value in normal.map(|n| n / length).iter().chain(&a).chain(&b).chain(&c) {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        out.write_all(&0u16.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// 3MF: an OPC package (a ZIP) holding the model XML with shared vertices
+fn write_3mf(mesh: &Mesh, out: &mut impl Write) -> Result<()> {
+    let mut model = String::new();
+    model.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    model.push_str("<model unit=\"millimeter\" xml:lang=\"en-US\" xmlns=\"http://schemas.microsoft.com/3dmanufacturing/core/2015/02\">\n");
+    model.push_str("  <metadata name=\"Application\">RustQR</metadata>\n");
+    model.push_str("  <resources>\n    <object id=\"1\" type=\"model\">\n      <mesh>\n        <vertices>\n");
+    for [x, y, z] in &mesh.vertices {
+        writeln!(model, "          <vertex x=\"{}\" y=\"{}\" z=\"{}\"/>", x, y, z)?;
+    }
+    model.push_str("        </vertices>\n        <triangles>\n");
+    for [a, b, c] in &mesh.triangles {
+        writeln!(model, "          <triangle v1=\"{}\" v2=\"{}\" v3=\"{}\"/>", a, b, c)?;
+    }
+    model.push_str("        </triangles>\n      </mesh>\n    </object>\n  </resources>\n");
+    model.push_str("  <build>\n    <item objectid=\"1\"/>\n  </build>\n</model>\n");
+
+    let content_types = concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">",
+        "<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>",
+        "<Default Extension=\"model\" ContentType=\"application/vnd.ms-package.3dmanufacturing-3dmodel+xml\"/>",
+        "</Types>\n"
+    );
+    let relationships = concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">",
+        "<Relationship Target=\"/3D/3dmodel.model\" Id=\"rel0\" ",
+        "Type=\"http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel\"/>",
+        "</Relationships>\n"
+    );
+
+    let mut zip = ZipWriter::new(out);
+    zip.add("[Content_Types].xml", content_types.as_bytes())?;
+    zip.add("_rels/.rels", relationships.as_bytes())?;
+    zip.add("3D/3dmodel.model", model.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use napi::bindgen_prelude::{AsyncTask, Buffer};
+use napi::{Env, Task};
+use napi_derive::napi;
+
+/// Options for `generate`: the data, the output format and any other command-line options
+#[napi(object)]
+pub struct GenerateOptions {
+    pub data: String,
+    /// png by default; any `--format` the CLI accepts, e.g. svg, webp or tiff
+    pub format: Option<String>,
+    /// Extra options as on the command line, e.g. `["--dot-style", "rounded"]`
+    pub args: Option<Vec<String>>,
+}
+
+/// Renders on the libuv thread pool so the event loop keeps serving requests
+pub struct Generate {
+    args: Vec<String>,
+    format: String,
+}
+
+impl Task for Generate {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> napi::Result<Vec<u8>> {
+        crate::render(&self.args, &self.format).map_err(|e| napi::Error::from_reason(format!("{:#}", e)))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Vec<u8>) -> napi::Result<Buffer> {
+        Ok(output.into())
+    }
+}
+
+/// Resolves to a Buffer holding the encoded file
+#[napi]
+pub fn generate(options: GenerateOptions) -> AsyncTask<Generate> {
+    let mut args = vec!["--data".to_string(), options.data];
+    args.extend(options.args.unwrap_or_default());
+    AsyncTask::new(Generate { args, format: options.format.unwrap_or_else(|| "png".to_string()).to_lowercase() })
+}
@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Named colors from a `--palette` file: a JSON object such as {"primary": "#1a73e8", "ink": "#202124"}
+pub struct Palette {
+    colors: HashMap<String, String>,
+}
+
+impl Palette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read palette: {}", path.display()))?;
+        let entries: HashMap<String, String> = serde_json::from_str(&text).with_context(|| {
+            format!("{} must be a JSON object of color names and values, e.g. {{\"primary\": \"#1a73e8\"}}", path.display())
+        })?;
+
+        let mut colors = HashMap::new();
+        for (name, value) in entries {
+            if name.is_empty() || name.contains(',') {
+                anyhow::bail!("Palette color name '{}' in {} must be non-empty and without commas", name, path.display());
+            }
+            csscolorparser::parse(&value)
+                .with_context(|| format!("Palette color '{}' in {} is not a color: '{}'", name, path.display(), value))?;
+            colors.insert(name.to_lowercase(), value);
+        }
+        Ok(Palette { colors })
+    }
+
+    /// The color named `value`, or `value` itself when the palette has no such name
+    pub fn resolve(&self, value: &str) -> String {
+        self.colors.get(&value.trim().to_lowercase()).cloned().unwrap_or_else(|| value.to_string())
+    }
+
+    /// Resolves every entry of a comma separated list such as a gradient
+    pub fn resolve_list(&self, list: &str) -> String {
+        list.split(',').map(|part| self.resolve(part)).collect::<Vec<_>>().join(",")
+    }
+}
@@ -0,0 +1,1002 @@
+use crate::address;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use clap::{Args, Subcommand};
+#[cfg(feature = "cli")]
+use dialoguer::Password;
+use std::io::{BufRead, IsTerminal};
+use url::{Host, Url};
+
+/// Structured payload builders; each one produces the text that gets encoded
+#[derive(Subcommand, Debug, Clone)]
+pub enum Payload {
+    /// Contact card in NTT DoCoMo MECARD format
+    Mecard(ContactArgs),
+    /// Contact card in vCard 3.0 format
+    Vcard(ContactArgs),
+    /// Calendar event (iCalendar VEVENT)
+    Event(EventArgs),
+    /// Geographic location (geo: URI or a maps link)
+    Geo(GeoArgs),
+    /// Text message (SMSTO)
+    Sms(SmsArgs),
+    /// Phone call (tel: URI)
+    Tel(TelArgs),
+    /// TOTP provisioning URI (otpauth://) for authenticator apps
+    Otp(OtpArgs),
+    /// SEPA credit transfer (EPC069-12 / GiroCode)
+    Epc(EpcArgs),
+    /// Bitcoin payment request (BIP-21)
+    Bitcoin(BitcoinArgs),
+    /// Ethereum payment request (EIP-681)
+    Ethereum(EthereumArgs),
+}
+
+/// Contact fields shared by the MECARD and vCard builders
+#[derive(Args, Debug, Clone)]
+pub struct ContactArgs {
+    /// First (given) name
+    #[arg(long)]
+    first_name: Option<String>,
+
+    /// Last (family) name
+    #[arg(long)]
+    last_name: Option<String>,
+
+    /// Organization
+    #[arg(long)]
+    org: Option<String>,
+
+    /// Job title
+    #[arg(long)]
+    job_title: Option<String>,
+
+    /// Phone number (repeatable)
+    #[arg(long)]
+    phone: Vec<String>,
+
+    /// Email address (repeatable)
+    #[arg(long)]
+    email: Vec<String>,
+
+    /// Website
+    #[arg(long)]
+    website: Option<String>,
+
+    /// Postal address
+    #[arg(long)]
+    address: Option<String>,
+
+    /// Birthday (YYYY-MM-DD)
+    #[arg(long)]
+    birthday: Option<String>,
+
+    /// Free-form note
+    #[arg(long)]
+    note: Option<String>,
+}
+
+/// Calendar event fields
+#[derive(Args, Debug, Clone)]
+pub struct EventArgs {
+    /// Event title
+    #[arg(long)]
+    title: String,
+
+    /// Start: YYYY-MM-DD for all-day events, YYYY-MM-DD HH:MM, or RFC 3339 with offset
+    #[arg(long)]
+    start: String,
+
+    /// End, in the same form as --start (default: 1 hour or 1 day after the start)
+    #[arg(long)]
+    end: Option<String>,
+
+    /// Time zone of --start/--end, e.g. Europe/Paris (times are written as UTC)
+    #[arg(long)]
+    tz: Option<String>,
+
+    /// Location
+    #[arg(long)]
+    location: Option<String>,
+
+    /// Description
+    #[arg(long)]
+    description: Option<String>,
+
+    /// Event website
+    #[arg(long)]
+    website: Option<String>,
+}
+
+/// Geo location fields
+#[derive(Args, Debug, Clone)]
+pub struct GeoArgs {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[arg(long, allow_hyphen_values = true)]
+    lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[arg(long, allow_hyphen_values = true)]
+    lon: f64,
+
+    /// Place name or search query shown by the maps app
+    #[arg(long)]
+    query: Option<String>,
+
+    /// Link style (geo, google, apple)
+    #[arg(long, default_value = "geo")]
+    maps: String,
+}
+
+/// SMS fields
+#[derive(Args, Debug, Clone)]
+pub struct SmsArgs {
+    /// Recipient phone number
+    #[arg(long)]
+    to: String,
+
+    /// Pre-filled message body
+    #[arg(long)]
+    message: Option<String>,
+}
+
+/// Phone call fields
+#[derive(Args, Debug, Clone)]
+pub struct TelArgs {
+    /// Phone number, national or international
+    #[arg(long)]
+    number: String,
+
+    /// Region for national numbers (ISO code, e.g. US, DE)
+    #[arg(long)]
+    country: Option<String>,
+
+    /// Rewrite the number in E.164 form (+<country code><number>)
+    #[arg(long)]
+    e164: bool,
+}
+
+/// TOTP fields
+#[derive(Args, Debug, Clone)]
+pub struct OtpArgs {
+    /// Service name shown in the authenticator app
+    #[arg(long)]
+    issuer: String,
+
+    /// Account name, usually a username or email
+    #[arg(long)]
+    account: String,
+
+    /// Base32 secret; omit to be prompted (or pipe it on stdin) so it stays out of shell history
+    #[arg(long)]
+    secret: Option<String>,
+
+    /// Hash algorithm (SHA1, SHA256, SHA512)
+    #[arg(long, default_value = "SHA1")]
+    algorithm: String,
+
+    /// Code length (6 or 8)
+    #[arg(long, default_value = "6")]
+    digits: u8,
+
+    /// Seconds each code is valid
+    #[arg(long, default_value = "30")]
+    period: u32,
+}
+
+/// SEPA payment fields
+#[derive(Args, Debug, Clone)]
+pub struct EpcArgs {
+    /// Beneficiary name (max 70 characters)
+    #[arg(long)]
+    name: String,
+
+    /// Beneficiary IBAN
+    #[arg(long)]
+    iban: String,
+
+    /// Beneficiary BIC (optional inside the EEA)
+    #[arg(long)]
+    bic: Option<String>,
+
+    /// Amount in EUR (0.01 to 999999999.99)
+    #[arg(long)]
+    amount: Option<String>,
+
+    /// Purpose code (4 letters, e.g. CHAR, GDDS)
+    #[arg(long)]
+    purpose: Option<String>,
+
+    /// Structured creditor reference (ISO 11649, max 35)
+    #[arg(long, conflicts_with = "text")]
+    reference: Option<String>,
+
+    /// Unstructured remittance text (max 140)
+    #[arg(long)]
+    text: Option<String>,
+
+    /// Note shown to the payer (max 70)
+    #[arg(long)]
+    info: Option<String>,
+}
+
+/// Bitcoin payment fields
+#[derive(Args, Debug, Clone)]
+pub struct BitcoinArgs {
+    /// Receiving address (legacy, P2SH or bech32)
+    #[arg(long)]
+    address: String,
+
+    /// Amount in BTC
+    #[arg(long)]
+    amount: Option<String>,
+
+    /// Recipient name shown by the wallet
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Payment description
+    #[arg(long)]
+    message: Option<String>,
+}
+
+/// Ethereum payment fields
+#[derive(Args, Debug, Clone)]
+pub struct EthereumArgs {
+    /// Receiving address (0x...)
+    #[arg(long)]
+    address: String,
+
+    /// Amount in ETH
+    #[arg(long)]
+    amount: Option<String>,
+
+    /// Chain ID (1 = mainnet)
+    #[arg(long)]
+    chain_id: Option<u64>,
+}
+
+impl Payload {
+    /// Error correction level the payload format mandates, if any
+    pub fn error_level(&self) -> Option<&'static str> {
+        match self {
+            Payload::Epc(_) => Some("M"),
+            _ => None,
+        }
+    }
+
+    /// Whether the payload holds a secret that must not be written anywhere else, like an OTP seed
+    #[cfg(feature = "history")]
+    pub fn is_secret(&self) -> bool {
+        matches!(self, Payload::Otp(_))
+    }
+
+    pub fn payload(&self) -> Result<String> {
+        match self {
+            Payload::Mecard(contact) => build_mecard(contact),
+            Payload::Vcard(contact) => build_vcard(contact),
+            Payload::Event(event) => build_event(event),
+            Payload::Geo(geo) => build_geo(geo),
+            Payload::Sms(sms) => sms_payload(&sms.to, sms.message.as_deref().unwrap_or_default()),
+            Payload::Tel(tel) => build_tel(tel),
+            Payload::Otp(otp) => build_otp(otp),
+            Payload::Epc(epc) => build_epc(epc),
+            Payload::Bitcoin(btc) => build_bitcoin(btc),
+            Payload::Ethereum(eth) => build_ethereum(eth),
+        }
+    }
+}
+
+impl ContactArgs {
+    fn validate(&self) -> Result<()> {
+        if self.first_name.is_none() && self.last_name.is_none() {
+            anyhow::bail!("A contact needs --first-name or --last-name");
+        }
+        if let Some(ref birthday) = self.birthday {
+            let digits: String = birthday.chars().filter(|c| c.is_ascii_digit()).collect();
+            if digits.len() != 8 {
+                anyhow::bail!("Birthday must be YYYY-MM-DD");
+            }
+        }
+        Ok(())
+    }
+
+    fn full_name(&self) -> String {
+        [self.first_name.as_deref(), self.last_name.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn build_mecard(contact: &ContactArgs) -> Result<String> {
+    contact.validate()?;
+    let esc = escape_mecard;
+
+    // N is "Last,First"; the comma is a separator so each part is escaped on its own
+    let name = match (&contact.last_name, &contact.first_name) {
+        (Some(last), Some(first)) => format!("{},{}", esc(last), esc(first)),
+        (Some(last), None) => esc(last),
+        (None, Some(first)) => esc(first),
+        (None, None) => unreachable!(),
+    };
+
+    let mut fields = vec![format!("N:{}", name)];
+    if let Some(ref org) = contact.org {
+        fields.push(format!("ORG:{}", esc(org)));
+    }
+    fields.extend(contact.phone.iter().map(|p| format!("TEL:{}", esc(p))));
+    fields.extend(contact.email.iter().map(|e| format!("EMAIL:{}", esc(e))));
+    if let Some(ref url) = contact.website {
+        fields.push(format!("URL:{}", esc(url)));
+    }
+    if let Some(ref address) = contact.address {
+        fields.push(format!("ADR:{}", esc(address)));
+    }
+    if let Some(ref birthday) = contact.birthday {
+        let digits: String = birthday.chars().filter(|c| c.is_ascii_digit()).collect();
+        fields.push(format!("BDAY:{}", digits));
+    }
+    if let Some(ref note) = contact.note {
+        fields.push(format!("NOTE:{}", esc(note)));
+    }
+
+    Ok(format!("MECARD:{};;", fields.join(";")))
+}
+
+fn build_vcard(contact: &ContactArgs) -> Result<String> {
+    contact.validate()?;
+    let esc = escape_vcard;
+
+    let mut lines = vec!["BEGIN:VCARD".to_string(), "VERSION:3.0".to_string()];
+    lines.push(format!(
+        "N:{};{};;;",
+        esc(contact.last_name.as_deref().unwrap_or_default()),
+        esc(contact.first_name.as_deref().unwrap_or_default())
+    ));
+    lines.push(format!("FN:{}", esc(&contact.full_name())));
+    if let Some(ref org) = contact.org {
+        lines.push(format!("ORG:{}", esc(org)));
+    }
+    if let Some(ref title) = contact.job_title {
+        lines.push(format!("TITLE:{}", esc(title)));
+    }
+    lines.extend(contact.phone.iter().map(|p| format!("TEL:{}", esc(p))));
+    lines.extend(contact.email.iter().map(|e| format!("EMAIL:{}", esc(e))));
+    if let Some(ref url) = contact.website {
+        lines.push(format!("URL:{}", esc(url)));
+    }
+    if let Some(ref address) = contact.address {
+        lines.push(format!("ADR:;;{};;;;", esc(address)));
+    }
+    if let Some(ref birthday) = contact.birthday {
+        lines.push(format!("BDAY:{}", birthday));
+    }
+    if let Some(ref note) = contact.note {
+        lines.push(format!("NOTE:{}", esc(note)));
+    }
+    lines.push("END:VCARD".to_string());
+
+    Ok(lines.join("\r\n"))
+}
+
+/// A point in time as written in DTSTART/DTEND
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum EventTime {
+    Date(NaiveDate),
+    Floating(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+impl EventTime {
+    fn parse(value: &str, tz: Option<Tz>) -> Result<Self> {
+        let value = value.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Ok(EventTime::Date(date));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            return Ok(EventTime::Utc(time.with_timezone(&Utc)));
+        }
+
+        let local = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+            .with_context(|| format!("Invalid date/time '{}'", value))?;
+
+        match tz {
+            Some(tz) => {
+                let time = tz
+                    .from_local_datetime(&local)
+                    .single()
+                    .with_context(|| format!("'{}' is ambiguous or skipped in {}", value, tz))?;
+                Ok(EventTime::Utc(time.with_timezone(&Utc)))
+            }
+            None => Ok(EventTime::Floating(local)),
+        }
+    }
+
+    fn default_end(self) -> Self {
+        match self {
+            EventTime::Date(date) => EventTime::Date(date + Duration::days(1)),
+            EventTime::Floating(time) => EventTime::Floating(time + Duration::hours(1)),
+            EventTime::Utc(time) => EventTime::Utc(time + Duration::hours(1)),
+        }
+    }
+
+    fn property(self, name: &str) -> String {
+        match self {
+            EventTime::Date(date) => format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d")),
+            EventTime::Floating(time) => format!("{}:{}", name, time.format("%Y%m%dT%H%M%S")),
+            EventTime::Utc(time) => format!("{}:{}", name, time.format("%Y%m%dT%H%M%SZ")),
+        }
+    }
+}
+
+fn build_event(event: &EventArgs) -> Result<String> {
+    let tz = event
+        .tz
+        .as_deref()
+        .map(|name| {
+            name.parse::<Tz>()
+                .map_err(|_| anyhow::anyhow!("Unknown time zone '{}'", name))
+        })
+        .transpose()?;
+
+    let start = EventTime::parse(&event.start, tz)?;
+    let end = match event.end {
+        // All-day end dates are inclusive on the command line but exclusive in iCalendar
+        Some(ref end) => match EventTime::parse(end, tz)? {
+            EventTime::Date(date) => EventTime::Date(date + Duration::days(1)),
+            end => end,
+        },
+        None => start.default_end(),
+    };
+
+    if std::mem::discriminant(&start) != std::mem::discriminant(&end) {
+        anyhow::bail!("--start and --end must use the same form (date, local time or offset)");
+    }
+    if end <= start {
+        anyhow::bail!("--end must be after --start");
+    }
+
+    let esc = escape_vcard;
+    let mut lines = vec!["BEGIN:VEVENT".to_string()];
+    lines.push(format!("SUMMARY:{}", esc(&event.title)));
+    lines.push(start.property("DTSTART"));
+    lines.push(end.property("DTEND"));
+    if let Some(ref location) = event.location {
+        lines.push(format!("LOCATION:{}", esc(location)));
+    }
+    if let Some(ref description) = event.description {
+        lines.push(format!("DESCRIPTION:{}", esc(description)));
+    }
+    if let Some(ref url) = event.website {
+        lines.push(format!("URL:{}", url));
+    }
+    lines.push("END:VEVENT".to_string());
+
+    Ok(lines.iter().map(|line| fold_line(line)).collect::<Vec<_>>().join("\r\n"))
+}
+
+/// iCalendar lines longer than 75 octets continue on lines starting with a space
+fn fold_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out
+}
+
+fn build_geo(geo: &GeoArgs) -> Result<String> {
+    if !(-90.0..=90.0).contains(&geo.lat) {
+        anyhow::bail!("Latitude must be between -90 and 90");
+    }
+    if !(-180.0..=180.0).contains(&geo.lon) {
+        anyhow::bail!("Longitude must be between -180 and 180");
+    }
+
+    let coords = format!("{},{}", geo.lat, geo.lon);
+    let query = geo.query.as_deref().map(percent_encode);
+
+    let uri = match geo.maps.to_lowercase().as_str() {
+        "geo" => match query {
+            Some(q) => format!("geo:{}?q={}", coords, q),
+            None => format!("geo:{}", coords),
+        },
+        // Google takes either coordinates or a search string, coordinates win
+        "google" => format!(
+            "https://www.google.com/maps/search/?api=1&query={}",
+            percent_encode(&coords)
+        ),
+        "apple" => match query {
+            Some(q) => format!("https://maps.apple.com/?ll={}&q={}", coords, q),
+            None => format!("https://maps.apple.com/?ll={}", coords),
+        },
+        other => anyhow::bail!("Unknown maps style '{}'. Use: geo, google, apple", other),
+    };
+
+    Ok(uri)
+}
+
+/// Builds an `SMSTO:number:message` payload
+pub fn sms_payload(to: &str, message: &str) -> Result<String> {
+    // The body runs to the end of the payload, so only the number has to be free of ':'
+    let number = clean_phone(to)?;
+    if message.is_empty() {
+        Ok(format!("SMSTO:{}", number))
+    } else {
+        Ok(format!("SMSTO:{}:{}", number, message))
+    }
+}
+
+fn build_tel(tel: &TelArgs) -> Result<String> {
+    let cleaned = clean_phone(&tel.number)?;
+
+    // Numbers we can place in a numbering plan are checked against it
+    let international = cleaned.starts_with('+');
+    if !international && tel.country.is_none() {
+        if tel.e164 {
+            anyhow::bail!("--e164 needs --country for a national number");
+        }
+        return Ok(format!("tel:{}", cleaned));
+    }
+
+    let country = tel
+        .country
+        .as_deref()
+        .map(|code| {
+            code.to_uppercase()
+                .parse::<phonenumber::country::Id>()
+                .map_err(|_| anyhow::anyhow!("Unknown country code '{}'", code))
+        })
+        .transpose()?;
+    let parsed = phonenumber::parse(country, &cleaned)
+        .map_err(|e| anyhow::anyhow!("Invalid phone number '{}': {}", tel.number, e))?;
+    if !phonenumber::is_valid(&parsed) {
+        anyhow::bail!("'{}' is not a valid phone number", tel.number);
+    }
+
+    if tel.e164 {
+        Ok(format!("tel:{}", parsed.format().mode(phonenumber::Mode::E164)))
+    } else {
+        Ok(format!("tel:{}", cleaned))
+    }
+}
+
+fn build_otp(otp: &OtpArgs) -> Result<String> {
+    let algorithm = otp.algorithm.to_uppercase();
+    if !matches!(algorithm.as_str(), "SHA1" | "SHA256" | "SHA512") {
+        anyhow::bail!("Unknown algorithm '{}'. Use: SHA1, SHA256, SHA512", otp.algorithm);
+    }
+    if otp.digits != 6 && otp.digits != 8 {
+        anyhow::bail!("--digits must be 6 or 8");
+    }
+    if otp.period == 0 {
+        anyhow::bail!("--period must be greater than 0");
+    }
+    if otp.issuer.contains(':') || otp.account.contains(':') {
+        anyhow::bail!("Issuer and account cannot contain ':'");
+    }
+
+    let secret = match otp.secret {
+        Some(ref secret) => {
+            log::warn!("⚠ --secret is stored in shell history; omit it to be prompted instead");
+            secret.clone()
+        }
+        None => read_secret()?,
+    };
+    let secret = normalize_base32(&secret)?;
+
+    let mut uri = format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}",
+        percent_encode(&otp.issuer),
+        percent_encode(&otp.account),
+        secret,
+        percent_encode(&otp.issuer)
+    );
+    // Authenticators assume the defaults, leaving them out keeps the code small
+    if algorithm != "SHA1" {
+        uri.push_str(&format!("&algorithm={}", algorithm));
+    }
+    if otp.digits != 6 {
+        uri.push_str(&format!("&digits={}", otp.digits));
+    }
+    if otp.period != 30 {
+        uri.push_str(&format!("&period={}", otp.period));
+    }
+
+    Ok(uri)
+}
+
+/// Prompts for the secret on a terminal, otherwise reads the first line of stdin
+fn read_secret() -> Result<String> {
+    if std::io::stdin().is_terminal() {
+        prompt_secret()
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        Ok(line.trim().to_string())
+    }
+}
+
+#[cfg(feature = "cli")]
+fn prompt_secret() -> Result<String> {
+    Ok(Password::new().with_prompt("OTP secret (base32)").interact()?)
+}
+
+#[cfg(not(feature = "cli"))]
+fn prompt_secret() -> Result<String> {
+    anyhow::bail!("This build has no terminal prompts, pipe the OTP secret on stdin")
+}
+
+/// Uppercases a base32 secret, drops spaces and padding, and checks the alphabet
+fn normalize_base32(secret: &str) -> Result<String> {
+    let secret: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if secret.is_empty() {
+        anyhow::bail!("OTP secret is empty");
+    }
+    if let Some(bad) = secret.chars().find(|c| !matches!(c, 'A'..='Z' | '2'..='7')) {
+        anyhow::bail!("OTP secret is not base32 (unexpected '{}')", bad);
+    }
+    // Lengths that leave 1, 3 or 6 dangling characters can't come from whole bytes
+    if matches!(secret.len() % 8, 1 | 3 | 6) {
+        anyhow::bail!("OTP secret has an invalid base32 length");
+    }
+    if secret.len() * 5 / 8 < 10 {
+        log::warn!("⚠ OTP secret is shorter than 80 bits");
+    }
+
+    Ok(secret)
+}
+
+fn build_epc(epc: &EpcArgs) -> Result<String> {
+    let name = epc.name.trim();
+    check_length("--name", name, 70)?;
+    if name.is_empty() {
+        anyhow::bail!("--name cannot be empty");
+    }
+
+    let iban: String = epc.iban.split_whitespace().collect::<String>().to_uppercase();
+    validate_iban(&iban)?;
+
+    let bic = match epc.bic {
+        Some(ref bic) => {
+            let bic = bic.trim().to_uppercase();
+            let valid = bic.is_ascii()
+                && (bic.len() == 8 || bic.len() == 11)
+                && bic[..6].chars().all(|c| c.is_ascii_uppercase())
+                && bic[6..].chars().all(|c| c.is_ascii_alphanumeric());
+            if !valid {
+                anyhow::bail!("Invalid BIC '{}'", bic);
+            }
+            bic
+        }
+        None => String::new(),
+    };
+
+    let amount = match epc.amount {
+        Some(ref amount) => format!("EUR{}", parse_eur_amount(amount)?),
+        None => String::new(),
+    };
+
+    let purpose = epc.purpose.as_deref().unwrap_or_default().trim().to_uppercase();
+    if !purpose.is_empty() && (purpose.len() != 4 || !purpose.chars().all(|c| c.is_ascii_uppercase())) {
+        anyhow::bail!("--purpose must be a 4-letter code");
+    }
+
+    let reference = epc.reference.as_deref().unwrap_or_default().trim();
+    check_length("--reference", reference, 35)?;
+    let text = epc.text.as_deref().unwrap_or_default().trim();
+    check_length("--text", text, 140)?;
+    // EPC069-12 carries either a structured reference or free text, never both
+    if !reference.is_empty() && !text.is_empty() {
+        anyhow::bail!("--reference and --text cannot be combined, use one of them");
+    }
+    let info = epc.info.as_deref().unwrap_or_default().trim();
+    check_length("--info", info, 70)?;
+
+    // Service tag, version 002 (BIC optional), charset 1 (UTF-8), SEPA credit transfer
+    let mut fields = vec![
+        "BCD", "002", "1", "SCT", &bic, name, &iban, &amount, &purpose, reference, text, info,
+    ];
+    while fields.last().is_some_and(|f| f.is_empty()) {
+        fields.pop();
+    }
+    let payload = fields.join("\n");
+
+    // 331 bytes is what version 13 holds at level M, the largest the standard allows
+    if payload.len() > 331 {
+        anyhow::bail!("EPC payload is {} bytes, the limit is 331", payload.len());
+    }
+
+    Ok(payload)
+}
+
+fn check_length(flag: &str, value: &str, max: usize) -> Result<()> {
+    if value.chars().count() > max {
+        anyhow::bail!("{} is limited to {} characters", flag, max);
+    }
+    Ok(())
+}
+
+/// Checks the IBAN shape and its ISO 13616 mod-97 checksum
+fn validate_iban(iban: &str) -> Result<()> {
+    let shape_ok = iban.is_ascii()
+        && (15..=34).contains(&iban.len())
+        && iban[..2].chars().all(|c| c.is_ascii_uppercase())
+        && iban[2..4].chars().all(|c| c.is_ascii_digit())
+        && iban.chars().all(|c| c.is_ascii_alphanumeric());
+    if !shape_ok {
+        anyhow::bail!("Invalid IBAN '{}'", iban);
+    }
+
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let value = c.to_digit(36).unwrap_or(0);
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    if remainder != 1 {
+        anyhow::bail!("IBAN '{}' has an invalid checksum", iban);
+    }
+    Ok(())
+}
+
+/// Parses a euro amount into the "12.50" form without going through floats
+fn parse_eur_amount(amount: &str) -> Result<String> {
+    let amount = amount.trim().replace(',', ".");
+    let (whole, cents) = amount.split_once('.').unwrap_or((&amount, ""));
+    let valid = !whole.is_empty()
+        && whole.len() <= 9
+        && cents.len() <= 2
+        && whole.chars().chain(cents.chars()).all(|c| c.is_ascii_digit());
+    if !valid {
+        anyhow::bail!("Invalid amount '{}' (use e.g. 12.50, max 999999999.99)", amount);
+    }
+
+    let whole: u64 = whole.parse()?;
+    let cents: u64 = format!("{:0<2}", cents).parse()?;
+    if whole == 0 && cents == 0 {
+        anyhow::bail!("Amount must be at least 0.01");
+    }
+    Ok(format!("{}.{:02}", whole, cents))
+}
+
+fn build_bitcoin(btc: &BitcoinArgs) -> Result<String> {
+    let address = btc.address.trim();
+    address::validate_bitcoin(address)?;
+
+    let mut params = Vec::new();
+    if let Some(ref amount) = btc.amount {
+        let satoshis = parse_decimal(amount, 8)
+            .filter(|&sat| sat > 0 && sat <= 21_000_000 * 100_000_000)
+            .with_context(|| format!("Invalid BTC amount '{}'", amount))?;
+        params.push(format!("amount={}", format_decimal(satoshis, 8)));
+    }
+    if let Some(ref label) = btc.label {
+        params.push(format!("label={}", percent_encode(label)));
+    }
+    if let Some(ref message) = btc.message {
+        params.push(format!("message={}", percent_encode(message)));
+    }
+
+    if params.is_empty() {
+        Ok(format!("bitcoin:{}", address))
+    } else {
+        Ok(format!("bitcoin:{}?{}", address, params.join("&")))
+    }
+}
+
+fn build_ethereum(eth: &EthereumArgs) -> Result<String> {
+    let address = address::checksum_ethereum(eth.address.trim())?;
+
+    let mut uri = format!("ethereum:{}", address);
+    if let Some(chain_id) = eth.chain_id {
+        uri.push_str(&format!("@{}", chain_id));
+    }
+    if let Some(ref amount) = eth.amount {
+        // EIP-681 values are in wei
+        let wei = parse_decimal(amount, 18)
+            .filter(|&wei| wei > 0)
+            .with_context(|| format!("Invalid ETH amount '{}'", amount))?;
+        uri.push_str(&format!("?value={}", wei));
+    }
+
+    Ok(uri)
+}
+
+/// Parses a decimal string into an integer count of 10^-`places` units
+fn parse_decimal(value: &str, places: usize) -> Option<u128> {
+    let value = value.trim();
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if whole.is_empty() && fraction.is_empty() || fraction.len() > places {
+        return None;
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = format!("{}{:0<width$}", whole, fraction, width = places);
+    digits.parse().ok()
+}
+
+/// Formats an integer count of 10^-`places` units without trailing zeros
+fn format_decimal(units: u128, places: usize) -> String {
+    let scale = 10u128.pow(places as u32);
+    let fraction = format!("{:0width$}", units % scale, width = places);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (units / scale).to_string()
+    } else {
+        format!("{}.{}", units / scale, fraction)
+    }
+}
+
+/// Strips visual separators from a phone number and checks what's left
+fn clean_phone(number: &str) -> Result<String> {
+    let cleaned: String = number
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')' | '/'))
+        .collect();
+    let digits = cleaned.strip_prefix('+').unwrap_or(&cleaned);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        anyhow::bail!("Invalid phone number '{}'", number);
+    }
+    Ok(cleaned)
+}
+
+/// Validates a URL, lowercases the scheme and host, punycodes IDNs and appends query parameters.
+/// Only http and https pass unless `schemes` allows another one.
+pub fn normalize_url(input: &str, params: &[(&str, &str)], schemes: &[String]) -> Result<String> {
+    let input = input.trim();
+    if input.chars().any(char::is_whitespace) {
+        log::warn!("⚠ URL contains whitespace, it will be percent-encoded: {}", input);
+    }
+
+    // "example.com:8080/x" parses as the scheme "example.com", so a host and port count as no scheme
+    let host_and_port = |url: &Url| !input.contains("://") && (url.scheme().contains('.') || url.scheme() == "localhost");
+    let mut url = match Url::parse(input) {
+        Ok(url) if !host_and_port(&url) => url,
+        Ok(_) | Err(url::ParseError::RelativeUrlWithoutBase) => {
+            log::warn!("⚠ URL has no scheme, assuming https://");
+            Url::parse(&format!("https://{}", input))
+                .with_context(|| format!("Invalid URL '{}'", input))?
+        }
+        Err(e) => anyhow::bail!("Invalid URL '{}': {}", input, e),
+    };
+
+    if !matches!(url.scheme(), "http" | "https") {
+        if !schemes.iter().any(|scheme| scheme.eq_ignore_ascii_case(url.scheme())) {
+            anyhow::bail!("URL '{}' uses the scheme '{}', not http or https; add --allow-scheme {} if that is intended", input, url.scheme(), url.scheme());
+        }
+    } else {
+        match url.host() {
+            Some(Host::Domain(domain)) if domain != "localhost" => {
+                let labels: Vec<&str> = domain.strip_suffix('.').unwrap_or(domain).split('.').collect();
+                if labels.iter().any(|label| label.is_empty()) {
+                    anyhow::bail!("Host '{}' in URL '{}' has an empty label", domain, input);
+                }
+                if labels.len() < 2 {
+                    // "https//example.com" reads as the host "https" followed by a path
+                    if matches!(domain, "http" | "https") {
+                        anyhow::bail!("URL '{}' has no host, is the ':' after {} missing?", input, domain);
+                    }
+                    anyhow::bail!("Host '{}' in URL '{}' has no top-level domain", domain, input);
+                }
+            }
+            None => anyhow::bail!("URL '{}' has no host", input),
+            _ => {}
+        }
+    }
+
+    if !params.is_empty() {
+        // Replace existing values rather than repeating keys
+        let kept: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| !params.iter().any(|(k, _)| k == key))
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        let mut query = url.query_pairs_mut();
+        query.clear();
+        query.extend_pairs(kept);
+        query.extend_pairs(params.iter().copied());
+    }
+
+    Ok(url.to_string())
+}
+
+/// Percent-encodes everything outside the URI unreserved set
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// MECARD reserves `\`, `;`, `,` and `:` inside values
+fn escape_mecard(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// vCard 3.0 and iCalendar text values escape `\`, `;`, `,` and newlines
+fn escape_vcard(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(input: &str) -> Result<String> {
+        normalize_url(input, &[], &[])
+    }
+
+    #[test]
+    fn normalizes_scheme_host_and_idns() {
+        assert_eq!(normalize("HTTPS://Example.COM/Path").unwrap(), "https://example.com/Path");
+        assert_eq!(normalize("example.com").unwrap(), "https://example.com/");
+        assert_eq!(normalize("example.com:8080/x").unwrap(), "https://example.com:8080/x");
+        assert_eq!(normalize("Bücher.example/spring").unwrap(), "https://xn--bcher-kva.example/spring");
+        assert_eq!(normalize("http://localhost:3000").unwrap(), "http://localhost:3000/");
+        assert_eq!(normalize("http://192.168.0.1/").unwrap(), "http://192.168.0.1/");
+    }
+
+    #[test]
+    fn rejects_typos_and_other_schemes() {
+        for input in ["htps://example.com", "javascript:alert(1)", "https//example.com", "example..com", "intranet", "https://.example.com"] {
+            assert!(normalize(input).is_err(), "{}", input);
+        }
+        let mailto = ["mailto".to_string()];
+        assert_eq!(normalize_url("mailto:a@example.com", &[], &mailto).unwrap(), "mailto:a@example.com");
+    }
+
+    #[test]
+    fn appends_and_replaces_query_parameters() {
+        let params = [("utm_source", "poster"), ("utm_medium", "print")];
+        assert_eq!(
+            normalize_url("example.com/?utm_source=old&id=7", &params, &[]).unwrap(),
+            "https://example.com/?id=7&utm_source=poster&utm_medium=print"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Names the built-in dot and eye styles already use
+const BUILT_IN: [&str; 6] = ["square", "circle", "rounded", "diamond", "frame", "random"];
+
+/// A module shape drawn into one square cell of the code; plugins add these under their own names.
+/// Programs using the library can implement it directly and hand it over in a [`Registry`]:
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// struct Plus;
+///
+/// impl rustqr::ModuleRenderer for Plus {
+///     fn name(&self) -> &str {
+///         "plus"
+///     }
+///
+///     fn contains(&self, u: f32, v: f32) -> bool {
+///         (0.35..0.65).contains(&u) || (0.35..0.65).contains(&v)
+///     }
+/// }
+///
+/// let mut styles = rustqr::Registry::default();
+/// styles.register(Arc::new(Plus))?;
+/// let png = rustqr::RenderOptions::new("https://example.com").styles(styles).dot_style("plus").render_to_bytes("png")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub trait ModuleRenderer: Send + Sync {
+    /// The name given to `--dot-style` and `--eye-style`
+    fn name(&self) -> &str;
+
+    /// Whether the point (u, v), both running from 0 to 1 across the cell, is inked
+    fn contains(&self, u: f32, v: f32) -> bool;
+
+    /// Inks every pixel whose centre lies inside the shape
+    fn draw(&self, img: &mut RgbaImage, x: u32, y: u32, scale: u32, color: Rgba<u8>) {
+        for dy in 0..scale {
+            for dx in 0..scale {
+                let (px, py) = (x + dx, y + dy);
+                let (u, v) = ((dx as f32 + 0.5) / scale as f32, (dy as f32 + 0.5) / scale as f32);
+                if px < img.width() && py < img.height() && self.contains(u, v) {
+                    img.put_pixel(px, py, color);
+                }
+            }
+        }
+    }
+}
+
+/// Module styles loaded with `--plugin` or registered by a program, looked up by name
+#[derive(Clone, Default)]
+pub struct Registry {
+    renderers: Vec<Arc<dyn ModuleRenderer>>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.renderers.iter().map(|r| r.name())).finish()
+    }
+}
+
+impl Registry {
+    /// Adds a style under its name, which must not clash with a built-in or registered one
+    pub fn register(&mut self, renderer: Arc<dyn ModuleRenderer>) -> Result<()> {
+        let name = renderer.name();
+        if name.is_empty() || name.contains([',', ':']) || name.chars().any(char::is_whitespace) {
+            anyhow::bail!("Plugin style name '{}' must be a single word without ',' or ':'", name);
+        }
+        if BUILT_IN.contains(&name.to_lowercase().as_str()) || self.get(name).is_some() {
+            anyhow::bail!("A style named '{}' already exists", name);
+        }
+        self.renderers.push(renderer);
+        Ok(())
+    }
+
+    /// The style registered as `name`, ignoring case
+    pub fn get(&self, name: &str) -> Option<Arc<dyn ModuleRenderer>> {
+        self.renderers.iter().find(|r| r.name().eq_ignore_ascii_case(name.trim())).cloned()
+    }
+
+    /// Loads a shared library plugin and registers every style it exports
+    #[cfg(feature = "plugins")]
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        for style in library::load(path)? {
+            self.register(style)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "plugins"))]
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        anyhow::bail!("This build has no plugin support, {} needs the plugins feature", path.display())
+    }
+}
+
+/// Shared library plugins export a small C interface, so they can be written in any language:
+///
+/// ```c
+/// uint32_t rustqr_plugin_abi(void);                    // returns 1
+/// uint32_t rustqr_style_count(void);
+/// const char *rustqr_style_name(uint32_t style);       // UTF-8, NUL-terminated, never freed
+/// bool rustqr_style_contains(uint32_t style, float u, float v);
+/// ```
+#[cfg(feature = "plugins")]
+mod library {
+    use super::ModuleRenderer;
+    use anyhow::{Context, Result};
+    use libloading::Library;
+    use std::ffi::{CStr, c_char};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    const ABI_VERSION: u32 = 1;
+
+    type ContainsFn = unsafe extern "C" fn(u32, f32, f32) -> bool;
+
+    struct LibraryStyle {
+        name: String,
+        index: u32,
+        contains: ContainsFn,
+        // Keeps the code behind `contains` mapped
+        _library: Arc<Library>,
+    }
+
+    impl ModuleRenderer for LibraryStyle {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn contains(&self, u: f32, v: f32) -> bool {
+            // SAFETY: the library stays loaded while `_library` is alive, and the ABI version
+            // was checked when it was loaded
+            unsafe { (self.contains)(self.index, u, v) }
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Vec<Arc<dyn ModuleRenderer>>> {
+        let fail = || format!("Failed to load style plugin {}", path.display());
+        // SAFETY: loading runs the library's initialisers; plugins are trusted like any other
+        // code the user chooses to run
+        let library = Arc::new(unsafe { Library::new(path) }.with_context(fail)?);
+
+        // SAFETY: the symbol types match the interface documented above
+        unsafe {
+            let abi = library.get::<unsafe extern "C" fn() -> u32>(b"rustqr_plugin_abi").with_context(fail)?;
+            if abi() != ABI_VERSION {
+                anyhow::bail!("{} was built for plugin interface {}, RustQR uses {}", path.display(), abi(), ABI_VERSION);
+            }
+            let count = library.get::<unsafe extern "C" fn() -> u32>(b"rustqr_style_count").with_context(fail)?;
+            let name = library.get::<unsafe extern "C" fn(u32) -> *const c_char>(b"rustqr_style_name").with_context(fail)?;
+            let contains = *library.get::<ContainsFn>(b"rustqr_style_contains").with_context(fail)?;
+
+            let mut styles: Vec<Arc<dyn ModuleRenderer>> = Vec::new();
+            for index in 0..count() {
+                let pointer = name(index);
+                if pointer.is_null() {
+                    anyhow::bail!("{} has no name for style {}", path.display(), index);
+                }
+                let name = CStr::from_ptr(pointer)
+                    .to_str()
+                    .with_context(|| format!("{} has a style name that is not UTF-8", path.display()))?
+                    .to_string();
+                styles.push(Arc::new(LibraryStyle { name, index, contains, _library: library.clone() }));
+            }
+            if styles.is_empty() {
+                anyhow::bail!("{} exports no styles", path.display());
+            }
+            Ok(styles)
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::Args;
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// The file of preset `name`: saved command-line options that `--preset NAME` fills in
+fn preset_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("Preset name '{}' may only use letters, digits, '-' and '_'", name);
+    }
+    Ok(presets_dir()?.join(format!("{}.json", name)))
+}
+
+fn presets_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("RUSTQR_PRESETS").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let dir = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    let dir = dir.context("Cannot find a config directory for presets, set RUSTQR_PRESETS to a directory")?;
+    Ok(dir.join("rustqr").join("presets"))
+}
+
+/// Names of the saved presets, sorted
+fn names() -> Vec<String> {
+    let Ok(entries) = presets_dir().and_then(|dir| Ok(std::fs::read_dir(dir)?)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let stem = path.file_stem()?.to_string_lossy().into_owned();
+            (path.extension()? == "json").then_some(stem)
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Saves command-line options as preset `name`, a JSON array of arguments; returns the file
+#[cfg(feature = "cli")]
+pub fn save(name: &str, arguments: &[String]) -> Result<PathBuf> {
+    let path = preset_path(name)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let text = serde_json::to_string_pretty(arguments)?;
+    std::fs::write(&path, text + "\n").with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// The command line with preset `name` filled in: its options come first, and any option
+/// the command line gives itself replaces the preset's
+pub fn apply(name: &str, invocation: &[String]) -> Result<Vec<String>> {
+    let path = preset_path(name)?;
+    let text = std::fs::read_to_string(&path).map_err(|_| {
+        let names = names();
+        if names.is_empty() {
+            anyhow::anyhow!("No preset named '{}', save one from --interactive", name)
+        } else {
+            anyhow::anyhow!("No preset named '{}', saved presets: {}", name, names.join(", "))
+        }
+    })?;
+    let preset: Vec<String> =
+        serde_json::from_str(&text).with_context(|| format!("{} must be a JSON array of command-line arguments", path.display()))?;
+
+    let invocation = option_groups(invocation);
+    let given: HashSet<&str> = invocation.iter().filter_map(|(id, _)| id.as_deref()).collect();
+    let preset = option_groups(&preset)
+        .into_iter()
+        .filter(|(id, _)| id.as_deref().is_some_and(|id| !given.contains(id)));
+    Ok(preset
+        .chain(invocation.iter().filter(|(id, _)| id.as_deref() != Some("preset")).cloned())
+        .flat_map(|(_, tokens)| tokens)
+        .collect())
+}
+
+/// Splits a command line into RustQR's options, each with the tokens of its value, so recorded
+/// settings can be replayed with some options replaced. Words and options RustQR does not know at
+/// the top level, like a payload subcommand and its fields, come out one token at a time without an ID.
+pub fn option_groups(arguments: &[String]) -> Vec<(Option<String>, Vec<String>)> {
+    let mut command = Args::command();
+    command.build();
+    let mut groups = Vec::new();
+    let mut tokens = arguments.iter().peekable();
+    while let Some(token) = tokens.next() {
+        if token == "--" {
+            groups.push((None, std::iter::once(token).chain(tokens).cloned().collect()));
+            break;
+        }
+        // --name, --name=value, -x or -xvalue
+        let (arg, attached) = if let Some(long) = token.strip_prefix("--") {
+            let (name, value) = long.split_once('=').map_or((long, None), |(name, value)| (name, Some(value)));
+            (command.get_arguments().find(|arg| arg.get_long() == Some(name)), value.is_some())
+        } else if let Some(short) = token.strip_prefix('-').and_then(|rest| rest.chars().next()) {
+            (command.get_arguments().find(|arg| arg.get_short() == Some(short)), token.len() > 1 + short.len_utf8())
+        } else {
+            (None, false)
+        };
+        let Some(arg) = arg else {
+            groups.push((None, vec![token.clone()]));
+            continue;
+        };
+
+        let mut group = vec![token.clone()];
+        if arg.get_action().takes_values() && !attached {
+            let optional = arg.get_num_args().is_some_and(|range| range.min_values() == 0);
+            if let Some(value) = tokens.next_if(|value| !optional || !value.starts_with('-')) {
+                group.push(value.clone());
+            }
+        }
+        groups.push((Some(arg.get_id().to_string()), group));
+    }
+    groups
+}
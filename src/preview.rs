@@ -0,0 +1,50 @@
+use anyhow::Result;
+use image::DynamicImage;
+use minifb::{Key, KeyRepeat, Scale, Window, WindowOptions};
+
+/// Images wider or taller than this are shrunk to fit the screen
+const MAX_ACTUAL_SIZE: u32 = 1200;
+
+/// Shows the rendered image in a window; Enter or Y accepts, Esc, N or closing the window cancels
+pub fn confirm(img: &DynamicImage) -> Result<bool> {
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    // Transparent areas are drawn over a light checkerboard
+    let buffer: Vec<u32> = rgba
+        .enumerate_pixels()
+        .map(|(x, y, pixel)| {
+            let checker = if (x / 8 + y / 8) % 2 == 0 { 255 } else { 204 };
+            let alpha = pixel[3] as u32;
+            let blend = |c: u8| (c as u32 * alpha + checker * (255 - alpha)) / 255;
+            (blend(pixel[0]) << 16) | (blend(pixel[1]) << 8) | blend(pixel[2])
+        })
+        .collect();
+
+    let options = WindowOptions {
+        scale: if width.max(height) > MAX_ACTUAL_SIZE { Scale::FitScreen } else { Scale::X1 },
+        ..WindowOptions::default()
+    };
+    let mut window = Window::new(
+        "RustQR preview - Enter to save, Esc to cancel",
+        width as usize,
+        height as usize,
+        options,
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open preview window: {}", e))?;
+    window.set_target_fps(30);
+
+    while window.is_open() {
+        for key in window.get_keys_pressed(KeyRepeat::No) {
+            match key {
+                Key::Enter | Key::Y => return Ok(true),
+                Key::Escape | Key::N => return Ok(false),
+                _ => {}
+            }
+        }
+        window
+            .update_with_buffer(&buffer, width as usize, height as usize)
+            .map_err(|e| anyhow::anyhow!("Failed to draw preview: {}", e))?;
+    }
+    Ok(false)
+}
@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+pub enum PrintJob {
+    /// Rendered image, rasterized by the print system
+    Png(Vec<u8>),
+    /// Printer-language program (EPL/TSPL) passed through untouched
+    Raw(Vec<u8>),
+}
+
+#[cfg(not(windows))]
+pub fn send_to_printer(job: &PrintJob, printer: Option<&str>, copies: u32, media: Option<&str>) -> Result<()> {
+    if copies == 0 {
+        anyhow::bail!("Copies must be at least 1");
+    }
+
+    let mut cmd = Command::new("lp");
+    if let Some(name) = printer {
+        cmd.arg("-d").arg(name);
+    }
+    cmd.arg("-n").arg(copies.to_string());
+    if let Some(media) = media {
+        cmd.arg("-o").arg(format!("media={}", media));
+    }
+
+    let data = match job {
+        PrintJob::Png(data) => {
+            cmd.arg("-o").arg("fit-to-page");
+            data
+        }
+        PrintJob::Raw(data) => {
+            cmd.arg("-o").arg("raw");
+            data
+        }
+    };
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .context("Failed to run 'lp'. Is CUPS installed?")?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open 'lp' input")?
+        .write_all(data)
+        .context("Failed to send data to 'lp'")?;
+
+    let status = child.wait().context("Failed to wait for 'lp'")?;
+    if !status.success() {
+        anyhow::bail!("'lp' exited with {}", status);
+    }
+    Ok(())
+}
+
+/// Windows has no stdin-driven spooler CLI, so the job goes through a temp file:
+/// images are printed by mspaint, raw programs are copied to the printer share
+#[cfg(windows)]
+pub fn send_to_printer(job: &PrintJob, printer: Option<&str>, copies: u32, media: Option<&str>) -> Result<()> {
+    if copies == 0 {
+        anyhow::bail!("Copies must be at least 1");
+    }
+    if media.is_some() {
+        log::warn!("⚠ --media is ignored on Windows; set the paper size in the printer preferences");
+    }
+
+    let (data, ext) = match job {
+        PrintJob::Png(data) => (data, "png"),
+        PrintJob::Raw(data) => (data, "prn"),
+    };
+    let path = std::env::temp_dir().join(format!("rustqr-print-{}.{}", std::process::id(), ext));
+    std::fs::write(&path, data).context("Failed to write print spool file")?;
+
+    let result = (0..copies).try_for_each(|_| {
+        let status = match job {
+            PrintJob::Png(_) => {
+                let mut cmd = Command::new("mspaint");
+                match printer {
+                    Some(name) => cmd.arg("/pt").arg(&path).arg(name),
+                    None => cmd.arg("/p").arg(&path),
+                };
+                cmd.status()
+            }
+            PrintJob::Raw(_) => {
+                let name = printer.context("Raw printing on Windows needs a shared printer name")?;
+                Command::new("cmd")
+                    .arg("/C")
+                    .arg("copy")
+                    .arg("/b")
+                    .arg(&path)
+                    .arg(format!(r"\\localhost\{}", name))
+                    .stdout(Stdio::null())
+                    .status()
+            }
+        }
+        .context("Failed to start the print command")?;
+
+        if !status.success() {
+            anyhow::bail!("Print command exited with {}", status);
+        }
+        Ok(())
+    });
+
+    let _ = std::fs::remove_file(&path);
+    result
+}
@@ -0,0 +1,225 @@
+use image::RgbaImage;
+use std::collections::HashMap;
+
+/// Neighbors whose brightness differs by more than this mark a module edge, which is never dithered
+const EDGE_THRESHOLD: i32 = 64;
+
+/// 4×4 Bayer matrix for ordered dithering
+const BAYER: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dither {
+    None,
+    Ordered,
+    FloydSteinberg,
+}
+
+impl Dither {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Some(Dither::None),
+            "ordered" | "bayer" => Some(Dither::Ordered),
+            "floyd-steinberg" | "floyd" | "fs" => Some(Dither::FloydSteinberg),
+            _ => None,
+        }
+    }
+}
+
+/// An image reduced to at most 256 colors
+pub struct Indexed {
+    pub palette: Vec<[u8; 3]>,
+    pub pixels: Vec<u8>,
+    pub transparent: Option<u8>,
+}
+
+/// Reduces the image to a palette of at most 256 colors (one of them transparent when the image
+/// has transparency, GIF only knows on and off). Images that already fit are mapped exactly,
+/// others get a median-cut palette and the chosen dithering away from module edges.
+pub fn quantize(img: &RgbaImage, dither: Dither) -> Indexed {
+    let is_transparent = |alpha: u8| alpha < 128;
+    let has_transparency = img.pixels().any(|p| is_transparent(p[3]));
+    let max_colors = if has_transparency { 255 } else { 256 };
+
+    let mut histogram: HashMap<[u8; 3], u32> = HashMap::new();
+    for p in img.pixels().filter(|p| !is_transparent(p[3])) {
+        *histogram.entry([p[0], p[1], p[2]]).or_default() += 1;
+    }
+    let mut colors: Vec<([u8; 3], u32)> = histogram.into_iter().collect();
+    // Sorted so the palette does not depend on hash order and output stays reproducible
+    colors.sort_unstable();
+
+    let exact = colors.len() <= max_colors;
+    let mut palette: Vec<[u8; 3]> = if exact {
+        colors.iter().map(|(color, _)| *color).collect()
+    } else {
+        median_cut(colors, max_colors)
+    };
+    let transparent = has_transparency.then(|| {
+        palette.push([0, 0, 0]);
+        (palette.len() - 1) as u8
+    });
+
+    let dither = if exact { Dither::None } else { dither };
+    let edges = if dither == Dither::None { Vec::new() } else { edge_mask(img) };
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let opaque = &palette[..palette.len() - transparent.is_some() as usize];
+    let mut cache: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut nearest = |color: [u8; 3]| *cache.entry(color).or_insert_with(|| nearest_index(opaque, color));
+
+    // Floyd–Steinberg carries the error of the current and the next row
+    let mut errors = vec![[0f32; 3]; width * 2];
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let p = img.get_pixel(x as u32, y as u32);
+            if is_transparent(p[3]) {
+                pixels.push(transparent.unwrap_or(0));
+                continue;
+            }
+            let on_edge = edges.get(y * width + x).copied().unwrap_or(false);
+            let color = [p[0], p[1], p[2]];
+
+            let index = match dither {
+                _ if on_edge => nearest(color),
+                Dither::None => nearest(color),
+                Dither::Ordered => {
+                    let offset = (BAYER[y % 4][x % 4] / 16.0 - 0.5) * 32.0;
+                    nearest(color.map(|c| (c as f32 + offset).clamp(0.0, 255.0) as u8))
+                }
+                Dither::FloydSteinberg => {
+                    let carried = errors[x];
+                    let wanted = [0, 1, 2].map(|i| (color[i] as f32 + carried[i]).clamp(0.0, 255.0));
+                    let index = nearest(wanted.map(|c| c.round() as u8));
+                    let error = [0, 1, 2].map(|i| wanted[i] - palette[index as usize][i] as f32);
+
+                    let mut spread = |dx: isize, dy: usize, weight: f32| {
+                        let nx = x as isize + dx;
+                        if nx < 0 || nx >= width as isize || y + dy >= height {
+                            return;
+                        }
+                        // Error never flows into an edge, so boundaries stay crisp
+                        if edges[(y + dy) * width + nx as usize] {
+                            return;
+                        }
+                        let slot = &mut errors[dy * width + nx as usize];
+                        for i in 0..3 {
+                            slot[i] += error[i] * weight;
+                        }
+                    };
+                    spread(1, 0, 7.0 / 16.0);
+                    spread(-1, 1, 3.0 / 16.0);
+                    spread(0, 1, 5.0 / 16.0);
+                    spread(1, 1, 1.0 / 16.0);
+                    index
+                }
+            };
+            pixels.push(index);
+        }
+        if dither == Dither::FloydSteinberg {
+            errors.copy_within(width.., 0);
+            errors[width..].fill([0.0; 3]);
+        }
+    }
+
+    Indexed { palette, pixels, transparent }
+}
+
+/// Splits the color histogram along its widest channel until there are `max_colors` boxes,
+/// then averages each box weighted by pixel count
+fn median_cut(colors: Vec<([u8; 3], u32)>, max_colors: usize) -> Vec<[u8; 3]> {
+    let range = |bucket: &[([u8; 3], u32)], channel: usize| {
+        let (min, max) = bucket
+            .iter()
+            .fold((255u8, 0u8), |(lo, hi), (c, _)| (lo.min(c[channel]), hi.max(c[channel])));
+        max.saturating_sub(min)
+    };
+    let widest = |bucket: &[([u8; 3], u32)]| (0..3).max_by_key(|&ch| range(bucket, ch)).unwrap_or(0);
+
+    let mut buckets = vec![colors];
+    while buckets.len() < max_colors {
+        let Some((i, _)) = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, bucket)| bucket.len() > 1)
+            .max_by_key(|(_, bucket)| {
+                let population: u64 = bucket.iter().map(|(_, n)| *n as u64).sum();
+                range(bucket, widest(bucket)) as u64 * population
+            })
+        else {
+            break;
+        };
+
+        let mut bucket = buckets.swap_remove(i);
+        let channel = widest(&bucket);
+        bucket.sort_by_key(|(c, _)| c[channel]);
+        let total: u32 = bucket.iter().map(|(_, n)| n).sum();
+        let mut seen = 0;
+        let split = bucket
+            .iter()
+            .position(|(_, n)| {
+                seen += n;
+                seen * 2 >= total
+            })
+            .map_or(1, |p| (p + 1).clamp(1, bucket.len() - 1));
+        let upper = bucket.split_off(split);
+        buckets.push(bucket);
+        buckets.push(upper);
+    }
+
+    let mut palette: Vec<[u8; 3]> = buckets
+        .iter()
+        .map(|bucket| {
+            let total: u64 = bucket.iter().map(|(_, n)| *n as u64).sum();
+            [0, 1, 2].map(|ch| {
+                let sum: u64 = bucket.iter().map(|(c, n)| c[ch] as u64 * *n as u64).sum();
+                (sum / total.max(1)) as u8
+            })
+        })
+        .collect();
+    palette.sort_unstable();
+    palette.dedup();
+    palette
+}
+
+fn nearest_index(palette: &[[u8; 3]], color: [u8; 3]) -> u8 {
+    let distance = |p: &[u8; 3]| -> i32 { (0..3).map(|i| (p[i] as i32 - color[i] as i32).pow(2)).sum() };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| distance(p))
+        .map_or(0, |(i, _)| i as u8)
+}
+
+/// Pixels that differ sharply in brightness or visibility from a horizontal or vertical neighbor
+fn edge_mask(img: &RgbaImage) -> Vec<bool> {
+    let (width, height) = img.dimensions();
+    let brightness = |x: u32, y: u32| {
+        let p = img.get_pixel(x, y);
+        let luma = (p[0] as i32 * 299 + p[1] as i32 * 587 + p[2] as i32 * 114) / 1000;
+        luma * p[3] as i32 / 255
+    };
+
+    let mut mask = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let here = brightness(x, y);
+            let neighbors = [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < width).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < height).then(|| (x, y + 1)),
+            ];
+            let edge = neighbors
+                .into_iter()
+                .flatten()
+                .any(|(nx, ny)| (brightness(nx, ny) - here).abs() > EDGE_THRESHOLD);
+            mask.push(edge);
+        }
+    }
+    mask
+}
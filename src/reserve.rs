@@ -0,0 +1,191 @@
+use anyhow::{Context, Result};
+use qrcode::bits::Bits;
+use qrcode::ec::construct_codewords;
+use qrcode::{QrCode, Version};
+
+/// A `--reserve` request: size in modules and an optional top-left corner, centred otherwise
+pub struct Region {
+    pub width: usize,
+    pub height: usize,
+    pub position: Option<(usize, usize)>,
+}
+
+impl Region {
+    /// Parses "WxH" or "WxH@X,Y"
+    pub fn parse(spec: &str) -> Result<Self> {
+        let usage = || format!("Invalid --reserve '{}', use WxH or WxH@X,Y in modules, e.g. 9x7 or 9x7@12,14", spec);
+        let (size, position) = match spec.split_once('@') {
+            Some((size, position)) => (size, Some(position)),
+            None => (spec, None),
+        };
+        let (width, height) = size.trim().split_once(['x', 'X', '×']).with_context(usage)?;
+        let width: usize = width.trim().parse().ok().with_context(usage)?;
+        let height: usize = height.trim().parse().ok().with_context(usage)?;
+        if width == 0 || height == 0 {
+            anyhow::bail!("--reserve needs a region of at least 1x1 modules");
+        }
+        let position = match position {
+            Some(position) => {
+                let (x, y) = position.split_once(',').with_context(usage)?;
+                Some((x.trim().parse().ok().with_context(usage)?, y.trim().parse().ok().with_context(usage)?))
+            }
+            None => None,
+        };
+        Ok(Region { width, height, position })
+    }
+}
+
+/// A region cleared out of a QR code, with the error correction it costs
+#[derive(Clone, Copy)]
+pub struct Reservation {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Codewords lost in the hardest-hit block, and how many that block can correct
+    pub damaged: usize,
+    pub correctable: usize,
+    /// Whether alignment patterns are covered; they only help readers with distorted codes
+    pub covers_alignment: bool,
+}
+
+impl Reservation {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Checks that clearing `region` leaves every finder, timing and format module intact and costs
+/// each block at most three quarters of its error correction, keeping the rest for real damage
+pub fn check(qr: &QrCode, region: &Region) -> Result<Reservation> {
+    let size = qr.width();
+    if region.width > size || region.height > size {
+        anyhow::bail!("A {}x{} region does not fit in a {}×{} code", region.width, region.height, size, size);
+    }
+    let (x, y) = region.position.unwrap_or(((size - region.width) / 2, (size - region.height) / 2));
+    if x + region.width > size || y + region.height > size {
+        anyhow::bail!("A {}x{} region at {},{} runs off the {}×{} code", region.width, region.height, x, y, size, size);
+    }
+    let (version, ec_level) = (qr.version(), qr.error_correction_level());
+    let mut reservation = Reservation {
+        x,
+        y,
+        width: region.width,
+        height: region.height,
+        damaged: 0,
+        correctable: 0,
+        covers_alignment: false,
+    };
+
+    let (block_of, blocks, correctable) = codeword_blocks(qr)?;
+    let mut damaged = vec![false; block_of.len()];
+    let data_modules = placement_order(qr);
+    for ry in y..y + region.height {
+        for rx in x..x + region.width {
+            if is_structural(version, size, rx, ry) {
+                anyhow::bail!("Module {},{} belongs to a finder, timing or format pattern and has to stay visible", rx, ry);
+            }
+            if qr.is_functional(rx, ry) {
+                reservation.covers_alignment = true;
+            }
+        }
+    }
+    // Every codeword that has a bit inside the region counts as an error
+    for (bit, &(mx, my)) in data_modules.iter().enumerate() {
+        if reservation.contains(mx, my) && bit / 8 < damaged.len() {
+            damaged[bit / 8] = true;
+        }
+    }
+    let mut per_block = vec![0; blocks];
+    for (codeword, &hit) in damaged.iter().enumerate() {
+        if hit {
+            per_block[block_of[codeword]] += 1;
+        }
+    }
+
+    reservation.damaged = per_block.into_iter().max().unwrap_or(0);
+    reservation.correctable = correctable;
+    if reservation.damaged * 4 > correctable * 3 {
+        let (Version::Normal(v) | Version::Micro(v)) = version;
+        anyhow::bail!(
+            "Clearing {}x{} modules costs {} of the {} codewords a block can correct at version {}, level {:?}",
+            region.width,
+            region.height,
+            reservation.damaged,
+            correctable,
+            v,
+            ec_level
+        );
+    }
+    Ok(reservation)
+}
+
+/// Whether the region covers an alignment pattern other than the one in the middle of the code,
+/// which anything centered hides from version 7 on
+pub fn covers_outer_alignment(qr: &QrCode, reservation: &Reservation) -> bool {
+    let (size, middle) = (qr.width(), qr.width() / 2);
+    (reservation.y..reservation.y + reservation.height).any(|y| {
+        (reservation.x..reservation.x + reservation.width).any(|x| {
+            let central = x.abs_diff(middle) <= 2 && y.abs_diff(middle) <= 2;
+            qr.is_functional(x, y) && !is_structural(qr.version(), size, x, y) && !central
+        })
+    })
+}
+
+/// The block each codeword belongs to, in the order they are placed, plus the number of blocks and
+/// how many errors one block can correct
+fn codeword_blocks(qr: &QrCode) -> Result<(Vec<usize>, usize, usize)> {
+    let (version, ec_level) = (qr.version(), qr.error_correction_level());
+    let data_len = Bits::new(version).max_len(ec_level).context("Unsupported QR version")? / 8;
+
+    // Interleaving two counters (low and high byte) shows where every data codeword ends up
+    let low: Vec<u8> = (0..data_len).map(|i| i as u8).collect();
+    let high: Vec<u8> = (0..data_len).map(|i| (i >> 8) as u8).collect();
+    let (low, ec) = construct_codewords(&low, version, ec_level).context("Unsupported QR version")?;
+    let (high, _) = construct_codewords(&high, version, ec_level).context("Unsupported QR version")?;
+    let order: Vec<usize> = low.iter().zip(&high).map(|(&l, &h)| (h as usize) << 8 | l as usize).collect();
+
+    // Blocks are dealt column by column, so the first codeword of the second column is codeword 1
+    let blocks = order.iter().position(|&i| i == 1).unwrap_or(1);
+    let starts = &order[..blocks];
+    let mut block_of: Vec<usize> = order.iter().map(|&i| starts.iter().rposition(|&s| s <= i).unwrap_or(0)).collect();
+    block_of.extend((0..ec.len()).map(|i| i % blocks));
+
+    let correctable = if blocks == 1 { qr.max_allowed_errors() } else { ec.len() / blocks / 2 };
+    Ok((block_of, blocks, correctable))
+}
+
+/// Data module positions in the order bits are written: two-column strips from the right,
+/// alternately upwards and downwards, skipping the vertical timing pattern
+fn placement_order(qr: &QrCode) -> Vec<(usize, usize)> {
+    let size = qr.width();
+    let mut modules = Vec::new();
+    let mut right = size as isize - 1;
+    let mut upwards = true;
+    while right > 0 {
+        if right == 6 {
+            right -= 1;
+        }
+        for i in 0..size {
+            let y = if upwards { size - 1 - i } else { i };
+            for x in [right as usize, right as usize - 1] {
+                if !qr.is_functional(x, y) {
+                    modules.push((x, y));
+                }
+            }
+        }
+        upwards = !upwards;
+        right -= 2;
+    }
+    modules
+}
+
+/// Finder patterns with their separators, format and version information and the timing lines
+fn is_structural(version: Version, size: usize, x: usize, y: usize) -> bool {
+    let near = |a: usize| a < 9;
+    let far = |a: usize| a >= size - 8;
+    let finders = (near(x) && near(y)) || (far(x) && near(y)) || (near(x) && far(y));
+    let version_info = matches!(version, Version::Normal(7..))
+        && ((x < 6 && (size - 11..size - 8).contains(&y)) || (y < 6 && (size - 11..size - 8).contains(&x)));
+    finders || version_info || x == 6 || y == 6
+}
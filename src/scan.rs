@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use image::{DynamicImage, RgbImage};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// Frames are thinned and scaled down so decoding keeps up with the stream
+const SCAN_FILTER: &str = "fps=5,scale='min(960,iw)':-2";
+
+/// A live video source read through ffmpeg, one decoded frame at a time
+pub struct FrameStream {
+    child: Child,
+    reader: BufReader<ChildStdout>,
+}
+
+impl FrameStream {
+    /// Opens a camera: an index or device path on Linux, an AVFoundation index on macOS and a
+    /// DirectShow device name on Windows
+    pub fn camera(device: &str) -> Result<Self> {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-loglevel", "error"]);
+        if cfg!(target_os = "macos") {
+            cmd.args(["-f", "avfoundation", "-framerate", "30", "-i", device]);
+        } else if cfg!(windows) {
+            if device.bytes().all(|b| b.is_ascii_digit()) {
+                anyhow::bail!(
+                    "On Windows --camera takes the device name, list them with: ffmpeg -list_devices true -f dshow -i dummy"
+                );
+            }
+            cmd.args(["-f", "dshow", "-i"]).arg(format!("video={}", device));
+        } else {
+            let path = if device.bytes().all(|b| b.is_ascii_digit()) { format!("/dev/video{}", device) } else { device.to_string() };
+            cmd.args(["-f", "v4l2", "-i"]).arg(path);
+        }
+        Self::spawn(cmd)
+    }
+
+    fn spawn(mut cmd: Command) -> Result<Self> {
+        cmd.args(["-vf", SCAN_FILTER, "-f", "image2pipe", "-vcodec", "ppm", "-"]);
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("Failed to run 'ffmpeg'. Is it installed and on the PATH?")?;
+        let stdout = child.stdout.take().context("Failed to read from 'ffmpeg'")?;
+        Ok(FrameStream { child, reader: BufReader::new(stdout) })
+    }
+
+    /// The next frame, or None once the device closes
+    pub fn next_frame(&mut self) -> Result<Option<DynamicImage>> {
+        // Binary PPM: "P6", width, height and max value separated by whitespace, then RGB bytes
+        let mut fields = Vec::new();
+        while fields.len() < 4 {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return self.finish();
+            }
+            fields.extend(line.split('#').next().unwrap_or("").split_whitespace().map(str::to_string));
+        }
+        if fields[0] != "P6" || fields[3] != "255" {
+            anyhow::bail!("Unexpected frame format from ffmpeg: {}", fields.join(" "));
+        }
+        let width: u32 = fields[1].parse().context("Bad frame width from ffmpeg")?;
+        let height: u32 = fields[2].parse().context("Bad frame height from ffmpeg")?;
+        let mut pixels = vec![0u8; width as usize * height as usize * 3];
+        self.reader.read_exact(&mut pixels).context("Video stream ended mid-frame")?;
+        let frame = RgbImage::from_raw(width, height, pixels).context("Bad frame size from ffmpeg")?;
+        Ok(Some(DynamicImage::ImageRgb8(frame)))
+    }
+
+    fn finish(&mut self) -> Result<Option<DynamicImage>> {
+        let status = self.child.wait().context("Failed to wait for 'ffmpeg'")?;
+        if !status.success() {
+            anyhow::bail!("'ffmpeg' exited with {}, check that the camera exists and is not in use", status);
+        }
+        Ok(None)
+    }
+}
+
+impl Drop for FrameStream {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Screenshots of every screen: screencapture on macOS, grim on Wayland and ffmpeg elsewhere
+pub fn capture_screens() -> Result<Vec<DynamicImage>> {
+    let dir = std::env::temp_dir();
+    let path = |i: usize| dir.join(format!("rustqr-screen-{}-{}.png", std::process::id(), i));
+
+    // screencapture writes one file per display, as many as it is given names for
+    let (mut cmd, tool, files) = if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("screencapture");
+        cmd.args(["-x", "-t", "png"]);
+        cmd.args((0..8).map(path));
+        (cmd, "screencapture", 8)
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut cmd = Command::new("grim");
+        cmd.arg(path(0));
+        (cmd, "grim", 1)
+    } else {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-loglevel", "error", "-y"]);
+        if cfg!(windows) {
+            cmd.args(["-f", "gdigrab", "-i", "desktop"]);
+        } else {
+            let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+            cmd.args(["-f", "x11grab", "-i"]).arg(display);
+        }
+        cmd.args(["-frames:v", "1"]).arg(path(0));
+        (cmd, "ffmpeg", 1)
+    };
+
+    let status = cmd
+        .stdin(Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run '{}'. Is it installed and on the PATH?", tool))?;
+    let mut screens = Vec::new();
+    for i in 0..files {
+        if let Ok(img) = image::open(path(i)) {
+            screens.push(img);
+        }
+        let _ = std::fs::remove_file(path(i));
+    }
+    if !status.success() || screens.is_empty() {
+        anyhow::bail!("'{}' could not capture the screen ({})", tool, status);
+    }
+    Ok(screens)
+}
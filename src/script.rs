@@ -0,0 +1,112 @@
+use crate::symbol::Symbol;
+use anyhow::Result;
+use std::path::Path;
+
+/// What a style script asked for one module; None keeps the usual drawing
+#[derive(Default)]
+pub struct ModuleStyle {
+    /// Whether the module is drawn at all; by default only dark modules are
+    pub draw: Option<bool>,
+    /// A dot style name, including plugin styles
+    pub shape: Option<String>,
+    pub color: Option<String>,
+}
+
+/// Dark modules among the eight around (x, y)
+#[cfg(feature = "scripting")]
+fn dark_neighbors(symbol: &Symbol, x: usize, y: usize) -> i64 {
+    let mut count = 0;
+    for ny in y.saturating_sub(1)..=(y + 1).min(symbol.height() - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(symbol.width() - 1) {
+            if (nx, ny) != (x, y) && symbol.is_dark(nx, ny) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// A Rhai script defining `fn style(x, y, is_dark, is_eye, neighbors)`, called once per module.
+/// It returns `()` to keep the module as it is, `false` to leave it out, `true` to draw it even when
+/// light, a style name such as "circle", or a map like `#{ shape: "diamond", color: "#d62828" }`.
+#[cfg(feature = "scripting")]
+pub struct StyleScript {
+    engine: rhai::Engine,
+    ast: rhai::AST,
+    scope: rhai::Scope<'static>,
+}
+
+#[cfg(feature = "scripting")]
+impl StyleScript {
+    pub fn load(path: &Path) -> Result<Self> {
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| anyhow::anyhow!("Failed to load style script {}: {}", path.display(), e))?;
+        if !ast.iter_functions().any(|f| f.name == "style" && f.params.len() == 5) {
+            anyhow::bail!("{} must define fn style(x, y, is_dark, is_eye, neighbors)", path.display());
+        }
+
+        // Top-level statements run once, so constants and lookup tables are not rebuilt per module
+        let mut scope = rhai::Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| anyhow::anyhow!("Style script {} failed: {}", path.display(), e))?;
+        Ok(StyleScript { engine, ast, scope })
+    }
+
+    pub fn style(&mut self, symbol: &Symbol, x: usize, y: usize) -> Result<ModuleStyle> {
+        let arguments = (x as i64, y as i64, symbol.is_dark(x, y), symbol.in_eye(x, y), dark_neighbors(symbol, x, y));
+        let options = rhai::CallFnOptions::new().eval_ast(false);
+        let result: rhai::Dynamic = self
+            .engine
+            .call_fn_with_options(options, &mut self.scope, &self.ast, "style", arguments)
+            .map_err(|e| anyhow::anyhow!("Style script failed at module {},{}: {}", x, y, e))?;
+
+        let text = |value: &rhai::Dynamic, key: &str| {
+            value
+                .clone()
+                .into_string()
+                .map_err(|kind| anyhow::anyhow!("Style script returned a {} for '{}' at module {},{}, expected a string", kind, key, x, y))
+        };
+        if result.is_unit() {
+            Ok(ModuleStyle::default())
+        } else if let Ok(draw) = result.as_bool() {
+            Ok(ModuleStyle { draw: Some(draw), ..Default::default() })
+        } else if result.is_string() {
+            Ok(ModuleStyle { shape: Some(text(&result, "shape")?), ..Default::default() })
+        } else if let Some(map) = result.clone().try_cast::<rhai::Map>() {
+            let mut style = ModuleStyle::default();
+            for (key, value) in &map {
+                match key.as_str() {
+                    "draw" => {
+                        let draw = value.as_bool().map_err(|kind| {
+                            anyhow::anyhow!("Style script returned a {} for 'draw' at module {},{}, expected true or false", kind, x, y)
+                        })?;
+                        style.draw = Some(draw);
+                    }
+                    "shape" => style.shape = Some(text(value, "shape")?),
+                    "color" => style.color = Some(text(value, "color")?),
+                    other => anyhow::bail!("Style script returned an unknown key '{}', use draw, shape or color", other),
+                }
+            }
+            Ok(style)
+        } else {
+            anyhow::bail!("Style script returned a {} at module {},{}, expected (), a bool, a style name or a map", result.type_name(), x, y)
+        }
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+pub enum StyleScript {}
+
+#[cfg(not(feature = "scripting"))]
+impl StyleScript {
+    pub fn load(path: &Path) -> Result<Self> {
+        anyhow::bail!("This build has no scripting support, {} needs the scripting feature", path.display())
+    }
+
+    pub fn style(&mut self, _symbol: &Symbol, _x: usize, _y: usize) -> Result<ModuleStyle> {
+        match *self {}
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::path::Path;
+
+/// Protected header of every JWS we write (RFC 8037)
+const JWS_HEADER: &str = r#"{"alg":"EdDSA"}"#;
+
+/// Reads an Ed25519 private key in PKCS#8 PEM form (`openssl genpkey -algorithm ed25519`)
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let pem = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read key: {}", path.display()))?;
+    SigningKey::from_pkcs8_pem(&pem)
+        .map_err(|e| anyhow::anyhow!("{} is not an Ed25519 private key: {}", path.display(), e))
+}
+
+/// Reads an Ed25519 public key PEM; a private key works too, its public half is used
+pub fn load_verifying_key(path: &Path) -> Result<VerifyingKey> {
+    let pem = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read key: {}", path.display()))?;
+    if let Ok(key) = VerifyingKey::from_public_key_pem(&pem) {
+        return Ok(key);
+    }
+    SigningKey::from_pkcs8_pem(&pem)
+        .map(|key| key.verifying_key())
+        .map_err(|_| anyhow::anyhow!("{} is not an Ed25519 public key", path.display()))
+}
+
+/// Wraps the data in a compact JWS: `header.payload.signature`, all base64url
+pub fn sign(data: &str, key: &SigningKey) -> String {
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(JWS_HEADER),
+        URL_SAFE_NO_PAD.encode(data)
+    );
+    let signature = key.sign(signing_input.as_bytes());
+    format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+}
+
+/// Checks a compact JWS against the key and returns the signed payload
+pub fn verify(jws: &str, key: &VerifyingKey) -> Result<String> {
+    let jws = jws.trim();
+    let (header, payload, signature) =
+        split_jws(jws).ok_or_else(|| anyhow::anyhow!("The code does not hold a signed payload"))?;
+
+    let header = decode_segment(header)?;
+    let header: String = String::from_utf8_lossy(&header).split_whitespace().collect();
+    if !header.contains(r#""alg":"EdDSA""#) {
+        anyhow::bail!("Unsupported JWS algorithm, only EdDSA (Ed25519) is supported");
+    }
+
+    let signature = Signature::from_slice(&decode_segment(signature)?)
+        .map_err(|_| anyhow::anyhow!("Malformed signature"))?;
+    let (signing_input, _) = jws.rsplit_once('.').unwrap_or_default();
+    key.verify(signing_input.as_bytes(), &signature)
+        .map_err(|_| anyhow::anyhow!("Signature does not match, the code may have been tampered with"))?;
+
+    Ok(String::from_utf8(decode_segment(payload)?)?)
+}
+
+/// True for anything shaped like a compact JWS with a JSON header
+pub fn is_signed(payload: &str) -> bool {
+    split_jws(payload.trim())
+        .and_then(|(header, _, _)| URL_SAFE_NO_PAD.decode(header).ok())
+        .is_some_and(|header| header.starts_with(b"{") && header.ends_with(b"}"))
+}
+
+fn split_jws(jws: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = jws.split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(payload), Some(signature), None) => Some((header, payload, signature)),
+        _ => None,
+    }
+}
+
+fn decode_segment(segment: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| anyhow::anyhow!("Signed payload is not valid base64url"))
+}
@@ -1,30 +1,121 @@
 use anyhow::{Context, Result};
 use image::{Rgba, RgbaImage};
 
+use crate::effects::noise;
+use crate::plugin::{ModuleRenderer, Registry};
+use std::sync::Arc;
+
 pub enum DotStyle {
     Square,
     Circle,
     Rounded,
+    Diamond,
+    /// A shape loaded with `--plugin`
+    Plugin(Arc<dyn ModuleRenderer>),
+}
+
+/// The dot style of every module: one style, or a seeded random pick per module
+pub enum DotPattern {
+    Single(DotStyle),
+    Random(Vec<DotStyle>),
 }
 
 pub enum EyeStyle {
     Square,
     Circle,
     Frame,
+    Plugin(Arc<dyn ModuleRenderer>),
 }
 
 impl DotStyle {
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_str(s: &str, plugins: &Registry) -> Self {
+        if let Some(renderer) = plugins.get(s) {
+            return DotStyle::Plugin(renderer);
+        }
         match s.to_lowercase().as_str() {
             "circle" => DotStyle::Circle,
             "rounded" => DotStyle::Rounded,
+            "diamond" => DotStyle::Diamond,
             _ => DotStyle::Square,
         }
     }
+
+    fn parse(s: &str, plugins: &Registry) -> Option<Self> {
+        if let Some(renderer) = plugins.get(s) {
+            return Some(DotStyle::Plugin(renderer));
+        }
+        match s.trim().to_lowercase().as_str() {
+            "square" => Some(DotStyle::Square),
+            "circle" => Some(DotStyle::Circle),
+            "rounded" => Some(DotStyle::Rounded),
+            "diamond" => Some(DotStyle::Diamond),
+            _ => None,
+        }
+    }
+}
+
+impl DotPattern {
+    /// `random:circle,square,diamond` mixes the listed styles (`random` alone mixes all of them),
+    /// anything else is a single style
+    pub fn from_str(s: &str, plugins: &Registry) -> Result<Self> {
+        let lower = s.to_lowercase();
+        let Some(list) = lower.strip_prefix("random") else {
+            return Ok(DotPattern::Single(DotStyle::from_str(s, plugins)));
+        };
+        let list = match list.strip_prefix(':') {
+            Some(list) => list,
+            None if list.is_empty() => "square,circle,rounded,diamond",
+            None => return Ok(DotPattern::Single(DotStyle::from_str(s, plugins))),
+        };
+
+        let styles = list
+            .split(',')
+            .map(|name| {
+                DotStyle::parse(name, plugins).with_context(|| {
+                    format!("Unknown dot style '{}' in '{}'. Use square, circle, rounded or diamond", name.trim(), s)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if styles.is_empty() {
+            anyhow::bail!("'{}' lists no dot styles", s);
+        }
+        Ok(DotPattern::Random(styles))
+    }
+
+    /// Like `from_str`, but an unknown style name is an error instead of square
+    pub fn parse(s: &str, plugins: &Registry) -> Result<Self> {
+        let pattern = DotPattern::from_str(s, plugins)?;
+        if matches!(pattern, DotPattern::Single(_)) && DotStyle::parse(s, plugins).is_none() {
+            anyhow::bail!("Unknown dot style '{}'. Use square, circle, rounded, diamond or random:STYLE,STYLE,...", s.trim());
+        }
+        Ok(pattern)
+    }
+
+    /// Whether any module is drawn by a plugin
+    pub fn uses_plugin(&self) -> bool {
+        match self {
+            DotPattern::Single(style) => matches!(style, DotStyle::Plugin(_)),
+            DotPattern::Random(styles) => styles.iter().any(|style| matches!(style, DotStyle::Plugin(_))),
+        }
+    }
+
+    /// The style of module (x, y); the same seed always gives the same picks
+    pub fn pick(&self, x: usize, y: usize, seed: u64) -> &DotStyle {
+        match self {
+            DotPattern::Single(style) => style,
+            DotPattern::Random(styles) => {
+                let roll = noise(x as u32, y as u32, seed);
+                &styles[((roll * styles.len() as f32) as usize).min(styles.len() - 1)]
+            }
+        }
+    }
 }
 
 impl EyeStyle {
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_str(s: &str, plugins: &Registry) -> Self {
+        if let Some(renderer) = plugins.get(s) {
+            return EyeStyle::Plugin(renderer);
+        }
         match s.to_lowercase().as_str() {
             "circle" => EyeStyle::Circle,
             "frame" => EyeStyle::Frame,
@@ -45,6 +136,8 @@ pub fn apply_dot_style(
         DotStyle::Square => draw_square(img, x, y, scale, color),
         DotStyle::Circle => draw_circle(img, x, y, scale, color),
         DotStyle::Rounded => draw_rounded_square(img, x, y, scale, color),
+        DotStyle::Diamond => draw_diamond(img, x, y, scale, color),
+        DotStyle::Plugin(renderer) => renderer.draw(img, x, y, scale, color),
     }
 }
 
@@ -60,6 +153,7 @@ pub fn apply_eye_style(
         EyeStyle::Square => draw_square(img, x, y, scale, color),
         EyeStyle::Circle => draw_circle(img, x, y, scale, color),
         EyeStyle::Frame => draw_frame(img, x, y, scale, color),
+        EyeStyle::Plugin(renderer) => renderer.draw(img, x, y, scale, color),
     }
 }
 
@@ -95,6 +189,24 @@ fn draw_circle(img: &mut RgbaImage, x: u32, y: u32, scale: u32, color: Rgba<u8>)
     }
 }
 
+fn draw_diamond(img: &mut RgbaImage, x: u32, y: u32, scale: u32, color: Rgba<u8>) {
+    let center = scale as f32 / 2.0;
+
+    for dy in 0..scale {
+        for dx in 0..scale {
+            let px = x + dx;
+            let py = y + dy;
+
+            if px < img.width() && py < img.height() {
+                let dist = (dx as f32 + 0.5 - center).abs() + (dy as f32 + 0.5 - center).abs();
+                if dist <= center {
+                    img.put_pixel(px, py, color);
+                }
+            }
+        }
+    }
+}
+
 fn draw_rounded_square(img: &mut RgbaImage, x: u32, y: u32, scale: u32, color: Rgba<u8>) {
     let corner_radius = scale as f32 * 0.3;
 
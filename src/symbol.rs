@@ -0,0 +1,191 @@
+use crate::reserve::Reservation;
+use qrcode::{Color, QrCode, Version};
+
+/// Which code to generate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbology {
+    Qr,
+    DataMatrix,
+    Aztec,
+    Code128,
+    Ean13,
+}
+
+impl Symbology {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "qr" | "qrcode" => Some(Symbology::Qr),
+            "datamatrix" | "dm" => Some(Symbology::DataMatrix),
+            "aztec" => Some(Symbology::Aztec),
+            "code128" | "128" => Some(Symbology::Code128),
+            "ean13" | "ean" => Some(Symbology::Ean13),
+            _ => None,
+        }
+    }
+
+    /// One row of bars instead of a 2D grid
+    pub fn is_linear(self) -> bool {
+        matches!(self, Symbology::Code128 | Symbology::Ean13)
+    }
+
+    /// Picks the name status messages use for the code: qr, datamatrix, aztec or barcode
+    pub fn message_kind(self) -> &'static str {
+        match self {
+            Symbology::Qr => "qr",
+            Symbology::DataMatrix => "datamatrix",
+            Symbology::Aztec => "aztec",
+            Symbology::Code128 | Symbology::Ean13 => "barcode",
+        }
+    }
+}
+
+/// QR patterns besides the eyes that scanners use to map the grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// The dotted lines running between the finder patterns
+    Timing,
+    Alignment,
+}
+
+/// A grid of modules, whatever symbology produced it. Renderers only look at this; QR specifics
+/// such as the version and error correction level stay reachable through `qr()`.
+pub struct Symbol {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+    /// Finder patterns drawn with `--eye-style`, as top-left corner and side length
+    eyes: Vec<(usize, usize, usize)>,
+    /// Timing lines and alignment patterns as x, y, width and height, alignment patterns first
+    patterns: Vec<(Pattern, usize, usize, usize, usize)>,
+    qr: Option<QrCode>,
+    symbology: Symbology,
+    /// Modules cleared with `--reserve`
+    reserved: Option<Reservation>,
+}
+
+impl Symbol {
+    pub fn from_qr(qr: QrCode) -> Self {
+        let size = qr.width();
+        let dark = qr.to_colors().into_iter().map(|c| c == Color::Dark).collect();
+        let eyes = vec![(0, 0, 7), (size - 7, 0, 7), (0, size - 7, 7)];
+        let patterns = match qr.version() {
+            Version::Normal(version) => qr_patterns(version as usize, size),
+            Version::Micro(_) => Vec::new(),
+        };
+        Symbol { width: size, height: size, dark, eyes, patterns, qr: Some(qr), symbology: Symbology::Qr, reserved: None }
+    }
+
+    /// A square symbol from rows of modules (true = dark) and its finder patterns, if any
+    pub fn from_modules(size: usize, dark: Vec<bool>, eyes: Vec<(usize, usize, usize)>, symbology: Symbology) -> Self {
+        debug_assert_eq!(dark.len(), size * size);
+        Symbol { width: size, height: size, dark, eyes, patterns: Vec::new(), qr: None, symbology, reserved: None }
+    }
+
+    /// A linear barcode: one row of bars repeated `height` times
+    pub fn from_bars(bars: &[bool], height: usize, symbology: Symbology) -> Self {
+        let dark = bars.repeat(height);
+        Symbol { width: bars.len(), height, dark, eyes: Vec::new(), patterns: Vec::new(), qr: None, symbology, reserved: None }
+    }
+
+    /// Modules per row, without the `--border` margin
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Rows of modules; the same as `width()` for everything but linear barcodes
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+
+    pub fn in_eye(&self, x: usize, y: usize) -> bool {
+        self.eye_at(x, y).is_some()
+    }
+
+    /// The finder pattern (x, y, side) containing module (x, y)
+    pub fn eye_at(&self, x: usize, y: usize) -> Option<(usize, usize, usize)> {
+        self.eyes.iter().copied().find(|&(ex, ey, side)| x >= ex && x < ex + side && y >= ey && y < ey + side)
+    }
+
+    pub fn eyes(&self) -> &[(usize, usize, usize)] {
+        &self.eyes
+    }
+
+    /// The timing line or alignment pattern module (x, y) belongs to; where an alignment pattern sits
+    /// on a timing line it counts as alignment
+    pub fn pattern_at(&self, x: usize, y: usize) -> Option<Pattern> {
+        self.patterns
+            .iter()
+            .find(|&&(_, px, py, width, height)| x >= px && x < px + width && y >= py && y < py + height)
+            .map(|&(pattern, ..)| pattern)
+    }
+
+    /// Timing lines and alignment patterns as (pattern, x, y, width, height)
+    pub fn patterns(&self) -> &[(Pattern, usize, usize, usize, usize)] {
+        &self.patterns
+    }
+
+    /// Clears the reserved modules to light
+    pub fn reserve(&mut self, reservation: Reservation) {
+        for y in reservation.y..reservation.y + reservation.height {
+            for x in reservation.x..reservation.x + reservation.width {
+                self.dark[y * self.width + x] = false;
+            }
+        }
+        self.reserved = Some(reservation);
+    }
+
+    pub fn reserved(&self) -> Option<&Reservation> {
+        self.reserved.as_ref()
+    }
+
+    pub fn symbology(&self) -> Symbology {
+        self.symbology
+    }
+
+    pub fn qr(&self) -> Option<&QrCode> {
+        self.qr.as_ref()
+    }
+
+    /// Short description for dry runs and the editor, e.g. "version 3, level M"
+    pub fn describe(&self) -> String {
+        match (&self.qr, self.symbology) {
+            (Some(qr), _) => {
+                let (Version::Normal(version) | Version::Micro(version)) = qr.version();
+                format!("version {}, level {:?}", version, qr.error_correction_level())
+            }
+            (None, Symbology::DataMatrix) => format!("Data Matrix {}×{}", self.width, self.height),
+            (None, Symbology::Aztec) => format!("Aztec {}×{}", self.width, self.height),
+            (None, Symbology::Code128) => format!("Code 128, {} modules wide", self.width),
+            (None, Symbology::Ean13) => "EAN-13".to_string(),
+            (None, Symbology::Qr) => format!("{}×{} modules", self.width, self.height),
+        }
+    }
+}
+
+/// Alignment patterns of a version 1-40 QR code, placed as ISO/IEC 18004 annex E lays them out,
+/// followed by the two timing lines
+fn qr_patterns(version: usize, size: usize) -> Vec<(Pattern, usize, usize, usize, usize)> {
+    let mut patterns = Vec::new();
+    if version >= 2 {
+        let count = version / 7 + 2;
+        let step = if version == 32 { 26 } else { (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2 };
+        let mut centers: Vec<usize> = (1..count).map(|i| size - 7 - (count - 1 - i) * step).collect();
+        centers.insert(0, 6);
+        let last = size - 7;
+        for &y in &centers {
+            for &x in &centers {
+                // The three corners hold finder patterns instead
+                if ![(6, 6), (last, 6), (6, last)].contains(&(x, y)) {
+                    patterns.push((Pattern::Alignment, x - 2, y - 2, 5, 5));
+                }
+            }
+        }
+    }
+    patterns.push((Pattern::Timing, 8, 6, size - 16, 1));
+    patterns.push((Pattern::Timing, 6, 8, 1, size - 16));
+    patterns
+}
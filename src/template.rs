@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use std::fmt::Write;
+
+/// Replaces each `{name}` or `{name:spec}` in `template` with what `resolve(name, spec)` returns,
+/// or leaves it as written when that is `None`. `{{` and `}}` stand for literal braces; other braces
+/// that do not form a placeholder are kept as they are.
+pub fn expand(template: &str, mut resolve: impl FnMut(&str, Option<&str>) -> Result<Option<String>>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let brace = &rest[start..];
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            out.push_str(&brace[..1]);
+            rest = &brace[2..];
+            continue;
+        }
+        let Some(end) = brace[1..].find(['{', '}']).map(|end| end + 1).filter(|&end| brace.starts_with('{') && brace[end..].starts_with('}')) else {
+            out.push_str(&brace[..1]);
+            rest = &brace[1..];
+            continue;
+        };
+        let placeholder = &brace[1..end];
+        let (name, spec) = match placeholder.split_once(':') {
+            Some((name, spec)) => (name, Some(spec)),
+            None => (placeholder, None),
+        };
+        match resolve(name.trim(), spec)? {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&brace[..=end]),
+        }
+        rest = &brace[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// A number formatted by a placeholder spec: none, or `0W` to zero-pad it to W digits
+pub fn format_number(n: u64, spec: Option<&str>) -> Result<String> {
+    match spec {
+        None => Ok(n.to_string()),
+        Some(spec) => match spec.strip_prefix('0').map(str::parse::<usize>) {
+            Some(Ok(width)) => Ok(format!("{:0width$}", n, width = width)),
+            _ => anyhow::bail!("Unknown number format ':{}', use e.g. {{n:06}} for six zero-padded digits", spec),
+        },
+    }
+}
+
+/// Fills the placeholders `--data` may hold: `{col:NAME}` from the batch row's columns,
+/// `{env:VAR}` from the environment and `{date}` (or `{date:%d.%m.%Y}`) with today's date.
+/// Any other placeholder is left as it is. `{{` and `}}` are escapes as in [`expand`], but only
+/// once something was filled in; data without placeholders comes back exactly as given.
+pub fn expand_data(data: &str, row: Option<&[(&str, &str)]>) -> Result<String> {
+    let mut filled = false;
+    let expanded = expand(data, |name, spec| {
+        let value = data_placeholder(name, spec, row)?;
+        filled |= value.is_some();
+        Ok(value)
+    })?;
+    Ok(if filled { expanded } else { data.to_string() })
+}
+
+fn data_placeholder(name: &str, spec: Option<&str>, row: Option<&[(&str, &str)]>) -> Result<Option<String>> {
+    match (name, spec) {
+        ("col", Some(column)) => {
+            let row = row.with_context(|| format!("{{col:{}}} takes values from --batch rows, add --batch", column))?;
+            let value = row
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(column))
+                .map(|(_, value)| *value)
+                .with_context(|| {
+                    let names: Vec<&str> = row.iter().map(|(name, _)| *name).collect();
+                    format!("The batch file has no '{}' column for {{col:{}}}, it has {}", column, column, names.join(", "))
+                })?;
+            Ok(Some(value.to_string()))
+        }
+        ("env", Some(var)) => {
+            let value = std::env::var(var).with_context(|| format!("Environment variable {} for {{env:{}}} is not set", var, var))?;
+            Ok(Some(value))
+        }
+        ("date", format) => {
+            let today = chrono::Local::now();
+            let mut text = String::new();
+            write!(text, "{}", today.format(format.unwrap_or("%Y-%m-%d")))
+                .map_err(|_| anyhow::anyhow!("Invalid date format '{}' in {{date:...}}, use strftime fields like %Y-%m-%d", format.unwrap_or_default()))?;
+            Ok(Some(text))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(template: &str, n: u64) -> Result<String> {
+        expand(template, |name, spec| match name {
+            "n" => format_number(n, spec).map(Some),
+            other => anyhow::bail!("unknown {}", other),
+        })
+    }
+
+    #[test]
+    fn expands_numbers_and_escapes() {
+        assert_eq!(sequence("T-{n:06}", 42).unwrap(), "T-000042");
+        assert_eq!(sequence("{{n}} is {n}", 7).unwrap(), "{n} is 7");
+        assert_eq!(sequence("a}b{c", 1).unwrap(), "a}b{c");
+        assert!(sequence("{x}", 1).is_err());
+        assert!(sequence("{n:6}", 1).is_err());
+    }
+
+    #[test]
+    fn data_without_placeholders_is_untouched() {
+        for data in [r#"{"a":1}"#, r#"{"a":{"b":{"c":1}}}"#, "{{x}}", "}}{{", "{unknown} {{date}}"] {
+            assert_eq!(expand_data(data, None).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn fills_columns_environment_and_dates() {
+        let row = [("Email", "a@example.com"), ("id", "7")];
+        assert_eq!(expand_data("mailto:{col:email}?n={col:id}", Some(&row)).unwrap(), "mailto:a@example.com?n=7");
+        assert!(expand_data("{col:missing}", Some(&row)).is_err());
+        assert!(expand_data("{col:email}", None).is_err());
+
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(expand_data("{env:PATH}", None).unwrap(), path);
+        assert!(expand_data("{env:RUSTQR_SURELY_UNSET_VARIABLE}", None).is_err());
+
+        let today = chrono::Local::now();
+        assert_eq!(expand_data("{date}", None).unwrap(), today.format("%Y-%m-%d").to_string());
+        assert_eq!(expand_data("{date:%Y}", None).unwrap(), today.format("%Y").to_string());
+    }
+
+    #[test]
+    fn escapes_apply_once_something_is_filled_in() {
+        let row = [("c", "1")];
+        assert_eq!(expand_data("{{date}} {col:c}", Some(&row)).unwrap(), "{date} 1");
+        assert_eq!(expand_data(r#"{"a":{"b":{"c":{col:c}}}}}}"#, Some(&row)).unwrap(), r#"{"a":{"b":{"c":1}}}"#);
+        assert_eq!(expand_data(r#"{"a":{"b":{col:c}}}"#, Some(&row)).unwrap(), r#"{"a":{"b":1}"#);
+    }
+}
@@ -0,0 +1,160 @@
+use ab_glyph::{Font, FontArc, PxScale, ScaleFont, point};
+use anyhow::{Context, Result};
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+/// Fallback font bundled into the binary so text works without any setup
+const DEFAULT_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "left" => Align::Left,
+            "right" => Align::Right,
+            _ => Align::Center,
+        }
+    }
+
+    /// Matching SVG `text-anchor` value
+    pub fn svg_anchor(self) -> &'static str {
+        match self {
+            Align::Left => "start",
+            Align::Center => "middle",
+            Align::Right => "end",
+        }
+    }
+}
+
+pub fn default_font() -> FontArc {
+    FontArc::try_from_slice(DEFAULT_FONT).expect("bundled font is valid")
+}
+
+/// Loads a TTF/OTF file, or the bundled font when no path is given
+pub fn load_font(path: Option<&Path>) -> Result<FontArc> {
+    let Some(path) = path else {
+        return Ok(default_font());
+    };
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read font file {}", path.display()))?;
+    FontArc::try_from_vec(bytes).with_context(|| format!("Invalid font file {}", path.display()))
+}
+
+/// Advance width of a single line in pixels
+pub fn text_width(font: &FontArc, size: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            width += scaled.kern(prev, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Largest size up to `max_size` at which the text fits into `max_width`
+pub fn fit_text_size(font: &FontArc, max_size: f32, text: &str, max_width: f32) -> f32 {
+    let width = text_width(font, max_size, text);
+    if width <= max_width || width == 0.0 {
+        max_size
+    } else {
+        max_size * max_width / width
+    }
+}
+
+/// Draws one line with its top edge at `y`, aligned inside `[x, x + width)`
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text(
+    img: &mut RgbaImage,
+    font: &FontArc,
+    size: f32,
+    color: Rgba<u8>,
+    text: &str,
+    x: f32,
+    y: f32,
+    width: f32,
+    align: Align,
+) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let line_width = text_width(font, size, text);
+    let start = match align {
+        Align::Left => x,
+        Align::Center => x + (width - line_width) / 2.0,
+        Align::Right => x + width - line_width,
+    };
+
+    let mut caret = start;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            caret += scaled.kern(prev, id);
+        }
+        let glyph = id.with_scale_and_position(size, point(caret, y + scaled.ascent()));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px >= 0 && py >= 0 && (px as u32) < img.width() && (py as u32) < img.height() {
+                    blend_pixel(img, px as u32, py as u32, color, coverage);
+                }
+            });
+        }
+    }
+}
+
+/// Height of one line (ascent to descent) in pixels
+pub fn line_height(font: &FontArc, size: f32) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    scaled.ascent() - scaled.descent()
+}
+
+/// Extends the canvas downwards and writes a caption line beneath the image
+pub fn add_caption(
+    img: &RgbaImage,
+    caption: &str,
+    font: &FontArc,
+    size: f32,
+    color: Rgba<u8>,
+    align: Align,
+    background: Rgba<u8>,
+) -> RgbaImage {
+    let margin = (size * 0.3).round();
+    let band = (line_height(font, size) + 2.0 * margin).ceil() as u32;
+    let mut canvas = RgbaImage::from_pixel(img.width(), img.height() + band, background);
+    image::imageops::replace(&mut canvas, img, 0, 0);
+
+    let text_w = img.width() as f32 - 2.0 * margin;
+    let size = fit_text_size(font, size, caption, text_w);
+    let y = img.height() as f32 + (band as f32 - line_height(font, size)) / 2.0;
+    draw_text(&mut canvas, font, size, color, caption, margin, y, text_w, align);
+    canvas
+}
+
+fn blend_pixel(img: &mut RgbaImage, x: u32, y: u32, color: Rgba<u8>, coverage: f32) {
+    let alpha = coverage.clamp(0.0, 1.0) * color[3] as f32 / 255.0;
+    let dst = img.get_pixel_mut(x, y);
+    let dst_alpha = dst[3] as f32 / 255.0;
+    let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+    if out_alpha <= 0.0 {
+        return;
+    }
+    for i in 0..3 {
+        let c = (color[i] as f32 * alpha + dst[i] as f32 * dst_alpha * (1.0 - alpha)) / out_alpha;
+        dst[i] = c.round() as u8;
+    }
+    dst[3] = (out_alpha * 255.0).round() as u8;
+}
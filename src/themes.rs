@@ -0,0 +1,53 @@
+/// A named set of colors and styles; every theme keeps dark modules on a light background so it scans
+pub struct Theme {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub fg_color: &'static str,
+    pub bg_color: &'static str,
+    pub gradient: Option<&'static str>,
+    pub dot_style: &'static str,
+    pub eye_style: &'static str,
+}
+
+pub const THEMES: [Theme; 4] = [
+    Theme {
+        name: "neon",
+        description: "Magenta to violet with rounded dots, for screens and event posters",
+        fg_color: "#d1007a",
+        bg_color: "#ffffff",
+        gradient: Some("#e0007a,#5b00d6"),
+        dot_style: "rounded",
+        eye_style: "square",
+    },
+    Theme {
+        name: "ocean",
+        description: "Deep blues on pale aqua with circular dots",
+        fg_color: "#03396c",
+        bg_color: "#e6f4fa",
+        gradient: Some("#011f4b,#0077b6"),
+        dot_style: "circle",
+        eye_style: "circle",
+    },
+    Theme {
+        name: "mono-print",
+        description: "Plain black squares on white, the safest choice for print and small sizes",
+        fg_color: "#000000",
+        bg_color: "#ffffff",
+        gradient: None,
+        dot_style: "square",
+        eye_style: "square",
+    },
+    Theme {
+        name: "sunset",
+        description: "Red to purple on warm cream with rounded dots",
+        fg_color: "#b5171f",
+        bg_color: "#fff4e6",
+        gradient: Some("#c1121f,#6a1b9a"),
+        dot_style: "rounded",
+        eye_style: "circle",
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Theme> {
+    THEMES.iter().find(|theme| theme.name.eq_ignore_ascii_case(name.trim()))
+}
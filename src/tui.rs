@@ -0,0 +1,315 @@
+use anyhow::Result;
+use image::Rgba;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::PathBuf;
+
+use crate::{Args, build_symbol, generate_qr_image};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Data,
+    FgColor,
+    BgColor,
+    Gradient,
+    DotStyle,
+    EyeStyle,
+    Error,
+    Border,
+    Size,
+    Logo,
+    Output,
+}
+
+const FIELDS: [Field; 11] = [
+    Field::Data,
+    Field::FgColor,
+    Field::BgColor,
+    Field::Gradient,
+    Field::DotStyle,
+    Field::EyeStyle,
+    Field::Error,
+    Field::Border,
+    Field::Size,
+    Field::Logo,
+    Field::Output,
+];
+
+impl Field {
+    fn label(&self) -> &'static str {
+        match self {
+            Field::Data => "Data",
+            Field::FgColor => "Foreground",
+            Field::BgColor => "Background",
+            Field::Gradient => "Gradient",
+            Field::DotStyle => "Dot style",
+            Field::EyeStyle => "Eye style",
+            Field::Error => "Error level",
+            Field::Border => "Border",
+            Field::Size => "Size",
+            Field::Logo => "Logo",
+            Field::Output => "Output",
+        }
+    }
+
+    fn value(&self, args: &Args) -> String {
+        match self {
+            Field::Data => args.data.clone().unwrap_or_default(),
+            Field::FgColor => args.fg_color.clone(),
+            Field::BgColor => args.bg_color.clone(),
+            Field::Gradient => args.gradient.clone().unwrap_or_default(),
+            Field::DotStyle => args.dot_style.clone(),
+            Field::EyeStyle => args.eye_style.clone(),
+            Field::Error => args.error.clone().unwrap_or_else(|| "M".to_string()),
+            Field::Border => args.border.to_string(),
+            Field::Size => args.size.to_string(),
+            Field::Logo => args.logo.as_ref().map(|p| p.display().to_string()).unwrap_or_default(),
+            Field::Output => output_path(args).display().to_string(),
+        }
+    }
+
+    /// Free-text fields are edited with Enter, the rest cycle with ←/→
+    fn is_text(&self) -> bool {
+        matches!(
+            self,
+            Field::Data | Field::FgColor | Field::BgColor | Field::Gradient | Field::Logo | Field::Output
+        )
+    }
+
+    fn set_text(&self, args: &mut Args, text: String) {
+        let optional = (!text.is_empty()).then(|| text.clone());
+        match self {
+            Field::Data => args.data = optional,
+            Field::FgColor => args.fg_color = text,
+            Field::BgColor => args.bg_color = text,
+            Field::Gradient => args.gradient = optional,
+            Field::Logo => args.logo = optional.map(PathBuf::from),
+            Field::Output => args.output = optional.map(PathBuf::from),
+            _ => {}
+        }
+    }
+
+    fn step(&self, args: &mut Args, forward: bool) {
+        match self {
+            Field::DotStyle => args.dot_style = cycle(&["square", "circle", "rounded", "diamond"], &args.dot_style, forward),
+            Field::EyeStyle => args.eye_style = cycle(&["square", "circle", "frame"], &args.eye_style, forward),
+            Field::Error => args.error = Some(cycle(&["L", "M", "Q", "H"], &self.value(args), forward)),
+            Field::Border => {
+                args.border = if forward { (args.border + 1).min(20) } else { args.border.saturating_sub(1) }
+            }
+            Field::Size => {
+                args.size = if forward { (args.size + 50).min(4000) } else { args.size.saturating_sub(50).max(100) }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn cycle(options: &[&str], current: &str, forward: bool) -> String {
+    let index = options.iter().position(|o| o.eq_ignore_ascii_case(current)).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % options.len()
+    } else {
+        (index + options.len() - 1) % options.len()
+    };
+    options[next].to_string()
+}
+
+fn output_path(args: &Args) -> PathBuf {
+    args.output.clone().unwrap_or_else(|| PathBuf::from(format!("qrcode.{}", args.format)))
+}
+
+/// Module colors sampled from the rendered image, quiet zone included
+pub struct Preview {
+    pub modules: Vec<Vec<Rgba<u8>>>,
+    pub summary: String,
+}
+
+pub fn render_preview(args: &Args) -> Result<Preview> {
+    let data = args.data.as_deref().filter(|d| !d.is_empty());
+    let data = data.ok_or_else(|| anyhow::anyhow!("Enter some data to see the code"))?;
+
+    // Frames and captions move the code inside the image, the grid is what matters here
+    let mut args = args.clone();
+    args.frame = None;
+    args.caption = None;
+
+    let symbol = build_symbol(data, &args)?;
+    let img = generate_qr_image(&symbol, &args)?.to_rgba8();
+    let columns = symbol.width() as u32 + 2 * args.border;
+    let rows = symbol.height() as u32 + 2 * args.border;
+    let scale = (args.size / columns.max(rows)).max(1);
+    let modules = (0..rows)
+        .map(|y| {
+            (0..columns)
+                .map(|x| *img.get_pixel((x * scale + scale / 2).min(img.width() - 1), (y * scale + scale / 2).min(img.height() - 1)))
+                .collect()
+        })
+        .collect();
+
+    let summary = format!("{} · {}×{} modules", symbol.describe(), symbol.width(), symbol.height());
+    Ok(Preview { modules, summary })
+}
+
+struct App {
+    selected: usize,
+    editing: Option<String>,
+    preview: Result<Preview, String>,
+}
+
+/// Opens the editor on top of the current arguments; returns true when the user chose to save
+pub fn run(args: &mut Args) -> Result<bool> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, args);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, args: &mut Args) -> Result<bool> {
+    let mut app = App {
+        selected: 0,
+        editing: None,
+        preview: render_preview(args).map_err(|e| e.to_string()),
+    };
+
+    loop {
+        terminal.draw(|frame| draw(frame, &app, args))?;
+
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let field = FIELDS[app.selected];
+
+        if let Some(ref mut buffer) = app.editing {
+            match key.code {
+                KeyCode::Enter => {
+                    field.set_text(args, std::mem::take(buffer));
+                    app.editing = None;
+                }
+                KeyCode::Esc => app.editing = None,
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Char(c) => buffer.push(c),
+                _ => {}
+            }
+            if app.editing.is_some() {
+                continue;
+            }
+        } else {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                KeyCode::Char('s') => {
+                    if app.preview.is_ok() {
+                        return Ok(true);
+                    }
+                    continue;
+                }
+                KeyCode::Up => {
+                    app.selected = (app.selected + FIELDS.len() - 1) % FIELDS.len();
+                    continue;
+                }
+                KeyCode::Down | KeyCode::Tab => {
+                    app.selected = (app.selected + 1) % FIELDS.len();
+                    continue;
+                }
+                KeyCode::Enter if field.is_text() => {
+                    app.editing = Some(field.value(args));
+                    continue;
+                }
+                KeyCode::Left => field.step(args, false),
+                KeyCode::Right => field.step(args, true),
+                _ => continue,
+            }
+        }
+
+        app.preview = render_preview(args).map_err(|e| e.to_string());
+        // Rendering may print warnings to stderr, repaint everything over them
+        terminal.clear()?;
+    }
+}
+
+fn draw(frame: &mut Frame, app: &App, args: &Args) {
+    let [left, right] = Layout::horizontal([Constraint::Length(44), Constraint::Min(0)]).areas(frame.area());
+    let [fields_area, help_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(6)]).areas(left);
+
+    let lines: Vec<Line> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let value = match app.editing {
+                Some(ref buffer) if i == app.selected => format!("{}▏", buffer),
+                _ => field.value(args),
+            };
+            let style = if i == app.selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<12}", field.label()), style.add_modifier(Modifier::BOLD)),
+                Span::styled(value, style),
+            ])
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" RustQR ")),
+        fields_area,
+    );
+
+    let help = "↑/↓ select   ←/→ change\nEnter edit text, Enter again to apply\ns save   q quit";
+    frame.render_widget(
+        Paragraph::new(help).wrap(Wrap { trim: true }).block(Block::default().borders(Borders::ALL)),
+        help_area,
+    );
+
+    let block = Block::default().borders(Borders::ALL).title(" Preview ");
+    let inner = block.inner(right);
+    frame.render_widget(block, right);
+
+    match app.preview {
+        Ok(ref preview) => {
+            let rows = preview.modules.len() as u16;
+            let columns = preview.modules[0].len() as u16;
+            if columns > inner.width || rows.div_ceil(2) + 1 > inner.height {
+                let message = format!("Enlarge the terminal to {}×{} to see the preview", columns + 46, rows.div_ceil(2) + 3);
+                frame.render_widget(Paragraph::new(message).wrap(Wrap { trim: true }), inner);
+                return;
+            }
+
+            // Each cell shows two modules: the upper half as foreground, the lower as background
+            let mut lines: Vec<Line> = preview
+                .modules
+                .chunks(2)
+                .map(|rows| {
+                    let spans: Vec<Span> = (0..rows[0].len())
+                        .map(|x| {
+                            let top = rows[0][x];
+                            let bottom = rows.get(1).map_or(top, |row| row[x]);
+                            Span::styled("▀", Style::default().fg(to_color(top)).bg(to_color(bottom)))
+                        })
+                        .collect();
+                    Line::from(spans)
+                })
+                .collect();
+            lines.push(Line::from(preview.summary.clone()));
+            frame.render_widget(Paragraph::new(lines), inner);
+        }
+        Err(ref message) => {
+            let text = Span::styled(message.clone(), Style::default().fg(Color::Red));
+            frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+        }
+    }
+}
+
+/// Blends transparency over white
+fn to_color(pixel: Rgba<u8>) -> Color {
+    let alpha = pixel[3] as u16;
+    let blend = |c: u8| ((c as u16 * alpha + 255 * (255 - alpha)) / 255) as u8;
+    Color::Rgb(blend(pixel[0]), blend(pixel[1]), blend(pixel[2]))
+}
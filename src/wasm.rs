@@ -0,0 +1,14 @@
+use wasm_bindgen::prelude::*;
+
+/// Renders a PNG from the same arguments the command line takes, e.g. `["--data", "hello", "--dot-style", "rounded"]`
+#[wasm_bindgen(js_name = generatePng)]
+pub fn generate_png(args: Vec<String>) -> Result<Vec<u8>, JsError> {
+    crate::render(&args, "png").map_err(|e| JsError::new(&format!("{:#}", e)))
+}
+
+/// Renders an SVG document from the same arguments the command line takes
+#[wasm_bindgen(js_name = generateSvg)]
+pub fn generate_svg(args: Vec<String>) -> Result<String, JsError> {
+    let bytes = crate::render(&args, "svg").map_err(|e| JsError::new(&format!("{:#}", e)))?;
+    String::from_utf8(bytes).map_err(|e| JsError::new(&e.to_string()))
+}